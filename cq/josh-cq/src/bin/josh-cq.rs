@@ -119,6 +119,7 @@ async fn run_serve(args: ServeArgs, data_dir: Option<&std::path::Path>) -> anyho
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    josh_core::git::configure_libgit2();
     let cli = Cli::parse();
 
     match cli.command {
@@ -38,21 +38,13 @@ pub fn handle_track(
     mode: &str,
     transaction: &josh_core::cache::Transaction,
 ) -> anyhow::Result<String> {
-    let repo = transaction.git2_repo();
-
     let refs = crate::remote::list_refs(url)?;
 
     transaction.spawn_git(&["fetch", url, "HEAD"], &[])?;
 
-    // PORT: FETCH_HEAD pseudo-ref read stays on the git2 handle until flag day
-    // (gix multi-entry FETCH_HEAD semantics still unresolved).
-    let fetch_head_ref = repo
-        .find_reference("FETCH_HEAD")
-        .context("Failed to find FETCH_HEAD")?;
-    let fetched_commit = fetch_head_ref
-        .peel_to_commit()
-        .context("Failed to peel FETCH_HEAD to commit")?
-        .id();
+    let fetched_commit = transaction
+        .fetch_head()
+        .context("Failed to read FETCH_HEAD")?;
 
     let head = transaction.head().context("Failed to get HEAD")?;
 
@@ -0,0 +1,232 @@
+//! `josh-cache-server`: the server side of `josh_core::cache::HttpCacheBackend`.
+//!
+//! Each shard is an append-only file `<dir>/<version>/<filter id>/<shard>` of `<from> <to>`
+//! lines. `GET` returns the file, `POST` appends the (validated) records in the body. Readers
+//! let later lines win, so records are never rewritten in place.
+//!
+//! Uploads must carry the shared token the server was started with as a bearer token; without
+//! one the server is read-only. Record targets are not checked here -- the server has no
+//! repository -- but clients ignore records whose target they do not have.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::Router;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use clap::Parser;
+use tokio::io::AsyncWriteExt;
+
+#[derive(Parser)]
+#[command(name = "josh-cache-server", version = josh_core::VERSION)]
+struct Args {
+    /// Directory to store the cache records in
+    #[arg(long)]
+    dir: PathBuf,
+
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    bind: String,
+
+    /// Port to listen on
+    #[arg(long, default_value = "8001")]
+    port: u16,
+
+    /// Token clients must present to upload records; without it the server is read-only
+    #[arg(long, env = "JOSH_CACHE_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+}
+
+struct ServerState {
+    dir: PathBuf,
+    token: Option<String>,
+    // Serializes appends, so that concurrent uploads never interleave within a file.
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+type ShardPath = Path<(String, String, String)>;
+
+/// The file of a shard, for well-formed path segments only: `v<number>`, a 40 digit hex
+/// filter id and a shard number. Anything else could escape `dir`.
+fn shard_file(
+    state: &ServerState,
+    (version, filter, shard): &(String, String, String),
+) -> Option<PathBuf> {
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let valid = version.strip_prefix('v').is_some_and(is_number)
+        && filter.len() == 40
+        && filter.bytes().all(|b| b.is_ascii_hexdigit())
+        && is_number(shard);
+    valid.then(|| state.dir.join(version).join(filter).join(shard))
+}
+
+/// Whether `headers` carry the upload token as `Authorization: Bearer <token>`. Compared in
+/// constant time, so the response time gives nothing away about the token.
+fn authorized(state: &ServerState, headers: &HeaderMap) -> bool {
+    let Some(token) = &state.token else {
+        return false;
+    };
+    let Some(presented) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn error(e: impl std::fmt::Display) -> Response {
+    tracing::error!("{e}");
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+}
+
+async fn get_shard(State(state): State<Arc<ServerState>>, Path(path): ShardPath) -> Response {
+    let Some(file) = shard_file(&state, &path) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    match tokio::fs::read(&file).await {
+        Ok(content) => {
+            tracing::debug!("serving {} bytes of {}", content.len(), file.display());
+            content.into_response()
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => error(e),
+    }
+}
+
+async fn post_shard(
+    State(state): State<Arc<ServerState>>,
+    Path(path): ShardPath,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    if !authorized(&state, &headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let Some(file) = shard_file(&state, &path) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let records = match josh_core::cache::http::parse_records(&body) {
+        Ok(records) => records,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    if records.is_empty() {
+        return StatusCode::NO_CONTENT.into_response();
+    }
+
+    let _guard = state.write_lock.lock().await;
+    let result = async {
+        tokio::fs::create_dir_all(file.parent().unwrap()).await?;
+        let mut out = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file)
+            .await?;
+        out.write_all(josh_core::cache::http::format_records(&records).as_bytes())
+            .await?;
+        out.flush().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            tracing::info!("stored {} records in {}", records.len(), file.display());
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => error(e),
+    }
+}
+
+fn app(state: ServerState) -> Router {
+    Router::new()
+        .route(
+            "/{version}/{filter}/{shard}",
+            get(get_shard).post(post_shard),
+        )
+        .with_state(Arc::new(state))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    let args = Args::parse();
+    if args.token.is_none() {
+        tracing::warn!("no upload token set; serving read-only");
+    }
+    let app = app(ServerState {
+        dir: args.dir,
+        token: args.token,
+        write_lock: Default::default(),
+    });
+
+    let listener = tokio::net::TcpListener::bind((args.bind.as_str(), args.port)).await?;
+    tracing::info!("listening on {}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use josh_core::cache::{CacheBackend, HistoryGraphHint, HttpCacheBackend};
+
+    const HINT: HistoryGraphHint = HistoryGraphHint {
+        sequence_number: 0,
+        parent_count: 1,
+        jump_delta: 1,
+        jump_is_second: false,
+    };
+
+    /// Serve a fresh cache directory on a free local port, returning its URL.
+    fn serve(dir: &std::path::Path, token: &str) -> String {
+        let state = ServerState {
+            dir: dir.to_owned(),
+            token: Some(token.to_string()),
+            write_lock: Default::default(),
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind(("127.0.0.1", 0)))
+            .unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            runtime.block_on(async { axum::serve(listener, app(state)).await })
+        });
+        url
+    }
+
+    // Records uploaded with the token are served to other clients; uploads without it are
+    // refused, which turns the uploading backend off instead of failing the caller.
+    #[test]
+    fn records_are_shared_through_the_server() {
+        let td = tempfile::tempdir().unwrap();
+        let url = serve(td.path(), "secret");
+        let filter = josh_core::filter::parse(":/lib").unwrap();
+        let a = git2::Oid::from_str("1111111111111111111111111111111111111111").unwrap();
+        let b = git2::Oid::from_str("2222222222222222222222222222222222222222").unwrap();
+        let c = git2::Oid::from_str("3333333333333333333333333333333333333333").unwrap();
+
+        let writer = HttpCacheBackend::writable(&url, "secret").unwrap();
+        writer.write(filter, a, b, HINT, false).unwrap();
+        writer.end();
+
+        let intruder = HttpCacheBackend::writable(&url, "guess").unwrap();
+        intruder.write(filter, c, c, HINT, false).unwrap();
+        intruder.end();
+
+        let reader = HttpCacheBackend::new(&url).unwrap();
+        assert_eq!(reader.read(filter, a, HINT, false).unwrap(), Some(b));
+        assert_eq!(reader.read(filter, c, HINT, false).unwrap(), None);
+    }
+}
@@ -79,7 +79,7 @@ impl Change {
         walk.simplify_first_parent();
         walk.push(self.commit)?;
         let base = self.base;
-        let mut oids = walk.into_topo_vec(|oid| base != git2::Oid::ZERO_SHA1 && oid == base)?;
+        let mut oids = walk.into_topo_vec(|oid| !base.is_zero() && oid == base)?;
         oids.retain(|oid| *oid != base);
         if oids.first() == Some(&self.commit) {
             oids.remove(0);
@@ -147,7 +147,7 @@ pub(crate) fn get_changes(
     let mut walk = objects::RevWalk::new(&odb);
     walk.simplify_first_parent();
     walk.push(tip)?;
-    let mut oids = walk.into_topo_vec(|oid| base != git2::Oid::ZERO_SHA1 && oid == base)?;
+    let mut oids = walk.into_topo_vec(|oid| !base.is_zero() && oid == base)?;
     oids.retain(|oid| *oid != base);
     oids.reverse();
 
@@ -224,7 +224,7 @@ pub fn list_changes(
             }
             break;
         }
-        if tip_oid == git2::Oid::ZERO_SHA1 {
+        if tip_oid.is_zero() {
             continue;
         }
         let commit = match objects::CommitData::read(&odb, tip_oid) {
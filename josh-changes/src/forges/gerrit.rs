@@ -92,7 +92,7 @@ fn rewrite_chain_with_gerrit_ids(
     }
     chain.reverse();
 
-    let mut new_parent = (base != git2::Oid::ZERO_SHA1).then_some(base);
+    let mut new_parent = (!base.is_zero()).then_some(base);
     for oid in chain {
         let commit = josh_core::objects::CommitData::read(&odb, oid)?;
         let (josh_id, _) = josh_core::trailers::commit_change_meta(&commit);
@@ -188,7 +188,7 @@ pub fn build_gerrit_independent_push(
         let parent = josh_core::objects::CommitData::read(&odb, change.commit)?
             .parent_ids()
             .next();
-        let has_no_deps = if base == git2::Oid::ZERO_SHA1 {
+        let has_no_deps = if base.is_zero() {
             parent.is_none()
         } else {
             parent == Some(base)
@@ -7,6 +7,20 @@ use crate::refs::{StackedChangeRef, StackedRef};
 use anyhow::anyhow;
 use josh_core::cache::Transaction;
 
+/// The options of a push to the proxy (`git push -o ...`).
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[serde(default)]
+#[derive(Default)]
+pub struct PushOptions {
+    pub merge: bool,
+    pub allow_orphans: bool,
+    pub edit: bool,
+    pub create: bool,
+    pub force: bool,
+    pub base: Option<String>,
+    pub author: Option<String>,
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum PushMode {
     Normal,
@@ -150,3 +164,122 @@ pub fn build_to_push(
         }]),
     }
 }
+
+/// What a push of a filtered commit publishes upstream, as decided by [`plan_push`].
+#[derive(Debug)]
+pub struct PushPlan {
+    /// The pushed commit in the upstream history: unapplied, and merged with the target when
+    /// the `merge` option is set.
+    pub oid: git2::Oid,
+    /// The refs to update upstream.
+    pub refs: Vec<PushRef>,
+    /// Whether the refs are force pushed.
+    pub force: bool,
+}
+
+/// Decide what a push of `new`, a commit of the history filtered by `filter`, to `refname`
+/// publishes upstream: unapply it onto the target in `transaction_mirror` (the upstream
+/// mirror), relative to `old`, the previous filtered commit, and list the refs to update.
+pub fn plan_push(
+    transaction: &Transaction,
+    transaction_mirror: &Transaction,
+    filter: josh_core::filter::Filter,
+    refname: &str,
+    old: git2::Oid,
+    new: git2::Oid,
+    options: &PushOptions,
+) -> anyhow::Result<PushPlan> {
+    let author = options.author.as_deref().unwrap_or("");
+    let (baseref, push_to, ref_options, push_mode) = baseref_and_options(refname, author)?;
+
+    let original_target_ref = if let Some(base) = &options.base {
+        // Allow user to use just the branch name as the base:
+        let full_path_base_refname = transaction_mirror.refname(&format!("refs/heads/{}", base));
+        if transaction_mirror
+            .resolve_ref(&full_path_base_refname)?
+            .is_some()
+        {
+            full_path_base_refname
+        } else {
+            transaction_mirror.refname(base)
+        }
+    } else {
+        transaction_mirror.refname(&baseref)
+    };
+
+    let Some(original_target) = transaction_mirror.resolve_ref(&original_target_ref)? else {
+        tracing::debug!(
+            original_target_ref = %original_target_ref,
+            "resolve_original_target"
+        );
+        return Err(anyhow!(
+            "Reference {:?} does not exist on remote.\n\
+             If you want to create it, pass \"-o base=<basebranch>\" or \"-o base=path/to/ref\"\n\
+             to specify a base branch/reference.\n",
+            baseref
+        ));
+    };
+    tracing::debug!(
+        original_target_oid = ?original_target,
+        original_target_ref = %original_target_ref,
+        "resolve_original_target"
+    );
+
+    let unapplied = josh_core::history::unapply_filter(
+        transaction,
+        filter,
+        original_target,
+        old,
+        new,
+        if options.merge || options.allow_orphans {
+            josh_core::history::OrphansMode::Keep
+        } else if options.edit {
+            josh_core::history::OrphansMode::Remove
+        } else {
+            josh_core::history::OrphansMode::Fail
+        },
+        options.create.then_some(original_target),
+    )?;
+    tracing::debug!(
+        processed_old = ?old,
+        unapply_result = ?unapplied,
+        "unapply_filter"
+    );
+
+    let oid = if options.merge {
+        let signature = josh_core::git::josh_commit_signature()?;
+        let odb = transaction.odb()?;
+        let merged_tree =
+            josh_core::objects::merge_commits(&odb, original_target, unapplied, None)?;
+        josh_core::objects::write_commit(
+            &odb,
+            merged_tree,
+            &[original_target, unapplied],
+            &signature,
+            &signature,
+            &format!("Merge from {}", josh_core::filter::spec(filter)),
+        )?
+    } else {
+        unapplied
+    };
+
+    let ref_with_options = if !ref_options.is_empty() {
+        format!("{}{}{}", push_to, "%", ref_options.join(","))
+    } else {
+        push_to
+    };
+    let refs = build_to_push(
+        transaction,
+        &push_mode,
+        &baseref,
+        &ref_with_options,
+        oid,
+        original_target,
+    )?;
+
+    Ok(PushPlan {
+        oid,
+        refs,
+        force: !matches!(push_mode, PushMode::Normal) || options.force,
+    })
+}
@@ -60,7 +60,7 @@ pub fn write_changes_tree(
     let prev_commit = transaction.resolve_ref(&ref_name)?;
     let base_tree = match prev_commit {
         Some(oid) => objects::CommitData::read(&odb, oid)?.tree_id()?,
-        None => tree::empty_id(),
+        None => tree::empty_id(odb.object_hash()),
     };
 
     // Skip if the blob already exists at this path.
@@ -137,7 +137,7 @@ pub fn store_diff_data(
     let entry_name = blob_oid.to_string();
     let tree_oid = tree::insert_oid(
         &odb,
-        tree::empty_id(),
+        tree::empty_id(odb.object_hash()),
         std::path::Path::new(&entry_name),
         blob_oid,
         git2::FileMode::Blob.into(),
@@ -147,7 +147,7 @@ pub fn store_diff_data(
     let prev_tip = transaction.resolve_ref(&ref_name)?;
     let base_tree = match prev_tip {
         Some(oid) => objects::CommitData::read(&odb, oid)?.tree_id()?,
-        None => tree::empty_id(),
+        None => tree::empty_id(odb.object_hash()),
     };
 
     let path = std::path::Path::new("diffs").join(encode_change_id_path(&change_id));
@@ -165,7 +165,7 @@ pub fn store_diff_data(
     let anchor_sig = git2::Signature::new("JOSH", "josh@josh-project.dev", &git2::Time::new(0, 0))?;
     let anchor_oid = objects::write_commit(
         &odb,
-        tree::empty_id(),
+        tree::empty_id(odb.object_hash()),
         &[change.commit()],
         &anchor_sig,
         &anchor_sig,
@@ -199,7 +199,7 @@ pub fn store_pr_data<T: serde::Serialize>(
 
     let tree_oid = tree::insert_oid(
         &odb,
-        tree::empty_id(),
+        tree::empty_id(odb.object_hash()),
         std::path::Path::new(&blob_oid.to_string()),
         blob_oid,
         git2::FileMode::Blob.into(),
@@ -209,7 +209,7 @@ pub fn store_pr_data<T: serde::Serialize>(
     let prev_tip = transaction.resolve_ref(&ref_name)?;
     let base_tree = match prev_tip {
         Some(oid) => objects::CommitData::read(&odb, oid)?.tree_id()?,
-        None => tree::empty_id(),
+        None => tree::empty_id(odb.object_hash()),
     };
 
     let path = std::path::Path::new("gh").join(encode_change_id_path(change_id));
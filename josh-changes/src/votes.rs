@@ -81,7 +81,7 @@ fn write_vote_inner(
 
     let tree_oid = tree::insert_oid(
         &odb,
-        tree::empty_id(),
+        tree::empty_id(odb.object_hash()),
         std::path::Path::new(&blob_oid.to_string()),
         blob_oid,
         git2::FileMode::Blob.into(),
@@ -104,7 +104,7 @@ fn write_vote_inner(
     let prev_commit = transaction.resolve_ref(&ref_name)?;
     let base_tree = match prev_commit {
         Some(oid) => objects::CommitData::read(&odb, oid)?.tree_id()?,
-        None => tree::empty_id(),
+        None => tree::empty_id(odb.object_hash()),
     };
 
     if let Ok(Some(existing)) = tree::get_path_entry(transaction, &odb, base_tree, &path) {
@@ -10,7 +10,7 @@ fn resolve_input_ref(
     let oid = josh_core::git::resolve_snapshot_input(transaction, input_ref)?;
     let ref_string = if input_ref == "+" || input_ref == "." {
         oid.to_string()
-    } else if git2::Oid::from_str(input_ref).is_ok() {
+    } else if josh_core::objects::parse_oid(input_ref).is_ok() {
         input_ref.to_string()
     } else if let Some(name) = transaction.expand_ref_name(input_ref)? {
         name
@@ -23,7 +23,68 @@ fn resolve_input_ref(
 fn make_app() -> clap::Command {
     let app = clap::Command::new("josh-filter");
 
-    let app = { app.arg(clap::Arg::new("search").long("search")) };
+    let app = {
+        app.arg(clap::Arg::new("search").long("search"))
+            .arg(
+                clap::Arg::new("regex")
+                    .long("regex")
+                    .action(clap::ArgAction::SetTrue)
+                    .requires("search")
+                    .help("Treat the --search string as a regular expression"),
+            )
+            .arg(
+                clap::Arg::new("ignore-case")
+                    .long("ignore-case")
+                    .action(clap::ArgAction::SetTrue)
+                    .requires("search")
+                    .help("Search case-insensitively"),
+            )
+            .arg(
+                clap::Arg::new("search-include")
+                    .long("search-include")
+                    .action(clap::ArgAction::Append)
+                    .requires("search")
+                    .help("Search only files matching this glob (may be repeated)"),
+            )
+            .arg(
+                clap::Arg::new("search-exclude")
+                    .long("search-exclude")
+                    .action(clap::ArgAction::Append)
+                    .requires("search")
+                    .help("Do not search files matching this glob (may be repeated)"),
+            )
+            .arg(
+                clap::Arg::new("context")
+                    .long("context")
+                    .value_parser(clap::value_parser!(usize))
+                    .requires("search")
+                    .help("Show this many lines of context around each match"),
+            )
+            .arg(
+                clap::Arg::new("max-results")
+                    .long("max-results")
+                    .value_parser(clap::value_parser!(usize))
+                    .requires("searching")
+                    .help("Stop after this many matching lines, or commits with --search-history"),
+            )
+            .arg(
+                clap::Arg::new("search-after")
+                    .long("search-after")
+                    .requires("search")
+                    .help("Continue a search cut short by --max-results from its cursor"),
+            )
+            .arg(
+                clap::Arg::new("search-history")
+                    .long("search-history")
+                    .conflicts_with("search")
+                    .help("List the filtered commits that change how often this string occurs"),
+            )
+            .group(
+                clap::ArgGroup::new("searching")
+                    .args(["search", "search-history"])
+                    .multiple(true),
+            )
+    };
 
     app
         .arg(
@@ -75,6 +136,12 @@ fn make_app() -> clap::Command {
                 .help("Pretty print the filter and exit")
                 .short('p'),
         )
+        .arg(
+            clap::Arg::new("explain")
+                .action(clap::ArgAction::SetTrue)
+                .help("Explain for every path of the filtered input commit which filter produced it from which input path, and why the remaining input paths were dropped")
+                .long("explain"),
+        )
         .arg(
             clap::Arg::new("filter-id")
                 .action(clap::ArgAction::SetTrue)
@@ -93,12 +160,39 @@ fn make_app() -> clap::Command {
                 .help("Don't load cache")
                 .short('n'),
         )
+        .arg(
+            clap::Arg::new("threads")
+                .long("threads")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("1")
+                .help("Filter independent branches of history on up to this many threads"),
+        )
         .arg(
             clap::Arg::new("distributed-cache")
                 .action(clap::ArgAction::SetTrue)
                 .help("Enables distributed cache")
                 .long("distributed-cache"),
         )
+        .arg(
+            clap::Arg::new("cache-url")
+                .help("Share the cache with the josh-cache-server at this URL")
+                .long("cache-url"),
+        )
+        .arg(
+            clap::Arg::new("cache-upload")
+                .action(clap::ArgAction::SetTrue)
+                .help("Upload new cache records to the --cache-url server")
+                .long("cache-upload")
+                .requires("cache-url")
+                .requires("cache-token"),
+        )
+        .arg(
+            clap::Arg::new("cache-token")
+                .help("Upload token of the --cache-url server")
+                .long("cache-token")
+                .env("JOSH_CACHE_TOKEN")
+                .hide_env_values(true),
+        )
         .arg(clap::Arg::new("query").long("query").short('q'))
         .arg(
             clap::Arg::new("graphql")
@@ -138,6 +232,7 @@ fn make_app() -> clap::Command {
 }
 
 struct GitNotesFilterHook {
+    refs: josh_core::cache::RefStore,
     repo: std::sync::Mutex<git2::Repository>,
 }
 
@@ -153,10 +248,16 @@ impl josh_core::cache::FilterHook for GitNotesFilterHook {
             format!("refs/notes/{}", arg)
         };
         let repo = self.repo.lock().unwrap();
-        let note = repo
-            .find_note(Some(notes_ref.as_str()), commit_oid)
+        let odb = repo.odb()?;
+        let objects = josh_core::objects::Git2Odb(&odb);
+        let notes = self
+            .refs
+            .resolve(&notes_ref)?
+            .with_context(|| format!("missing notes ref {}", notes_ref))?;
+        let note = josh_core::objects::note_blob(&objects, notes, commit_oid)?
             .context("missing git note for commit")?;
-        let msg = note.message().context("empty git note")?;
+        let blob = odb.read(note)?;
+        let msg = std::str::from_utf8(blob.data()).context("empty git note")?;
         josh_core::filter::parse(msg)
     }
 }
@@ -185,6 +286,15 @@ fn run_filter(args: Vec<String>) -> anyhow::Result<i32> {
             cache = cache.with_backend(josh_core::cache::SledCacheBackend::new(&repo_path));
         }
 
+        if let Some(url) = args.get_one::<String>("cache-url") {
+            cache = cache.with_backend(if args.get_flag("cache-upload") {
+                let token = args.get_one::<String>("cache-token").unwrap();
+                josh_core::cache::HttpCacheBackend::writable(url, token)?
+            } else {
+                josh_core::cache::HttpCacheBackend::new(url)?
+            });
+        }
+
         if args.get_flag("distributed-cache") {
             cache.with_backend(josh_core::cache::DistributedCacheBackend::new(&repo_path)?)
         } else {
@@ -194,6 +304,7 @@ fn run_filter(args: Vec<String>) -> anyhow::Result<i32> {
 
     let mut transaction = josh_core::cache::TransactionContext::from_env(cache.clone())?
         .with_mem_odb_limit(josh_cli::MAX_MEM_PACK_SIZE)
+        .with_threads(*args.get_one::<usize>("threads").unwrap())
         .open()?;
 
     let repo_for_hook = git2::Repository::open_ext(
@@ -202,11 +313,12 @@ fn run_filter(args: Vec<String>) -> anyhow::Result<i32> {
         &[] as &[&std::ffi::OsStr],
     )?;
     let hook = GitNotesFilterHook {
+        refs: josh_core::cache::RefStore::open(&repo_for_hook),
         repo: std::sync::Mutex::new(repo_for_hook),
     };
     transaction = transaction.with_filter_hook(std::sync::Arc::new(hook));
 
-    let repo = transaction.git2_repo();
+    let odb = transaction.odb()?;
 
     // If the filter spec doesn't contain a colon and it's not from a file,
     // treat it as a SHA and read from tree
@@ -214,7 +326,7 @@ fn run_filter(args: Vec<String>) -> anyhow::Result<i32> {
         josh_core::filter::parse(&specstr)?
     } else {
         // Try to parse as SHA and read filter from tree
-        let tree_oid = git2::Oid::from_str(specstr.trim())
+        let tree_oid = josh_core::objects::parse_oid(specstr.trim())
             .with_context(|| format!("Invalid filter spec or SHA: {}", specstr))?;
         josh_core::filter::from_tree(&transaction, tree_oid)?
     };
@@ -242,7 +354,7 @@ fn run_filter(args: Vec<String>) -> anyhow::Result<i32> {
             if !matcher.matches(name) {
                 return Ok(());
             }
-            let target = repo.find_object(oid, None)?.peel_to_commit()?.id();
+            let target = josh_core::objects::peel_to_commit(&odb, oid)?;
             ids.push((target, josh_core::filter::Filter::new().message(name)));
             refs.push((name.to_string(), target));
             Ok(())
@@ -258,8 +370,8 @@ fn run_filter(args: Vec<String>) -> anyhow::Result<i32> {
         for line in reflist.lines() {
             let split = line.split(' ').collect::<Vec<_>>();
             if let [sha, name] = split.as_slice() {
-                let target = git2::Oid::from_str(sha)?;
-                let target = repo.find_object(target, None)?.peel_to_commit()?.id();
+                let target = josh_core::objects::parse_oid(sha)?;
+                let target = josh_core::objects::peel_to_commit(&odb, target)?;
                 ids.push((target, josh_core::filter::Filter::new().message(name)));
                 refs.push((name.to_string(), target));
             } else if !split.is_empty() {
@@ -294,6 +406,45 @@ fn run_filter(args: Vec<String>) -> anyhow::Result<i32> {
         return Ok(0);
     }
 
+    if args.get_flag("explain") {
+        let odb = transaction.odb()?;
+        let tree = josh_core::objects::CommitData::read(&odb, oid)?.tree_id()?;
+        let explanation = josh_core::filter::explain(&transaction, filterobj, tree)?;
+        let spec = josh_core::filter::spec;
+        for o in &explanation.outputs {
+            let source = o.source.as_deref().unwrap_or("(generated)");
+            println!("{} <- {} by {}", o.path, source, spec(o.filter));
+        }
+        for d in &explanation.dropped {
+            match &d.reason {
+                josh_core::filter::DropReason::NotSelected => {
+                    println!("dropped {}: not selected", d.path)
+                }
+                josh_core::filter::DropReason::Removed(f) => {
+                    println!("dropped {}: removed by {}", d.path, spec(*f))
+                }
+                josh_core::filter::DropReason::Overwritten { output, by } => {
+                    println!(
+                        "dropped {}: {} overwritten by {}",
+                        d.path,
+                        output,
+                        spec(*by)
+                    )
+                }
+            }
+        }
+        for s in &explanation.shadowed {
+            println!(
+                "shadowed {} <- {} by {}: source taken by {}",
+                s.origin.path,
+                s.origin.source.as_deref().unwrap_or("(generated)"),
+                spec(s.origin.filter),
+                spec(s.by)
+            );
+        }
+        return Ok(0);
+    }
+
     let finish = defer::defer(|| {
         if args.get_flag("cache-stats") {
             josh_core::cache::sled_print_stats().expect("failed to collect cache stats");
@@ -359,32 +510,87 @@ fn run_filter(args: Vec<String>) -> anyhow::Result<i32> {
         )?
         .tree_id()?;
 
-        // The trigram index is experimental; without it every file is a candidate and
-        // search_matches does all the filtering, so results are identical, just slower.
-        let candidates = if josh_core::filter::experimental_features_enabled() {
+        // The trigram index is experimental; without it every file in scope is read, so
+        // results are identical, just slower.
+        let index_tree = if josh_core::filter::experimental_features_enabled() {
             let ifilterobj = filterobj.chain(josh_core::filter::parse(":SQUASH:INDEX")?);
             let index_commit = josh_core::filter_commit(&transaction, ifilterobj, commit)?;
-            let index_tree = josh_core::objects::CommitData::read(&odb, index_commit)?.tree_id()?;
-            josh_search::search_candidates(&odb, index_tree, tree, searchstring)?
+            Some(josh_core::objects::CommitData::read(&odb, index_commit)?.tree_id()?)
         } else {
-            let mut scan = vec![];
-            josh_core::objects::walk_tree_preorder(&odb, tree, &mut |parent, entry| {
-                if !entry.mode.is_tree()
-                    && !entry.mode.is_commit()
-                    && let Ok(name) = std::str::from_utf8(entry.filename)
-                {
-                    let separator = if parent.is_empty() { "" } else { "/" };
-                    scan.push(format!("{}{}{}", parent, separator, name));
-                }
-                Ok(())
-            })?;
-            scan
+            None
+        };
+
+        let globs = |name: &str| -> anyhow::Result<Vec<_>> {
+            args.get_many::<String>(name)
+                .into_iter()
+                .flatten()
+                .map(|glob| Ok(josh_core::filter::tree::CompiledPattern::compile(glob)?))
+                .collect()
         };
-        let matches = josh_search::search_matches(&odb, tree, searchstring, &candidates)?;
+        let context = args.get_one::<usize>("context").copied().unwrap_or(0);
+        let options = josh_search::SearchOptions {
+            regex: args.get_flag("regex"),
+            ignore_case: args.get_flag("ignore-case"),
+            include: globs("search-include")?,
+            exclude: globs("search-exclude")?,
+            before: context,
+            after: context,
+            max_results: args.get_one::<usize>("max-results").copied(),
+            cursor: args.get_one::<String>("search-after").cloned(),
+        };
+        let page = josh_search::search(&odb, index_tree, tree, searchstring, &options)?;
+
+        // grep's convention: ':' after the line number of a match, '-' after a context line.
+        for file in page.files {
+            for line in file.lines {
+                let separator = if line.is_match { ':' } else { '-' };
+                println!(
+                    "{}{}{}{} {}",
+                    file.path, separator, line.number, separator, line.text
+                );
+            }
+        }
+        if let Some(next) = page.next {
+            eprintln!("more results: --search-after '{}'", next);
+        }
+    }
+
+    if let Some(searchstring) = args.get_one::<String>("search-history") {
+        let commit = transaction
+            .rev_parse(&input_ref)?
+            .ok_or_else(|| anyhow!("no such revision: {}", input_ref))?;
+        let head = josh_core::filter_commit(&transaction, filterobj, commit)?;
+
+        let odb = transaction.odb()?;
+        // As with --search, the index only saves reading files that cannot have changed.
+        let ifilterobj = josh_core::filter::experimental_features_enabled()
+            .then(|| josh_core::filter::parse(":SQUASH:INDEX"))
+            .transpose()?;
+        let mut index = |tree: git2::Oid| -> anyhow::Result<Option<git2::Oid>> {
+            let Some(ifilterobj) = ifilterobj else {
+                return Ok(None);
+            };
+            let rewrite = josh_core::filter::Rewrite::from_tree(tree);
+            Ok(Some(
+                josh_core::filter::apply(&transaction, ifilterobj, rewrite)?.tree_id(),
+            ))
+        };
+        let mut history = josh_core::objects::HistoryWalk::new(&odb, |oid| {
+            josh_core::cache::compute_sequence_number(&transaction, oid)
+        });
+        history.push(head)?;
+        let matches = josh_search::search_history(
+            &odb,
+            history,
+            searchstring,
+            &mut index,
+            args.get_one::<usize>("max-results").copied(),
+        )?;
 
-        for r in matches {
-            for l in r.1 {
-                println!("{}:{}: {}", r.0, l.0, l.1);
+        for m in matches {
+            println!("{}", m.commit);
+            for change in m.changes {
+                println!("  {}: {} -> {}", change.path, change.before, change.after);
             }
         }
     }
@@ -519,6 +725,7 @@ fn run_filter(args: Vec<String>) -> anyhow::Result<i32> {
 
 fn main() {
     env_logger::init();
+    josh_core::git::configure_libgit2();
     let args = {
         let mut args = vec![];
         for arg in std::env::args() {
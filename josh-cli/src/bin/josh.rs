@@ -27,6 +27,23 @@ pub struct Cli {
     #[arg(long = "no-distributed-cache", action = clap::ArgAction::SetFalse, global = true)]
     pub distributed_cache: bool,
 
+    /// URL of a josh-cache-server to share the filter cache with
+    #[arg(long = "cache-url", env = "JOSH_CACHE_URL", global = true)]
+    pub cache_url: Option<String>,
+
+    /// Also upload new cache records to the --cache-url server
+    #[arg(long = "cache-upload", requires = "cache_url", global = true)]
+    pub cache_upload: bool,
+
+    /// Upload token of the --cache-url server, required by --cache-upload
+    #[arg(
+        long = "cache-token",
+        env = "JOSH_CACHE_TOKEN",
+        hide_env_values = true,
+        global = true
+    )]
+    pub cache_token: Option<String>,
+
     /// Subcommand to run
     #[command(subcommand)]
     pub command: Command,
@@ -188,19 +205,52 @@ pub struct ForgeArgs {
 }
 
 #[derive(Debug, clap::Parser)]
+#[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
 pub struct FilterArgs {
     /// Remote name to apply filtering to
     #[arg()]
-    pub remote: String,
+    pub remote: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<FilterCommand>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum FilterCommand {
+    /// Check a filter spec for mistakes without applying it
+    Lint(LintArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+#[command(arg_required_else_help = true)]
+pub struct LintArgs {
+    /// Filter spec to check
+    #[arg(conflicts_with = "file")]
+    pub spec: Option<String>,
+
+    /// Read the spec from a file, e.g. a workspace.josh
+    #[arg(long = "file")]
+    pub file: Option<std::path::PathBuf>,
+
+    /// Print the diagnostics as a JSON array
+    #[arg(long = "json")]
+    pub json: bool,
 }
 
 fn main() {
     env_logger::init();
+    josh_core::git::configure_libgit2();
     let cli = Cli::parse();
 
     let result = match &cli.command {
         Command::Standalone(cmd) => run_standalone(cmd),
-        Command::Repo(cmd) => run_repo(cmd, cli.distributed_cache),
+        Command::Repo(cmd) => run_repo(
+            cmd,
+            cli.distributed_cache,
+            cli.cache_url.as_deref(),
+            cli.cache_upload,
+            cli.cache_token.as_deref(),
+        ),
     };
 
     if let Err(e) = result {
@@ -220,7 +270,22 @@ fn run_standalone(cmd: &StandaloneCommand) -> anyhow::Result<()> {
     }
 }
 
-fn run_repo(cmd: &RepoCommand, distributed_cache: bool) -> anyhow::Result<()> {
+fn run_repo(
+    cmd: &RepoCommand,
+    distributed_cache: bool,
+    cache_url: Option<&str>,
+    cache_upload: bool,
+    cache_token: Option<&str>,
+) -> anyhow::Result<()> {
+    // Linting only looks at the spec, so it works outside of a repository too
+    if let RepoCommand::Filter(FilterArgs {
+        command: Some(FilterCommand::Lint(args)),
+        ..
+    }) = cmd
+    {
+        return handle_filter_lint(args);
+    }
+
     // For clone, do the initial repo setup before creating transaction
     let repo_path = if let RepoCommand::Clone(args) = cmd {
         // For clone, we're not in a git repo initially, so clone first and use that path
@@ -248,6 +313,17 @@ fn run_repo(cmd: &RepoCommand, distributed_cache: bool) -> anyhow::Result<()> {
         cache_stack =
             cache_stack.with_backend(josh_core::cache::SledCacheBackend::new(&git_common_dir));
     }
+    if let Some(url) = cache_url {
+        let backend = if cache_upload {
+            let token = cache_token
+                .context("--cache-upload needs the server's upload token (--cache-token)")?;
+            josh_core::cache::HttpCacheBackend::writable(url, token)
+        } else {
+            josh_core::cache::HttpCacheBackend::new(url)
+        };
+        cache_stack =
+            cache_stack.with_backend(backend.context("Failed to create HttpCacheBackend")?);
+    }
     if distributed_cache {
         cache_stack = cache_stack.with_backend(
             josh_core::cache::DistributedCacheBackend::new(&git_common_dir)
@@ -385,21 +461,24 @@ fn handle_clone(
     // Use handle_fetch to do the actual fetching and filtering
     josh_cli::commands::fetch::handle_fetch(&fetch_args, transaction, distributed_cache)?;
 
+    // Start the commit-graph, with changed-path filters, over the fetched history. Fetches do
+    // not add to it: git's own maintenance (`gc --auto`) extends it later, and keeps writing
+    // the filters once a graph has them.
+    transaction
+        .spawn_git(josh_core::housekeeping::COMMIT_GRAPH_WRITE, &[])
+        .context("failed to write commit-graph")?;
+
     // Get the default branch name from the remote HEAD symref
     let default_branch = if args.branch == "HEAD" {
         // Read the remote HEAD symref to get the default branch
         let head_ref = "refs/remotes/origin/HEAD".to_string();
 
-        // PORT: symbolic-target read needs the target name, not the oid, so it is not
-        // expressible via resolve_ref; stays on the git2 handle until flag day (gix
-        // try_find_reference then).
-        let head_reference = repo
-            .find_reference(&head_ref)
-            .with_context(|| format!("Failed to find remote HEAD reference {}", head_ref))?;
-
-        let symref_target = head_reference
-            .symbolic_target()?
-            .context("Remote HEAD reference is not a symbolic reference")?;
+        let symref_target = transaction.symbolic_target(&head_ref)?.with_context(|| {
+            format!(
+                "Remote HEAD reference {} is missing or not a symbolic reference",
+                head_ref
+            )
+        })?;
 
         // Extract branch name from symref target (e.g., "refs/remotes/origin/master" -> "master")
         let branch_name = symref_target
@@ -544,25 +623,50 @@ fn handle_filter(
 ) -> anyhow::Result<()> {
     let repo_path = normalize_repo_path(transaction.path());
 
-    let config = read_remote_config(&repo_path, &args.remote)
-        .with_context(|| format!("Failed to read remote config for '{}'", args.remote))?;
+    let remote = args.remote.as_deref().context("No remote given")?;
+    let config = read_remote_config(&repo_path, remote)
+        .with_context(|| format!("Failed to read remote config for '{}'", remote))?;
 
     let filter = config.semantic_filter();
     let filter_str = josh_core::filter::spec(filter);
 
-    println!(
-        "Applying filter '{}' to remote '{}'",
-        filter_str, args.remote
-    );
+    println!("Applying filter '{}' to remote '{}'", filter_str, remote);
 
-    let default_branch = josh_cli::remote_ops::resolve_default_branch(transaction, &args.remote)?;
+    let default_branch = josh_cli::remote_ops::resolve_default_branch(transaction, remote)?;
 
-    josh_cli::remote_ops::apply_josh_filtering(transaction, filter, &args.remote, &default_branch)?;
+    josh_cli::remote_ops::apply_josh_filtering(transaction, filter, remote, &default_branch)?;
 
-    println!(
-        "Applied filter '{}' to remote '{}'",
-        filter_str, args.remote
-    );
+    println!("Applied filter '{}' to remote '{}'", filter_str, remote);
+
+    Ok(())
+}
+
+/// Handle `josh filter lint` - report problems in a filter spec. Exits with status 1 if there
+/// are any, so it can be used as a check in CI.
+fn handle_filter_lint(args: &LintArgs) -> anyhow::Result<()> {
+    let (spec, source) = match (&args.spec, &args.file) {
+        (_, Some(path)) => (
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+            path.display().to_string(),
+        ),
+        (Some(spec), None) => (spec.clone(), "<spec>".to_string()),
+        (None, None) => anyhow::bail!("No filter spec given"),
+    };
+
+    let diagnostics = josh_core::filter::lint::lint(&spec);
+
+    if args.json {
+        let json: Vec<_> = diagnostics.iter().map(|d| d.to_json()).collect();
+        println!("{}", serde_json::to_string_pretty(&json)?);
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{}:{}", source, diagnostic);
+        }
+    }
 
+    if !diagnostics.is_empty() {
+        std::process::exit(1);
+    }
     Ok(())
 }
@@ -3,7 +3,8 @@ use std::collections::HashSet;
 use anyhow::Context;
 
 use josh_core::cache::{
-    CACHE_VERSION, CacheStack, DistributedCacheBackend, Transaction, TransactionContext,
+    CACHE_VERSION, CacheStack, DistributedCacheBackend, SledTreeStats, Transaction,
+    TransactionContext,
 };
 use josh_core::filter::{self, Filter, flatten_chain, from_tree};
 use josh_core::git::normalize_repo_path;
@@ -25,6 +26,10 @@ pub enum CacheCommand {
     Push(CachePushArgs),
     /// Fetch the distributed cache and filtered refs from the remote
     Fetch(CacheFetchArgs),
+    /// Show the size and last use of each filter in the local cache
+    Stats,
+    /// Evict filters from the local cache to bound its size
+    Gc(CacheGcArgs),
 }
 
 #[derive(Debug, clap::Parser)]
@@ -48,14 +53,75 @@ pub struct CacheFetchArgs {
     pub remote: String,
 }
 
+#[derive(Debug, clap::Parser)]
+pub struct CacheGcArgs {
+    /// Evict least recently used filters until the cache holds at most this much (e.g. 512M, 4G)
+    #[arg(long, value_parser = filter::BlobSelect::parse_size)]
+    pub max_size: Option<u64>,
+    /// Evict filters not used for this many days
+    #[arg(long)]
+    pub max_age: Option<u64>,
+}
+
 pub fn handle_cache(args: &CacheArgs, transaction: &Transaction) -> anyhow::Result<()> {
     match &args.command {
         CacheCommand::Build(a) => handle_cache_build(a, transaction),
         CacheCommand::Push(a) => handle_cache_push(a, transaction),
         CacheCommand::Fetch(a) => handle_cache_fetch(a, transaction),
+        CacheCommand::Stats => handle_cache_stats(),
+        CacheCommand::Gc(a) => handle_cache_gc(a),
+    }
+}
+
+fn format_age(last_access: Option<u64>) -> String {
+    let Some(last_access) = last_access else {
+        return "never".to_string();
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    match now.saturating_sub(last_access) {
+        secs if secs < 3600 => format!("{}m ago", secs / 60),
+        secs if secs < 86400 => format!("{}h ago", secs / 3600),
+        secs => format!("{}d ago", secs / 86400),
+    }
+}
+
+fn print_tree_stats(stats: &[SledTreeStats]) {
+    for tree in stats {
+        println!(
+            "{:>10} {:>10}  {:<10} {}",
+            tree.entries,
+            tree.bytes,
+            format_age(tree.last_access),
+            tree.name
+        );
     }
 }
 
+fn handle_cache_stats() -> anyhow::Result<()> {
+    let stats = josh_core::cache::sled_stats()?;
+    println!(
+        "{:>10} {:>10}  {:<10} filter",
+        "entries", "bytes", "last used"
+    );
+    print_tree_stats(&stats);
+    let total: u64 = stats.iter().map(|tree| tree.bytes).sum();
+    println!("{} filters, {} bytes", stats.len(), total);
+    Ok(())
+}
+
+fn handle_cache_gc(args: &CacheGcArgs) -> anyhow::Result<()> {
+    let max_age = args
+        .max_age
+        .map(|days| std::time::Duration::from_secs(days * 86400));
+    let evicted = josh_core::cache::sled_gc(args.max_size, max_age)?;
+    print_tree_stats(&evicted);
+    let total: u64 = evicted.iter().map(|tree| tree.bytes).sum();
+    eprintln!("Evicted {} filters, {} bytes", evicted.len(), total);
+    Ok(())
+}
+
 fn handle_cache_build(args: &CacheBuildArgs, transaction: &Transaction) -> anyhow::Result<()> {
     let repo_path = normalize_repo_path(transaction.path());
 
@@ -184,7 +250,7 @@ fn handle_cache_build(args: &CacheBuildArgs, transaction: &Transaction) -> anyho
             let mut next_commits = Vec::new();
 
             for (branch_name, filtered_oid) in filtered {
-                if filtered_oid == git2::Oid::ZERO_SHA1 {
+                if filtered_oid.is_zero() {
                     continue;
                 }
                 let filtered_ref =
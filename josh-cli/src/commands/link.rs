@@ -85,8 +85,6 @@ fn handle_link_add(
     args: &LinkAddArgs,
     transaction: &josh_core::cache::Transaction,
 ) -> anyhow::Result<()> {
-    let repo = transaction.git2_repo();
-
     // Validate the path (should not be empty and should be a valid path)
     if args.path.is_empty() {
         return Err(anyhow!("Path cannot be empty"));
@@ -117,7 +115,7 @@ fn handle_link_add(
         .context("Failed to apply export filter")?;
 
     // If the export filter found no local content, fall back to fetching the remote.
-    let initial_oid = if export_oid != git2::Oid::ZERO_SHA1 {
+    let initial_oid = if !export_oid.is_zero() {
         eprintln!(
             "Using local content at '{}' ({})",
             normalized_path, export_oid
@@ -133,14 +131,9 @@ fn handle_link_add(
             .spawn_git(&["fetch", &args.url, target], &[])
             .context("Failed to execute git fetch")?;
 
-        // PORT: FETCH_HEAD pseudo-ref read stays on the git2 handle until flag day
-        // (gix multi-entry FETCH_HEAD semantics still unresolved).
-        let fetched_oid = repo
-            .find_reference("FETCH_HEAD")
-            .context("Failed to find FETCH_HEAD after fetch")?
-            .peel_to_commit()
-            .context("Failed to peel FETCH_HEAD to commit")?
-            .id();
+        let fetched_oid = transaction
+            .fetch_head()
+            .context("Failed to read FETCH_HEAD after fetch")?;
 
         eprintln!("Using fetched commit {}", fetched_oid);
         fetched_oid
@@ -187,8 +180,6 @@ fn handle_link_fetch(
     args: &LinkFetchArgs,
     transaction: &josh_core::cache::Transaction,
 ) -> anyhow::Result<()> {
-    let repo = transaction.git2_repo();
-
     let head_commit = transaction.head().context("Failed to get HEAD")?.commit;
 
     let commit_oid = if let Some(filter_str) = &args.filter {
@@ -217,16 +208,16 @@ fn handle_link_fetch(
         link_refs.len()
     );
 
-    let odb = repo.odb().context("Failed to open object database")?;
+    let odb = transaction.odb()?;
 
     let mut fetched = 0;
     let mut skipped = 0;
 
     for link_ref in &link_refs {
-        let oid = git2::Oid::from_str(&link_ref.commit)
+        let oid = josh_core::objects::parse_oid(&link_ref.commit)
             .with_context(|| format!("Invalid commit SHA in link file: {}", link_ref.commit))?;
 
-        if odb.exists(oid) {
+        if odb.contains(oid) {
             skipped += 1;
             continue;
         }
@@ -264,8 +255,6 @@ fn handle_link_update(
     args: &LinkUpdateArgs,
     transaction: &josh_core::cache::Transaction,
 ) -> anyhow::Result<()> {
-    let repo = transaction.git2_repo();
-
     let head_commit = transaction.head().context("Failed to get HEAD")?.commit;
     let head_tree = josh_core::objects::CommitData::read(&transaction.odb()?, head_commit)?
         .tree_id()
@@ -280,7 +269,7 @@ fn handle_link_update(
         );
         let filtered_oid = josh_core::filter_commit(transaction, roundtrip, head_commit)
             .context("Failed to apply filter")?;
-        if filtered_oid == git2::Oid::ZERO_SHA1 {
+        if filtered_oid.is_zero() {
             vec![]
         } else {
             let odb = transaction.odb()?;
@@ -320,14 +309,9 @@ fn handle_link_update(
             .spawn_git(&["fetch", &remote, &branch], &[])
             .with_context(|| format!("git fetch failed for '{}'", path.display()))?;
 
-        // PORT: FETCH_HEAD pseudo-ref read stays on the git2 handle until flag day
-        // (gix multi-entry FETCH_HEAD semantics still unresolved).
-        let new_oid = repo
-            .find_reference("FETCH_HEAD")
-            .context("Failed to find FETCH_HEAD")?
-            .peel_to_commit()
-            .context("Failed to get FETCH_HEAD commit")?
-            .id();
+        let new_oid = transaction
+            .fetch_head()
+            .context("Failed to read FETCH_HEAD")?;
 
         links_to_update.push((path.clone(), new_oid));
     }
@@ -397,7 +381,7 @@ fn handle_link_push(
     let exported_commit = josh_core::filter_commit(transaction, combined_filter, head_commit)
         .context("Failed to apply export filter")?;
 
-    if exported_commit == git2::Oid::ZERO_SHA1 {
+    if exported_commit.is_zero() {
         return Err(anyhow!("No content found at path '{}' to push", args.path));
     }
 
@@ -231,17 +231,21 @@ fn upstream_change_ids(
     tip: git2::Oid,
     base: git2::Oid,
 ) -> anyhow::Result<std::collections::HashSet<String>> {
-    let repo = transaction.git2_repo();
     let odb = transaction.odb()?;
     let mut ids = std::collections::HashSet::new();
-    let mut walk = repo.revwalk()?;
-    walk.push(tip)?;
-    if base != git2::Oid::ZERO_SHA1 {
-        walk.hide(base)?;
-    }
+    let commits = if base.is_zero() {
+        let mut walk = josh_core::objects::RevWalk::new(&odb);
+        walk.push(tip)?;
+        walk.into_topo_vec(|_| false)?
+    } else {
+        josh_core::objects::RangeWalk::new(&odb, |oid| {
+            josh_core::cache::compute_sequence_number(transaction, oid)
+        })
+        .into_topo_vec(tip, base)?
+    };
 
-    for oid in walk {
-        let commit = josh_core::objects::CommitData::read(&odb, oid?)?;
+    for oid in commits {
+        let commit = josh_core::objects::CommitData::read(&odb, oid)?;
         if let (Some(id), _) = commit_change_meta(&commit) {
             ids.insert(id);
         }
@@ -310,8 +314,6 @@ pub fn integrate(
     transaction: &josh_core::cache::Transaction,
     remote: &str,
 ) -> anyhow::Result<IntegrateReport> {
-    let repo = transaction.git2_repo();
-
     let head = transaction.head().context("Failed to resolve HEAD")?;
     let branch_ref = head
         .branch()
@@ -347,16 +349,11 @@ pub fn integrate(
     let (new_tip, report) = if merge_base == old {
         (new, IntegrateReport::FastForward { branch })
     } else {
-        // Diverged: collect the local-only commits (linear stack expected).
-        let mut walk = repo.revwalk()?;
-        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
-        walk.simplify_first_parent()?;
-        walk.push(old)?;
-        walk.hide(merge_base)?;
-
+        // Diverged: collect the local-only commits (linear stack expected), down the first
+        // parents to the merge base, which a stack without merges passes through.
         let mut local_commits = Vec::new();
-        for oid in walk {
-            let oid = oid?;
+        let mut next = Some(old);
+        while let Some(oid) = next.filter(|&oid| oid != merge_base) {
             let commit = josh_core::objects::CommitData::read(&transaction.odb()?, oid)?;
             if commit.parent_ids().count() > 1 {
                 anyhow::bail!(
@@ -365,7 +362,9 @@ pub fn integrate(
                 );
             }
             local_commits.push(oid);
+            next = commit.first_parent_id();
         }
+        local_commits.reverse();
 
         let applied = upstream_change_ids(transaction, new, merge_base)?;
 
@@ -405,6 +404,7 @@ pub fn integrate(
 
     // Update the worktree first (safe: refuses to clobber conflicting local
     // modifications), only then move the branch ref.
+    let repo = transaction.git2_repo();
     let new_tip_commit = repo.find_commit(new_tip)?;
     repo.checkout_tree(
         new_tip_commit.as_object(),
@@ -180,7 +180,7 @@ fn prepare_push(
     .context("Failed to unapply filter")?;
 
     let unfiltered_oid = if merge {
-        if original_target == git2::Oid::ZERO_SHA1 {
+        if original_target.is_zero() {
             return Err(anyhow!(
                 "--merge requires --base=<ref> or an existing destination ref"
             ));
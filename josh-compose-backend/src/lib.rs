@@ -19,11 +19,14 @@ pub enum NetworkPolicy {
     Host,
 }
 
+/// Writes a tar archive into the writer it is handed, so a backend can stream the archive to
+/// where it goes (a process's stdin, say) without it ever being held in memory as a whole.
+pub type WriteTar<'a> = &'a dyn Fn(&mut dyn std::io::Write) -> anyhow::Result<()>;
+
 /// Recipe for preparing an environment: a tar build context plus build arguments.
-#[derive(Debug, Clone)]
-pub struct EnvRecipe {
-    /// Build context as a tar archive.
-    pub context: Vec<u8>,
+pub struct EnvRecipe<'a> {
+    /// Writes the build context as a tar archive.
+    pub context: WriteTar<'a>,
     /// Build arguments, e.g. `[("ARCH", "amd64"), ("BASE", "josh_ws_image_..")]`.
     pub build_args: Vec<(String, String)>,
 }
@@ -97,7 +100,7 @@ pub trait EnvironmentBackend {
     fn env_exists(&self, key: &str) -> anyhow::Result<bool>;
     /// Prepare the environment for `key` from `recipe` (build it). Idempotent
     /// only insofar as the caller checks [`EnvironmentBackend::env_exists`] first.
-    fn prepare_env(&self, key: &str, recipe: EnvRecipe) -> anyhow::Result<()>;
+    fn prepare_env(&self, key: &str, recipe: EnvRecipe<'_>) -> anyhow::Result<()>;
     /// List environment keys whose tag starts with `prefix`.
     fn list_envs(&self, prefix: &str) -> anyhow::Result<Vec<String>>;
     /// Remove a prepared environment.
@@ -118,10 +121,10 @@ pub trait ArtifactBackend {
     fn remove_artifact(&self, name: &str, force: bool) -> anyhow::Result<()>;
     /// List artifacts whose name starts with `prefix`.
     fn list_artifacts(&self, prefix: &str) -> anyhow::Result<Vec<String>>;
-    /// Create a uniquely-named ephemeral artifact seeded with `tar` and return its
-    /// opaque name. The caller mounts it and removes it when done. The backend
-    /// fixes ownership for the invoking user as needed.
-    fn create_scratch_artifact(&self, tar: &[u8]) -> anyhow::Result<String>;
+    /// Create a uniquely-named ephemeral artifact seeded with the archive `tar` writes
+    /// and return its opaque name. The caller mounts it and removes it when done. The
+    /// backend fixes ownership for the invoking user as needed.
+    fn create_scratch_artifact(&self, tar: WriteTar<'_>) -> anyhow::Result<String>;
 
     /// Ensure an artifact exists, creating it if missing.
     fn ensure_artifact(&self, name: &str) -> anyhow::Result<()> {
@@ -1,9 +1,8 @@
 use anyhow::Context;
-use std::io::Write;
 use std::process::{Command, Stdio};
 
 use super::{PodmanRuntime, align_artifact};
-use josh_compose_backend::ArtifactBackend;
+use josh_compose_backend::{ArtifactBackend, WriteTar};
 
 fn artifact_exists(name: &str) -> anyhow::Result<bool> {
     let status = Command::new("podman")
@@ -28,6 +27,10 @@ fn create_artifact(name: &str) -> anyhow::Result<()> {
 }
 
 fn import_artifact(name: &str, tar: &[u8]) -> anyhow::Result<()> {
+    import_artifact_from(name, &|out| Ok(out.write_all(tar)?))
+}
+
+fn import_artifact_from(name: &str, tar: WriteTar<'_>) -> anyhow::Result<()> {
     let mut child = Command::new("podman")
         .args(["volume", "import", name, "-"])
         .stdin(Stdio::piped())
@@ -36,11 +39,7 @@ fn import_artifact(name: &str, tar: &[u8]) -> anyhow::Result<()> {
         .spawn()
         .context("failed to spawn podman volume import")?;
 
-    child
-        .stdin
-        .take()
-        .unwrap()
-        .write_all(tar)
+    tar(&mut child.stdin.take().unwrap())
         .context("failed to write tar data to podman volume import")?;
 
     let output = child
@@ -103,11 +102,11 @@ fn extract_artifact(name: &str, dest: &std::path::Path) -> anyhow::Result<()> {
         .map_err(|e| anyhow::anyhow!("failed to extract artifact {name}: {e}"))
 }
 
-fn create_scratch_artifact(tar: &[u8]) -> anyhow::Result<String> {
+fn create_scratch_artifact(tar: WriteTar<'_>) -> anyhow::Result<String> {
     let bytes: [u8; 4] = rand::random();
     let name = format!("josh-scratch-{}", hex::encode(bytes));
     create_artifact(&name)?;
-    if let Err(error) = import_artifact(&name, tar).and_then(|_| align_artifact(&name)) {
+    if let Err(error) = import_artifact_from(&name, tar).and_then(|_| align_artifact(&name)) {
         if let Err(cleanup_error) = remove_artifact(&name, true) {
             return Err(error.context(format!(
                 "failed to remove scratch artifact {name} after initialization failed: \
@@ -165,7 +164,7 @@ impl ArtifactBackend for PodmanRuntime {
         list_artifacts(prefix)
     }
 
-    fn create_scratch_artifact(&self, tar: &[u8]) -> anyhow::Result<String> {
+    fn create_scratch_artifact(&self, tar: WriteTar<'_>) -> anyhow::Result<String> {
         create_scratch_artifact(tar)
     }
 
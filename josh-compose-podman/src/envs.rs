@@ -1,5 +1,4 @@
 use anyhow::Context;
-use std::io::Write;
 use std::process::{Command, Stdio};
 
 use super::{PodmanRuntime, host_uid_gid};
@@ -15,7 +14,7 @@ fn env_exists(key: &str) -> anyhow::Result<bool> {
     Ok(status.success())
 }
 
-fn prepare_env(key: &str, recipe: EnvRecipe) -> anyhow::Result<()> {
+fn prepare_env(key: &str, recipe: EnvRecipe<'_>) -> anyhow::Result<()> {
     // Standard build args a Containerfile expects: the target architecture
     // (Go-style naming) and the host UID/GID so images can match the invoking
     // user. These are container-build concerns, so the backend owns them —
@@ -41,9 +40,7 @@ fn prepare_env(key: &str, recipe: EnvRecipe) -> anyhow::Result<()> {
 
     let mut child = cmd.spawn().context("failed to spawn podman build")?;
     if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(&recipe.context)
-            .context("failed to write tar to podman build stdin")?;
+        (recipe.context)(&mut stdin).context("failed to write tar to podman build stdin")?;
     }
     let status = child.wait().context("failed to wait for podman build")?;
     if !status.success() {
@@ -88,7 +85,7 @@ impl EnvironmentBackend for PodmanRuntime {
         env_exists(key)
     }
 
-    fn prepare_env(&self, key: &str, recipe: EnvRecipe) -> anyhow::Result<()> {
+    fn prepare_env(&self, key: &str, recipe: EnvRecipe<'_>) -> anyhow::Result<()> {
         prepare_env(key, recipe)
     }
 
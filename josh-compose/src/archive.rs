@@ -2,6 +2,10 @@ use anyhow::Context;
 use josh_core::cache;
 use josh_core::filter::tree;
 use josh_core::memodb;
+use miniz_oxide::deflate::core::{CompressorOxide, create_comp_flags_from_zip_params};
+use miniz_oxide::deflate::stream::deflate;
+use miniz_oxide::{MZFlush, MZStatus};
+use std::io::{Read, Write};
 
 /// Produce a tar archive (as bytes) from a git tree.
 pub fn tree_to_tar(
@@ -10,14 +14,24 @@ pub fn tree_to_tar(
     tree_oid: git2::Oid,
 ) -> anyhow::Result<Vec<u8>> {
     let mut buf = Vec::new();
-    {
-        let mut builder = tar::Builder::new(&mut buf);
-        append_tree(transaction, odb, tree_oid, "", &mut builder)?;
-        builder.finish()?;
-    }
+    write_tar(transaction, odb, tree_oid, &mut buf)?;
     Ok(buf)
 }
 
+/// Write a tar archive of a git tree to `out`. Blobs are streamed from the object database,
+/// so a big one is never held in memory as a whole on the way.
+pub fn write_tar(
+    transaction: &cache::Transaction,
+    odb: &memodb::Odb,
+    tree_oid: git2::Oid,
+    out: impl std::io::Write,
+) -> anyhow::Result<()> {
+    let mut builder = tar::Builder::new(out);
+    append_tree(transaction, odb, tree_oid, "", &mut builder)?;
+    builder.finish()?;
+    Ok(())
+}
+
 fn append_tree(
     transaction: &cache::Transaction,
     odb: &memodb::Odb,
@@ -58,8 +72,9 @@ fn append_tree(
 
             append_tree(transaction, odb, id, &path, builder)?;
         } else if entry.mode.is_blob() {
-            let content =
-                tree::blob_bytes(odb, id).with_context(|| format!("blob not found: {path}"))?;
+            let (_, size, content) = odb
+                .read_stream(id)
+                .with_context(|| format!("blob not found: {path}"))?;
             let mut header = tar::Header::new_gnu();
             header.set_entry_type(tar::EntryType::Regular);
             header.set_path(&path)?;
@@ -68,11 +83,298 @@ fn append_tree(
             } else {
                 0o644
             });
-            header.set_size(content.len() as u64);
+            header.set_size(size);
             header.set_cksum();
-            builder.append(&header, &content[..])?;
+            builder.append(&header, content)?;
         }
         // Skip other types (submodules etc.)
     }
     Ok(())
 }
+
+/// Write a gzip-compressed tar archive of a git tree to `out`. The gzip member (RFC 1952) has
+/// no file name or modification time, so the same tree always compresses to the same bytes.
+pub fn write_tar_gz(
+    transaction: &cache::Transaction,
+    odb: &memodb::Odb,
+    tree_oid: git2::Oid,
+    mut out: impl Write,
+) -> anyhow::Result<()> {
+    out.write_all(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255])?;
+    let mut deflater = Deflater::new(out);
+    write_tar(transaction, odb, tree_oid, &mut deflater)?;
+    let (mut out, crc, size) = deflater.finish()?;
+    out.write_all(&crc.to_le_bytes())?;
+    // The trailer holds the size modulo 2^32.
+    out.write_all(&(size as u32).to_le_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Write a zip archive of a git tree to `out`. Entries carry their unix mode, symlinks
+/// included, and no timestamp. Blobs are streamed from the object database, their checksum
+/// and sizes follow the data in a data descriptor.
+pub fn write_zip(
+    transaction: &cache::Transaction,
+    odb: &memodb::Odb,
+    tree_oid: git2::Oid,
+    out: impl Write,
+) -> anyhow::Result<()> {
+    let mut zip = Zip::new(out);
+    append_zip_tree(transaction, odb, tree_oid, "", &mut zip)?;
+    zip.finish()
+}
+
+fn append_zip_tree(
+    transaction: &cache::Transaction,
+    odb: &memodb::Odb,
+    tree_oid: git2::Oid,
+    prefix: &str,
+    zip: &mut Zip<impl Write>,
+) -> anyhow::Result<()> {
+    let reader = tree::read_tree(transaction, odb, tree_oid)?;
+    for entry in reader.entries() {
+        let name = String::from_utf8_lossy(entry.filename);
+        let path = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{prefix}/{name}")
+        };
+        let id = josh_core::objects::git2_oid(entry.oid);
+
+        if entry.mode.is_tree() {
+            zip.add_dir(&format!("{path}/"))?;
+            append_zip_tree(transaction, odb, id, &path, zip)?;
+        } else if entry.mode.is_link() || entry.mode.is_blob() {
+            let (_, _, content) = odb
+                .read_stream(id)
+                .with_context(|| format!("blob not found: {path}"))?;
+            let mode = if entry.mode.is_link() {
+                0o120777
+            } else if entry.mode.is_executable() {
+                0o100755
+            } else {
+                0o100644
+            };
+            zip.add_file(&path, mode, content)?;
+        }
+        // Skip other types (submodules etc.)
+    }
+    Ok(())
+}
+
+/// UTF-8 names.
+const ZIP_UTF8: u16 = 0x0800;
+/// Checksum and sizes follow the data.
+const ZIP_DATA_DESCRIPTOR: u16 = 0x0008;
+
+/// A zip archive being written: the entries go to `out` as they come, their central directory
+/// records are kept for the end. Without zip64 extensions, so archives are limited to 4 GiB
+/// and 65535 entries.
+struct Zip<W> {
+    out: CountingWriter<W>,
+    central: Vec<u8>,
+    count: u16,
+}
+
+struct ZipEntry<'a> {
+    name: &'a str,
+    mode: u32,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed: u32,
+    size: u32,
+}
+
+impl ZipEntry<'_> {
+    /// The fields the local header and the central directory record share: version 2.0,
+    /// flags, method, 1980-01-01 00:00, checksum, sizes and name length.
+    fn write_common(&self, out: &mut Vec<u8>) {
+        out.extend(20u16.to_le_bytes());
+        out.extend(self.flags.to_le_bytes());
+        out.extend(self.method.to_le_bytes());
+        out.extend(0u16.to_le_bytes());
+        out.extend(0x0021u16.to_le_bytes());
+        out.extend(self.crc.to_le_bytes());
+        out.extend(self.compressed.to_le_bytes());
+        out.extend(self.size.to_le_bytes());
+        out.extend((self.name.len() as u16).to_le_bytes());
+        out.extend(0u16.to_le_bytes());
+    }
+
+    fn local_header(&self) -> Vec<u8> {
+        let mut out = 0x04034b50u32.to_le_bytes().to_vec();
+        self.write_common(&mut out);
+        out.extend(self.name.as_bytes());
+        out
+    }
+}
+
+impl<W: Write> Zip<W> {
+    fn new(out: W) -> Self {
+        Zip {
+            out: CountingWriter {
+                inner: out,
+                count: 0,
+            },
+            central: Vec::new(),
+            count: 0,
+        }
+    }
+
+    fn offset(&self) -> anyhow::Result<u32> {
+        u32::try_from(self.out.count).context("zip archive too large")
+    }
+
+    fn add_dir(&mut self, name: &str) -> anyhow::Result<()> {
+        let entry = ZipEntry {
+            name,
+            mode: 0o040755,
+            flags: ZIP_UTF8,
+            method: 0,
+            crc: 0,
+            compressed: 0,
+            size: 0,
+        };
+        let offset = self.offset()?;
+        self.out.write_all(&entry.local_header())?;
+        self.push_central(&entry, offset)
+    }
+
+    fn add_file(&mut self, name: &str, mode: u32, mut content: impl Read) -> anyhow::Result<()> {
+        let mut entry = ZipEntry {
+            name,
+            mode,
+            flags: ZIP_UTF8 | ZIP_DATA_DESCRIPTOR,
+            method: 8,
+            crc: 0,
+            compressed: 0,
+            size: 0,
+        };
+        let offset = self.offset()?;
+        self.out.write_all(&entry.local_header())?;
+
+        let start = self.out.count;
+        let mut deflater = Deflater::new(&mut self.out);
+        std::io::copy(&mut content, &mut deflater)?;
+        let (_, crc, size) = deflater.finish()?;
+        entry.crc = crc;
+        entry.compressed = u32::try_from(self.out.count - start).context("zip entry too large")?;
+        entry.size = u32::try_from(size).context("zip entry too large")?;
+
+        let mut descriptor = 0x08074b50u32.to_le_bytes().to_vec();
+        descriptor.extend(entry.crc.to_le_bytes());
+        descriptor.extend(entry.compressed.to_le_bytes());
+        descriptor.extend(entry.size.to_le_bytes());
+        self.out.write_all(&descriptor)?;
+        self.push_central(&entry, offset)
+    }
+
+    fn push_central(&mut self, entry: &ZipEntry, offset: u32) -> anyhow::Result<()> {
+        self.count = self.count.checked_add(1).context("too many zip entries")?;
+        self.central.extend(0x02014b50u32.to_le_bytes());
+        // Made by unix, so that the external attributes hold the mode.
+        self.central.extend((3u16 << 8 | 20).to_le_bytes());
+        entry.write_common(&mut self.central);
+        self.central.extend([0; 6]);
+        self.central.extend((entry.mode << 16).to_le_bytes());
+        self.central.extend(offset.to_le_bytes());
+        self.central.extend(entry.name.as_bytes());
+        Ok(())
+    }
+
+    fn finish(mut self) -> anyhow::Result<()> {
+        let offset = self.offset()?;
+        let mut end = std::mem::take(&mut self.central);
+        let central_size = end.len() as u32;
+        end.extend(0x06054b50u32.to_le_bytes());
+        end.extend([0; 4]);
+        end.extend(self.count.to_le_bytes());
+        end.extend(self.count.to_le_bytes());
+        end.extend(central_size.to_le_bytes());
+        end.extend(offset.to_le_bytes());
+        end.extend(0u16.to_le_bytes());
+        self.out.write_all(&end)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Passes writes through, counting the bytes.
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Compresses what is written to it into a raw deflate stream (RFC 1951) on `out`, keeping the
+/// checksum and size of the input for the gzip trailer or the zip data descriptor.
+struct Deflater<W> {
+    out: W,
+    compressor: Box<CompressorOxide>,
+    buf: Vec<u8>,
+    crc: crc32fast::Hasher,
+    size: u64,
+}
+
+impl<W: Write> Deflater<W> {
+    fn new(out: W) -> Self {
+        // A negative window size leaves out the zlib header and trailer.
+        let flags = create_comp_flags_from_zip_params(6, -15, 0);
+        Deflater {
+            out,
+            compressor: Box::new(CompressorOxide::new(flags)),
+            buf: vec![0; 64 * 1024],
+            crc: crc32fast::Hasher::new(),
+            size: 0,
+        }
+    }
+
+    /// End the stream, returning `out` along with the checksum and size of the input.
+    fn finish(mut self) -> std::io::Result<(W, u32, u64)> {
+        loop {
+            let result = deflate(&mut self.compressor, &[], &mut self.buf, MZFlush::Finish);
+            let status = result
+                .status
+                .map_err(|e| std::io::Error::other(format!("deflate failed: {e:?}")))?;
+            self.out.write_all(&self.buf[..result.bytes_written])?;
+            if status == MZStatus::StreamEnd {
+                break;
+            }
+        }
+        Ok((self.out, self.crc.finalize(), self.size))
+    }
+}
+
+impl<W: Write> Write for Deflater<W> {
+    fn write(&mut self, input: &[u8]) -> std::io::Result<usize> {
+        let mut rest = input;
+        while !rest.is_empty() {
+            let result = deflate(&mut self.compressor, rest, &mut self.buf, MZFlush::None);
+            result
+                .status
+                .map_err(|e| std::io::Error::other(format!("deflate failed: {e:?}")))?;
+            self.out.write_all(&self.buf[..result.bytes_written])?;
+            rest = &rest[result.bytes_consumed..];
+        }
+        self.crc.update(input);
+        self.size += input.len() as u64;
+        Ok(input.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
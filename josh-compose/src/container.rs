@@ -214,8 +214,9 @@ pub fn run_container(
 
     // Create an ephemeral scratch artifact seeded with the worktree contents. The
     // runtime owns its naming and ownership; we just hold the opaque name.
-    let worktree_tar = crate::archive::tree_to_tar(transaction, odb, worktree_oid)?;
-    let snapshot_vol = runtime.create_scratch_artifact(&worktree_tar)?;
+    let snapshot_vol = runtime.create_scratch_artifact(&|out| {
+        crate::archive::write_tar(transaction, odb, worktree_oid, out)
+    })?;
 
     let snapshot_vol_clone = snapshot_vol.clone();
     let _cleanup = defer::defer(move || {
@@ -47,12 +47,10 @@ pub fn ensure_image(
         .map(|e| objects::git2_oid(e.oid))
         .context("workspace image tree missing 'context' subtree")?;
 
-    let context = crate::archive::tree_to_tar(transaction, odb, context_entry)?;
-
     runtime.prepare_env(
         &image_name,
         EnvRecipe {
-            context,
+            context: &|out| crate::archive::write_tar(transaction, odb, context_entry, out),
             build_args,
         },
     )?;
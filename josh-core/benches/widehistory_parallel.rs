@@ -0,0 +1,203 @@
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use josh_core::filter::Filter;
+use josh_core::git::josh_commit_signature;
+use rand::prelude::*;
+use std::path::PathBuf;
+
+// The scaling parameter of this benchmark is the number of threads a history walk may use (see
+// `TransactionContext::with_threads`). The history is *wide*: BRANCHES branches off a common root,
+// merged into the mainline one after the other, so the walk splits into independent segments that
+// can be filtered concurrently. A linear history -- as in the `deephistory_*` benches -- has a
+// single segment and gains nothing from more threads.
+const THREADS: &[usize] = &[1, 2, 4, 8];
+
+// Shape of the history: BRANCHES branches of BRANCH_LEN commits each. Kept small in debug builds
+// so `cargo test`/`--test` runs stay fast, but above the walk's threshold for going parallel.
+const BRANCHES: usize = 8;
+const BRANCH_LEN: usize = if cfg!(debug_assertions) { 150 } else { 2_000 };
+
+// A fixed, modest tree shared as the root of every branch: TREE_FILES files spread evenly across
+// N_DIRS top-level directories.
+const TREE_FILES: usize = 200;
+const N_DIRS: usize = 10;
+
+// The directory the benchmarked filter selects; churn keeps touching it.
+const SUBDIR: &str = "dir_00";
+
+const CHURN_FRACTION: f64 = 0.1;
+const CHURN_CONTENT_LEN: usize = 10;
+
+/// Expected oid of the cached bench repo's head: the cache validity key (see `provision_repo`).
+/// Debug and release builds generate different history lengths, hence two values.
+const EXPECTED_HEAD: &str = if cfg!(debug_assertions) {
+    "43baa4af0665e0561b9c84fa06a13b125ca35b11"
+} else {
+    "5e6d81befd75848d9bb0e097d04f964bbb9b27e1"
+};
+
+/// Fixed commit timestamp so the built history, and with it `EXPECTED_HEAD`, is reproducible.
+const JOSH_BENCH_COMMIT_TIME: &str = "1700000000";
+
+struct WideBench {
+    // Keeps the on-disk repository (and its tempdir) alive for the duration of the benchmark.
+    repo: josh_test_support::provision_repo::ProvisionedRepo,
+    cache: std::sync::Arc<josh_core::cache::CacheStack>,
+    filter: Filter,
+}
+
+impl WideBench {
+    fn setup() -> anyhow::Result<Self> {
+        let _setup = tracing::info_span!(target: "bench", "setup").entered();
+
+        // SAFETY: setup runs single-threaded, before any benchmark iteration.
+        unsafe {
+            std::env::set_var("JOSH_COMMIT_TIME", JOSH_BENCH_COMMIT_TIME);
+        }
+
+        let provisioned = josh_test_support::provision_repo::provision_repo(
+            "widehistory_parallel",
+            &git2::Oid::from_str(EXPECTED_HEAD).expect("EXPECTED_HEAD must be a valid oid"),
+            build_history,
+        )?;
+
+        // Pin the sled db open across iterations, like the other history benches.
+        let sled = josh_core::cache::SledCacheBackend::new(provisioned.path());
+        sled.pin()?;
+        let cache = std::sync::Arc::new(josh_core::cache::CacheStack::new().with_backend(sled));
+        let filter = Filter::new().subdir(SUBDIR);
+
+        // Correctness gate (untimed): the parallel walk must produce exactly the serial walk's
+        // commit, or the bench would compare different work.
+        let filtered = |threads| -> anyhow::Result<git2::Oid> {
+            josh_core::reset_caches()?;
+            let transaction =
+                josh_core::cache::TransactionContext::new(provisioned.path(), cache.clone())
+                    .with_threads(threads)
+                    .open()?;
+            josh_core::filter_commit(&transaction, filter, provisioned.head)
+        };
+        let serial = filtered(1)?;
+        let parallel = filtered(*THREADS.last().unwrap())?;
+        anyhow::ensure!(
+            serial == parallel,
+            "parallel walk produced {parallel}, serial walk {serial}"
+        );
+        josh_core::reset_caches()?;
+
+        Ok(Self {
+            repo: provisioned,
+            cache,
+            filter,
+        })
+    }
+}
+
+fn random_string(rng: &mut StdRng, len: usize) -> String {
+    (0..len)
+        .map(|_| {
+            use rand::distr::Alphabetic;
+            let ch = Alphabetic.sample(rng) as char;
+            ch.to_ascii_lowercase()
+        })
+        .collect()
+}
+
+/// Build the root commit, BRANCHES branches of BRANCH_LEN commits off it, and merge them into the
+/// mainline one after the other. Returns the last merge, tagged `refs/heads/main` so the history
+/// stays reachable through `git prune`.
+fn build_history(repo: &git2::Repository) -> anyhow::Result<git2::Oid> {
+    use rand::RngExt;
+
+    let mut builder = git2::build::TreeUpdateBuilder::new();
+    let mut all_paths = vec![];
+    for i in 0..TREE_FILES {
+        let path = PathBuf::from(format!("dir_{:02}", i % N_DIRS)).join(format!("file_{i:04}"));
+        let oid = repo.blob(path.to_string_lossy().as_bytes())?;
+        builder.upsert(&path, oid, git2::FileMode::Blob);
+        all_paths.push(path);
+    }
+    let baseline = repo.find_tree(repo.treebuilder(None)?.write()?)?;
+    let root_tree = repo.find_tree(builder.create_updated(repo, &baseline)?)?;
+
+    let sig = josh_commit_signature()?;
+    let root = repo.commit(None, &sig, &sig, "content", &root_tree, &[])?;
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut head = root;
+    for branch in 0..BRANCHES {
+        let mut tip = root;
+        for i in 0..BRANCH_LEN {
+            let parent = repo.find_commit(tip)?;
+            let mut builder = git2::build::TreeUpdateBuilder::new();
+            let churned = all_paths
+                .iter()
+                .filter(|_| rng.random_bool(CHURN_FRACTION))
+                .cloned()
+                .collect::<Vec<_>>();
+            for path in &churned {
+                let content = random_string(&mut rng, CHURN_CONTENT_LEN);
+                builder.upsert(path, repo.blob(content.as_bytes())?, git2::FileMode::Blob);
+            }
+            let tree = repo.find_tree(builder.create_updated(repo, &parent.tree()?)?)?;
+            tip = repo.commit(
+                None,
+                &sig,
+                &sig,
+                &format!("branch {branch} commit {i}"),
+                &tree,
+                &[&parent],
+            )?;
+        }
+
+        // The merge takes the branch's tree: which one does not matter to the walk.
+        let mainline = repo.find_commit(head)?;
+        let branch_tip = repo.find_commit(tip)?;
+        head = repo.commit(
+            None,
+            &sig,
+            &sig,
+            &format!("merge branch {branch}"),
+            &branch_tip.tree()?,
+            &[&mainline, &branch_tip],
+        )?;
+    }
+
+    repo.reference("refs/heads/main", head, true, "bench head")?;
+    Ok(head)
+}
+
+fn widehistory_parallel(c: &mut Criterion) {
+    josh_test_support::init_tracing("bench=trace");
+
+    let bench = WideBench::setup().expect("set up benchmark");
+
+    let mut group = c.benchmark_group("widehistory_parallel");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements((BRANCHES * BRANCH_LEN) as u64));
+    for &threads in THREADS {
+        let context =
+            josh_core::cache::TransactionContext::new(bench.repo.path(), bench.cache.clone())
+                .with_threads(threads);
+        group.bench_function(BenchmarkId::from_parameter(threads), |b| {
+            b.iter_batched(
+                // Per-iteration setup (untimed): cold caches and a fresh transaction.
+                || {
+                    josh_core::reset_caches().expect("reset caches");
+                    let transaction = context.open().expect("open transaction");
+                    let iter_span = tracing::info_span!(target: "bench", "iter").entered();
+                    (transaction, iter_span)
+                },
+                |(transaction, iter_span)| {
+                    josh_core::filter_commit(&transaction, bench.filter, bench.repo.head)
+                        .expect("filter commit");
+                    (transaction, iter_span)
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, widehistory_parallel);
+criterion_main!(benches);
@@ -1,5 +1,6 @@
 use super::CACHE_VERSION;
 use super::backend::{CacheBackend, HistoryGraphHint};
+use super::refs::{RefStore, object_target};
 use crate::filter;
 use crate::filter::Filter;
 use crate::objects;
@@ -13,6 +14,7 @@ const FLUSH_AFTER: usize = 1000;
 pub struct DistributedCacheBackend {
     new_entries: std::sync::Mutex<HashMap<(Filter, u64), HashMap<git2::Oid, git2::Oid>>>,
     repo: std::sync::Mutex<git2::Repository>,
+    refs: RefStore,
     // Whether this backend accepts writes. The default ([`Self::new`]) is read-only: regular
     // sessions consume the fetched cache but should not each grow the shard chains with a
     // commit, pack and ref update for the few entries they produce -- the local sled cache
@@ -56,8 +58,13 @@ impl DistributedCacheBackend {
 
     fn open(repo_path: impl AsRef<std::path::Path>, writable: bool) -> anyhow::Result<Self> {
         let repo = git2::Repository::open(repo_path.as_ref())?;
-        let mem_odb = josh_memodb::MemOdb::new(None, josh_memodb::objects_dir(&repo));
+        let mem_odb = josh_memodb::MemOdb::new(
+            None,
+            josh_memodb::objects_dir(&repo),
+            josh_memodb::object_hash(&repo),
+        );
         Ok(Self {
+            refs: RefStore::open(&repo),
             repo: std::sync::Mutex::new(repo),
             mem_odb,
             writable,
@@ -101,9 +108,8 @@ impl DistributedCacheBackend {
             // batches.
             let base = if let Some(oid) = pending.get(&rp) {
                 Some(*oid)
-            } else if let Ok(r) = repo.revparse_single(&rp) {
-                // PORT: resolves a ref -- stays on git2 until flag day.
-                Some(r.peel_to_commit()?.id())
+            } else if let Some(oid) = self.refs.resolve(&rp)? {
+                Some(objects::peel_to_commit(&odb, oid)?)
             } else {
                 None
             };
@@ -116,7 +122,7 @@ impl DistributedCacheBackend {
                 }
                 None => gix_object::Tree::default(),
             };
-            let mut editor = gix_object::tree::Editor::new(root, &odb, gix_hash::Kind::Sha1);
+            let mut editor = gix_object::tree::Editor::new(root, &odb, odb.object_hash());
 
             // Each entry is a gitlink: the tree entry stores the target oid directly, and git
             // never requires a gitlink target to be present, so no blob objects are needed and
@@ -125,7 +131,7 @@ impl DistributedCacheBackend {
             // in tree entries -- so it is encoded as a blob entry pointing at the empty blob;
             // the entry mode disambiguates on read.
             for (from, to) in &mut *m {
-                let (kind, target) = if *to == git2::Oid::ZERO_SHA1 {
+                let (kind, target) = if to.is_zero() {
                     (
                         gix_object::tree::EntryKind::Blob,
                         objects::write_blob(&odb, &[])?,
@@ -175,7 +181,12 @@ impl DistributedCacheBackend {
         self.mem_odb.flush()?;
 
         for (rp, commit) in pending.drain() {
-            repo.reference(&rp, commit, true, "cache")?;
+            self.refs.update(
+                &rp,
+                gix_ref::transaction::PreviousValue::Any,
+                object_target(commit),
+                "cache",
+            )?;
         }
 
         Ok(())
@@ -249,9 +260,8 @@ impl CacheBackend for DistributedCacheBackend {
         let pending = self.pending_refs.lock().unwrap();
         let tree = if let Some(oid) = pending.get(&rp) {
             objects::CommitData::read(&odb, *oid)?.tree_id()?
-        } else if let Ok(r) = repo.revparse_single(&rp) {
-            // PORT: resolves a ref -- stays on git2 until flag day.
-            r.peel_to_tree()?.id()
+        } else if let Some(oid) = self.refs.resolve(&rp)? {
+            objects::CommitData::read(&odb, objects::peel_to_commit(&odb, oid)?)?.tree_id()?
         } else {
             return Ok(None);
         };
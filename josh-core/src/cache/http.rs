@@ -0,0 +1,253 @@
+use super::CACHE_VERSION;
+use super::backend::{CacheBackend, HistoryGraphHint};
+use crate::filter;
+use crate::filter::Filter;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Upload a shard once it gained this many new entries; everything else goes up when the
+// transaction ends.
+const FLUSH_AFTER: usize = 1000;
+
+type Shard = HashMap<git2::Oid, git2::Oid>;
+
+/// Cache backend talking to a `josh-cache-server` over HTTP, so that several machines share one
+/// cache live instead of through `josh cache push/fetch` round trips.
+///
+/// Records are exchanged per shard -- all records of one filter within one range of
+/// [`HistoryGraphHint::sequence_number`]s -- as text lines of `<from> <to>`:
+///
+/// * `GET <url>/v<CACHE_VERSION>/<filter id>/<shard>` returns the shard (404: empty),
+/// * `POST` to the same path appends the lines in the body; it takes the server's upload token
+///   as a bearer token.
+///
+/// A shard is downloaded once per backend and then served from memory, and writes are
+/// buffered and uploaded in batches. Like the distributed cache this one is sparse: only sample
+/// points are stored (see [`HistoryGraphHint::is_sample_point`]), relying on a dense local cache
+/// in front of it.
+///
+/// The server is an accelerator, never a dependency: when a request fails, the backend logs it
+/// and turns itself off for the rest of the process, so filtering goes on without it.
+pub struct HttpCacheBackend {
+    url: String,
+    client: reqwest::blocking::Client,
+    // The upload token when this backend uploads records; see [`Self::writable`].
+    token: Option<String>,
+    offline: AtomicBool,
+    // Shards downloaded so far, including the records written since.
+    shards: std::sync::Mutex<HashMap<(Filter, u64), Shard>>,
+    // Records written but not yet uploaded.
+    new_entries: std::sync::Mutex<HashMap<(Filter, u64), Shard>>,
+}
+
+impl Drop for HttpCacheBackend {
+    fn drop(&mut self) {
+        if self.flush(true).is_err() {
+            log::warn!("HttpCacheBackend: flush failed");
+        }
+    }
+}
+
+impl HttpCacheBackend {
+    /// Open the backend read-only: records are fetched from the server at `url`, but writes
+    /// are ignored.
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        Self::open(url, None)
+    }
+
+    /// Open the backend for reading and writing: new records are uploaded with the server's
+    /// upload `token` once a shard gained [`FLUSH_AFTER`] of them, and when a transaction ends.
+    pub fn writable(url: &str, token: &str) -> anyhow::Result<Self> {
+        Self::open(url, Some(token.to_string()))
+    }
+
+    fn open(url: &str, token: Option<String>) -> anyhow::Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()?;
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            client,
+            token,
+            offline: AtomicBool::new(false),
+            shards: Default::default(),
+            new_entries: Default::default(),
+        })
+    }
+
+    fn shard_url(&self, filter: Filter, shard: u64) -> String {
+        format!("{}/v{}/{}/{}", self.url, CACHE_VERSION, filter.id(), shard)
+    }
+
+    /// Log a failed request and stop using the server.
+    fn go_offline(&self, e: anyhow::Error) {
+        if !self.offline.swap(true, Ordering::Relaxed) {
+            log::warn!(
+                "HttpCacheBackend: {}: {:#}; continuing without it",
+                self.url,
+                e
+            );
+        }
+    }
+
+    fn download(&self, filter: Filter, shard: u64) -> anyhow::Result<Shard> {
+        let response = self.client.get(self.shard_url(filter, shard)).send()?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Default::default());
+        }
+        parse_records(&response.error_for_status()?.text()?)
+    }
+
+    /// Upload buffered records: with `force` all of them, otherwise only shards that reached
+    /// [`FLUSH_AFTER`]. The buffer is only locked to take the records out, not during uploads.
+    pub fn flush(&self, force: bool) -> anyhow::Result<()> {
+        let batches: Vec<_> = self
+            .new_entries
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter(|(_, records)| !records.is_empty() && (force || records.len() >= FLUSH_AFTER))
+            .map(|(key, records)| (*key, std::mem::take(records)))
+            .collect();
+        let Some(token) = &self.token else {
+            return Ok(());
+        };
+        for ((filter, shard), records) in batches {
+            if self.offline.load(Ordering::Relaxed) {
+                break;
+            }
+            let result = self
+                .client
+                .post(self.shard_url(filter, shard))
+                .bearer_auth(token)
+                .body(format_records(&records))
+                .send()
+                .and_then(|r| r.error_for_status());
+            match result {
+                Ok(_) => log::info!("CACHE upload {} {}", records.len(), filter::spec(filter)),
+                Err(e) => self.go_offline(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse the `<from> <to>` lines of a shard; later lines win.
+pub fn parse_records(text: &str) -> anyhow::Result<Shard> {
+    let mut records = Shard::new();
+    for line in text.lines().filter(|line| !line.is_empty()) {
+        let (from, to) = line
+            .split_once(' ')
+            .ok_or_else(|| anyhow::anyhow!("invalid cache record: {:?}", line))?;
+        records.insert(
+            crate::objects::parse_oid(from)?,
+            crate::objects::parse_oid(to)?,
+        );
+    }
+    Ok(records)
+}
+
+pub fn format_records(records: &Shard) -> String {
+    records
+        .iter()
+        .map(|(from, to)| format!("{} {}\n", from, to))
+        .collect()
+}
+
+impl CacheBackend for HttpCacheBackend {
+    fn read(
+        &self,
+        filter: Filter,
+        from: git2::Oid,
+        hint: HistoryGraphHint,
+        tree_keyed: bool,
+    ) -> anyhow::Result<Option<git2::Oid>> {
+        if filter == filter::sequence_number() || filter == filter::reachable_roots() {
+            return Ok(None);
+        }
+        // Same eligibility rules as the distributed backend, which this one mirrors.
+        if !tree_keyed && !hint.is_sample_point() {
+            return Ok(None);
+        }
+        if self.offline.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+
+        let key = (filter, hint.sequence_number / 10000);
+        if let Some(records) = self.shards.lock().unwrap().get(&key) {
+            return Ok(records.get(&from).copied());
+        }
+        // Download without holding the lock, so other threads are not held up by a slow server;
+        // if two threads race here, the first download to finish is kept.
+        let mut downloaded = match self.download(filter, key.1) {
+            Ok(records) => records,
+            Err(e) => {
+                self.go_offline(e);
+                return Ok(None);
+            }
+        };
+        if let Some(pending) = self.new_entries.lock().unwrap().get(&key) {
+            downloaded.extend(pending);
+        }
+        let mut shards = self.shards.lock().unwrap();
+        Ok(shards.entry(key).or_insert(downloaded).get(&from).copied())
+    }
+
+    fn write(
+        &self,
+        filter: Filter,
+        from: git2::Oid,
+        to: git2::Oid,
+        hint: HistoryGraphHint,
+        _tree_keyed: bool,
+    ) -> anyhow::Result<()> {
+        if self.token.is_none() || self.offline.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        if filter == filter::sequence_number() || filter == filter::reachable_roots() {
+            return Ok(());
+        }
+        if !hint.is_sample_point() {
+            return Ok(());
+        }
+
+        let key = (filter, hint.sequence_number / 10000);
+        if let Some(records) = self.shards.lock().unwrap().get_mut(&key) {
+            records.insert(from, to);
+        }
+
+        let mut guard = self.new_entries.lock().unwrap();
+        let records = guard.entry(key).or_default();
+        records.insert(from, to);
+        if records.len() < FLUSH_AFTER {
+            return Ok(());
+        }
+        std::mem::drop(guard);
+
+        self.flush(false)
+    }
+
+    /// Upload what the transaction produced, so other machines see it right away.
+    fn end(&self) {
+        if let Err(e) = self.flush(true) {
+            log::warn!("HttpCacheBackend: flush failed: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_roundtrip_and_later_lines_win() {
+        let a = git2::Oid::from_str("1111111111111111111111111111111111111111").unwrap();
+        let b = git2::Oid::from_str("2222222222222222222222222222222222222222").unwrap();
+        let records = Shard::from([(a, b), (b, git2::Oid::ZERO_SHA1)]);
+        assert_eq!(parse_records(&format_records(&records)).unwrap(), records);
+
+        let text = format!("{a} {a}\n{a} {b}\n");
+        assert_eq!(parse_records(&text).unwrap(), Shard::from([(a, b)]));
+        assert!(parse_records("garbage\n").is_err());
+    }
+}
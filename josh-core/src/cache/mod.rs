@@ -1,6 +1,10 @@
 mod backend;
 pub mod distributed;
 mod history_graph;
+pub mod http;
+mod promisor;
+mod refs;
+mod reftable;
 pub mod sled;
 pub mod stack;
 mod transaction;
@@ -19,7 +23,11 @@ pub use history_graph::{
     HistoryGraphInfo, collect_history_graph_info, compute_history_hint, compute_sequence_number,
     parents_share_root,
 };
-pub use sled::{SledCacheBackend, sled_clear, sled_print_stats};
+pub use http::HttpCacheBackend;
+pub use refs::RefStore;
+pub use sled::{
+    SledCacheBackend, SledTreeStats, sled_clear, sled_gc, sled_print_stats, sled_stats,
+};
 pub use stack::CacheStack;
 pub use transaction::*;
 pub use tree_cache::TreeBytes;
@@ -0,0 +1,128 @@
+//! Lazy object fetches for partial clones. A repository cloned with `--filter=blob:none` (or
+//! any other object filter) marks the remotes that promise the objects it left out with
+//! `remote.<name>.promisor` (older git: `extensions.partialClone`); [`GitPromisor`] asks them
+//! for an object the first time a read needs it, the way git itself does
+//! (`promisor-remote.c`).
+//!
+//! Nothing is fetched up front. Filters that only move trees around never read the blobs they
+//! pass through, so on a blobless clone they run without touching the network; content
+//! filters fetch the missing blobs of each tree they look into with one request
+//! ([`josh_memodb::Odb::prefetch`]), stored filters and search the single blobs they read.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Objects per `git fetch`, keeping the command line well below the system's limit.
+const FETCH_BATCH: usize = 1000;
+
+/// How long an object a fetch failed to bring in is not asked for again. Without it every read
+/// of the object would start another `git fetch`; with it a remote that was only briefly
+/// unreachable gets asked again later in the same transaction.
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+
+pub(crate) struct GitPromisor {
+    git_dir: std::path::PathBuf,
+    /// Asked in order until one has the objects.
+    remotes: Vec<String>,
+    /// Objects a single-object fetch failed to bring in, and when. A failed batch records
+    /// nothing: one object the remotes lack fails the whole batch, and the reads that follow
+    /// fetch the others one by one.
+    unavailable: Mutex<HashMap<git2::Oid, Instant>>,
+}
+
+impl GitPromisor {
+    /// The promisor of `repo`, if it is a partial clone.
+    pub(crate) fn detect(repo: &git2::Repository) -> Option<GitPromisor> {
+        let config = repo.config().ok()?;
+        let mut remotes = vec![];
+        let mut entries = config.entries(Some(r"^remote\..*\.promisor$")).ok()?;
+        while let Some(Ok(entry)) = entries.next() {
+            // A bare `promisor` key without a value counts as true, as in git.
+            let promised = entry.value().map_or(true, |value| {
+                git2::Config::parse_bool(value).unwrap_or(false)
+            });
+            let name = entry
+                .name()
+                .ok()
+                .and_then(|name| name.strip_prefix("remote.")?.strip_suffix(".promisor"));
+            if let (true, Some(name)) = (promised, name) {
+                remotes.push(name.to_owned());
+            }
+        }
+        // Like git, ask the remote named by the extension last.
+        if let Ok(remote) = config.get_string("extensions.partialClone") {
+            remotes.retain(|name| *name != remote);
+            remotes.push(remote);
+        }
+        if remotes.is_empty() {
+            return None;
+        }
+        Some(GitPromisor {
+            git_dir: repo.path().to_owned(),
+            remotes,
+            unavailable: Default::default(),
+        })
+    }
+
+    /// Fetch `oids` from `remote` with the arguments git uses for its own lazy fetches: no
+    /// negotiation (the objects are all that is wanted), and the fetch stays filtered so a tree
+    /// does not drag its blobs along.
+    fn fetch_from(&self, remote: &str, oids: &[git2::Oid]) -> anyhow::Result<()> {
+        let args = [
+            "-c",
+            "fetch.negotiationAlgorithm=noop",
+            "fetch",
+            "--quiet",
+            "--no-tags",
+            "--no-write-fetch-head",
+            "--recurse-submodules=no",
+            "--filter=blob:none",
+            remote,
+        ]
+        .into_iter()
+        .map(str::to_owned)
+        .chain(oids.iter().map(git2::Oid::to_string));
+        crate::git::GitCommand::new(&self.git_dir, args, [] as [(&str, &str); 0])
+            .with_stdout(std::process::Stdio::null())
+            .with_stderr(std::process::Stdio::piped())
+            .spawn()?;
+        Ok(())
+    }
+}
+
+impl josh_memodb::Promisor for GitPromisor {
+    fn fetch(&self, oids: &[git2::Oid]) -> bool {
+        let oids: Vec<_> = {
+            let mut unavailable = self.unavailable.lock().unwrap();
+            unavailable.retain(|_, failed| failed.elapsed() < RETRY_AFTER);
+            oids.iter()
+                .filter(|oid| !unavailable.contains_key(oid))
+                .copied()
+                .collect()
+        };
+        if oids.is_empty() {
+            return false;
+        }
+        let mut fetched = true;
+        for batch in oids.chunks(FETCH_BATCH) {
+            fetched &= self.remotes.iter().any(|remote| {
+                log::debug!(
+                    "fetching {} objects from promisor remote {}",
+                    batch.len(),
+                    remote
+                );
+                self.fetch_from(remote, batch)
+                    .inspect_err(|e| log::warn!("could not fetch from {}: {}", remote, e))
+                    .is_ok()
+            });
+        }
+        if let (false, [oid]) = (fetched, &oids[..]) {
+            self.unavailable
+                .lock()
+                .unwrap()
+                .insert(*oid, Instant::now());
+        }
+        fetched
+    }
+}
@@ -0,0 +1,514 @@
+//! The repository's refs, read and written the way the repository stores them: through
+//! gix-ref's file store (loose refs, `packed-refs` and reflogs, with the same on-disk results
+//! git and libgit2 produce), or in a reftable (see [`super::reftable`]).
+//!
+//! Lookups are by exact name. gix's own `try_find` resolves partial names the way git resolves
+//! a revision argument (`main` -> `refs/heads/main`), which is right for user input
+//! ([`RefStore::expand`]) but not for the fully qualified names josh computes: those must never
+//! silently resolve to some other ref that happens to match.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, anyhow};
+use gix_ref::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+
+use super::reftable::{self, Stack};
+use crate::objects::{git2_oid, gix_oid};
+
+/// How many symbolic refs a chain may pass through before it counts as a loop; git and
+/// libgit2 both stop at 5.
+const MAX_SYMREF_DEPTH: usize = 5;
+
+/// Where git looks for the ref a short name means, in order.
+const EXPAND_RULES: [&str; 6] = [
+    "{}",
+    "refs/{}",
+    "refs/tags/{}",
+    "refs/heads/{}",
+    "refs/remotes/{}",
+    "refs/remotes/{}/HEAD",
+];
+
+pub struct RefStore {
+    backend: Backend,
+    git_dir: PathBuf,
+    /// The identity recorded in reflog entries, as `(name, email)`. `None` when the
+    /// repository has none configured; reflogs then say `unknown`, like libgit2's.
+    identity: Option<(String, String)>,
+}
+
+enum Backend {
+    Files(gix_ref::file::Store),
+    /// The stack of the common directory, and in a linked worktree the worktree's own, which
+    /// holds the refs private to the worktree.
+    Reftable {
+        common: Stack,
+        worktree: Option<Stack>,
+        write_reflog: gix_ref::store::WriteReflog,
+    },
+}
+
+impl RefStore {
+    /// The ref store of `repo`, configured from it the way git would: refs in the format
+    /// `extensions.refStorage` names, the worktree's private refs in front of the common
+    /// directory's, and reflogs written per `core.logAllRefUpdates` (unset means on, except in
+    /// bare repositories).
+    pub fn open(repo: &git2::Repository) -> RefStore {
+        let config = repo.config().ok();
+        let write_reflog = match config
+            .as_ref()
+            .and_then(|c| c.get_string("core.logAllRefUpdates").ok())
+        {
+            Some(value) if value.eq_ignore_ascii_case("always") => {
+                gix_ref::store::WriteReflog::Always
+            }
+            Some(value) if git2::Config::parse_bool(&value).unwrap_or(true) => {
+                gix_ref::store::WriteReflog::Normal
+            }
+            Some(_) => gix_ref::store::WriteReflog::Disable,
+            None if repo.is_bare() => gix_ref::store::WriteReflog::Disable,
+            None => gix_ref::store::WriteReflog::Normal,
+        };
+        let object_hash = josh_memodb::object_hash(repo);
+        let linked_worktree = repo.path() != repo.commondir();
+        let reftable = config
+            .as_ref()
+            .and_then(|c| c.get_string("extensions.refStorage").ok())
+            .is_some_and(|format| format.eq_ignore_ascii_case("reftable"));
+        let backend = if reftable {
+            Backend::Reftable {
+                common: Stack::new(repo.commondir().join("reftable"), object_hash),
+                worktree: linked_worktree
+                    .then(|| Stack::new(repo.path().join("reftable"), object_hash)),
+                write_reflog,
+            }
+        } else {
+            let options = gix_ref::store::init::Options {
+                write_reflog,
+                object_hash,
+                precompose_unicode: config
+                    .as_ref()
+                    .and_then(|c| c.get_bool("core.precomposeUnicode").ok())
+                    .unwrap_or(false),
+                prohibit_windows_device_names: cfg!(windows),
+            };
+            Backend::Files(if linked_worktree {
+                gix_ref::file::Store::for_linked_worktree(
+                    repo.path().to_owned(),
+                    repo.commondir().to_owned(),
+                    options,
+                )
+            } else {
+                gix_ref::file::Store::at(repo.path().to_owned(), options)
+            })
+        };
+        let identity = repo
+            .signature()
+            .ok()
+            .and_then(|sig| Some((sig.name().ok()?.to_string(), sig.email().ok()?.to_string())));
+        RefStore {
+            backend,
+            git_dir: repo.path().to_owned(),
+            identity,
+        }
+    }
+
+    /// The ref named exactly `refname`, unresolved. Errors on a malformed name.
+    pub(crate) fn find(&self, refname: &str) -> anyhow::Result<Option<gix_ref::Reference>> {
+        let (common, worktree) = match &self.backend {
+            Backend::Files(store) => {
+                let found = store
+                    .try_find(refname)
+                    .with_context(|| format!("failed to look up ref '{}'", refname))?;
+                return Ok(found.filter(|reference| reference.name.as_bstr() == refname));
+            }
+            Backend::Reftable {
+                common, worktree, ..
+            } => (common, worktree),
+        };
+        let name = gix_ref::FullName::try_from(refname)
+            .with_context(|| format!("invalid ref name '{}'", refname))?;
+        let refs = stack_for(common, worktree, refname.as_bytes())
+            .refs()
+            .with_context(|| format!("failed to look up ref '{}'", refname))?;
+        let Some(value) = refs.get(refname.as_bytes()) else {
+            return Ok(None);
+        };
+        let (target, peeled) = match value {
+            reftable::Value::Object { id, peeled } => (gix_ref::Target::Object(*id), *peeled),
+            reftable::Value::Symbolic(target) => {
+                let target = std::str::from_utf8(target)
+                    .ok()
+                    .and_then(|target| gix_ref::FullName::try_from(target).ok())
+                    .ok_or_else(|| anyhow!("ref '{}' points to a malformed name", refname))?;
+                (gix_ref::Target::Symbolic(target), None)
+            }
+            reftable::Value::Deleted => return Ok(None),
+        };
+        Ok(Some(gix_ref::Reference {
+            name,
+            target,
+            peeled,
+        }))
+    }
+
+    /// The oid `refname` points at after following symbolic refs, unpeeled. `None` when the
+    /// ref, or the end of its symbolic chain, does not exist.
+    pub fn resolve(&self, refname: &str) -> anyhow::Result<Option<git2::Oid>> {
+        Ok(self
+            .resolve_chain(refname)?
+            .and_then(|reference| match reference.target {
+                gix_ref::Target::Object(id) => Some(git2_oid(&id)),
+                gix_ref::Target::Symbolic(_) => None,
+            }))
+    }
+
+    /// Follow `refname` through symbolic refs to the last ref of the chain. That last ref is
+    /// symbolic only when its target does not exist (a dangling or unborn ref).
+    pub(crate) fn resolve_chain(
+        &self,
+        refname: &str,
+    ) -> anyhow::Result<Option<gix_ref::Reference>> {
+        let Some(mut reference) = self.find(refname)? else {
+            return Ok(None);
+        };
+        for _ in 0..MAX_SYMREF_DEPTH {
+            let gix_ref::Target::Symbolic(target) = &reference.target else {
+                return Ok(Some(reference));
+            };
+            match self.find(&target.as_bstr().to_string())? {
+                Some(next) => reference = next,
+                None => return Ok(Some(reference)),
+            }
+        }
+        match reference.target {
+            gix_ref::Target::Object(_) => Ok(Some(reference)),
+            gix_ref::Target::Symbolic(_) => Err(anyhow!(
+                "ref '{}' nests symbolic refs more than {} deep",
+                refname,
+                MAX_SYMREF_DEPTH
+            )),
+        }
+    }
+
+    /// The name the symbolic ref `refname` points at; `None` when `refname` does not exist
+    /// or is a direct ref.
+    pub fn symbolic_target(&self, refname: &str) -> anyhow::Result<Option<String>> {
+        Ok(self
+            .find(refname)?
+            .and_then(|reference| match reference.target {
+                gix_ref::Target::Symbolic(target) => Some(target.as_bstr().to_string()),
+                gix_ref::Target::Object(_) => None,
+            }))
+    }
+
+    /// The full name of the ref `short_name` refers to, found the way git resolves a revision
+    /// argument. `None` when nothing matches or `short_name` cannot name a ref.
+    pub fn expand(&self, short_name: &str) -> anyhow::Result<Option<String>> {
+        let Backend::Files(store) = &self.backend else {
+            if <&gix_ref::PartialNameRef>::try_from(short_name).is_err() {
+                return Ok(None);
+            }
+            for rule in EXPAND_RULES {
+                let candidate = rule.replace("{}", short_name);
+                if gix_ref::FullName::try_from(candidate.as_str()).is_ok()
+                    && self.find(&candidate)?.is_some()
+                {
+                    return Ok(Some(candidate));
+                }
+            }
+            return Ok(None);
+        };
+        match store.try_find(short_name) {
+            Ok(found) => Ok(found.map(|reference| reference.name.as_bstr().to_string())),
+            Err(gix_ref::file::find::Error::RefnameValidation(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The direct refs whose full names start with `prefix`, sorted by name. Symbolic refs
+    /// and refs with non-UTF-8 names are left out.
+    pub fn prefixed(&self, prefix: &str) -> anyhow::Result<Vec<(String, git2::Oid)>> {
+        let relative: &gix_path::RelativePath = prefix
+            .try_into()
+            .with_context(|| format!("invalid ref prefix '{}'", prefix))?;
+        let mut refs = vec![];
+        let (common, worktree) = match &self.backend {
+            Backend::Files(store) => {
+                let platform = store.iter()?;
+                // The overlay of loose and packed refs comes out merged in name order.
+                for reference in platform.prefixed(relative)? {
+                    let reference = reference?;
+                    if let (Ok(name), gix_ref::Target::Object(id)) = (
+                        std::str::from_utf8(reference.name.as_bstr()),
+                        &reference.target,
+                    ) {
+                        refs.push((name.to_owned(), git2_oid(id)));
+                    }
+                }
+                return Ok(refs);
+            }
+            Backend::Reftable {
+                common, worktree, ..
+            } => (common, worktree),
+        };
+        let mut stacks = vec![(common.refs()?, worktree.is_none())];
+        if let Some(worktree) = worktree {
+            stacks.push((worktree.refs()?, true));
+        }
+        for (stack_refs, all) in &stacks {
+            for (name, value) in stack_refs.range(prefix.as_bytes().to_vec()..) {
+                if !name.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                // With a worktree of its own, each stack only has a say on its own refs.
+                if !all && is_per_worktree(name) {
+                    continue;
+                }
+                if let (Ok(name), reftable::Value::Object { id, .. }) =
+                    (std::str::from_utf8(name), value)
+                {
+                    refs.push((name.to_owned(), git2_oid(id)));
+                }
+            }
+        }
+        refs.sort();
+        Ok(refs)
+    }
+
+    /// Point the ref `refname` itself (not what a symbolic `refname` points to) at `target`,
+    /// provided it currently is what `expected` says.
+    pub(crate) fn update(
+        &self,
+        refname: &str,
+        expected: PreviousValue,
+        target: gix_ref::Target,
+        log_message: &str,
+    ) -> anyhow::Result<()> {
+        self.edit(
+            refname,
+            Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: log_message.into(),
+                },
+                expected,
+                new: target,
+            },
+        )
+    }
+
+    /// Delete the ref `refname` itself, loose and packed, with its reflog.
+    pub(crate) fn delete(&self, refname: &str, expected: PreviousValue) -> anyhow::Result<()> {
+        self.edit(
+            refname,
+            Change::Delete {
+                expected,
+                log: RefLog::AndReference,
+            },
+        )
+    }
+
+    fn edit(&self, refname: &str, change: Change) -> anyhow::Result<()> {
+        let name: gix_ref::FullName = refname
+            .try_into()
+            .with_context(|| format!("invalid ref name '{}'", refname))?;
+        let committer = self.committer()?;
+        let (common, worktree, write_reflog) = match &self.backend {
+            Backend::Files(store) => {
+                let edit = RefEdit {
+                    change,
+                    name,
+                    deref: false,
+                };
+                // Like libgit2, give up right away on a lock someone else holds.
+                store
+                    .transaction()
+                    .prepare(
+                        [edit],
+                        gix_lock::acquire::Fail::Immediately,
+                        gix_lock::acquire::Fail::Immediately,
+                    )
+                    .with_context(|| format!("failed to update ref '{}'", refname))?
+                    .commit(committer.to_ref(&mut gix_actor::date::parse::TimeBuf::default()))
+                    .with_context(|| format!("failed to update ref '{}'", refname))?;
+                return Ok(());
+            }
+            Backend::Reftable {
+                common,
+                worktree,
+                write_reflog,
+            } => (common, worktree, *write_reflog),
+        };
+        let key = refname.as_bytes();
+        stack_for(common, worktree, key)
+            .update(|tables| {
+                let current = tables.get(key);
+                let expected = match &change {
+                    Change::Update { expected, .. } | Change::Delete { expected, .. } => expected,
+                };
+                if !matches_expected(current, expected) {
+                    return Err(anyhow!("the ref is not what the update expects"));
+                }
+                let mut update = reftable::Update::default();
+                match change {
+                    Change::Update { log, new, .. } => {
+                        let value = match new {
+                            gix_ref::Target::Object(id) => {
+                                reftable::Value::Object { id, peeled: None }
+                            }
+                            gix_ref::Target::Symbolic(target) => {
+                                reftable::Value::Symbolic(target.as_bstr().to_vec())
+                            }
+                        };
+                        // Setting a ref to what it is changes nothing.
+                        if current == Some(&value) {
+                            return Ok(update);
+                        }
+                        if let reftable::Value::Object { id, .. } = &value
+                            && log.mode == RefLog::AndReference
+                            && writes_reflog(write_reflog, refname, log.force_create_reflog)
+                        {
+                            let old = match current {
+                                Some(reftable::Value::Object { id, .. }) => *id,
+                                _ => id.kind().null(),
+                            };
+                            update.logs.push((
+                                key.to_vec(),
+                                log_entry(old, *id, &committer, log.message.as_ref())?,
+                            ));
+                        }
+                        update.refs.push((key.to_vec(), value));
+                    }
+                    Change::Delete { log, .. } => {
+                        // Deleting what does not exist changes nothing.
+                        if current.is_none() {
+                            return Ok(update);
+                        }
+                        update.refs.push((key.to_vec(), reftable::Value::Deleted));
+                        if log == RefLog::AndReference {
+                            for index in tables.log_indices(key) {
+                                update.deleted_logs.push((key.to_vec(), index));
+                            }
+                        }
+                    }
+                }
+                Ok(update)
+            })
+            .with_context(|| format!("failed to update ref '{}'", refname))
+    }
+
+    fn committer(&self) -> anyhow::Result<gix_actor::Signature> {
+        let (name, email) = self
+            .identity
+            .as_ref()
+            .map_or(("unknown", "unknown"), |(name, email)| (name, email));
+        crate::objects::gix_signature(&git2::Signature::now(name, email)?)
+    }
+
+    /// The commit a `git fetch` just fetched, as `FETCH_HEAD` records it: the first entry,
+    /// which is what `git rev-parse FETCH_HEAD` resolves to. git lists the refs fetched for
+    /// merging first, so for a fetch of one ref that is the ref. Unpeeled; `None` when there
+    /// is no `FETCH_HEAD`.
+    pub fn fetch_head(&self) -> anyhow::Result<Option<git2::Oid>> {
+        let path = self.git_dir.join("FETCH_HEAD");
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {:?}", path)),
+        };
+        let Some(hex) = content
+            .lines()
+            .next()
+            .and_then(|line| line.split('\t').next())
+        else {
+            return Ok(None);
+        };
+        let id = gix_hash::ObjectId::from_hex(hex.trim().as_bytes())
+            .with_context(|| format!("malformed FETCH_HEAD entry '{}'", hex))?;
+        Ok(Some(git2_oid(&id)))
+    }
+}
+
+/// The target `oid` as a ref target, for [`RefStore::update`] and the guards it takes.
+pub(crate) fn object_target(oid: git2::Oid) -> gix_ref::Target {
+    gix_ref::Target::Object(gix_oid(oid))
+}
+
+/// The stack of a linked worktree holds the refs private to it; the common stack all others.
+fn stack_for<'a>(common: &'a Stack, worktree: &'a Option<Stack>, name: &[u8]) -> &'a Stack {
+    match worktree {
+        Some(worktree) if is_per_worktree(name) => worktree,
+        _ => common,
+    }
+}
+
+/// Whether git keeps `name` per worktree: `HEAD` and the other refs outside `refs/`, and the
+/// bisect, rebase and `refs/worktree/` namespaces.
+fn is_per_worktree(name: &[u8]) -> bool {
+    !name.starts_with(b"refs/")
+        || [&b"refs/worktree/"[..], b"refs/bisect/", b"refs/rewritten/"]
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+fn matches_expected(current: Option<&reftable::Value>, expected: &PreviousValue) -> bool {
+    let is = |target: &gix_ref::Target| match (current, target) {
+        (Some(reftable::Value::Object { id, .. }), gix_ref::Target::Object(expected)) => {
+            id == expected
+        }
+        (Some(reftable::Value::Symbolic(name)), gix_ref::Target::Symbolic(expected)) => {
+            name.as_slice() == expected.as_bstr().as_ref() as &[u8]
+        }
+        _ => false,
+    };
+    match expected {
+        PreviousValue::Any => true,
+        PreviousValue::MustExist => current.is_some(),
+        PreviousValue::MustNotExist => current.is_none(),
+        PreviousValue::MustExistAndMatch(target) => is(target),
+        PreviousValue::ExistingMustMatch(target) => current.is_none() || is(target),
+    }
+}
+
+/// Whether an update of `refname` gets a reflog entry, by the rules gix-ref's file store
+/// follows.
+fn writes_reflog(mode: gix_ref::store::WriteReflog, refname: &str, force: bool) -> bool {
+    match mode {
+        gix_ref::store::WriteReflog::Always => true,
+        gix_ref::store::WriteReflog::Disable => false,
+        gix_ref::store::WriteReflog::Normal => {
+            force
+                || refname == "HEAD"
+                || ["refs/heads/", "refs/remotes/", "refs/notes/"]
+                    .iter()
+                    .any(|prefix| refname.starts_with(prefix))
+        }
+    }
+}
+
+fn log_entry(
+    old: gix_hash::ObjectId,
+    new: gix_hash::ObjectId,
+    committer: &gix_actor::Signature,
+    message: &gix_ref::bstr::BStr,
+) -> anyhow::Result<reftable::LogEntry> {
+    if message.contains(&b'\n') {
+        return Err(anyhow!("reflog message must not contain newlines"));
+    }
+    // git stores the message with a trailing newline.
+    let mut message = message.to_vec();
+    if !message.is_empty() {
+        message.push(b'\n');
+    }
+    Ok(reftable::LogEntry {
+        old,
+        new,
+        name: committer.name.to_vec(),
+        email: committer.email.to_vec(),
+        time: u64::try_from(committer.time.seconds).unwrap_or(0),
+        tz_offset: (committer.time.offset / 60) as i16,
+        message,
+    })
+}
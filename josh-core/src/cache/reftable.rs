@@ -0,0 +1,1227 @@
+//! Refs kept in a reftable, git's binary ref storage (`extensions.refStorage = reftable`): a
+//! stack of immutable tables named in `reftable/tables.list`, oldest first, each holding the
+//! ref records and reflog entries of one or more updates.
+//!
+//! Reading merges the stack: a table's record for a name shadows the records of older tables,
+//! and a deletion record hides the name altogether. Writing appends a table holding the update,
+//! then compacts the newest tables the way git does, so that the stack stays logarithmic in the
+//! number of updates. Tables are written without the optional ref index and object sections,
+//! which readers, git's included, do without by scanning the ref blocks.
+//!
+//! The format is git's `Documentation/technical/reftable.txt`.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, anyhow};
+use gix_hash::ObjectId;
+
+const MAGIC: &[u8; 4] = b"REFT";
+/// The block size of the tables josh writes, git's default.
+const BLOCK_SIZE: usize = 4096;
+/// Every this many records a block stores a key whole, for readers to seek to.
+const RESTART_INTERVAL: usize = 16;
+const BLOCK_TYPE_REF: u8 = b'r';
+const BLOCK_TYPE_LOG: u8 = b'g';
+/// The footer fields after its copy of the header: the ref index, object and object index,
+/// log and log index positions, then the CRC-32 of everything before it.
+const FOOTER_FIELDS_LEN: usize = 5 * 8 + 4;
+/// How often a reader re-reads `tables.list` when a table it names was compacted away
+/// between reading the list and opening the table.
+const READ_ATTEMPTS: usize = 3;
+
+/// What a ref record says about its name.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    /// The name was deleted; it hides records of older tables.
+    Deleted,
+    Object {
+        id: ObjectId,
+        /// What `id` peels to, when it is an annotated tag.
+        peeled: Option<ObjectId>,
+    },
+    Symbolic(Vec<u8>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct RefRecord {
+    name: Vec<u8>,
+    update_index: u64,
+    value: Value,
+}
+
+/// One reflog entry of a ref, as the ref's update number `update_index` left it.
+#[derive(Clone, Debug, PartialEq)]
+struct LogRecord {
+    name: Vec<u8>,
+    update_index: u64,
+    /// `None` for a deletion record, which hides the entry of the same update in older tables.
+    entry: Option<LogEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LogEntry {
+    pub old: ObjectId,
+    pub new: ObjectId,
+    pub name: Vec<u8>,
+    pub email: Vec<u8>,
+    pub time: u64,
+    /// Minutes east of UTC.
+    pub tz_offset: i16,
+    /// The message with its trailing newline, or empty.
+    pub message: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Table {
+    min_update_index: u64,
+    max_update_index: u64,
+    /// Sorted by name.
+    refs: Vec<RefRecord>,
+    /// Sorted by name, newest update first.
+    logs: Vec<LogRecord>,
+}
+
+/// The changes of one update, made against the state of the stack it is handed.
+#[derive(Default)]
+pub(crate) struct Update {
+    pub refs: Vec<(Vec<u8>, Value)>,
+    /// New reflog entries, made by this update.
+    pub logs: Vec<(Vec<u8>, LogEntry)>,
+    /// Reflog entries to delete, by ref and the update that made them.
+    pub deleted_logs: Vec<(Vec<u8>, u64)>,
+}
+
+/// The tables of a stack, oldest first, as an update sees them under the stack's lock.
+pub(crate) struct Tables(Vec<Table>);
+
+impl Tables {
+    /// The current value of `name`; `None` when it does not exist.
+    pub(crate) fn get(&self, name: &[u8]) -> Option<&Value> {
+        self.0
+            .iter()
+            .rev()
+            .find_map(|table| {
+                table
+                    .refs
+                    .binary_search_by(|record| record.name.as_slice().cmp(name))
+                    .ok()
+                    .map(|i| &table.refs[i].value)
+            })
+            .filter(|value| **value != Value::Deleted)
+    }
+
+    /// The update numbers of the reflog entries `name` has.
+    pub(crate) fn log_indices(&self, name: &[u8]) -> Vec<u64> {
+        let mut entries = BTreeMap::new();
+        for table in &self.0 {
+            for record in table.logs.iter().filter(|record| record.name == name) {
+                entries.insert(record.update_index, record.entry.is_some());
+            }
+        }
+        entries
+            .into_iter()
+            .filter_map(|(index, live)| live.then_some(index))
+            .collect()
+    }
+}
+
+/// The refs of a stack by name, deletions left out.
+pub(crate) type Refs = Arc<BTreeMap<Vec<u8>, Value>>;
+
+/// One reftable stack, the directory holding `tables.list` and the tables it names.
+pub(crate) struct Stack {
+    dir: PathBuf,
+    object_hash: gix_hash::Kind,
+    /// The stack's refs merged, deletions left out, with the `tables.list` they were read for.
+    /// Tables never change once written, so the list identifies their content.
+    merged: Mutex<Option<(String, Refs)>>,
+}
+
+impl Stack {
+    pub(crate) fn new(dir: PathBuf, object_hash: gix_hash::Kind) -> Stack {
+        Stack {
+            dir,
+            object_hash,
+            merged: Mutex::new(None),
+        }
+    }
+
+    /// The refs of the stack as it is now.
+    pub(crate) fn refs(&self) -> anyhow::Result<Refs> {
+        for _ in 0..READ_ATTEMPTS {
+            let list = self.read_list()?;
+            let mut merged = self.merged.lock().unwrap();
+            if let Some((cached, refs)) = merged.as_ref()
+                && *cached == list
+            {
+                return Ok(refs.clone());
+            }
+            // Another process compacted the stack since the list was read: read the new one.
+            let Some(tables) = self.read_tables(&list, false)? else {
+                continue;
+            };
+            let mut refs = BTreeMap::new();
+            for record in tables.into_iter().flat_map(|table| table.refs) {
+                match record.value {
+                    Value::Deleted => refs.remove(&record.name),
+                    value => refs.insert(record.name, value),
+                };
+            }
+            let refs = Arc::new(refs);
+            *merged = Some((list, refs.clone()));
+            return Ok(refs);
+        }
+        Err(anyhow!(
+            "reftable stack {:?} keeps changing while it is read",
+            self.dir
+        ))
+    }
+
+    /// Append the update `build` makes against the current state of the stack, holding the
+    /// stack's lock throughout; like libgit2, give up right away on a lock someone else holds.
+    pub(crate) fn update(
+        &self,
+        build: impl FnOnce(&Tables) -> anyhow::Result<Update>,
+    ) -> anyhow::Result<()> {
+        let list_path = self.dir.join("tables.list");
+        let mut lock = gix_lock::File::acquire_to_update_resource(
+            &list_path,
+            gix_lock::acquire::Fail::Immediately,
+            None,
+        )
+        .with_context(|| format!("failed to lock {:?}", list_path))?;
+        let list = self.read_list()?;
+        let mut names: Vec<String> = table_names(&list).map(str::to_owned).collect();
+        let tables = Tables(
+            self.read_tables(&list, true)?
+                .ok_or_else(|| anyhow!("a table of {:?} vanished under its lock", self.dir))?,
+        );
+        let update = build(&tables)?;
+        if update.refs.is_empty() && update.logs.is_empty() && update.deleted_logs.is_empty() {
+            return Ok(());
+        }
+        let mut tables = tables.0;
+
+        let update_index = tables.last().map_or(1, |table| table.max_update_index + 1);
+        let mut table = Table {
+            min_update_index: update_index,
+            max_update_index: update_index,
+            refs: update
+                .refs
+                .into_iter()
+                .map(|(name, value)| RefRecord {
+                    name,
+                    update_index,
+                    value,
+                })
+                .collect(),
+            logs: update
+                .logs
+                .into_iter()
+                .map(|(name, entry)| LogRecord {
+                    name,
+                    update_index,
+                    entry: Some(entry),
+                })
+                .chain(
+                    update
+                        .deleted_logs
+                        .into_iter()
+                        .map(|(name, update_index)| LogRecord {
+                            name,
+                            update_index,
+                            entry: None,
+                        }),
+                )
+                .collect(),
+        };
+        table.refs.sort_by(|a, b| a.name.cmp(&b.name));
+        table.refs.dedup_by(|a, b| a.name == b.name);
+        table.logs.sort_by(|a, b| {
+            (&a.name, std::cmp::Reverse(a.update_index))
+                .cmp(&(&b.name, std::cmp::Reverse(b.update_index)))
+        });
+        let (name, size) = self.write_table(&table)?;
+        names.push(name);
+        tables.push(table);
+
+        let mut sizes = vec![];
+        for name in &names[..names.len() - 1] {
+            let path = self.dir.join(name);
+            sizes.push(
+                std::fs::metadata(&path)
+                    .with_context(|| format!("failed to read {:?}", path))?
+                    .len(),
+            );
+        }
+        sizes.push(size);
+        let start = compaction_start(&sizes);
+        let mut obsolete = vec![];
+        if start + 1 < names.len() {
+            let compacted = compact(&tables[start..], start == 0);
+            let (name, _) = self.write_table(&compacted)?;
+            obsolete = names.split_off(start);
+            names.push(name);
+        }
+
+        for name in &names {
+            writeln!(lock, "{}", name)
+                .with_context(|| format!("failed to write {:?}", list_path))?;
+        }
+        lock.commit()
+            .map_err(|e| e.error)
+            .with_context(|| format!("failed to write {:?}", list_path))?;
+        // Readers that still go by the old list retry with the new one.
+        for name in obsolete {
+            std::fs::remove_file(self.dir.join(name)).ok();
+        }
+        Ok(())
+    }
+
+    fn read_list(&self) -> anyhow::Result<String> {
+        let path = self.dir.join("tables.list");
+        match std::fs::read_to_string(&path) {
+            Ok(list) => Ok(list),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(e).with_context(|| format!("failed to read {:?}", path)),
+        }
+    }
+
+    /// The tables `list` names, oldest first; `None` when one of them no longer exists.
+    fn read_tables(&self, list: &str, with_logs: bool) -> anyhow::Result<Option<Vec<Table>>> {
+        let mut tables = vec![];
+        for name in table_names(list) {
+            let path = self.dir.join(name);
+            let data = match std::fs::read(&path) {
+                Ok(data) => data,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e).with_context(|| format!("failed to read {:?}", path)),
+            };
+            tables.push(
+                Table::parse(&data, self.object_hash, with_logs)
+                    .with_context(|| format!("failed to read reftable {:?}", path))?,
+            );
+        }
+        Ok(Some(tables))
+    }
+
+    /// Write `table` to a new file of the stack, returning its name and size.
+    fn write_table(&self, table: &Table) -> anyhow::Result<(String, u64)> {
+        let data = table.write(self.object_hash)?;
+        let name = format!(
+            "0x{:012x}-0x{:012x}-{:08x}.ref",
+            table.min_update_index,
+            table.max_update_index,
+            random_suffix()
+        );
+        let path = self.dir.join(&name);
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| format!("failed to create {:?}", path))?;
+        file.write_all(&data)
+            .and_then(|_| file.sync_all())
+            .with_context(|| format!("failed to write {:?}", path))?;
+        Ok((name, data.len() as u64))
+    }
+}
+
+/// Where the run of newest tables to compact into one starts, given the table sizes oldest
+/// first: git's geometric rule, under which every table is at least twice the size of all
+/// newer ones together. `sizes.len() - 1` when nothing needs compacting.
+fn compaction_start(sizes: &[u64]) -> usize {
+    let mut start = sizes.len() - 1;
+    let mut newer = sizes[start];
+    while start > 0 && sizes[start - 1] < 2 * newer {
+        start -= 1;
+        newer += sizes[start];
+    }
+    start
+}
+
+/// The tables `tables`, oldest first, merged into one. Deletion records only need to stay when
+/// there are older tables left for them to hide records of.
+fn compact(tables: &[Table], drop_deletions: bool) -> Table {
+    let mut refs = BTreeMap::new();
+    let mut logs = BTreeMap::new();
+    for table in tables {
+        for record in &table.refs {
+            refs.insert(record.name.clone(), record.clone());
+        }
+        for record in &table.logs {
+            logs.insert(
+                (record.name.clone(), std::cmp::Reverse(record.update_index)),
+                record.clone(),
+            );
+        }
+    }
+    Table {
+        min_update_index: tables[0].min_update_index,
+        max_update_index: tables[tables.len() - 1].max_update_index,
+        refs: refs
+            .into_values()
+            .filter(|record| !drop_deletions || record.value != Value::Deleted)
+            .collect(),
+        logs: logs
+            .into_values()
+            .filter(|record| !drop_deletions || record.entry.is_some())
+            .collect(),
+    }
+}
+
+fn table_names(list: &str) -> impl Iterator<Item = &str> {
+    list.lines().filter(|name| !name.is_empty())
+}
+
+fn random_suffix() -> u32 {
+    use std::hash::BuildHasher;
+    std::collections::hash_map::RandomState::new().hash_one(std::time::SystemTime::now()) as u32
+}
+
+impl Table {
+    fn parse(data: &[u8], object_hash: gix_hash::Kind, with_logs: bool) -> anyhow::Result<Table> {
+        if data.len() < 24 || &data[..4] != MAGIC {
+            return Err(anyhow!("not a reftable"));
+        }
+        let (header_len, hash) = match data[4] {
+            1 => (24, gix_hash::Kind::Sha1),
+            2 => {
+                let mut hash_id = Reader::new(data, 24);
+                match hash_id.bytes(4)? {
+                    b"sha1" => (28, gix_hash::Kind::Sha1),
+                    b"s256" => (28, gix_hash::Kind::Sha256),
+                    id => return Err(anyhow!("unknown hash id {:?}", id)),
+                }
+            }
+            version => return Err(anyhow!("unsupported version {}", version)),
+        };
+        if hash != object_hash {
+            return Err(anyhow!(
+                "table is for {} object ids, the repository uses {}",
+                hash,
+                object_hash
+            ));
+        }
+        let footer_len = header_len + FOOTER_FIELDS_LEN;
+        let blocks_end = data
+            .len()
+            .checked_sub(footer_len)
+            .filter(|end| *end >= header_len)
+            .ok_or_else(|| anyhow!("truncated reftable"))?;
+        let footer = &data[blocks_end..];
+        if footer[..header_len] != data[..header_len] {
+            return Err(anyhow!("footer does not repeat the header"));
+        }
+        let crc = u32::from_be_bytes(footer[footer_len - 4..].try_into().unwrap());
+        if crc != crc32fast::hash(&footer[..footer_len - 4]) {
+            return Err(anyhow!("footer checksum mismatch"));
+        }
+        let mut header = Reader::new(data, 8);
+        let min_update_index = header.u64()?;
+        let max_update_index = header.u64()?;
+        let mut fields = Reader::new(footer, header_len);
+        let ref_index_position = fields.u64()? as usize;
+        let obj_position = (fields.u64()? >> 5) as usize;
+        let _obj_index_position = fields.u64()?;
+        let log_position = fields.u64()? as usize;
+        let log_index_position = fields.u64()? as usize;
+
+        let first_block_type = data
+            .get(header_len)
+            .copied()
+            .filter(|_| blocks_end > header_len);
+        let mut table = Table {
+            min_update_index,
+            max_update_index,
+            refs: vec![],
+            logs: vec![],
+        };
+        if first_block_type == Some(BLOCK_TYPE_REF) {
+            let refs_end = [ref_index_position, obj_position, log_position]
+                .into_iter()
+                .filter(|position| *position != 0)
+                .min()
+                .unwrap_or(blocks_end);
+            read_blocks(
+                data,
+                0,
+                refs_end,
+                header_len,
+                BLOCK_TYPE_REF,
+                |key, value_type, record| {
+                    let update_index = min_update_index + record.varint()?;
+                    let value = match value_type {
+                        0 => Value::Deleted,
+                        1 => Value::Object {
+                            id: record.oid(hash)?,
+                            peeled: None,
+                        },
+                        2 => Value::Object {
+                            id: record.oid(hash)?,
+                            peeled: Some(record.oid(hash)?),
+                        },
+                        3 => {
+                            let len = record.varint()? as usize;
+                            Value::Symbolic(record.bytes(len)?.to_vec())
+                        }
+                        other => return Err(anyhow!("unknown ref value type {}", other)),
+                    };
+                    table.refs.push(RefRecord {
+                        name: key.to_vec(),
+                        update_index,
+                        value,
+                    });
+                    Ok(())
+                },
+            )?;
+        }
+        let log_start = match (log_position, first_block_type) {
+            (0, Some(BLOCK_TYPE_LOG)) => Some(0),
+            (0, _) => None,
+            (position, _) => Some(position),
+        };
+        if let Some(start) = log_start.filter(|_| with_logs) {
+            let end = match log_index_position {
+                0 => blocks_end,
+                position => position,
+            };
+            read_blocks(
+                data,
+                start,
+                end,
+                header_len,
+                BLOCK_TYPE_LOG,
+                |key, value_type, record| {
+                    let split = key
+                        .len()
+                        .checked_sub(9)
+                        .filter(|split| key[*split] == 0)
+                        .ok_or_else(|| anyhow!("malformed log key"))?;
+                    let update_index = !u64::from_be_bytes(key[split + 1..].try_into().unwrap());
+                    let entry = match value_type {
+                        0 => None,
+                        1 => Some(LogEntry {
+                            old: record.oid(hash)?,
+                            new: record.oid(hash)?,
+                            name: record.string()?,
+                            email: record.string()?,
+                            time: record.varint()?,
+                            tz_offset: record.u16()? as i16,
+                            message: record.string()?,
+                        }),
+                        other => return Err(anyhow!("unknown log value type {}", other)),
+                    };
+                    table.logs.push(LogRecord {
+                        name: key[..split].to_vec(),
+                        update_index,
+                        entry,
+                    });
+                    Ok(())
+                },
+            )?;
+        }
+        Ok(table)
+    }
+
+    fn write(&self, object_hash: gix_hash::Kind) -> anyhow::Result<Vec<u8>> {
+        let mut out = MAGIC.to_vec();
+        match object_hash {
+            gix_hash::Kind::Sha1 => out.push(1),
+            _ => out.push(2),
+        }
+        out.extend_from_slice(&(BLOCK_SIZE as u32).to_be_bytes()[1..]);
+        out.extend_from_slice(&self.min_update_index.to_be_bytes());
+        out.extend_from_slice(&self.max_update_index.to_be_bytes());
+        match object_hash {
+            gix_hash::Kind::Sha1 => {}
+            gix_hash::Kind::Sha256 => out.extend_from_slice(b"s256"),
+            other => return Err(anyhow!("no reftable hash id for {}", other)),
+        }
+        let header = out.clone();
+        let header_len = header.len();
+
+        let mut blocks = BlockWriter::new(BLOCK_TYPE_REF, header_len);
+        for record in &self.refs {
+            let mut value = vec![];
+            put_varint(&mut value, record.update_index - self.min_update_index);
+            let value_type = match &record.value {
+                Value::Deleted => 0,
+                Value::Object { id, peeled: None } => {
+                    value.extend_from_slice(id.as_bytes());
+                    1
+                }
+                Value::Object {
+                    id,
+                    peeled: Some(peeled),
+                } => {
+                    value.extend_from_slice(id.as_bytes());
+                    value.extend_from_slice(peeled.as_bytes());
+                    2
+                }
+                Value::Symbolic(target) => {
+                    put_varint(&mut value, target.len() as u64);
+                    value.extend_from_slice(target);
+                    3
+                }
+            };
+            blocks.add(&mut out, &record.name, value_type, &value)?;
+        }
+        blocks.flush(&mut out)?;
+
+        let mut log_position = 0;
+        if !self.logs.is_empty() {
+            // A log block that comes first starts the file, just like a ref block would.
+            if out.len() > header_len {
+                log_position = out.len();
+            }
+            let mut blocks = BlockWriter::new(BLOCK_TYPE_LOG, header_len);
+            for record in &self.logs {
+                let mut key = record.name.clone();
+                key.push(0);
+                key.extend_from_slice(&(!record.update_index).to_be_bytes());
+                let mut value = vec![];
+                let value_type = match &record.entry {
+                    None => 0,
+                    Some(entry) => {
+                        value.extend_from_slice(entry.old.as_bytes());
+                        value.extend_from_slice(entry.new.as_bytes());
+                        put_string(&mut value, &entry.name);
+                        put_string(&mut value, &entry.email);
+                        put_varint(&mut value, entry.time);
+                        value.extend_from_slice(&entry.tz_offset.to_be_bytes());
+                        put_string(&mut value, &entry.message);
+                        1
+                    }
+                };
+                blocks.add(&mut out, &key, value_type, &value)?;
+            }
+            blocks.flush(&mut out)?;
+        }
+
+        let footer_start = out.len();
+        out.extend_from_slice(&header);
+        // No ref index, objects, object index or log index.
+        for position in [0, 0, 0, log_position as u64, 0] {
+            out.extend_from_slice(&position.to_be_bytes());
+        }
+        let crc = crc32fast::hash(&out[footer_start..]);
+        out.extend_from_slice(&crc.to_be_bytes());
+        Ok(out)
+    }
+}
+
+/// Call `record` with the key, value type and value of every record in the blocks of type
+/// `block_type` between `start` and `end` of the table `data`.
+fn read_blocks(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    header_len: usize,
+    block_type: u8,
+    mut record: impl FnMut(&[u8], u8, &mut Reader) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut offset = start;
+    while offset < end {
+        // The first block of a table starts with the file header, and counts it.
+        let header_off = if offset == 0 { header_len } else { 0 };
+        let mut header = Reader::new(data, offset + header_off);
+        if header.byte()? != block_type {
+            break;
+        }
+        let block_len = header.u24()? as usize;
+        let (block, next) = if block_type == BLOCK_TYPE_LOG {
+            // Log blocks are deflated after their header; `block_len` is the inflated size.
+            let body = offset + header_off + 4;
+            let mut block = data[offset..body].to_vec();
+            block.resize(block_len.max(block.len()), 0);
+            let mut inflate = gix_zlib::Decompress::new();
+            let status = inflate
+                .decompress(
+                    &data[body..end],
+                    &mut block[header_off + 4..],
+                    gix_zlib::FlushDecompress::Finish,
+                )
+                .map_err(|e| anyhow!("corrupt log block: {}", e))?;
+            if status != gix_zlib::Status::StreamEnd
+                || inflate.total_out() as usize != block_len - header_off - 4
+            {
+                return Err(anyhow!("corrupt log block"));
+            }
+            (block, body + inflate.total_in() as usize)
+        } else {
+            let block = data
+                .get(offset..offset + block_len)
+                .ok_or_else(|| anyhow!("truncated block"))?;
+            (block.to_vec(), offset + block_len)
+        };
+
+        let restart_count = block
+            .len()
+            .checked_sub(2)
+            .map(|at| u16::from_be_bytes([block[at], block[at + 1]]) as usize)
+            .ok_or_else(|| anyhow!("truncated block"))?;
+        let records_end = block
+            .len()
+            .checked_sub(2 + 3 * restart_count)
+            .filter(|records_end| *records_end >= header_off + 4)
+            .ok_or_else(|| anyhow!("truncated block"))?;
+        let mut reader = Reader::new(&block[..records_end], header_off + 4);
+        let mut key = vec![];
+        while reader.pos < records_end {
+            let prefix_len = reader.varint()? as usize;
+            let suffix_and_type = reader.varint()?;
+            if prefix_len > key.len() {
+                return Err(anyhow!("malformed record key"));
+            }
+            key.truncate(prefix_len);
+            key.extend_from_slice(reader.bytes((suffix_and_type >> 3) as usize)?);
+            record(&key, (suffix_and_type & 7) as u8, &mut reader)?;
+        }
+
+        // Ref blocks are padded to the block size.
+        offset = next;
+        while offset < end && data[offset] == 0 {
+            offset += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Packs records, in key order, into the blocks of one section of a table.
+struct BlockWriter {
+    block_type: u8,
+    header_len: usize,
+    /// Where the current block starts in the table, once it has records.
+    start: Option<usize>,
+    records: Vec<u8>,
+    count: usize,
+    /// The offsets of the records that store their key whole, from the start of the block.
+    restarts: Vec<usize>,
+    last_key: Vec<u8>,
+}
+
+impl BlockWriter {
+    fn new(block_type: u8, header_len: usize) -> BlockWriter {
+        BlockWriter {
+            block_type,
+            header_len,
+            start: None,
+            records: vec![],
+            count: 0,
+            restarts: vec![],
+            last_key: vec![],
+        }
+    }
+
+    fn add(
+        &mut self,
+        out: &mut Vec<u8>,
+        key: &[u8],
+        value_type: u8,
+        value: &[u8],
+    ) -> anyhow::Result<()> {
+        let restart = self.count.is_multiple_of(RESTART_INTERVAL);
+        let last_key = if restart { &[][..] } else { &self.last_key };
+        let record = encode_record(last_key, key, value_type, value);
+        if self.fits(out, record.len(), restart) {
+            self.push(out, key, record, restart);
+            return Ok(());
+        }
+        self.flush(out)?;
+        let record = encode_record(&[], key, value_type, value);
+        // A log record too large for a block gets a larger block of its own: log blocks are
+        // not padded, so nothing depends on their size.
+        if !self.fits(out, record.len(), true) && self.block_type == BLOCK_TYPE_REF {
+            return Err(anyhow!(
+                "ref '{}' is too long for a reftable block",
+                String::from_utf8_lossy(key)
+            ));
+        }
+        self.push(out, key, record, true);
+        Ok(())
+    }
+
+    /// The offset from the start of the current block at which its records begin, past the
+    /// block header and, in the first block of the table, the file header.
+    fn records_offset(&mut self, out: &[u8]) -> usize {
+        let header_len = self.header_len;
+        let start = *self.start.get_or_insert(if out.len() == header_len {
+            0
+        } else {
+            out.len()
+        });
+        out.len() - start + 4
+    }
+
+    fn fits(&mut self, out: &[u8], record_len: usize, restart: bool) -> bool {
+        let restarts = self.restarts.len() + usize::from(restart);
+        self.records_offset(out) + self.records.len() + record_len + 3 * restarts + 2 <= BLOCK_SIZE
+    }
+
+    fn push(&mut self, out: &[u8], key: &[u8], record: Vec<u8>, restart: bool) {
+        if restart {
+            let offset = self.records_offset(out) + self.records.len();
+            self.restarts.push(offset);
+        }
+        self.records.extend_from_slice(&record);
+        self.last_key = key.to_vec();
+        self.count += 1;
+    }
+
+    /// Write the current block, if it has records, to `out`.
+    fn flush(&mut self, out: &mut Vec<u8>) -> anyhow::Result<()> {
+        let Some(start) = self.start.take() else {
+            return Ok(());
+        };
+        if self.count == 0 {
+            return Ok(());
+        }
+        let mut body = std::mem::take(&mut self.records);
+        let restart_count = self.restarts.len() as u16;
+        for restart in self.restarts.drain(..) {
+            body.extend_from_slice(&u24(restart)?);
+        }
+        body.extend_from_slice(&restart_count.to_be_bytes());
+        self.count = 0;
+        self.last_key.clear();
+
+        out.push(self.block_type);
+        out.extend_from_slice(&u24(out.len() - start + 3 + body.len())?);
+        if self.block_type == BLOCK_TYPE_LOG {
+            let mut deflate =
+                gix_zlib::stream::deflate::Write::new(Vec::new(), gix_zlib::Compression::DEFAULT);
+            deflate.write_all(&body).and_then(|_| deflate.flush())?;
+            out.extend_from_slice(&deflate.into_inner());
+        } else {
+            out.extend_from_slice(&body);
+            out.resize(start + BLOCK_SIZE, 0);
+        }
+        Ok(())
+    }
+}
+
+/// A record: its key, prefix-compressed against the key before it, then its value.
+fn encode_record(last_key: &[u8], key: &[u8], value_type: u8, value: &[u8]) -> Vec<u8> {
+    let prefix_len = last_key.iter().zip(key).take_while(|(a, b)| a == b).count();
+    let mut record = vec![];
+    put_varint(&mut record, prefix_len as u64);
+    put_varint(
+        &mut record,
+        (((key.len() - prefix_len) as u64) << 3) | u64::from(value_type),
+    );
+    record.extend_from_slice(&key[prefix_len..]);
+    record.extend_from_slice(value);
+    record
+}
+
+fn u24(value: usize) -> anyhow::Result<[u8; 3]> {
+    let value = u32::try_from(value)
+        .ok()
+        .filter(|value| *value < 1 << 24)
+        .ok_or_else(|| anyhow!("reftable block too large"))?;
+    let bytes = value.to_be_bytes();
+    Ok([bytes[1], bytes[2], bytes[3]])
+}
+
+/// git's variable-length integer, the one of pack offsets: big-endian groups of seven bits,
+/// each but the last with the high bit set and one less than its value.
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    let mut buf = [0u8; 10];
+    let mut pos = buf.len() - 1;
+    buf[pos] = (value & 0x7f) as u8;
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        pos -= 1;
+        buf[pos] = 0x80 | (value & 0x7f) as u8;
+        value >>= 7;
+    }
+    out.extend_from_slice(&buf[pos..]);
+}
+
+fn put_string(out: &mut Vec<u8>, value: &[u8]) {
+    put_varint(out, value.len() as u64);
+    out.extend_from_slice(value);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Reader<'a> {
+        Reader { data, pos }
+    }
+
+    fn bytes(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or_else(|| anyhow!("truncated reftable"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u24(&mut self) -> anyhow::Result<u32> {
+        let bytes = self.bytes(3)?;
+        Ok(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+    }
+
+    fn u64(&mut self) -> anyhow::Result<u64> {
+        Ok(u64::from_be_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn varint(&mut self) -> anyhow::Result<u64> {
+        let mut byte = self.byte()?;
+        let mut value = u64::from(byte & 0x7f);
+        while byte & 0x80 != 0 {
+            byte = self.byte()?;
+            value = value
+                .checked_add(1)
+                .and_then(|value| value.checked_mul(0x80))
+                .ok_or_else(|| anyhow!("varint overflows"))?
+                | u64::from(byte & 0x7f);
+        }
+        Ok(value)
+    }
+
+    fn oid(&mut self, hash: gix_hash::Kind) -> anyhow::Result<ObjectId> {
+        Ok(ObjectId::from_bytes_or_panic(
+            self.bytes(hash.len_in_bytes())?,
+        ))
+    }
+
+    fn string(&mut self) -> anyhow::Result<Vec<u8>> {
+        let len = self.varint()? as usize;
+        Ok(self.bytes(len)?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oid(byte: u8) -> ObjectId {
+        ObjectId::from_bytes_or_panic(&[byte; 20])
+    }
+
+    fn record(name: &str, update_index: u64, value: Value) -> RefRecord {
+        RefRecord {
+            name: name.as_bytes().to_vec(),
+            update_index,
+            value,
+        }
+    }
+
+    #[test]
+    fn varint_is_gits_offset_encoding() {
+        for (value, encoded) in [
+            (0, &[0x00][..]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x00]),
+            (16511, &[0xff, 0x7f]),
+            (16512, &[0x80, 0x80, 0x00]),
+        ] {
+            let mut out = vec![];
+            put_varint(&mut out, value);
+            assert_eq!(out, encoded, "{}", value);
+            assert_eq!(Reader::new(encoded, 0).varint().unwrap(), value);
+        }
+        let mut out = vec![];
+        put_varint(&mut out, u64::MAX);
+        assert_eq!(Reader::new(&out, 0).varint().unwrap(), u64::MAX);
+    }
+
+    /// A table assembled by hand from the format description: one ref block, unpadded, with
+    /// a symbolic `HEAD` and a branch whose key shares nothing with it.
+    #[test]
+    fn reads_hand_assembled_table() {
+        let mut header = b"REFT\x01".to_vec();
+        header.extend_from_slice(&[0, 0, 0]);
+        header.extend_from_slice(&5u64.to_be_bytes());
+        header.extend_from_slice(&6u64.to_be_bytes());
+
+        let mut records = vec![];
+        // HEAD: no prefix, 4 suffix bytes, symbolic; update 5 + 1.
+        records.extend_from_slice(&[0, (4 << 3) | 3]);
+        records.extend_from_slice(b"HEAD");
+        records.extend_from_slice(&[1, 15]);
+        records.extend_from_slice(b"refs/heads/main");
+        let second = header.len() + 4 + records.len();
+        // refs/heads/main: a restart point too, so no prefix; one object id.
+        records.extend_from_slice(&[0, (15 << 3) | 1]);
+        records.extend_from_slice(b"refs/heads/main");
+        records.push(0);
+        records.extend_from_slice(&[0xab; 20]);
+        let mut block = records;
+        block.extend_from_slice(&u24(header.len() + 4).unwrap());
+        block.extend_from_slice(&u24(second).unwrap());
+        block.extend_from_slice(&2u16.to_be_bytes());
+
+        let mut data = header.clone();
+        data.push(b'r');
+        data.extend_from_slice(&u24(header.len() + 4 + block.len()).unwrap());
+        data.extend_from_slice(&block);
+        let footer_start = data.len();
+        data.extend_from_slice(&header);
+        data.extend_from_slice(&[0; 40]);
+        let crc = crc32fast::hash(&data[footer_start..]);
+        data.extend_from_slice(&crc.to_be_bytes());
+
+        let table = Table::parse(&data, gix_hash::Kind::Sha1, true).unwrap();
+        assert_eq!(
+            table,
+            Table {
+                min_update_index: 5,
+                max_update_index: 6,
+                refs: vec![
+                    record("HEAD", 6, Value::Symbolic(b"refs/heads/main".to_vec())),
+                    record(
+                        "refs/heads/main",
+                        5,
+                        Value::Object {
+                            id: oid(0xab),
+                            peeled: None
+                        }
+                    ),
+                ],
+                logs: vec![],
+            }
+        );
+
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(Table::parse(&data, gix_hash::Kind::Sha1, true).is_err());
+    }
+
+    fn sample(count: usize, object_hash: gix_hash::Kind) -> Table {
+        let id = |byte: u8| ObjectId::from_bytes_or_panic(&vec![byte; object_hash.len_in_bytes()]);
+        let mut refs = vec![];
+        let mut logs = vec![];
+        for i in 0..count {
+            let name = format!("refs/heads/branch-{:05}", i).into_bytes();
+            let value = match i % 4 {
+                0 => Value::Deleted,
+                1 => Value::Object {
+                    id: id(i as u8),
+                    peeled: None,
+                },
+                2 => Value::Object {
+                    id: id(i as u8),
+                    peeled: Some(id(!(i as u8))),
+                },
+                _ => Value::Symbolic(b"refs/heads/main".to_vec()),
+            };
+            refs.push(RefRecord {
+                name: name.clone(),
+                update_index: 10 + (i % 3) as u64,
+                value,
+            });
+            for update_index in [12, 10] {
+                logs.push(LogRecord {
+                    name: name.clone(),
+                    update_index,
+                    entry: (i % 5 != 0).then(|| LogEntry {
+                        old: id(1),
+                        new: id(2),
+                        name: b"t".to_vec(),
+                        email: b"t@example.com".to_vec(),
+                        time: 1_700_000_000 + i as u64,
+                        tz_offset: -90,
+                        message: format!("update {}\n", i).into_bytes(),
+                    }),
+                });
+            }
+        }
+        Table {
+            min_update_index: 10,
+            max_update_index: 12,
+            refs,
+            logs,
+        }
+    }
+
+    #[test]
+    fn written_tables_read_back() {
+        for (count, object_hash) in [
+            (0, gix_hash::Kind::Sha1),
+            (1, gix_hash::Kind::Sha1),
+            (2000, gix_hash::Kind::Sha1),
+            (300, gix_hash::Kind::Sha256),
+        ] {
+            let table = sample(count, object_hash);
+            let data = table.write(object_hash).unwrap();
+            assert_eq!(
+                Table::parse(&data, object_hash, true).unwrap(),
+                table,
+                "{} refs, {}",
+                count,
+                object_hash
+            );
+        }
+        let data = sample(10, gix_hash::Kind::Sha1)
+            .write(gix_hash::Kind::Sha1)
+            .unwrap();
+        assert!(Table::parse(&data, gix_hash::Kind::Sha256, true).is_err());
+    }
+
+    /// Ref blocks fill up to the block size and are padded to it; the first one counts the
+    /// file header.
+    #[test]
+    fn ref_blocks_are_aligned() {
+        let data = sample(2000, gix_hash::Kind::Sha1)
+            .write(gix_hash::Kind::Sha1)
+            .unwrap();
+        assert_eq!(data[24], BLOCK_TYPE_REF);
+        let mut offset = BLOCK_SIZE;
+        while data[offset] == BLOCK_TYPE_REF {
+            offset += BLOCK_SIZE;
+        }
+        assert_eq!(data[offset], BLOCK_TYPE_LOG);
+        assert!(offset > 4 * BLOCK_SIZE);
+    }
+
+    #[test]
+    fn a_table_of_only_logs_starts_with_the_log_block() {
+        let mut table = sample(3, gix_hash::Kind::Sha1);
+        table.refs.clear();
+        let data = table.write(gix_hash::Kind::Sha1).unwrap();
+        assert_eq!(data[24], BLOCK_TYPE_LOG);
+        assert_eq!(
+            Table::parse(&data, gix_hash::Kind::Sha1, true).unwrap(),
+            table
+        );
+    }
+
+    fn update(stack: &Stack, refs: &[(&str, Value)]) {
+        stack
+            .update(|_| {
+                Ok(Update {
+                    refs: refs
+                        .iter()
+                        .map(|(name, value)| (name.as_bytes().to_vec(), value.clone()))
+                        .collect(),
+                    ..Update::default()
+                })
+            })
+            .unwrap();
+    }
+
+    fn object(byte: u8) -> Value {
+        Value::Object {
+            id: oid(byte),
+            peeled: None,
+        }
+    }
+
+    #[test]
+    fn stack_merges_updates() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = Stack::new(dir.path().to_owned(), gix_hash::Kind::Sha1);
+        assert!(stack.refs().unwrap().is_empty());
+
+        update(
+            &stack,
+            &[("refs/heads/a", object(1)), ("refs/heads/b", object(2))],
+        );
+        update(&stack, &[("refs/heads/a", object(3))]);
+        update(&stack, &[("refs/heads/b", Value::Deleted)]);
+        let refs = stack.refs().unwrap();
+        assert_eq!(
+            refs.iter().collect::<Vec<_>>(),
+            [(&b"refs/heads/a".to_vec(), &object(3))]
+        );
+
+        // Another handle on the same stack sees the same refs.
+        let other = Stack::new(dir.path().to_owned(), gix_hash::Kind::Sha1);
+        assert_eq!(other.refs().unwrap(), refs);
+    }
+
+    #[test]
+    fn stack_stays_short() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = Stack::new(dir.path().to_owned(), gix_hash::Kind::Sha1);
+        for i in 0..200u8 {
+            let name = format!("refs/heads/b{}", i % 7);
+            update(&stack, &[(name.as_str(), object(i))]);
+        }
+        let list = stack.read_list().unwrap();
+        assert!(table_names(&list).count() <= 8, "{}", list);
+        // Exactly the tables listed are left.
+        assert_eq!(
+            std::fs::read_dir(dir.path()).unwrap().count(),
+            table_names(&list).count() + 1
+        );
+        let refs = stack.refs().unwrap();
+        assert_eq!(refs.len(), 7);
+        assert_eq!(refs[&b"refs/heads/b3".to_vec()], object(199));
+    }
+
+    #[test]
+    fn updates_see_the_stack_and_its_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = Stack::new(dir.path().to_owned(), gix_hash::Kind::Sha1);
+        let entry = LogEntry {
+            old: gix_hash::Kind::Sha1.null(),
+            new: oid(1),
+            name: b"t".to_vec(),
+            email: b"t@example.com".to_vec(),
+            time: 0,
+            tz_offset: 0,
+            message: vec![],
+        };
+        for _ in 0..2 {
+            stack
+                .update(|_| {
+                    Ok(Update {
+                        refs: vec![(b"refs/heads/a".to_vec(), object(1))],
+                        logs: vec![(b"refs/heads/a".to_vec(), entry.clone())],
+                        ..Update::default()
+                    })
+                })
+                .unwrap();
+        }
+        stack
+            .update(|tables| {
+                assert_eq!(tables.get(b"refs/heads/a"), Some(&object(1)));
+                assert_eq!(tables.get(b"refs/heads/b"), None);
+                assert_eq!(tables.log_indices(b"refs/heads/a"), [1, 2]);
+                Ok(Update {
+                    refs: vec![(b"refs/heads/a".to_vec(), Value::Deleted)],
+                    deleted_logs: vec![(b"refs/heads/a".to_vec(), 1)],
+                    ..Update::default()
+                })
+            })
+            .unwrap();
+        stack
+            .update(|tables| {
+                assert_eq!(tables.get(b"refs/heads/a"), None);
+                assert_eq!(tables.log_indices(b"refs/heads/a"), [2]);
+                Ok(Update::default())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn locked_stack_is_not_updated() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = Stack::new(dir.path().to_owned(), gix_hash::Kind::Sha1);
+        std::fs::write(dir.path().join("tables.list.lock"), "").unwrap();
+        assert!(stack.update(|_| Ok(Update::default())).is_err());
+        assert!(stack.refs().unwrap().is_empty());
+    }
+}
@@ -18,6 +18,9 @@ struct State {
     path: Option<std::path::PathBuf>,
     db: Option<sled::Db>,
     trees: std::collections::HashMap<git2::Oid, sled::Tree>,
+    /// When each filter's last use was last written to [`ACCESS_TREE`] since the db was opened,
+    /// to write it at most once per [`ACCESS_INTERVAL`].
+    stamped: std::collections::HashMap<git2::Oid, u64>,
     active: usize,
 }
 
@@ -26,6 +29,7 @@ static STATE: LazyLock<std::sync::Mutex<State>> = LazyLock::new(|| {
         path: None,
         db: None,
         trees: Default::default(),
+        stamped: Default::default(),
         active: 0,
     })
 });
@@ -49,12 +53,30 @@ impl State {
         Ok(self.db.as_ref().unwrap())
     }
 
+    /// The tree of `filter`, for a read or write of it: also records the use in [`ACCESS_TREE`],
+    /// at most once per [`ACCESS_INTERVAL`] per filter, so the record follows the last use even
+    /// while the db (and the tree handle) stays open.
     fn tree(&mut self, filter: Filter) -> anyhow::Result<sled::Tree> {
-        if let Some(tree) = self.trees.get(&filter.id()) {
-            return Ok(tree.clone());
+        let tree = match self.trees.get(&filter.id()) {
+            Some(tree) => tree.clone(),
+            None => {
+                let tree = self.ensure_open()?.open_tree(filter::spec(filter))?;
+                self.trees.insert(filter.id(), tree.clone());
+                tree
+            }
+        };
+
+        let now = unix_now();
+        let due = self
+            .stamped
+            .get(&filter.id())
+            .is_none_or(|stamped| now.saturating_sub(*stamped) >= ACCESS_INTERVAL);
+        if due {
+            self.ensure_open()?
+                .open_tree(ACCESS_TREE)?
+                .insert(filter::spec(filter), &now.to_be_bytes())?;
+            self.stamped.insert(filter.id(), now);
         }
-        let tree = self.ensure_open()?.open_tree(filter::spec(filter))?;
-        self.trees.insert(filter.id(), tree.clone());
         Ok(tree)
     }
 
@@ -65,10 +87,24 @@ impl State {
             log::error!("failed to flush sled cache: {e}");
         }
         self.trees.clear();
+        self.stamped.clear();
         self.db = None;
     }
 }
 
+/// The tree recording, per filter tree name, the unix time the filter was last used. Not a
+/// valid filter spec, so it can never collide with a filter tree.
+const ACCESS_TREE: &str = "josh/last-access";
+
+/// Seconds between two writes of a filter's last use.
+const ACCESS_INTERVAL: u64 = 60;
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
 /// Per-filter on-disk cache backed by sled.
 ///
 /// The backend is a thin handle onto the process-wide sled db (see [`State`]); the db itself is
@@ -160,6 +196,10 @@ fn with_maintenance_db<T>(
     }
     let opened_here = state.db.is_none();
     let result = f(state.ensure_open()?);
+    // Maintenance may drop trees and their access records; let active transactions reopen their
+    // handles and record their next use.
+    state.trees.clear();
+    state.stamped.clear();
     // If we opened the db just for this maintenance op, close it again to keep the "open only while
     // a transaction is active" invariant.
     if opened_here && state.active == 0 {
@@ -184,37 +224,116 @@ pub fn sled_clear() -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn sled_print_stats() -> anyhow::Result<()> {
-    let printed = with_maintenance_db(|db| {
-        db.flush()?;
-        log::debug!("Trees:");
+/// Usage of one filter tree of the sled cache.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SledTreeStats {
+    /// The tree name: the spec of the filter.
+    pub name: String,
+    pub entries: usize,
+    /// Size of the records, not counting sled's own overhead.
+    pub bytes: u64,
+    /// Unix time of the last use, `None` for trees not used since access tracking started.
+    pub last_access: Option<u64>,
+}
 
-        let mut v = vec![];
-        for name in db.tree_names() {
-            let name = String::from_utf8(name.to_vec())?;
-            let t = db.open_tree(&name)?;
-
-            if !t.is_empty() {
-                let name = if let Ok(filter) = filter::parse(&name) {
-                    filter::pretty(filter, 4)
-                } else {
-                    name.clone()
-                };
-                v.push((t.len(), name));
-            }
+fn tree_stats(db: &sled::Db) -> anyhow::Result<Vec<SledTreeStats>> {
+    let access = db.open_tree(ACCESS_TREE)?;
+    let mut stats = vec![];
+    for name in db.tree_names() {
+        if name == db.name() || name == ACCESS_TREE.as_bytes() {
+            continue;
         }
+        let tree = db.open_tree(&name)?;
+        let entries = tree.len();
+        // Every record is a key and a value oid, of the hash of the repo the cache is for.
+        let record_bytes = tree
+            .first()?
+            .map_or(0, |(key, value)| (key.len() + value.len()) as u64);
+        let last_access = access
+            .get(&name)?
+            .and_then(|v| Some(u64::from_be_bytes(v.as_ref().try_into().ok()?)));
+        stats.push(SledTreeStats {
+            name: String::from_utf8(name.to_vec())?,
+            entries,
+            bytes: entries as u64 * record_bytes,
+            last_access,
+        });
+    }
+    Ok(stats)
+}
 
-        v.sort();
+/// Per-filter usage of the on-disk cache, largest trees first. Errors if no cache directory has
+/// been configured.
+pub fn sled_stats() -> anyhow::Result<Vec<SledTreeStats>> {
+    let mut stats = with_maintenance_db(|db| {
+        db.flush()?;
+        tree_stats(db)
+    })?
+    .ok_or_else(|| anyhow!("cache not initialized"))?;
+    stats.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    Ok(stats)
+}
+
+/// Bound the on-disk cache by dropping whole filter trees, and return the dropped ones:
+///
+/// * empty trees,
+/// * trees not used within `max_age`,
+/// * then least recently used trees until the records of the remaining ones fit in `max_bytes`.
+///
+/// Trees without a recorded access count as the least recently used. Dropping a tree in use
+/// is safe -- its filter is recomputed on the next use -- but costly, so limits should leave
+/// room for the working set. Errors if no cache directory has been configured.
+pub fn sled_gc(
+    max_bytes: Option<u64>,
+    max_age: Option<std::time::Duration>,
+) -> anyhow::Result<Vec<SledTreeStats>> {
+    with_maintenance_db(|db| {
+        db.flush()?;
+        let mut stats = tree_stats(db)?;
+        stats.sort_by_key(|tree| (tree.last_access, tree.bytes));
 
-        for (len, name) in v.iter() {
-            println!("[{}] {}", len, name);
+        let now = unix_now();
+        let mut total: u64 = stats.iter().map(|tree| tree.bytes).sum();
+        let mut evicted = vec![];
+        for tree in stats {
+            let expired = match (max_age, tree.last_access) {
+                (Some(max_age), Some(last_access)) => {
+                    now.saturating_sub(last_access) > max_age.as_secs()
+                }
+                (Some(_), None) => true,
+                (None, _) => false,
+            };
+            let too_big = max_bytes.is_some_and(|max| total > max);
+            if tree.entries == 0 || expired || too_big {
+                db.drop_tree(&tree.name)?;
+                db.open_tree(ACCESS_TREE)?.remove(&tree.name)?;
+                total -= tree.bytes;
+                evicted.push(tree);
+            }
         }
+        db.flush()?;
+        Ok(evicted)
+    })?
+    .ok_or_else(|| anyhow!("cache not initialized"))
+}
 
-        Ok(())
-    })?;
+pub fn sled_print_stats() -> anyhow::Result<()> {
+    let mut v: Vec<_> = sled_stats()?
+        .into_iter()
+        .filter(|tree| tree.entries > 0)
+        .map(|tree| {
+            let name = if let Ok(filter) = filter::parse(&tree.name) {
+                filter::pretty(filter, 4)
+            } else {
+                tree.name
+            };
+            (tree.entries, name)
+        })
+        .collect();
+    v.sort();
 
-    if printed.is_none() {
-        return Err(anyhow!("cache not initialized"));
+    for (len, name) in v.iter() {
+        println!("[{}] {}", len, name);
     }
 
     Ok(())
@@ -1,8 +1,10 @@
 use super::backend::HistoryGraphHint;
 use super::history_graph::compute_history_hint;
+use super::refs::{RefStore, object_target};
 use super::stack::CacheStack;
 use super::tree_cache::{TreeBytes, TreeCache};
 use anyhow::anyhow;
+use gix_ref::transaction::PreviousValue;
 
 use std::collections::HashMap;
 use std::sync::{LazyLock, RwLock};
@@ -76,6 +78,12 @@ static PATHS_MAP: LazyLock<RwLock<HashMap<(git2::Oid, String), git2::Oid>>> =
 static INVERT_MAP: LazyLock<RwLock<HashMap<(git2::Oid, String), git2::Oid>>> =
     LazyLock::new(Default::default);
 
+// Per-blob memoization for `:eol=...` and `:utf8`, keyed by (input blob, conversion). Which
+// conversion a blob gets depends on the attributes of the path it is found at; the tree walk
+// resolves those, so the entry itself is shared by every path the blob appears at.
+static NORMALIZE_MAP: LazyLock<RwLock<HashMap<(git2::Oid, String), git2::Oid>>> =
+    LazyLock::new(Default::default);
+
 /// Placeholder hint for tree-keyed records with no commit context. Sequence 0 is
 /// eligible and lands in shard 0 of the distributed backend; the local backend
 /// ignores the hint.
@@ -86,6 +94,13 @@ const TREE_KEYED_FALLBACK_HINT: HistoryGraphHint = HistoryGraphHint {
     jump_is_second: false,
 };
 
+/// The cache stack namespace of the records that run `filter` backwards, of the given `kind`:
+/// the filter tagged with a `cache` meta entry. Forward filtering never looks such a filter up,
+/// and the sled backend keeps it in a tree of its own, named after the filter.
+fn reverse_namespace(filter: crate::filter::Filter, kind: &str) -> crate::filter::Filter {
+    filter.with_meta("cache", kind)
+}
+
 /// Clear the process-global in-memory caches shared across all transactions.
 pub fn clear_global_caches() {
     REF_CACHE.write().unwrap().clear();
@@ -93,6 +108,7 @@ pub fn clear_global_caches() {
     GLOB_MAP.write().unwrap().clear();
     PATHS_MAP.write().unwrap().clear();
     INVERT_MAP.write().unwrap().clear();
+    NORMALIZE_MAP.write().unwrap().clear();
 }
 
 pub struct TransactionContext {
@@ -101,11 +117,13 @@ pub struct TransactionContext {
     ref_prefix: Option<String>,
     mem_odb_limit: Option<usize>,
     ephemeral: bool,
+    threads: usize,
 }
 
 impl TransactionContext {
     pub fn from_env(cache: std::sync::Arc<CacheStack>) -> anyhow::Result<Self> {
-        let repo = git2::Repository::open_from_env()?;
+        crate::git::configure_libgit2();
+        let repo = git2::Repository::open_from_env().map_err(open_error)?;
         let path = repo.path().to_owned();
 
         Ok(Self {
@@ -114,6 +132,7 @@ impl TransactionContext {
             ref_prefix: None,
             mem_odb_limit: None,
             ephemeral: false,
+            threads: 1,
         })
     }
 
@@ -124,6 +143,7 @@ impl TransactionContext {
             ref_prefix: None,
             mem_odb_limit: None,
             ephemeral: false,
+            threads: 1,
         }
     }
 
@@ -137,6 +157,14 @@ impl TransactionContext {
         self
     }
 
+    /// Let history walks filter independent parts of the commit graph on up to `threads`
+    /// threads (see [`crate::history::walk2`]). The result is the same as with the default of 1;
+    /// only histories with parallel branches get faster.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
     pub fn ephemeral(mut self) -> Self {
         self.mem_odb_limit = None;
         self.ephemeral = true;
@@ -149,22 +177,56 @@ impl TransactionContext {
         }
 
         Ok(Transaction::new(
-            git2::Repository::open_ext(
-                &self.path,
-                git2::RepositoryOpenFlags::NO_SEARCH,
-                &[] as &[&std::ffi::OsStr],
-            )?,
+            open_repo(&self.path)?,
             self.cache.clone(),
             self.ref_prefix.as_deref(),
             self.mem_odb_limit,
             self.ephemeral,
+            self.threads,
+            None,
         ))
     }
 }
 
+/// Open the repository at `path` exactly (no discovery).
+fn open_repo(path: &std::path::Path) -> anyhow::Result<git2::Repository> {
+    crate::git::configure_libgit2();
+    git2::Repository::open_ext(
+        path,
+        git2::RepositoryOpenFlags::NO_SEARCH,
+        &[] as &[&std::ffi::OsStr],
+    )
+    .map_err(open_error)
+}
+
+/// libgit2 opens SHA-256 repositories only when josh is built with the `sha256` feature; without
+/// it, libgit2 refuses them and the error says how to get a josh that can.
+fn open_error(e: git2::Error) -> anyhow::Error {
+    if e.message().starts_with("unknown object format") {
+        anyhow!(
+            "{}; josh is built without SHA-256 support (build it with the `sha256` feature)",
+            e.message()
+        )
+    } else {
+        e.into()
+    }
+}
+
+/// The repository's `core.bigFileThreshold`, git's default when unset or unreadable.
+fn big_object_threshold(repo: &git2::Repository) -> u64 {
+    repo.config()
+        .and_then(|config| config.get_i64("core.bigFileThreshold"))
+        .ok()
+        .and_then(|threshold| u64::try_from(threshold).ok())
+        .unwrap_or(josh_memodb::DEFAULT_BIG_OBJECT_THRESHOLD)
+}
+
+type CommitMap = HashMap<git2::Oid, HashMap<git2::Oid, git2::Oid>>;
+type SharedCommitMap = std::sync::Arc<RwLock<CommitMap>>;
+
 #[allow(unused)]
 struct Transaction2 {
-    commit_map: HashMap<git2::Oid, HashMap<git2::Oid, git2::Oid>>,
+    commit_map: CommitMap,
     apply_map: HashMap<git2::Oid, HashMap<git2::Oid, git2::Oid>>,
     subtract_map: HashMap<(git2::Oid, git2::Oid), git2::Oid>,
     intersect_map: HashMap<(git2::Oid, git2::Oid), git2::Oid>,
@@ -175,11 +237,14 @@ struct Transaction2 {
     merge_trees_map: HashMap<(git2::Oid, git2::Oid, git2::Oid), git2::Oid>,
     last_written_commit: Option<(git2::Oid, git2::Oid)>,
     tree_cache: TreeCache,
+    // Set while this transaction takes part in a parallel history walk: the commit map of all
+    // its participants. Replaces `commit_map` for writes until the walk joins its workers.
+    shared_commit_map: Option<SharedCommitMap>,
 
     cache: std::sync::Arc<CacheStack>,
-    // In-transaction memoization of the trigram index (source tree -> index tree); the
-    // cache backend behind it holds the durable, cross-transaction copy.
-    index_map: HashMap<git2::Oid, git2::Oid>,
+    // In-transaction memoization of the tree indexes ((index filter, source tree) -> index
+    // tree); the cache backend behind it holds the durable, cross-transaction copy.
+    index_map: HashMap<(crate::filter::Filter, git2::Oid), git2::Oid>,
     missing: Vec<(usize, crate::filter::Filter, git2::Oid)>,
     misses: usize,
     nesting_level: usize,
@@ -192,12 +257,29 @@ pub struct Transaction {
     /// borrows it for the entire call while also borrowing other caches through `t2`.
     trigram_indexer: std::cell::RefCell<josh_search::Indexer>,
     repo: git2::Repository,
+    /// The repository's refs. Every ref read and write of the transaction goes through here,
+    /// not through `repo`.
+    refs: RefStore,
     /// Per-transaction in-memory object store, flushed to a packfile when the transaction drops, at
     /// an explicit boundary, or mid-transaction when it exceeds its size limit. Never shared with
     /// another transaction.
     mem_odb: std::sync::Arc<josh_memodb::MemOdb>,
     mem_odb_limit: Option<usize>,
+    /// Where reads get the objects a partial clone left out; `None` for a complete
+    /// repository. Shared with forks, so an object one of them failed to fetch is not
+    /// fetched again by the others.
+    promisor: Option<std::sync::Arc<dyn josh_memodb::Promisor>>,
+    /// `core.bigFileThreshold`: objects above it are written straight to disk and read as
+    /// streams instead of going through the memory store.
+    big_object_threshold: u64,
     ephemeral: bool,
+    /// A worker of a parallel history walk (see [`Transaction::fork_workers`]): its memory store
+    /// belongs to the transaction it was forked from, which flushes it.
+    forked: bool,
+    threads: usize,
+    /// The runtime alternates added with [`Transaction::add_disk_alternate`], for forks to
+    /// register on their own repository handle.
+    disk_alternates: std::cell::RefCell<Vec<String>>,
     ref_prefix: Option<String>,
     filter_hook: Option<std::sync::Arc<dyn FilterHook + Send + Sync>>,
 }
@@ -208,8 +290,9 @@ impl Drop for Transaction {
         // locking backend can release once the last transaction ends.
         self.t2.borrow().cache.end();
 
-        // Skip flushing to disk, the mem odb will be lost, as requested.
-        if self.ephemeral {
+        // Skip flushing to disk, the mem odb will be lost, as requested -- or, for a fork, is
+        // flushed by its owner.
+        if self.ephemeral || self.forked {
             return;
         }
 
@@ -226,24 +309,20 @@ impl Transaction {
         ref_prefix: Option<&str>,
         mem_odb_limit: Option<usize>,
         ephemeral: bool,
+        threads: usize,
+        mem_odb: Option<std::sync::Arc<josh_memodb::MemOdb>>,
     ) -> Transaction {
-        // Turn off libgit2's strictness checks. These are process-wide C globals, set
-        // exactly once.
-        static GIT2_OPTIONS: std::sync::Once = std::sync::Once::new();
-        GIT2_OPTIONS.call_once(|| {
-            // Don't check per write that referenced objects exist: josh only ever writes
-            // objects whose referenced objects it has just produced or read, so the checks
-            // are pure overhead.
-            git2::opts::strict_object_creation(false);
-            // Don't re-hash objects on every read: libgit2 defaults to verifying each
-            // object against its id with collision-detecting SHA1 on every lookup. josh
-            // only reads objects it wrote itself or that git verified on transfer, so this
-            // costs a full hash pass per object read and buys nothing.
-            git2::opts::strict_hash_verification(false);
+        crate::git::configure_libgit2();
+
+        let forked = mem_odb.is_some();
+        let mem_odb = mem_odb.unwrap_or_else(|| {
+            josh_memodb::MemOdb::new(
+                mem_odb_limit,
+                josh_memodb::objects_dir(&repo),
+                josh_memodb::object_hash(&repo),
+            )
         });
 
-        let mem_odb = josh_memodb::MemOdb::new(mem_odb_limit, josh_memodb::objects_dir(&repo));
-
         // Balanced in `Drop`; lets a locking backend (sled) hold its lock only while a
         // transaction is live.
         cache.begin();
@@ -263,6 +342,7 @@ impl Transaction {
                 merge_trees_map: HashMap::new(),
                 last_written_commit: None,
                 tree_cache: Default::default(),
+                shared_commit_map: None,
                 cache,
                 index_map: HashMap::new(),
                 missing: vec![],
@@ -270,10 +350,17 @@ impl Transaction {
                 nesting_level: 0,
             }),
             trigram_indexer: Default::default(),
+            refs: RefStore::open(&repo),
+            promisor: super::promisor::GitPromisor::detect(&repo)
+                .map(|promisor| std::sync::Arc::new(promisor) as _),
+            big_object_threshold: big_object_threshold(&repo),
             repo,
             mem_odb,
             mem_odb_limit,
             ephemeral,
+            forked,
+            threads,
+            disk_alternates: Default::default(),
             ref_prefix: ref_prefix.map(|prefix| prefix.to_owned()),
             filter_hook: None,
         }
@@ -286,27 +373,109 @@ impl Transaction {
             ref_prefix: self.ref_prefix.clone(),
             mem_odb_limit: self.mem_odb_limit,
             ephemeral: self.ephemeral,
+            threads: self.threads,
         };
 
         context.open()
     }
 
+    /// How many threads history walks may use; see [`TransactionContext::with_threads`].
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Open `n` transactions for the worker threads of a parallel history walk. They share
+    /// this transaction's memory store, cache stack and filter hook, and -- until
+    /// [`Transaction::join_workers`] -- one commit map with it and each other, so every commit
+    /// filtered by one of them is known to all.
+    pub(crate) fn fork_workers(&self, n: usize) -> anyhow::Result<Vec<Transaction>> {
+        let shared = {
+            let mut t2 = self.t2.borrow_mut();
+            let commit_map = std::mem::take(&mut t2.commit_map);
+            t2.shared_commit_map
+                .get_or_insert_with(|| std::sync::Arc::new(RwLock::new(commit_map)))
+                .clone()
+        };
+        let cache = self.t2.borrow().cache.clone();
+
+        let mut workers = Vec::with_capacity(n);
+        for _ in 0..n {
+            let repo = open_repo(self.repo.path())?;
+            for path in self.disk_alternates.borrow().iter() {
+                repo.odb()?.add_disk_alternate(path)?;
+            }
+            let mut worker = Transaction::new(
+                repo,
+                cache.clone(),
+                self.ref_prefix.as_deref(),
+                self.mem_odb_limit,
+                self.ephemeral,
+                1,
+                Some(self.mem_odb.clone()),
+            );
+            worker.filter_hook = self.filter_hook.clone();
+            worker.promisor = self.promisor.clone();
+            worker.t2.get_mut().shared_commit_map = Some(shared.clone());
+            workers.push(worker);
+        }
+        Ok(workers)
+    }
+
+    /// End a parallel history walk: take over what the workers recorded as missing, and make
+    /// the shared commit map this transaction's own again.
+    pub(crate) fn join_workers(&self, workers: Vec<Transaction>) {
+        let mut t2 = self.t2.borrow_mut();
+        for worker in workers {
+            let mut w2 = worker.t2.borrow_mut();
+            t2.misses += w2.misses;
+            t2.missing.append(&mut w2.missing);
+        }
+        if let Some(shared) = t2.shared_commit_map.take() {
+            let shared = std::mem::take(&mut *shared.write().unwrap());
+            for (filter, map) in shared {
+                t2.commit_map.entry(filter).or_default().extend(map);
+            }
+        }
+    }
+
     /// The libgit2 handle on this repository, for the porcelain josh has not moved to gix:
-    /// worktree and index operations, `FETCH_HEAD`'s multi-entry semantics, notes, and
-    /// git's DWIM name resolution. It must never read or write objects josh produces --
-    /// those live in this transaction's store until it flushes, and this handle cannot see
-    /// them (use [`Transaction::odb`]).
+    /// worktree and index operations, diff line statistics, revision parsing and remote and
+    /// branch configuration. Refs are not read or written through it (see [`RefStore`]), and
+    /// neither are objects: those josh produces live in this transaction's store until it
+    /// flushes, and this handle cannot see them (use [`Transaction::odb`]).
     pub fn git2_repo(&self) -> &git2::Repository {
         &self.repo
     }
 
     /// The transaction's object-database facade: memory store first, repository odb
-    /// fallback (see [`josh_memodb::Odb`]).
+    /// fallback, and in a partial clone the promisor remote for objects missing on disk (see
+    /// [`josh_memodb::Odb`]).
     pub fn odb(&self) -> anyhow::Result<josh_memodb::Odb<'_>> {
-        Ok(josh_memodb::Odb::new(
-            self.mem_odb.clone(),
-            self.repo.odb()?,
-        ))
+        Ok(
+            josh_memodb::Odb::new(self.mem_odb.clone(), self.repo.odb()?)
+                .with_promisor(self.promisor.clone())
+                .with_big_object_threshold(self.big_object_threshold),
+        )
+    }
+
+    /// The hash kind of the repository's object format.
+    pub fn object_hash(&self) -> gix_hash::Kind {
+        self.mem_odb.object_hash()
+    }
+
+    /// The cache stack namespace of `filter`'s records. Outside SHA-1 repositories the filter is
+    /// tagged with the object format, so a backend shared between repositories of both formats
+    /// never serves the records of one to the other. The sequence number and root filters are
+    /// kept as they are: the backends recognise them by identity.
+    fn cache_namespace(&self, filter: crate::filter::Filter) -> crate::filter::Filter {
+        let kind = self.object_hash();
+        if kind == gix_hash::Kind::Sha1
+            || filter == crate::filter::sequence_number()
+            || filter == crate::filter::reachable_roots()
+        {
+            return filter;
+        }
+        filter.with_meta("object-format", kind.to_string())
     }
 
     /// Add `path` (an objects directory) as a runtime alternate of both the repository odb
@@ -314,6 +483,7 @@ impl Transaction {
     pub fn add_disk_alternate(&self, path: &str) -> anyhow::Result<()> {
         self.repo.odb()?.add_disk_alternate(path)?;
         self.mem_odb.add_alternate(path)?;
+        self.disk_alternates.borrow_mut().push(path.to_string());
         Ok(())
     }
 
@@ -387,11 +557,26 @@ impl Transaction {
     /// exist. The target is not peeled: for an annotated tag ref this is the tag oid,
     /// peeling is an object-store concern.
     pub fn resolve_ref(&self, refname: &str) -> anyhow::Result<Option<git2::Oid>> {
-        match self.repo.refname_to_id(refname) {
-            Ok(oid) => Ok(Some(oid)),
-            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
-            Err(e) => Err(e.into()),
-        }
+        self.refs.resolve(refname)
+    }
+
+    /// The name of the ref the symbolic ref `refname` points at, such as the branch
+    /// `refs/remotes/origin/HEAD` names. `Ok(None)` when `refname` does not exist or is a
+    /// direct ref. The target need not exist.
+    pub fn symbolic_target(&self, refname: &str) -> anyhow::Result<Option<String>> {
+        self.refs.symbolic_target(refname)
+    }
+
+    /// The commit the last `git fetch` into this repository fetched, from `FETCH_HEAD`:
+    /// its first entry, as `git rev-parse FETCH_HEAD` reads it (see
+    /// [`RefStore::fetch_head`]). Peeled to a commit through the transaction's objects;
+    /// errors when nothing was fetched yet.
+    pub fn fetch_head(&self) -> anyhow::Result<git2::Oid> {
+        let target = self
+            .refs
+            .fetch_head()?
+            .ok_or_else(|| anyhow!("FETCH_HEAD not found"))?;
+        crate::objects::peel_to_commit(&self.odb()?, target)
     }
 
     /// The repository's git directory, for the callers that build paths beside it or hand
@@ -403,20 +588,25 @@ impl Transaction {
     /// Where HEAD points. Errors when HEAD is unborn (a repository whose HEAD names a
     /// branch that does not exist yet) or detached at an object that is not a commit;
     /// annotated tags are peeled. The commit is resolved through the transaction's objects,
-    /// so a HEAD moved to a commit this transaction produced resolves. (gix mapping:
-    /// `Repository::head()` + `head_id()`.)
+    /// so a HEAD moved to a commit this transaction produced resolves.
     pub fn head(&self) -> anyhow::Result<Head> {
-        let head = self.repo.head()?;
-        let reference = if head.is_branch() {
-            head.name()
-                .map_err(|e| anyhow!("HEAD ref name is not valid UTF-8: {}", e))?
-                .to_string()
+        let head = self
+            .refs
+            .resolve_chain("HEAD")?
+            .ok_or_else(|| anyhow!("HEAD not found"))?;
+        let name = std::str::from_utf8(head.name.as_bstr())
+            .map_err(|e| anyhow!("HEAD ref name is not valid UTF-8: {}", e))?;
+        let target = match head.target {
+            gix_ref::Target::Object(id) => crate::objects::git2_oid(&id),
+            gix_ref::Target::Symbolic(_) => {
+                return Err(anyhow!("HEAD points to the unborn branch '{}'", name));
+            }
+        };
+        let reference = if name.starts_with("refs/heads/") {
+            name.to_string()
         } else {
             "HEAD".to_string()
         };
-        let target = head
-            .target()
-            .ok_or_else(|| anyhow!("HEAD does not point at an object"))?;
         Ok(Head {
             reference,
             target,
@@ -429,7 +619,20 @@ impl Transaction {
     /// nothing, which covers both a malformed spec and one naming something absent: a
     /// revision a user typed is input, not a contract. (gix mapping: `rev_parse_single`.)
     pub fn rev_parse(&self, spec: &str) -> anyhow::Result<Option<git2::Oid>> {
-        match self.repo.revparse_single(spec) {
+        // The ref a spec starts from is looked up in the ref store, which knows every ref
+        // storage; libgit2 only knows files. Like libgit2, a full object id is not taken for a
+        // ref name.
+        let (base, rest) = spec.split_at(spec.find(['~', '^', ':']).unwrap_or(spec.len()));
+        let spec = match self.refs.expand(base)? {
+            Some(name) if gix_hash::ObjectId::from_hex(base.as_bytes()).is_err() => {
+                match self.refs.resolve(&name)? {
+                    Some(oid) => format!("{}{}", oid, rest),
+                    None => return Ok(None),
+                }
+            }
+            _ => spec.to_owned(),
+        };
+        match self.repo.revparse_single(&spec) {
             Ok(object) => Ok(Some(object.id())),
             Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
             Err(e) if e.code() == git2::ErrorCode::InvalidSpec => Ok(None),
@@ -439,15 +642,9 @@ impl Transaction {
 
     /// The fully qualified name of the ref a short name refers to, resolved the way git
     /// resolves an argument that could name several things (`master` ->
-    /// `refs/heads/master`). `Ok(None)` when no ref matches. (gix mapping:
-    /// `find_reference` with a partial name.)
+    /// `refs/heads/master`). `Ok(None)` when no ref matches.
     pub fn expand_ref_name(&self, short_name: &str) -> anyhow::Result<Option<String>> {
-        match self.repo.resolve_reference_from_short_name(short_name) {
-            Ok(reference) => Ok(reference.name().ok().map(|name| name.to_string())),
-            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
-            Err(e) if e.code() == git2::ErrorCode::InvalidSpec => Ok(None),
-            Err(e) => Err(e.into()),
-        }
+        self.refs.expand(short_name)
     }
 
     /// The remote-tracking ref that `branch_ref` is configured to track, from
@@ -485,8 +682,7 @@ impl Transaction {
     /// On a failed assertion — including a ref that appeared or disappeared
     /// concurrently — an error is returned and the ref is unchanged. Writers that derive
     /// `target` from the ref's old value pass `At`/`Absent` so they never overwrite a
-    /// concurrent update. (gix mapping: `PreviousValue::Any` / `MustExistAndMatch` /
-    /// `MustNotExist`.)
+    /// concurrent update.
     pub fn update_ref(
         &self,
         refname: &str,
@@ -494,19 +690,13 @@ impl Transaction {
         target: git2::Oid,
         log_message: &str,
     ) -> anyhow::Result<()> {
-        match expected {
-            Expected::Any => {
-                self.repo.reference(refname, target, true, log_message)?;
-            }
-            Expected::At(old) => {
-                self.repo
-                    .reference_matching(refname, target, true, old, log_message)?;
-            }
-            Expected::Absent => {
-                self.repo.reference(refname, target, false, log_message)?;
-            }
-        }
-        Ok(())
+        let expected = match expected {
+            Expected::Any => PreviousValue::Any,
+            Expected::At(old) => PreviousValue::MustExistAndMatch(object_target(old)),
+            Expected::Absent => PreviousValue::MustNotExist,
+        };
+        self.refs
+            .update(refname, expected, object_target(target), log_message)
     }
 
     /// Delete the ref `refname`, guarded by `expected`: `Expected::Any` deletes whatever
@@ -514,49 +704,37 @@ impl Transaction {
     /// currently points at `oid` and errors, leaving the ref in place, on mismatch or
     /// absence. `Expected::Absent` is a contract error. The ref entry itself is deleted
     /// (a symbolic ref is deleted, not followed); loose and packed entries and the reflog
-    /// are removed. Under git2 an `Any` delete of a ref concurrently modified (not
-    /// deleted) mid-call may error (libgit2 CASes against the value read at find time);
-    /// gix's Any-delete succeeds -- acceptable widening at flag day. (gix mapping: RefEdit
-    /// Change::Delete with PreviousValue::Any / MustExistAndMatch, RefLog::AndReference.)
+    /// are removed. An `Any` delete of a ref concurrently modified (not deleted) mid-call
+    /// deletes the modified ref.
     pub fn delete_ref(&self, refname: &str, expected: Expected) -> anyhow::Result<()> {
-        match expected {
-            Expected::Absent => Err(anyhow!("delete_ref: Expected::Absent is not a valid guard")),
-            Expected::Any => match self.repo.find_reference(refname) {
-                Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(()),
-                Err(e) => Err(e.into()),
-                Ok(mut reference) => match reference.delete() {
-                    Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(()),
-                    other => Ok(other?),
-                },
-            },
-            Expected::At(old) => {
-                let mut reference = self.repo.find_reference(refname)?;
-                if reference.target() != Some(old) {
-                    return Err(anyhow!(
-                        "delete_ref: '{}' does not point at {}",
-                        refname,
-                        old
-                    ));
-                }
-                Ok(reference.delete()?)
+        let expected = match expected {
+            Expected::Absent => {
+                return Err(anyhow!("delete_ref: Expected::Absent is not a valid guard"));
             }
-        }
+            Expected::Any => PreviousValue::Any,
+            Expected::At(old) => PreviousValue::MustExistAndMatch(object_target(old)),
+        };
+        self.refs.delete(refname, expected)
     }
 
     /// Force-create or update the symbolic ref `refname` to point at the ref named
     /// `target`, which is validated for refname format but need not exist (dangling
     /// symrefs are allowed). Always overwrites, like `Expected::Any`; grow a guard
-    /// parameter only when a consumer needs one (as update_ref did). (gix mapping:
-    /// RefEdit Change::Update with Target::Symbolic, PreviousValue::Any.)
+    /// parameter only when a consumer needs one (as update_ref did).
     pub fn create_symref(
         &self,
         refname: &str,
         target: &str,
         log_message: &str,
     ) -> anyhow::Result<()> {
-        self.repo
-            .reference_symbolic(refname, target, true, log_message)?;
-        Ok(())
+        let target = gix_ref::FullName::try_from(target)
+            .map_err(|e| anyhow!("invalid symref target '{}': {}", target, e))?;
+        self.refs.update(
+            refname,
+            PreviousValue::Any,
+            gix_ref::Target::Symbolic(target),
+            log_message,
+        )
     }
 
     /// Run `cb` for every direct ref whose full name starts with `prefix`, byte-sorted by
@@ -567,23 +745,12 @@ impl Transaction {
         prefix: &str,
         mut cb: impl FnMut(&str, git2::Oid) -> anyhow::Result<()>,
     ) -> anyhow::Result<()> {
-        // Glob metacharacters (*?[\) are all invalid in refnames, so appending `*` — which
-        // matches across `/` — turns the glob walk into plain prefix iteration.
         debug_assert!(
             !prefix.contains(['*', '?', '[', '\\']),
             "prefix must consist of refname-valid characters"
         );
-        let mut refs = vec![];
-        for reference in self.repo.references_glob(&format!("{}*", prefix))? {
-            let reference = reference?;
-            if let (Ok(name), Some(target)) = (reference.name(), reference.target()) {
-                refs.push((name.to_owned(), target));
-            }
-        }
-        // git2 yields loose refs in filesystem order followed by packed ones; sorting makes
-        // the order part of the API contract instead of an artifact of the backend.
-        refs.sort();
-        for (name, target) in refs {
+        // Collected up front, so `cb` may update or delete the refs it is handed.
+        for (name, target) in self.refs.prefixed(prefix)? {
             cb(&name, target)?;
         }
         Ok(())
@@ -734,9 +901,19 @@ impl Transaction {
 
     /// Build a josh-search index cache that shards records by `commit`'s history-graph
     /// position. Pass [`git2::Oid::ZERO_SHA1`] for a bare tree with no commit context.
-    pub fn trigram_index_cache(&self, commit: git2::Oid) -> TrigramIndexCache<'_> {
-        TrigramIndexCache {
+    pub fn trigram_index_cache(&self, commit: git2::Oid) -> TreeIndexCache<'_> {
+        TreeIndexCache {
+            transaction: self,
+            filter: crate::filter::index(),
+            hint: self.tree_keyed_hint(commit),
+        }
+    }
+
+    /// Like [`Transaction::trigram_index_cache`], for `josh_search::symbol_index`.
+    pub fn symbol_index_cache(&self, commit: git2::Oid) -> TreeIndexCache<'_> {
+        TreeIndexCache {
             transaction: self,
+            filter: crate::filter::symbols(),
             hint: self.tree_keyed_hint(commit),
         }
     }
@@ -745,29 +922,44 @@ impl Transaction {
     /// of the commit being indexed. Falls back to the placeholder when there is no commit
     /// context or the hint cannot be computed.
     fn tree_keyed_hint(&self, commit: git2::Oid) -> HistoryGraphHint {
-        if commit == git2::Oid::ZERO_SHA1 {
+        if commit.is_zero() {
             return TREE_KEYED_FALLBACK_HINT;
         }
         compute_history_hint(self, commit).unwrap_or(TREE_KEYED_FALLBACK_HINT)
     }
 
-    fn insert_trigram_index(&self, tree: git2::Oid, result: git2::Oid, hint: HistoryGraphHint) {
-        let filter = crate::filter::index();
+    fn insert_tree_index(
+        &self,
+        filter: crate::filter::Filter,
+        tree: git2::Oid,
+        result: git2::Oid,
+        hint: HistoryGraphHint,
+    ) {
         let mut t2 = self.t2.borrow_mut();
-        t2.index_map.entry(tree).or_insert(result);
-        if let Err(e) = t2.cache.write_all(filter, tree, result, hint, true) {
-            log::warn!("trigram index cache write failed: {e}");
+        t2.index_map.entry((filter, tree)).or_insert(result);
+        if let Err(e) = t2
+            .cache
+            .write_all(self.cache_namespace(filter), tree, result, hint, true)
+        {
+            log::warn!("tree index cache write failed: {e}");
         }
     }
 
-    fn get_trigram_index(&self, tree: git2::Oid, hint: HistoryGraphHint) -> Option<git2::Oid> {
-        let filter = crate::filter::index();
+    fn get_tree_index(
+        &self,
+        filter: crate::filter::Filter,
+        tree: git2::Oid,
+        hint: HistoryGraphHint,
+    ) -> Option<git2::Oid> {
         let t2 = self.t2.borrow_mut();
-        if let Some(oid) = t2.index_map.get(&tree).cloned() {
+        if let Some(oid) = t2.index_map.get(&(filter, tree)).cloned() {
             // Written this transaction, so the index tree is live in the mem odb -- no odb check.
             return Some(oid);
         }
-        let oid = t2.cache.read_propagate(filter, tree, hint, true).ok()??;
+        let oid = t2
+            .cache
+            .read_propagate(self.cache_namespace(filter), tree, hint, true)
+            .ok()??;
         // Per-subtree index trees are anchored by no ref, so gc may have pruned a
         // cached one; treat a dangling hit as a miss and reindex.
         if self.odb().ok()?.contains(oid) {
@@ -793,6 +985,30 @@ impl Transaction {
         GLOB_MAP.read().unwrap().get(&tree).cloned()
     }
 
+    pub fn insert_normalized(&self, blob: (git2::Oid, String), result: git2::Oid) {
+        NORMALIZE_MAP.write().unwrap().entry(blob).or_insert(result);
+    }
+
+    pub fn get_normalized(&self, blob: (git2::Oid, String)) -> Option<git2::Oid> {
+        NORMALIZE_MAP.read().unwrap().get(&blob).cloned()
+    }
+
+    /// Record that `filter` (an `Op::Normalize`) produced a blob from `original`, under the
+    /// normalized blob and conversion hashed into `key`, so that `:restore[...]` can give the
+    /// original bytes back. Kept in the cache stack like [`Transaction::insert_unapply_tree`].
+    pub fn insert_restore(
+        &self,
+        filter: crate::filter::Filter,
+        key: git2::Oid,
+        original: git2::Oid,
+    ) {
+        self.insert_reverse(reverse_namespace(filter, "restore"), key, original);
+    }
+
+    pub fn get_restore(&self, filter: crate::filter::Filter, key: git2::Oid) -> Option<git2::Oid> {
+        self.get_reverse(reverse_namespace(filter, "restore"), key)
+    }
+
     pub fn insert_ref(&self, filter: crate::filter::Filter, from: git2::Oid, to: git2::Oid) {
         REF_CACHE
             .write()
@@ -820,6 +1036,93 @@ impl Transaction {
         None
     }
 
+    /// Record that [`crate::filter::unapply`] of `filter` produced the tree `result` for the
+    /// inputs hashed into `key`. Kept in the cache stack, so later pushes through the same
+    /// filter reuse it.
+    pub fn insert_unapply_tree(
+        &self,
+        filter: crate::filter::Filter,
+        key: git2::Oid,
+        result: git2::Oid,
+    ) {
+        self.insert_reverse(reverse_namespace(filter, "unapply"), key, result);
+    }
+
+    pub fn get_unapply_tree(
+        &self,
+        filter: crate::filter::Filter,
+        key: git2::Oid,
+    ) -> Option<git2::Oid> {
+        self.get_reverse(reverse_namespace(filter, "unapply"), key)
+    }
+
+    /// Record `original` as the original commit of the filtered commit described by `key`
+    /// (see [`crate::history::unapply_filter`]). Kept in the cache stack like
+    /// [`Transaction::insert_unapply_tree`].
+    pub fn insert_original(
+        &self,
+        filter: crate::filter::Filter,
+        key: git2::Oid,
+        original: git2::Oid,
+    ) {
+        self.insert_reverse(reverse_namespace(filter, "original"), key, original);
+    }
+
+    pub fn get_original(&self, filter: crate::filter::Filter, key: git2::Oid) -> Option<git2::Oid> {
+        self.get_reverse(reverse_namespace(filter, "original"), key)
+    }
+
+    fn insert_reverse(&self, namespace: crate::filter::Filter, key: git2::Oid, to: git2::Oid) {
+        let mut t2 = self.t2.borrow_mut();
+        t2.unapply_map
+            .entry(namespace.id())
+            .or_default()
+            .insert(key, to);
+        if let Err(e) = t2.cache.write_all(
+            self.cache_namespace(namespace),
+            key,
+            to,
+            TREE_KEYED_FALLBACK_HINT,
+            true,
+        ) {
+            log::warn!("unapply cache write failed: {e}");
+        }
+    }
+
+    fn get_reverse(&self, namespace: crate::filter::Filter, key: git2::Oid) -> Option<git2::Oid> {
+        if let Some(oid) = self
+            .t2
+            .borrow()
+            .unapply_map
+            .get(&namespace.id())
+            .and_then(|m| m.get(&key))
+        {
+            return Some(*oid);
+        }
+        let oid = self
+            .t2
+            .borrow()
+            .cache
+            .read_propagate(
+                self.cache_namespace(namespace),
+                key,
+                TREE_KEYED_FALLBACK_HINT,
+                true,
+            )
+            .ok()??;
+        // What an unapply produces is anchored by no ref until the push lands, so gc may have
+        // pruned it since; treat a dangling hit as a miss. By kind, not `contains`: an unapplied
+        // tree can be the empty tree, which need not be stored anywhere.
+        self.odb().ok()?.try_kind(oid).ok()??;
+        self.t2
+            .borrow_mut()
+            .unapply_map
+            .entry(namespace.id())
+            .or_default()
+            .insert(key, oid);
+        Some(oid)
+    }
+
     pub fn lookup_filter_hook(
         &self,
         hook: &str,
@@ -856,10 +1159,19 @@ impl Transaction {
             }
         };
         let mut t2 = self.t2.borrow_mut();
-        t2.commit_map
-            .entry(filter.id())
-            .or_default()
-            .insert(from, to);
+        if let Some(shared) = &t2.shared_commit_map {
+            shared
+                .write()
+                .unwrap()
+                .entry(filter.id())
+                .or_default()
+                .insert(from, to);
+        } else {
+            t2.commit_map
+                .entry(filter.id())
+                .or_default()
+                .insert(from, to);
+        }
 
         // In addition to commits that are explicitly requested to be stored, also store the
         // sample points, so that every backward walk reaches a stored entry within a bounded
@@ -867,7 +1179,8 @@ impl Transaction {
         // filters that reduce the history length by a very large factor (which store almost
         // nothing of their own) from re-walking all of history on every request.
         if store || hint.is_sample_point() {
-            t2.cache.write_all(filter, from, to, hint, false)?;
+            t2.cache
+                .write_all(self.cache_namespace(filter), from, to, hint, false)?;
         }
         Ok(())
     }
@@ -933,11 +1246,19 @@ impl Transaction {
         {
             return Ok(Some(oid));
         }
+        if let Some(shared) = &t2.shared_commit_map
+            && let Some(m) = shared.read().unwrap().get(&filter.id())
+            && let Some(oid) = m.get(&from).cloned()
+        {
+            return Ok(Some(oid));
+        }
 
-        let oid = t2.cache.read_propagate(filter, from, hint, false)?;
+        let oid = t2
+            .cache
+            .read_propagate(self.cache_namespace(filter), from, hint, false)?;
 
         if let Some(oid) = oid {
-            if oid == git2::Oid::ZERO_SHA1 {
+            if oid.is_zero() {
                 return Ok(Some(oid));
             }
             if filter == crate::filter::sequence_number() {
@@ -963,23 +1284,25 @@ impl Transaction {
     }
 }
 
-/// josh-search index memoization backed by the cache backend under the `:INDEX`
-/// filter: durable (sled) and shareable (distributed) across processes, keyed by
-/// source tree oid. The bound `hint` is the indexed commit's history position,
+/// josh-search index memoization backed by the cache backend under the index's filter
+/// (`:INDEX` or `:SYMBOLS`): durable (sled) and shareable (distributed) across processes,
+/// keyed by source tree oid. The bound `hint` is the indexed commit's history position,
 /// resolved once per commit and reused for every per-subtree lookup of its walk.
-pub struct TrigramIndexCache<'a> {
+pub struct TreeIndexCache<'a> {
     transaction: &'a Transaction,
+    filter: crate::filter::Filter,
     hint: HistoryGraphHint,
 }
 
-impl josh_search::IndexCache for TrigramIndexCache<'_> {
+impl josh_search::IndexCache for TreeIndexCache<'_> {
     fn get_index(&self, tree: git2::Oid) -> Option<git2::Oid> {
-        self.transaction.get_trigram_index(tree, self.hint)
+        self.transaction
+            .get_tree_index(self.filter, tree, self.hint)
     }
 
     fn set_index(&self, tree: git2::Oid, index: git2::Oid) {
         self.transaction
-            .insert_trigram_index(tree, index, self.hint)
+            .insert_tree_index(self.filter, tree, index, self.hint)
     }
 }
 
@@ -1408,4 +1731,94 @@ mod tests {
             Some(oid)
         );
     }
+
+    #[test]
+    fn head_names_branch_or_detached_head() {
+        let (_dir, transaction) = test_transaction();
+        let oid = commit(&transaction, "a");
+        transaction
+            .update_ref("refs/heads/main", Expected::Any, oid, "test")
+            .unwrap();
+        transaction
+            .create_symref("HEAD", "refs/heads/main", "test")
+            .unwrap();
+        let head = transaction.head().unwrap();
+        assert_eq!(head.reference, "refs/heads/main");
+        assert_eq!((head.target, head.commit), (oid, oid));
+
+        transaction
+            .update_ref("HEAD", Expected::Any, oid, "test")
+            .unwrap();
+        let head = transaction.head().unwrap();
+        assert_eq!(head.reference, "HEAD");
+        assert_eq!(head.branch(), None);
+        assert_eq!(head.commit, oid);
+    }
+
+    #[test]
+    fn head_unborn_is_error() {
+        let (_dir, transaction) = test_transaction();
+        assert!(transaction.head().is_err());
+    }
+
+    /// `FETCH_HEAD` lists one line per fetched ref, the ones fetched for merging first; the
+    /// first line is what `git rev-parse FETCH_HEAD` names.
+    #[test]
+    fn fetch_head_is_first_entry() {
+        let (dir, transaction) = test_transaction();
+        assert!(transaction.fetch_head().is_err());
+
+        let a = commit(&transaction, "a");
+        let b = commit(&transaction, "b");
+        std::fs::write(
+            dir.path().join("FETCH_HEAD"),
+            format!(
+                "{}\t\tbranch 'main' of /remote\n\
+                 {}\tnot-for-merge\tbranch 'other' of /remote\n",
+                a, b
+            ),
+        )
+        .unwrap();
+        assert_eq!(transaction.fetch_head().unwrap(), a);
+    }
+
+    /// As in git, bare repositories keep no reflogs unless `core.logAllRefUpdates` asks for
+    /// them, and repositories with a worktree keep them for branches.
+    #[test]
+    fn reflogs_follow_log_all_ref_updates() {
+        let open = |path: &std::path::Path| {
+            TransactionContext::new(path, std::sync::Arc::new(crate::cache::CacheStack::new()))
+                .open()
+                .unwrap()
+        };
+
+        let (dir, transaction) = test_transaction();
+        let oid = commit(&transaction, "a");
+        transaction
+            .update_ref("refs/heads/main", Expected::Any, oid, "test")
+            .unwrap();
+        assert!(!dir.path().join("logs/refs/heads/main").exists());
+
+        git2::Repository::open(dir.path())
+            .unwrap()
+            .config()
+            .unwrap()
+            .set_str("core.logAllRefUpdates", "always")
+            .unwrap();
+        open(dir.path())
+            .update_ref("refs/josh/a", Expected::Any, oid, "test")
+            .unwrap();
+        assert!(dir.path().join("logs/refs/josh/a").exists());
+
+        let worktree = tempfile::tempdir().unwrap();
+        git2::Repository::init(worktree.path()).unwrap();
+        let transaction = open(&worktree.path().join(".git"));
+        let oid = commit(&transaction, "a");
+        transaction
+            .update_ref("refs/heads/main", Expected::Any, oid, "test message")
+            .unwrap();
+        let log =
+            std::fs::read_to_string(worktree.path().join(".git/logs/refs/heads/main")).unwrap();
+        assert!(log.trim_end().ends_with("\ttest message"), "{log}");
+    }
 }
@@ -0,0 +1,195 @@
+//! The subset of `.gitattributes` the attribute-driven filters need: per-directory rule files,
+//! fnmatch patterns, set/unset/unspecified/value states and the `binary` macro. Rules are
+//! collected while walking down a tree, so a subtree only ever sees the rules of its ancestors
+//! and its own `.gitattributes`; there is no support for `$GIT_DIR/info/attributes` or for
+//! user-defined macros.
+
+/// gitattributes patterns are matched with fnmatch semantics: `*` never crosses a '/', but
+/// unlike `Op::Pattern` a leading dot needs no literal match.
+const ATTR_MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// The state of one attribute for a path, as in gitattributes(5).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum AttrState {
+    /// `attr`
+    Set,
+    /// `-attr`
+    Unset,
+    /// `!attr`, or no matching rule at all
+    Unspecified,
+    /// `attr=value`
+    Value(String),
+}
+
+impl AttrState {
+    /// Whether the attribute is set, either bare or with a value.
+    pub(crate) fn is_set(&self) -> bool {
+        matches!(self, AttrState::Set | AttrState::Value(_))
+    }
+}
+
+/// The assignment of one attribute on one line of a `.gitattributes` file.
+#[derive(Clone, Debug)]
+pub(crate) struct AttrRule {
+    /// Slash-separated directory of the `.gitattributes` file the rule comes from ("" for the
+    /// root).
+    base: String,
+    pattern: glob::Pattern,
+    /// A pattern containing a '/' matches the path relative to `base`; any other pattern matches
+    /// the entry name at any depth below `base`.
+    anchored: bool,
+    attr: String,
+    state: AttrState,
+}
+
+/// The assignments to any of `attrs` in the `.gitattributes` file of directory `base`, in file
+/// order. Comments, lines not mentioning one of `attrs` and patterns that do not compile are
+/// skipped. The built-in `binary` macro is expanded to `-text` (and `-diff`, `-merge`).
+pub(crate) fn parse(base: &str, content: &str, attrs: &[&str]) -> Vec<AttrRule> {
+    let mut rules = vec![];
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let Some(pattern) = words.next() else {
+            continue;
+        };
+        let Ok(compiled) = glob::Pattern::new(pattern.trim_start_matches('/')) else {
+            continue;
+        };
+
+        let mut assignments: Vec<(&str, AttrState)> = vec![];
+        for word in words {
+            let expanded = if word == "binary" {
+                vec![
+                    ("text", AttrState::Unset),
+                    ("diff", AttrState::Unset),
+                    ("merge", AttrState::Unset),
+                ]
+            } else if let Some(name) = word.strip_prefix('-') {
+                vec![(name, AttrState::Unset)]
+            } else if let Some(name) = word.strip_prefix('!') {
+                vec![(name, AttrState::Unspecified)]
+            } else if let Some((name, value)) = word.split_once('=') {
+                vec![(name, AttrState::Value(value.to_string()))]
+            } else {
+                vec![(word, AttrState::Set)]
+            };
+            for (name, state) in expanded {
+                if attrs.contains(&name) {
+                    // The last mention of an attribute on a line wins.
+                    assignments.retain(|(n, _)| *n != name);
+                    assignments.push((name, state));
+                }
+            }
+        }
+
+        rules.extend(assignments.into_iter().map(|(name, state)| AttrRule {
+            base: base.to_string(),
+            pattern: compiled.clone(),
+            anchored: pattern.contains('/'),
+            attr: name.to_string(),
+            state,
+        }));
+    }
+    rules
+}
+
+/// The state of `attr` for the entry `name` at `path`: the last matching rule wins, and rules of
+/// deeper `.gitattributes` files come after those of their ancestors.
+pub(crate) fn lookup(rules: &[AttrRule], attr: &str, path: &str, name: &str) -> AttrState {
+    rules
+        .iter()
+        .rev()
+        .filter(|rule| rule.attr == attr)
+        .find_map(|rule| {
+            let matched = if rule.anchored {
+                let rel = if rule.base.is_empty() {
+                    Some(path)
+                } else {
+                    path.strip_prefix(rule.base.as_str())
+                        .and_then(|p| p.strip_prefix('/'))
+                };
+                rel.is_some_and(|rel| rule.pattern.matches_with(rel, ATTR_MATCH_OPTIONS))
+            } else {
+                rule.pattern.matches_with(name, ATTR_MATCH_OPTIONS)
+            };
+            matched.then(|| rule.state.clone())
+        })
+        .unwrap_or(AttrState::Unspecified)
+}
+
+/// A cache key for a subtree at `path` reached with the `inherited` rules, for walks whose
+/// result depends on nothing but the subtree, `key` and those rules. The path only takes part
+/// when an inherited rule is anchored, so subtrees without such rules share entries across paths.
+pub(crate) fn cache_key(
+    key: &str,
+    path: &str,
+    inherited: &[AttrRule],
+) -> anyhow::Result<git2::Oid> {
+    let mut rules_key = key.to_string();
+    if inherited.iter().any(|rule| rule.anchored) {
+        rules_key.push_str(&format!(":{}", path));
+    }
+    for rule in inherited {
+        rules_key.push_str(&format!(
+            "\n{}:{}:{}:{}={:?}",
+            rule.base,
+            rule.pattern.as_str(),
+            rule.anchored,
+            rule.attr,
+            rule.state
+        ));
+    }
+    Ok(git2::Oid::hash_object(
+        git2::ObjectType::Blob,
+        rules_key.as_bytes(),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_follows_gitattributes_precedence() {
+        let mut rules = parse(
+            "",
+            "# comment\n*.txt text eol=crlf\n*.bin binary\ndocs/*.txt -text\n",
+            &["text", "eol"],
+        );
+        rules.extend(parse("docs", "a.txt !text\n", &["text", "eol"]));
+
+        assert_eq!(lookup(&rules, "text", "x.txt", "x.txt"), AttrState::Set);
+        assert_eq!(
+            lookup(&rules, "eol", "src/x.txt", "x.txt"),
+            AttrState::Value("crlf".to_string())
+        );
+        assert_eq!(
+            lookup(&rules, "text", "img.bin", "img.bin"),
+            AttrState::Unset
+        );
+        assert_eq!(
+            lookup(&rules, "text", "docs/b.txt", "b.txt"),
+            AttrState::Unset
+        );
+        assert_eq!(
+            lookup(&rules, "text", "docs/a.txt", "a.txt"),
+            AttrState::Unspecified
+        );
+        assert_eq!(
+            lookup(&rules, "text", "other/docs/b.txt", "b.txt"),
+            AttrState::Set
+        );
+        assert_eq!(
+            lookup(&rules, "diff", "img.bin", "img.bin"),
+            AttrState::Unspecified
+        );
+    }
+}
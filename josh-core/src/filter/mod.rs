@@ -13,13 +13,16 @@ pub use josh_filter::filter::MESSAGE_MATCH_ALL_REGEX;
 pub use josh_filter::filter::index;
 pub use josh_filter::filter::reachable_roots;
 pub use josh_filter::filter::sequence_number;
-pub use josh_filter::flang::parse::{get_comments, parse};
+pub use josh_filter::filter::symbols;
+pub use josh_filter::flang::lint;
+pub use josh_filter::flang::parse::{get_comments, item_spans, parse};
 pub use josh_filter::opt;
 pub use josh_filter::opt::invert;
 pub use josh_filter::persist::{peel_filter, peel_op, to_filter, to_op, to_ops};
-pub use josh_filter::{Filter, InsertContent, LazyRef, Op, RevMatch};
+pub use josh_filter::{BlobSelect, Filter, InsertContent, LazyRef, Normalize, Op, RevMatch};
 pub use josh_filter::{as_file, pretty, spec};
 
+mod attributes;
 pub mod text;
 pub mod tree;
 
@@ -601,7 +604,7 @@ pub fn apply_to_commit2(
         Op::Chain(_) => {
             let mut current_oid = commit_id;
             for f in flatten_chain(filter) {
-                if current_oid == git2::Oid::ZERO_SHA1 {
+                if current_oid.is_zero() {
                     break;
                 }
                 let r = some_or!(apply_to_commit2(f, current_oid, transaction)?, {
@@ -758,7 +761,7 @@ pub fn apply_to_commit2(
                 ".link.josh",
             ) {
                 if let Some(commit_str) = link_file.get_meta("commit") {
-                    if let Ok(commit_oid) = git2::Oid::from_str(&commit_str) {
+                    if let Ok(commit_oid) = objects::parse_oid(&commit_str) {
                         if filtered_parent_ids.contains(&commit_oid) {
                             while filtered_parent_ids[0] != commit_oid {
                                 filtered_parent_ids.rotate_right(1);
@@ -794,7 +797,7 @@ pub fn apply_to_commit2(
             let mut link_parents = vec![];
             for (link_path, link_file) in find_link_files(&odb, commit.tree_id()?)?.into_iter() {
                 if let Some(commit_str) = link_file.get_meta("commit") {
-                    if let Ok(commit_oid) = git2::Oid::from_str(&commit_str) {
+                    if let Ok(commit_oid) = objects::parse_oid(&commit_str) {
                         if let Some(cmt) =
                             transaction.get(to_filter(Op::Prefix(link_path)), commit_oid)?
                         {
@@ -1053,7 +1056,7 @@ pub fn apply_to_commit2(
                         ".link.josh",
                     ) {
                         if let Some(commit_str) = link.get_meta("commit") {
-                            if let Ok(link_commit) = git2::Oid::from_str(&commit_str) {
+                            if let Ok(link_commit) = objects::parse_oid(&commit_str) {
                                 if commit.id() == link_commit {
                                     let unapply =
                                         to_filter(Op::Unapply(LazyRef::Resolved(parent.id()), *uf));
@@ -1089,7 +1092,7 @@ pub fn apply_to_commit2(
                 let subdir = filter::invert(link.peel())?;
                 let unapply = to_filter(Op::Unapply(LazyRef::Resolved(commit.id()), subdir));
                 if let Some(commit_str) = link.get_meta("commit") {
-                    if let Ok(commit_oid) = git2::Oid::from_str(&commit_str) {
+                    if let Ok(commit_oid) = objects::parse_oid(&commit_str) {
                         let r = some_or!(transaction.get(unapply, commit_oid)?, {
                             return Ok(None);
                         });
@@ -1239,7 +1242,7 @@ fn apply_impl(
     let op = peel_op(filter);
     match &op {
         Op::Nop => Ok(x),
-        Op::Empty => Ok(x.with_tree(tree::empty_id())),
+        Op::Empty => Ok(x.with_tree(tree::empty_id(odb.object_hash()))),
         Op::Fold => Ok(x),
         Op::Squash(None) => Ok(x),
         Op::Author(author, email) => {
@@ -1252,7 +1255,7 @@ fn apply_impl(
         Op::Message(m, r) => {
             // Rewriting a message leaves the tree alone, so with neither a commit nor a
             // message to transform this is identity, like the other history-only filters.
-            if x.commit == git2::Oid::ZERO_SHA1 && x.message.is_none() {
+            if x.commit.is_zero() && x.message.is_none() {
                 return Ok(x);
             }
 
@@ -1408,7 +1411,7 @@ fn apply_impl(
                 // Get commit from metadata
                 let commit_oid = link_file
                     .get_meta("commit")
-                    .and_then(|s| git2::Oid::from_str(&s).ok())
+                    .and_then(|s| objects::parse_oid(&s).ok())
                     .ok_or_else(|| anyhow!("Link file missing commit metadata"))?;
 
                 let submodule_tree = git::read_tree_id(odb, commit_oid)?;
@@ -1476,6 +1479,37 @@ fn apply_impl(
             };
             Ok(if t == input { x } else { x.with_tree(t) })
         }
+        Op::Blobs(select) => {
+            let input = x.tree_id();
+            let key = peel_filter(filter).id();
+            let t = match select {
+                BlobSelect::Larger(size) => tree::remove_blob_pred(
+                    transaction,
+                    input,
+                    &|odb, oid| Ok(odb.read_header(oid)?.1 > *size),
+                    key,
+                )?,
+                BlobSelect::Binary => tree::remove_blob_pred(
+                    transaction,
+                    input,
+                    &|odb, oid| Ok(tree::is_binary_blob(odb, oid)),
+                    key,
+                )?,
+                BlobSelect::ExportIgnore => tree::remove_export_ignore(transaction, input, key)?,
+            };
+            Ok(if t == input { x } else { x.with_tree(t) })
+        }
+        Op::Normalize(n) | Op::Restore(n) => {
+            let input = x.tree_id();
+            // Both directions share the forward filter, which keys the restore records.
+            let forward = to_filter(Op::Normalize(*n));
+            let t = if let Op::Restore(_) = op {
+                tree::restore_normalized(transaction, input, *n, forward)?
+            } else {
+                tree::normalize(transaction, input, *n, forward)?
+            };
+            Ok(if t == input { x } else { x.with_tree(t) })
+        }
         Op::Insert(dest_path, content) => {
             let (oid, mode, is_tree) = match content {
                 InsertContent::Inline(s) => (
@@ -1509,7 +1543,7 @@ fn apply_impl(
             }
             Ok(x.with_tree(tree::insert_oid(
                 odb,
-                tree::empty_id(),
+                tree::empty_id(odb.object_hash()),
                 dest_path,
                 oid,
                 mode,
@@ -1525,7 +1559,7 @@ fn apply_impl(
                 };
             Ok(x.with_tree(tree::insert_oid(
                 odb,
-                tree::empty_id(),
+                tree::empty_id(odb.object_hash()),
                 dest_path,
                 file,
                 mode,
@@ -1537,7 +1571,7 @@ fn apply_impl(
             // subtree.
             let subtree = match tree::get_path_entry(transaction, odb, x.tree_id(), path) {
                 Ok(Some(entry)) if entry.mode.is_tree() => objects::git2_oid(&entry.oid),
-                _ => tree::empty_id(),
+                _ => tree::empty_id(odb.object_hash()),
             };
             Ok(x.with_tree(subtree))
         }
@@ -1545,7 +1579,7 @@ fn apply_impl(
             let tree = x.tree_id();
             Ok(x.with_tree(tree::insert_oid(
                 odb,
-                tree::empty_id(),
+                tree::empty_id(odb.object_hash()),
                 path,
                 tree,
                 git2::FileMode::Tree.into(),
@@ -1584,6 +1618,11 @@ fn apply_impl(
                 tree,
             )?))
         }
+        Op::Symbols => {
+            let index_cache = transaction.symbol_index_cache(x.commit);
+            let tree = x.tree_id();
+            Ok(x.with_tree(josh_search::symbol_index(odb, &index_cache, tree)?))
+        }
 
         Op::Invert => {
             let tree = x.tree_id();
@@ -1626,7 +1665,7 @@ fn apply_impl(
                 let blob_oid = odb.write(gix_object::Kind::Blob, oid_str.as_bytes());
                 Ok(x.with_tree(tree::insert_oid(
                     odb,
-                    tree::empty_id(),
+                    tree::empty_id(odb.object_hash()),
                     path,
                     blob_oid,
                     git2::FileMode::Blob.into(),
@@ -1652,7 +1691,7 @@ fn apply_impl(
             } else {
                 String::new()
             };
-            if let Ok(oid) = git2::Oid::from_str(&oid_str) {
+            if let Ok(oid) = objects::parse_oid(&oid_str) {
                 // Kind by header, never by `contains`: `read_header`'s disk fallback
                 // virtualizes the empty tree, `exists` does not.
                 let (oid, mode) = match odb.try_kind(oid) {
@@ -1662,13 +1701,19 @@ fn apply_impl(
                         return Err(anyhow::anyhow!(":#: object not found in repo: {}", oid));
                     }
                 };
-                Ok(x.with_tree(tree::insert_oid(odb, tree::empty_id(), path, oid, mode)?))
+                Ok(x.with_tree(tree::insert_oid(
+                    odb,
+                    tree::empty_id(odb.object_hash()),
+                    path,
+                    oid,
+                    mode,
+                )?))
             } else {
                 // Content is not a valid OID: insert empty blob at path.
                 let empty_blob = odb.write(gix_object::Kind::Blob, b"");
                 Ok(x.with_tree(tree::insert_oid(
                     odb,
-                    tree::empty_id(),
+                    tree::empty_id(odb.object_hash()),
                     path,
                     empty_blob,
                     git2::FileMode::Blob.into(),
@@ -1704,7 +1749,7 @@ fn apply_impl(
                 let target = objects::CommitData::read(odb, *target)?;
                 // The message must parse as an oid, so non-UTF-8 is an error.
                 let target_msg = target.message()?;
-                let target = git2::Oid::from_str(std::str::from_utf8(target_msg)?)?;
+                let target = objects::parse_oid(std::str::from_utf8(target_msg)?)?;
                 let target_tree = git::read_tree_id(odb, target)?;
                 /* dbg!(&uf); */
                 Ok(Rewrite::from_tree(filter::unapply(
@@ -1731,12 +1776,47 @@ fn apply_impl(
 /// `parent_tree`. It is required to reverse commit-resolved per-commit filters (`:rev` / `:hook`),
 /// whose sub-filter is chosen from commit identity rather than the tree; callers that only ever pass
 /// tree-reversible filters pass `None`.
+///
+/// Results are cached in the transaction's cache stack, keyed by the trees and, for filters that
+/// consult them, the commits.
 pub fn unapply(
     transaction: &cache::Transaction,
     filter: Filter,
     tree: git2::Oid,
     parent_tree: git2::Oid,
     commits: Option<(git2::Oid, git2::Oid)>,
+) -> anyhow::Result<git2::Oid> {
+    // Leaving the commits out of the key where they make no difference lets a reworded or
+    // rebased commit reuse the result for its unchanged tree.
+    let commits = commits.filter(|_| unapply_uses_commits(filter));
+    let key = git2::Oid::hash_object(
+        git2::ObjectType::Blob,
+        format!("unapply:{}:{}:{:?}", tree, parent_tree, commits).as_bytes(),
+    )?;
+    if let Some(cached) = transaction.get_unapply_tree(filter, key) {
+        return Ok(cached);
+    }
+    let result = unapply_uncached(transaction, filter, tree, parent_tree, commits)?;
+    transaction.insert_unapply_tree(filter, key, result);
+    Ok(result)
+}
+
+/// Whether [`unapply`] of `filter` depends on the commits it is given and not only on the trees:
+/// true for `:rev` and `:hook`, on their own or as a link of a chain -- the only places the
+/// commits are passed on to.
+fn unapply_uses_commits(filter: Filter) -> bool {
+    match peel_op(filter) {
+        Op::Chain(filters) => filters.iter().any(|f| unapply_uses_commits(*f)),
+        op => is_commit_resolved_filter(&op),
+    }
+}
+
+fn unapply_uncached(
+    transaction: &cache::Transaction,
+    filter: Filter,
+    tree: git2::Oid,
+    parent_tree: git2::Oid,
+    commits: Option<(git2::Oid, git2::Oid)>,
 ) -> anyhow::Result<git2::Oid> {
     // A `:rev(...)` filter has no static inverse (`invert` returns `Err`, like `:workspace` /
     // `:stored` / `:starlark`), so this generic path is automatically skipped for it and it falls
@@ -2063,13 +2143,245 @@ fn compute_warnings2(
 
     let x = apply(transaction, filter, Rewrite::from_tree(tree));
     if let Ok(x) = x
-        && x.tree_id() == tree::empty_id()
+        && objects::is_empty_tree(x.tree_id())
     {
         warnings.push(format!("No match for \"{}\"", pretty(filter, 2)));
     }
     warnings
 }
 
+/// Where one path of a filtered tree came from, see [`explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathOrigin {
+    /// The path in the filtered tree.
+    pub path: String,
+    /// The input path the entry was taken from; `None` when the filter generated it (`:$`,
+    /// `:insert` and the like) or the mapping is not a plain path translation.
+    pub source: Option<String>,
+    /// The top-level compose entry that produced the path (the whole filter when it is not a
+    /// compose).
+    pub filter: Filter,
+}
+
+/// Why an input path is not part of the filtered tree, see [`explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DropReason {
+    /// No compose entry selects the path.
+    NotSelected,
+    /// The path lies below the source directory of this entry, but an op inside it (typically
+    /// an `:exclude`) removes it.
+    Removed(Filter),
+    /// The path was mapped to `output` by one entry, but the later entry `by` produced the same
+    /// output path and wins.
+    Overwritten { output: String, by: Filter },
+}
+
+/// An input path missing from the filtered tree, see [`explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedPath {
+    pub path: String,
+    pub reason: DropReason,
+}
+
+/// A path an entry would have produced, but did not because an earlier entry already
+/// consumed its source: compose entries never share input paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShadowedPath {
+    pub origin: PathOrigin,
+    /// The earlier entry that took the source path.
+    pub by: Filter,
+}
+
+/// The provenance of a filtered tree, as computed by [`explain`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Explanation {
+    /// Every path of the filtered tree, sorted.
+    pub outputs: Vec<PathOrigin>,
+    /// Every input path that is not in the filtered tree, sorted.
+    pub dropped: Vec<DroppedPath>,
+    /// Outputs that lost against an earlier entry, in entry order.
+    pub shadowed: Vec<ShadowedPath>,
+}
+
+/// All non-tree paths of `tree`, sorted.
+fn tree_paths(odb: &josh_memodb::Odb, tree: git2::Oid) -> anyhow::Result<Vec<String>> {
+    let mut paths = vec![];
+    objects::walk_tree_preorder(odb, tree, &mut |parent, entry| {
+        if !entry.mode.is_tree() {
+            let name = std::str::from_utf8(entry.filename)
+                .map_err(|_| anyhow!("non-utf8 file name in tree walk"))?;
+            let separator = if parent.is_empty() { "" } else { "/" };
+            paths.push(format!("{}{}{}", parent, separator, name));
+        }
+        Ok(())
+    })?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// The input path `output` was mapped from by `filter`, following [`src_path`] and
+/// [`dst_path`]; `None` when that path does not exist in the input.
+fn source_of(filter: Filter, output: &str, input: &[String]) -> Option<String> {
+    let rest = Path::new(output).strip_prefix(dst_path(filter)).ok()?;
+    let mut source = src_path(filter);
+    if !rest.as_os_str().is_empty() {
+        source.push(rest);
+    }
+    let source = source.to_string_lossy().into_owned();
+    input.binary_search(&source).is_ok().then_some(source)
+}
+
+/// The compose entries of `filter` one by one. Parsing groups entries by their common leading
+/// and trailing ops (`:[a=:/lib/x,b=:/lib/y]` becomes `:/lib:[...]`), which this distributes
+/// back.
+fn compose_entries(filter: Filter) -> Vec<Filter> {
+    match to_op(filter) {
+        Op::Compose(filters) => filters.into_iter().flat_map(compose_entries).collect(),
+        Op::Chain(filters) => {
+            let Some(i) = filters
+                .iter()
+                .position(|f| matches!(to_op(*f), Op::Compose(_)))
+            else {
+                return vec![filter];
+            };
+            compose_entries(filters[i])
+                .into_iter()
+                .flat_map(|entry| {
+                    let mut chain = filters[..i].to_vec();
+                    match to_op(entry) {
+                        Op::Chain(nested) => chain.extend(nested),
+                        _ => chain.push(entry),
+                    }
+                    chain.extend_from_slice(&filters[i + 1..]);
+                    compose_entries(to_filter(Op::Chain(chain)))
+                })
+                .collect()
+        }
+        _ => vec![filter],
+    }
+}
+
+/// Explain how `filter` maps `tree`: for every output path the top-level compose entry and the
+/// input path that produced it, and for every input path that does not make it into the output
+/// the reason it was dropped. `:workspace`, `:stored` and `:starlark` at the top level are
+/// resolved against `tree` first, so their compose entries are explained individually.
+///
+/// Compose entries are replayed the way [`tree::compose`] combines them: an entry only sees
+/// input paths no earlier entry consumed, and on an output path conflict the later entry wins.
+pub fn explain(
+    transaction: &cache::Transaction,
+    filter: Filter,
+    tree: git2::Oid,
+) -> anyhow::Result<Explanation> {
+    let odb = transaction.odb()?;
+    let mut filter = filter;
+    // The entries that select a workspace's own workspace.josh. That file is also within the
+    // workspace directory the entry after it maps, which is not worth reporting as shadowed.
+    let mut workspace_files = vec![];
+    loop {
+        let reader = tree::read_tree(transaction, &odb, tree)?;
+        filter = match peel_op(filter) {
+            Op::Workspace(path) => {
+                workspace_files.push(
+                    to_filter(Op::Subdir(path.clone())).chain(Filter::new().file("workspace.josh")),
+                );
+                get_workspace(transaction, &odb, tree, &reader, &path)
+            }
+            Op::Stored(path) => get_stored(transaction, &odb, tree, &reader, &path),
+            Op::Starlark(path, sub) => get_starlark(transaction, &odb, tree, &reader, &path, sub),
+            _ => break,
+        };
+    }
+    let entries = compose_entries(filter);
+
+    let input = tree_paths(&odb, tree)?;
+    let mut consumed_by = std::collections::HashMap::<String, Filter>::new();
+    let mut produced = std::collections::BTreeMap::<String, PathOrigin>::new();
+    let mut overwritten = std::collections::HashMap::<String, (String, Filter)>::new();
+    let mut explanation = Explanation::default();
+    let mut taken = tree::empty_id(odb.object_hash());
+
+    for entry in &entries {
+        let applied = apply(transaction, *entry, Rewrite::from_tree(tree))?.tree_id();
+        let normalized = invert(invert(*entry)?)?;
+        let taken_applied = apply(transaction, normalized, Rewrite::from_tree(taken))?.tree_id();
+        let shadowed = tree_paths(&odb, taken_applied)?;
+
+        for path in tree_paths(&odb, applied)? {
+            let origin = PathOrigin {
+                source: source_of(*entry, &path, &input),
+                path,
+                filter: *entry,
+            };
+            if shadowed.binary_search(&origin.path).is_ok() {
+                let by = origin
+                    .source
+                    .as_ref()
+                    .and_then(|s| consumed_by.get(s))
+                    .copied()
+                    .unwrap_or(*entry);
+                if !workspace_files.contains(&by) {
+                    explanation.shadowed.push(ShadowedPath { origin, by });
+                }
+                continue;
+            }
+            if let Some(previous) = produced.insert(origin.path.clone(), origin.clone())
+                && let Some(source) = previous.source
+            {
+                overwritten.insert(source, (origin.path.clone(), *entry));
+            }
+        }
+
+        let unapplied = apply(
+            transaction,
+            invert(normalized)?,
+            Rewrite::from_tree(applied),
+        )?;
+        for path in tree_paths(&odb, unapplied.tree_id())? {
+            consumed_by.entry(path).or_insert(*entry);
+        }
+        taken = tree::overlay(transaction, taken, unapplied.tree_id())?;
+    }
+
+    let result = apply(transaction, filter, Rewrite::from_tree(tree))?.tree_id();
+    for path in tree_paths(&odb, result)? {
+        explanation
+            .outputs
+            .push(produced.remove(&path).unwrap_or(PathOrigin {
+                path,
+                source: None,
+                filter,
+            }));
+    }
+
+    let outputs_from: std::collections::HashSet<_> = explanation
+        .outputs
+        .iter()
+        .filter_map(|o| o.source.clone())
+        .collect();
+    for path in input {
+        if outputs_from.contains(&path) {
+            continue;
+        }
+        let reason = if let Some((output, by)) = overwritten.remove(&path) {
+            DropReason::Overwritten { output, by }
+        } else if consumed_by.contains_key(&path) {
+            // Consumed, but produced under a path the mapping above does not recover.
+            continue;
+        } else if let Some(entry) = entries.iter().find(|e| {
+            let src = src_path(**e);
+            !src.as_os_str().is_empty() && Path::new(&path).starts_with(&src)
+        }) {
+            DropReason::Removed(*entry)
+        } else {
+            DropReason::NotSelected
+        };
+        explanation.dropped.push(DroppedPath { path, reason });
+    }
+
+    Ok(explanation)
+}
+
 /// Check if `commit` is an ancestor of `tip`.
 ///
 /// Creates a cache for a given `tip` so repeated queries with the same `tip` are more efficient.
@@ -2254,7 +2566,7 @@ fn compute_splice_parents(
     Ok(Some(
         splice_parents
             .into_iter()
-            .filter(|&oid| oid != git2::Oid::ZERO_SHA1)
+            .filter(|&oid| !oid.is_zero())
             .collect(),
     ))
 }
@@ -2339,10 +2651,7 @@ fn per_rev_filter(
         // that only join through other parents (subtree sync merges carry the
         // newer subtree tip as their second parent).
         let mut target = None;
-        for id in filtered_parent_ids
-            .iter()
-            .filter(|x| **x != git2::Oid::ZERO_SHA1)
-        {
+        for id in filtered_parent_ids.iter().filter(|x| !x.is_zero()) {
             let seq = cache::compute_sequence_number(transaction, *id)?;
             if target.map(|(top, _)| seq > top).unwrap_or(true) {
                 target = Some((seq, *id));
@@ -2355,7 +2664,7 @@ fn per_rev_filter(
         if let Some((_, target_id)) = target {
             for id in filtered_parent_ids
                 .iter()
-                .filter(|x| **x != git2::Oid::ZERO_SHA1 && **x != target_id)
+                .filter(|x| !x.is_zero() && **x != target_id)
             {
                 if !objects::is_descendant_of(&transaction.odb()?, target_id, *id)? {
                     return Err(anyhow!(
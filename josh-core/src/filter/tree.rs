@@ -25,7 +25,7 @@ fn pathstree_inner(
     let bytes = transaction
         .read_tree_bytes(odb, input)?
         .ok_or_else(|| anyhow!("pathstree: {} is not a tree", input))?;
-    let tree = gix_object::TreeRef::from_bytes(&bytes, gix_hash::Kind::Sha1)?;
+    let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(input))?;
     let mut rebuild = TreeRebuild::new(tree.entries.len());
 
     for entry in &tree.entries {
@@ -38,7 +38,7 @@ fn pathstree_inner(
                 odb,
             )?;
 
-            if s != empty_id() {
+            if !objects::is_empty_tree(s) {
                 rebuild.keep(gix_object::tree::Entry {
                     mode: gix_object::tree::EntryKind::Tree.into(),
                     filename: entry.filename.to_owned(),
@@ -91,7 +91,8 @@ fn regex_replace_inner(
     let bytes = transaction
         .read_tree_bytes(odb, input)?
         .ok_or_else(|| anyhow!("regex_replace: {} is not a tree", input))?;
-    let tree = gix_object::TreeRef::from_bytes(&bytes, gix_hash::Kind::Sha1)?;
+    let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(input))?;
+    prefetch_blobs(odb, &tree, |_| true);
     let mut rebuild = TreeRebuild::new(tree.entries.len());
 
     for entry in &tree.entries {
@@ -106,7 +107,7 @@ fn regex_replace_inner(
                 odb,
             )?;
 
-            if s != tree::empty_id() {
+            if !objects::is_empty_tree(s) {
                 rebuild.keep(gix_object::tree::Entry {
                     mode: entry.mode,
                     filename: entry.filename.to_owned(),
@@ -114,6 +115,15 @@ fn regex_replace_inner(
                 });
             }
         } else if !entry.mode.is_commit() {
+            // Too big to hold as text: passed through like git passes them by for diffs.
+            if is_big_blob(odb, objects::git2_oid(entry.oid)) {
+                rebuild.keep(gix_object::tree::Entry {
+                    mode: entry.mode,
+                    filename: entry.filename.to_owned(),
+                    oid: entry.oid.to_owned(),
+                });
+                continue;
+            }
             let file_contents = blob_text(odb, objects::git2_oid(entry.oid));
             let replaced = regex.replacen(&file_contents, 0, replacement);
 
@@ -136,6 +146,28 @@ pub fn blob_bytes<'a>(odb: &'a josh_memodb::Odb, oid: git2::Oid) -> Option<josh_
     }
 }
 
+/// Fetch the blobs of `tree` that `wanted` selects and a partial clone lacks, with one request,
+/// ahead of a walk that reads them one by one (see [`josh_memodb::Odb::prefetch`]).
+fn prefetch_blobs(
+    odb: &josh_memodb::Odb,
+    tree: &gix_object::TreeRef,
+    wanted: impl Fn(&gix_object::tree::EntryRef) -> bool,
+) {
+    odb.prefetch(
+        tree.entries
+            .iter()
+            .filter(|entry| entry.mode.is_blob() && wanted(entry))
+            .map(|entry| objects::git2_oid(entry.oid)),
+    );
+}
+
+/// Whether `oid` is above the big-object threshold (`core.bigFileThreshold`). Such blobs are
+/// never read whole for their text.
+fn is_big_blob(odb: &josh_memodb::Odb, oid: git2::Oid) -> bool {
+    odb.read_header(oid)
+        .is_ok_and(|(kind, size)| kind == gix_object::Kind::Blob && odb.is_big(size))
+}
+
 /// The text content of the blob `oid`, or the empty string when the blob is missing, contains
 /// a NUL byte, or is not valid UTF-8 -- the same tolerant semantics as [`get_blob`], minus the
 /// path lookup. The NUL check keeps binary blobs from reading as content in workspace/link
@@ -266,19 +298,20 @@ fn lookup_entry<'a>(
 /// commit); [`ParsedTree`] is the counterpart for iterating every entry.
 pub struct TreeReader<'a> {
     bytes: cache::TreeBytes<'a>,
+    /// The hash of the id the tree was read by, which its entry ids share.
+    kind: gix_hash::Kind,
 }
 
 impl TreeReader<'_> {
     /// Every entry, in stored tree order. Malformed entries are skipped.
     pub fn entries(&self) -> impl Iterator<Item = gix_object::tree::EntryRef<'_>> {
-        gix_object::TreeRefIter::from_bytes(&self.bytes, gix_hash::Kind::Sha1)
-            .filter_map(Result::ok)
+        gix_object::TreeRefIter::from_bytes(&self.bytes, self.kind).filter_map(Result::ok)
     }
 
     /// Entry by name irrespective of kind, first occurrence winning -- tree entry order is
     /// not necessarily canonical, and a name is unique in a valid tree.
     pub fn entry(&self, name: &[u8]) -> Option<gix_object::tree::EntryRef<'_>> {
-        gix_object::TreeRefIter::from_bytes(&self.bytes, gix_hash::Kind::Sha1)
+        gix_object::TreeRefIter::from_bytes(&self.bytes, self.kind)
             .filter_map(Result::ok)
             .find(|entry| entry.filename == name)
     }
@@ -293,7 +326,10 @@ pub fn read_tree<'a>(
     let bytes = transaction
         .read_tree_bytes(odb, oid)?
         .ok_or_else(|| anyhow!("{} is not a tree", oid))?;
-    Ok(TreeReader { bytes })
+    Ok(TreeReader {
+        bytes,
+        kind: objects::oid_kind(oid),
+    })
 }
 
 /// A parsed tree with its canonical-order flag computed once, so sites that iterate every
@@ -304,8 +340,9 @@ pub(crate) struct ParsedTree<'b> {
 }
 
 impl<'b> ParsedTree<'b> {
-    pub(crate) fn from_bytes(bytes: &'b [u8]) -> anyhow::Result<Self> {
-        let tree = gix_object::TreeRef::from_bytes(bytes, gix_hash::Kind::Sha1)?;
+    /// Parse the raw tree `bytes` of a `kind` repository.
+    pub(crate) fn from_bytes(bytes: &'b [u8], kind: gix_hash::Kind) -> anyhow::Result<Self> {
+        let tree = gix_object::TreeRef::from_bytes(bytes, kind)?;
         let sorted = entries_canonically_sorted(&tree);
         Ok(ParsedTree { tree, sorted })
     }
@@ -352,7 +389,11 @@ pub fn get_path_entry(
         Some(bytes) => bytes,
         None => return Ok(None),
     };
-    get_path_entry_at(transaction, odb, &(TreeReader { bytes }), path)
+    let root = TreeReader {
+        bytes,
+        kind: objects::oid_kind(root),
+    };
+    get_path_entry_at(transaction, odb, &root, path)
 }
 
 /// [`get_path_entry`] resolving the first component against a caller-held parse -- the hoist
@@ -378,7 +419,8 @@ pub fn get_path_entry_at(
             Some(bytes) => bytes,
             None => return Ok(None),
         };
-        entry = match (TreeReader { bytes }).entry(name) {
+        let kind = entry.oid.kind();
+        entry = match (TreeReader { bytes, kind }).entry(name) {
             Some(entry) => entry.into(),
             None => return Ok(None),
         };
@@ -445,9 +487,9 @@ fn remove_pred_inner(
     let bytes = transaction
         .read_tree_bytes(odb, input)?
         .ok_or_else(|| anyhow!("remove_pred: {} is not a tree", input))?;
-    let tree = gix_object::TreeRef::from_bytes(&bytes, gix_hash::Kind::Sha1)?;
+    let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(input))?;
     let mut rebuild = TreeRebuild::new(tree.entries.len());
-    let empty = empty_id();
+    let empty = empty_id(objects::oid_kind(input));
 
     for entry in &tree.entries {
         let name = std::str::from_utf8(entry.filename).map_err(|_| anyhow!("INVALID_FILENAME"))?;
@@ -554,9 +596,9 @@ fn remove_pattern_inner(
     let bytes = transaction
         .read_tree_bytes(odb, input)?
         .ok_or_else(|| anyhow!("remove_pattern: {} is not a tree", input))?;
-    let tree = gix_object::TreeRef::from_bytes(&bytes, gix_hash::Kind::Sha1)?;
+    let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(input))?;
     let mut rebuild = TreeRebuild::new(tree.entries.len());
-    let empty = empty_id();
+    let empty = empty_id(objects::oid_kind(input));
 
     for entry in &tree.entries {
         let name = std::str::from_utf8(entry.filename).map_err(|_| anyhow!("INVALID_FILENAME"))?;
@@ -641,6 +683,495 @@ fn remove_pattern_inner(
     Ok(result)
 }
 
+/// Rebuild `input` keeping only the blobs accepted by `pred`, which is handed the blob's oid
+/// rather than its path (`Op::Blobs` size and binary selection). The result of a subtree
+/// therefore depends on its oid alone, so unlike [`remove_pred`] no path is folded into the
+/// cache key: results are cached by `(input, key)`, `key` being the peeled filter's id.
+/// Gitlinks are dropped, as in [`remove_pred`].
+pub fn remove_blob_pred(
+    transaction: &cache::Transaction,
+    input: git2::Oid,
+    pred: &dyn Fn(&josh_memodb::Odb, git2::Oid) -> anyhow::Result<bool>,
+    key: git2::Oid,
+) -> anyhow::Result<git2::Oid> {
+    let odb = transaction.odb()?;
+    remove_blob_pred_inner(transaction, &odb, input, pred, key)
+}
+
+/// Recursive body of [`remove_blob_pred`]; see [`remove_pred_inner`] for why the odb is hoisted.
+fn remove_blob_pred_inner(
+    transaction: &cache::Transaction,
+    odb: &josh_memodb::Odb,
+    input: git2::Oid,
+    pred: &dyn Fn(&josh_memodb::Odb, git2::Oid) -> anyhow::Result<bool>,
+    key: git2::Oid,
+) -> anyhow::Result<git2::Oid> {
+    if let Some(cached) = transaction.get_glob((input, key, 0)) {
+        return Ok(cached);
+    }
+
+    let bytes = transaction
+        .read_tree_bytes(odb, input)?
+        .ok_or_else(|| anyhow!("remove_blob_pred: {} is not a tree", input))?;
+    let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(input))?;
+    prefetch_blobs(odb, &tree, |_| true);
+    let mut rebuild = TreeRebuild::new(tree.entries.len());
+    let empty = empty_id(objects::oid_kind(input));
+
+    for entry in &tree.entries {
+        if entry.mode.is_tree() {
+            let s =
+                remove_blob_pred_inner(transaction, odb, objects::git2_oid(entry.oid), pred, key)?;
+            if s != objects::git2_oid(entry.oid) || s == empty {
+                rebuild.mark_changed();
+            }
+            if s != empty {
+                rebuild.keep(gix_object::tree::Entry {
+                    mode: entry.mode,
+                    filename: entry.filename.to_owned(),
+                    oid: objects::gix_oid(s),
+                });
+            }
+        } else if entry.mode.is_commit() {
+            rebuild.mark_changed();
+        } else if pred(odb, objects::git2_oid(entry.oid))? {
+            rebuild.keep((*entry).into());
+        } else {
+            rebuild.mark_changed();
+        }
+    }
+
+    let result = rebuild.finish(odb, input)?;
+    transaction.insert_glob((input, key, 0), result);
+    Ok(result)
+}
+
+/// Whether the blob `oid` is binary by git's own heuristic: a NUL byte within its first 8000
+/// bytes. Missing objects read as text. Only big blobs are streamed for their first bytes;
+/// others are read whole, which is cheaper than setting up a stream.
+pub fn is_binary_blob(odb: &josh_memodb::Odb, oid: git2::Oid) -> bool {
+    const FIRST_FEW_BYTES: usize = 8000;
+    if !is_big_blob(odb, oid) {
+        return blob_bytes(odb, oid)
+            .is_some_and(|bytes| bytes[..bytes.len().min(FIRST_FEW_BYTES)].contains(&0));
+    }
+    let Ok((gix_object::Kind::Blob, _, reader)) = odb.read_stream(oid) else {
+        return false;
+    };
+    let mut first_few = Vec::new();
+    std::io::Read::read_to_end(
+        &mut std::io::Read::take(reader, FIRST_FEW_BYTES as u64),
+        &mut first_few,
+    )
+    .is_ok_and(|_| first_few.contains(&0))
+}
+
+/// Rebuild `input` keeping exactly the paths `git archive` would leave out: those carrying the
+/// `export-ignore` attribute according to the `.gitattributes` files found along the walk. A
+/// directory carrying the attribute is kept whole. Gitlinks are dropped, as in [`remove_pred`].
+///
+/// The result of a subtree depends on the rules inherited from its ancestors, so those are
+/// folded into the cache key together with `key` (the peeled filter's id), and so is the path
+/// when an inherited rule is anchored. Subtrees without inherited rules share one entry.
+pub fn remove_export_ignore(
+    transaction: &cache::Transaction,
+    input: git2::Oid,
+    key: git2::Oid,
+) -> anyhow::Result<git2::Oid> {
+    let odb = transaction.odb()?;
+    remove_export_ignore_inner(transaction, &odb, &mut String::new(), input, &[], key)
+}
+
+/// Recursive body of [`remove_export_ignore`]; see [`remove_pred_inner`] for why the odb is
+/// hoisted and how the shared `path` buffer is handled.
+fn remove_export_ignore_inner(
+    transaction: &cache::Transaction,
+    odb: &josh_memodb::Odb,
+    path: &mut String,
+    input: git2::Oid,
+    inherited: &[attributes::AttrRule],
+    key: git2::Oid,
+) -> anyhow::Result<git2::Oid> {
+    let root_key = attributes::cache_key(&format!("export-ignore:{:?}", key), path, inherited)?;
+    if let Some(cached) = transaction.get_glob((input, root_key, 0)) {
+        return Ok(cached);
+    }
+
+    let bytes = transaction
+        .read_tree_bytes(odb, input)?
+        .ok_or_else(|| anyhow!("remove_export_ignore: {} is not a tree", input))?;
+    let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(input))?;
+
+    let mut rules = inherited.to_vec();
+    if let Some(attributes) = tree
+        .entries
+        .iter()
+        .find(|e| e.filename == ".gitattributes" && e.mode.is_blob())
+    {
+        let content = blob_text(odb, objects::git2_oid(attributes.oid));
+        rules.extend(attributes::parse(path, &content, &["export-ignore"]));
+    }
+
+    let mut rebuild = TreeRebuild::new(tree.entries.len());
+    let empty = empty_id(objects::oid_kind(input));
+
+    for entry in &tree.entries {
+        let name = std::str::from_utf8(entry.filename).map_err(|_| anyhow!("INVALID_FILENAME"))?;
+        let base = path.len();
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(name);
+
+        if entry.mode.is_commit() {
+            rebuild.mark_changed();
+        } else if attributes::lookup(&rules, "export-ignore", path, name).is_set() {
+            rebuild.keep((*entry).into());
+        } else if entry.mode.is_tree() {
+            let s = remove_export_ignore_inner(
+                transaction,
+                odb,
+                path,
+                objects::git2_oid(entry.oid),
+                &rules,
+                key,
+            )?;
+            if s != objects::git2_oid(entry.oid) || s == empty {
+                rebuild.mark_changed();
+            }
+            if s != empty {
+                rebuild.keep(gix_object::tree::Entry {
+                    mode: entry.mode,
+                    filename: entry.filename.to_owned(),
+                    oid: objects::gix_oid(s),
+                });
+            }
+        } else {
+            rebuild.mark_changed();
+        }
+        path.truncate(base);
+    }
+
+    let result = rebuild.finish(odb, input)?;
+    transaction.insert_glob((input, root_key, 0), result);
+    Ok(result)
+}
+
+/// The conversion `normalize` amounts to for the entry `name` at `path`, and whether the path is
+/// explicitly marked as text; `None` when the path is `-text` (or `binary`). An `eol=lf` or
+/// `eol=crlf` attribute overrides the line ending of `:eol=...` and, as in git, implies `text`.
+fn normalize_for_path(
+    rules: &[attributes::AttrRule],
+    path: &str,
+    name: &str,
+    normalize: Normalize,
+) -> Option<(Normalize, bool)> {
+    let text = attributes::lookup(rules, "text", path, name);
+    if text == attributes::AttrState::Unset {
+        return None;
+    }
+    let eol = match attributes::lookup(rules, "eol", path, name) {
+        attributes::AttrState::Value(v) if v == "lf" => Some(Normalize::Lf),
+        attributes::AttrState::Value(v) if v == "crlf" => Some(Normalize::Crlf),
+        _ => None,
+    };
+    let forced = text == attributes::AttrState::Set || eol.is_some();
+    match normalize {
+        Normalize::Utf8 => Some((Normalize::Utf8, forced)),
+        Normalize::Lf | Normalize::Crlf => Some((eol.unwrap_or(normalize), forced)),
+    }
+}
+
+/// `content` converted as `conversion` asks, or `None` when there is nothing to convert. Unless
+/// `forced` (the path is explicitly `text`), line endings are only converted in content git's
+/// `text=auto` would consider text: no NUL byte and no CR that does not start a CRLF. UTF-16 is
+/// only recognized by its byte order mark, and content that does not decode is left alone.
+pub fn convert_text(content: &[u8], conversion: Normalize, forced: bool) -> Option<Vec<u8>> {
+    let looks_binary = || {
+        content.contains(&0)
+            || content
+                .iter()
+                .enumerate()
+                .any(|(i, b)| *b == b'\r' && content.get(i + 1) != Some(&b'\n'))
+    };
+    match conversion {
+        Normalize::Lf => {
+            if !content.windows(2).any(|w| w == b"\r\n") || (!forced && looks_binary()) {
+                return None;
+            }
+            let mut out = Vec::with_capacity(content.len());
+            for (i, b) in content.iter().enumerate() {
+                if !(*b == b'\r' && content.get(i + 1) == Some(&b'\n')) {
+                    out.push(*b);
+                }
+            }
+            Some(out)
+        }
+        Normalize::Crlf => {
+            let lone_lf = |i: usize| content[i] == b'\n' && (i == 0 || content[i - 1] != b'\r');
+            if !(0..content.len()).any(lone_lf) || (!forced && looks_binary()) {
+                return None;
+            }
+            let mut out = Vec::with_capacity(content.len() + content.len() / 16);
+            for (i, b) in content.iter().enumerate() {
+                if lone_lf(i) {
+                    out.push(b'\r');
+                }
+                out.push(*b);
+            }
+            Some(out)
+        }
+        Normalize::Utf8 => {
+            let (big_endian, body) = match content {
+                [0xff, 0xfe, body @ ..] => (false, body),
+                [0xfe, 0xff, body @ ..] => (true, body),
+                _ => return None,
+            };
+            if body.len() % 2 != 0 {
+                return None;
+            }
+            let units = body.chunks_exact(2).map(|c| {
+                if big_endian {
+                    u16::from_be_bytes([c[0], c[1]])
+                } else {
+                    u16::from_le_bytes([c[0], c[1]])
+                }
+            });
+            let text = char::decode_utf16(units)
+                .collect::<Result<String, _>>()
+                .ok()?;
+            Some(text.into_bytes())
+        }
+    }
+}
+
+/// [`convert_text`] for the blob `oid`, memoized per blob and conversion. A blob that changes is
+/// recorded for [`restore_normalized`] under `filter` at `path`, on every walk that reaches it:
+/// the memo is shared by all paths holding the same blob, the records are not. Missing objects
+/// are returned as they are.
+fn normalize_blob(
+    transaction: &cache::Transaction,
+    odb: &josh_memodb::Odb,
+    filter: Filter,
+    path: &str,
+    oid: git2::Oid,
+    conversion: Normalize,
+    forced: bool,
+) -> anyhow::Result<git2::Oid> {
+    let memo = conversion_memo(conversion, forced);
+    let result = match transaction.get_normalized((oid, memo.clone())) {
+        Some(cached) => cached,
+        None => {
+            let result = blob_bytes(odb, oid)
+                .and_then(|bytes| convert_text(&bytes, conversion, forced))
+                .map_or(oid, |converted| {
+                    odb.write(gix_object::Kind::Blob, &converted)
+                });
+            transaction.insert_normalized((oid, memo.clone()), result);
+            result
+        }
+    };
+    if result != oid {
+        transaction.insert_restore(filter, restore_key(path, result, &memo)?, oid);
+    }
+    Ok(result)
+}
+
+fn conversion_memo(conversion: Normalize, forced: bool) -> String {
+    format!(
+        "{}{}",
+        if forced { "text:" } else { "" },
+        conversion.as_str()
+    )
+}
+
+/// The key of the restore record of the blob at `path` normalized to `normalized` by the
+/// conversion `memo`. The path is part of it: another file with the same normalized content has
+/// an original of its own, or none.
+fn restore_key(path: &str, normalized: git2::Oid, memo: &str) -> anyhow::Result<git2::Oid> {
+    Ok(git2::Oid::hash_object(
+        git2::ObjectType::Blob,
+        format!("restore:{}:{}:{}", normalized, memo, path).as_bytes(),
+    )?)
+}
+
+/// `Op::Restore` for a single blob: the original the forward walk recorded for it, or the blob
+/// itself when it is already in the form git would store it in -- LF line endings for a CRLF
+/// checkout, the converted form otherwise -- as an edit committed through git is. Anything else
+/// has no original to go back to and fails rather than being guessed at.
+fn restore_blob(
+    transaction: &cache::Transaction,
+    odb: &josh_memodb::Odb,
+    filter: Filter,
+    path: &str,
+    oid: git2::Oid,
+    conversion: Normalize,
+    forced: bool,
+) -> anyhow::Result<git2::Oid> {
+    let memo = conversion_memo(conversion, forced);
+    if let Some(original) = transaction.get_restore(filter, restore_key(path, oid, &memo)?) {
+        return Ok(original);
+    }
+    let stored = match conversion {
+        Normalize::Crlf => Normalize::Lf,
+        other => other,
+    };
+    if blob_bytes(odb, oid).is_none_or(|bytes| convert_text(&bytes, stored, forced).is_none()) {
+        return Ok(oid);
+    }
+    Err(anyhow!(
+        "restore: {} is not in {} form and has no recorded original",
+        path,
+        stored.as_str()
+    ))
+}
+
+/// Rewrite the text files of `input` as `normalize` asks (`Op::Normalize`), honoring the `text`
+/// and `eol` attributes of the `.gitattributes` files found along the walk, see
+/// [`normalize_for_path`]. Symlinks and gitlinks are kept as they are.
+///
+/// Conversions are memoized per blob and the walk per subtree, keyed by `filter` (the forward
+/// filter) and the inherited attribute rules. Every blob a conversion changes is recorded in the
+/// cache stack for [`restore_normalized`].
+pub fn normalize(
+    transaction: &cache::Transaction,
+    input: git2::Oid,
+    normalize: Normalize,
+    filter: Filter,
+) -> anyhow::Result<git2::Oid> {
+    let odb = transaction.odb()?;
+    normalize_inner(
+        transaction,
+        &odb,
+        &mut String::new(),
+        input,
+        &[],
+        normalize,
+        filter,
+        false,
+    )
+}
+
+/// `Op::Restore`: undo [`normalize`]. A blob the forward filter produced gets back the exact
+/// bytes it was normalized from; an edited or added one is kept if git would store it as it is,
+/// and fails the walk otherwise, see [`restore_blob`].
+pub fn restore_normalized(
+    transaction: &cache::Transaction,
+    input: git2::Oid,
+    normalize: Normalize,
+    filter: Filter,
+) -> anyhow::Result<git2::Oid> {
+    let odb = transaction.odb()?;
+    normalize_inner(
+        transaction,
+        &odb,
+        &mut String::new(),
+        input,
+        &[],
+        normalize,
+        filter,
+        true,
+    )
+}
+
+/// Recursive body of [`normalize`] and [`restore_normalized`]; see [`remove_pred_inner`] for why
+/// the odb is hoisted and how the shared `path` buffer is handled. Only the forward direction is
+/// memoized per subtree: what a restore gives back depends on the records present at the time.
+#[allow(clippy::too_many_arguments)]
+fn normalize_inner(
+    transaction: &cache::Transaction,
+    odb: &josh_memodb::Odb,
+    path: &mut String,
+    input: git2::Oid,
+    inherited: &[attributes::AttrRule],
+    normalize: Normalize,
+    filter: Filter,
+    restore: bool,
+) -> anyhow::Result<git2::Oid> {
+    let root_key = attributes::cache_key(&format!("normalize:{:?}", filter.id()), path, inherited)?;
+    if !restore && let Some(cached) = transaction.get_glob((input, root_key, 0)) {
+        return Ok(cached);
+    }
+
+    let bytes = transaction
+        .read_tree_bytes(odb, input)?
+        .ok_or_else(|| anyhow!("normalize: {} is not a tree", input))?;
+    let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(input))?;
+
+    let mut rules = inherited.to_vec();
+    if let Some(attributes) = tree
+        .entries
+        .iter()
+        .find(|e| e.filename == ".gitattributes" && e.mode.is_blob())
+    {
+        let content = blob_text(odb, objects::git2_oid(attributes.oid));
+        rules.extend(attributes::parse(path, &content, &["text", "eol"]));
+    }
+    prefetch_blobs(odb, &tree, |entry| {
+        let Ok(name) = std::str::from_utf8(entry.filename) else {
+            return false;
+        };
+        let path = if path.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}/{}", path, name)
+        };
+        normalize_for_path(&rules, &path, name, normalize).is_some()
+    });
+
+    let mut rebuild = TreeRebuild::new(tree.entries.len());
+
+    for entry in &tree.entries {
+        let name = std::str::from_utf8(entry.filename).map_err(|_| anyhow!("INVALID_FILENAME"))?;
+        let base = path.len();
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(name);
+
+        let oid = objects::git2_oid(entry.oid);
+        let result = if entry.mode.is_tree() {
+            normalize_inner(
+                transaction,
+                odb,
+                path,
+                oid,
+                &rules,
+                normalize,
+                filter,
+                restore,
+            )?
+        } else if !entry.mode.is_blob() {
+            oid
+        } else if let Some((conversion, forced)) = normalize_for_path(&rules, path, name, normalize)
+        {
+            if restore {
+                restore_blob(transaction, odb, filter, path, oid, conversion, forced)?
+            } else {
+                normalize_blob(transaction, odb, filter, path, oid, conversion, forced)?
+            }
+        } else {
+            oid
+        };
+
+        if result != oid {
+            rebuild.mark_changed();
+        }
+        rebuild.keep(gix_object::tree::Entry {
+            mode: entry.mode,
+            filename: entry.filename.to_owned(),
+            oid: objects::gix_oid(result),
+        });
+        path.truncate(base);
+    }
+
+    let result = rebuild.finish(odb, input)?;
+    if !restore {
+        transaction.insert_glob((input, root_key, 0), result);
+    }
+    Ok(result)
+}
+
 pub fn subtract(
     transaction: &cache::Transaction,
     input1: git2::Oid,
@@ -657,11 +1188,9 @@ fn subtract_inner(
     input1: git2::Oid,
     input2: git2::Oid,
 ) -> anyhow::Result<git2::Oid> {
-    if input1 == input2 {
-        return Ok(empty_id());
-    }
-    if input1 == empty_id() {
-        return Ok(empty_id());
+    let empty = empty_id(objects::oid_kind(input1));
+    if input1 == input2 || objects::is_empty_tree(input1) {
+        return Ok(empty);
     }
 
     if let Some(cached) = transaction.get_subtract((input1, input2)) {
@@ -671,11 +1200,11 @@ fn subtract_inner(
     let bytes1 = transaction.read_tree_bytes(odb, input1)?;
     let bytes2 = transaction.read_tree_bytes(odb, input2)?;
     if let (Some(bytes1), Some(bytes2)) = (bytes1, bytes2) {
-        if input2 == empty_id() {
+        if objects::is_empty_tree(input2) {
             return Ok(input1);
         }
-        let tree1 = gix_object::TreeRef::from_bytes(&bytes1, gix_hash::Kind::Sha1)?;
-        let tree2 = gix_object::TreeRef::from_bytes(&bytes2, gix_hash::Kind::Sha1)?;
+        let tree1 = gix_object::TreeRef::from_bytes(&bytes1, objects::oid_kind(input1))?;
+        let tree2 = gix_object::TreeRef::from_bytes(&bytes2, objects::oid_kind(input2))?;
         let sorted1 = entries_canonically_sorted(&tree1);
         // Start from `tree1` and drop or replace each path that also appears in `tree2`.
         // Modifications are collected by name first and applied in one pass: `None` removes
@@ -691,7 +1220,7 @@ fn subtract_inner(
                     objects::git2_oid(e1.oid),
                     objects::git2_oid(entry.oid),
                 )?;
-                if sub == empty_id() || sub == git2::Oid::ZERO_SHA1 {
+                if objects::is_empty_tree(sub) || sub.is_zero() {
                     mods.insert(&**entry.filename, None);
                 } else {
                     mods.insert(&**entry.filename, Some(sub));
@@ -713,9 +1242,9 @@ fn subtract_inner(
         return Ok(result);
     }
 
-    transaction.insert_subtract((input1, input2), empty_id());
+    transaction.insert_subtract((input1, input2), empty);
 
-    Ok(empty_id())
+    Ok(empty)
 }
 
 /// Intersect two trees by path: keep every entry of `input1` whose path also exists in `input2`,
@@ -745,8 +1274,8 @@ fn intersect_inner(
     if input1 == input2 {
         return Ok(input1);
     }
-    if input1 == empty_id() || input2 == empty_id() {
-        return Ok(empty_id());
+    if objects::is_empty_tree(input1) || objects::is_empty_tree(input2) {
+        return Ok(empty_id(objects::oid_kind(input1)));
     }
 
     if let Some(cached) = transaction.get_intersect((input1, input2)) {
@@ -756,8 +1285,8 @@ fn intersect_inner(
     let bytes1 = transaction.read_tree_bytes(odb, input1)?;
     let bytes2 = transaction.read_tree_bytes(odb, input2)?;
     let result = if let (Some(bytes1), Some(bytes2)) = (bytes1, bytes2) {
-        let tree1 = gix_object::TreeRef::from_bytes(&bytes1, gix_hash::Kind::Sha1)?;
-        let tree2 = gix_object::TreeRef::from_bytes(&bytes2, gix_hash::Kind::Sha1)?;
+        let tree1 = gix_object::TreeRef::from_bytes(&bytes1, objects::oid_kind(input1))?;
+        let tree2 = gix_object::TreeRef::from_bytes(&bytes2, objects::oid_kind(input2))?;
         let sorted1 = entries_canonically_sorted(&tree1);
         // Iterate the selector (`input2`), keeping each of its paths that also exists in `tree1`
         // with `tree1`'s content and raw mode; cost tracks the size of the selected set.
@@ -769,7 +1298,7 @@ fn intersect_inner(
                     objects::git2_oid(e1.oid),
                     objects::git2_oid(entry.oid),
                 )?;
-                if child != empty_id() && child != git2::Oid::ZERO_SHA1 {
+                if !objects::is_empty_tree(child) && !child.is_zero() {
                     insert_in_order(
                         &mut out,
                         gix_object::tree::Entry {
@@ -823,11 +1352,11 @@ fn replace_child_inner(
     let mut out = match tree_bytes(odb, tree_oid) {
         Some(bytes) => seed_entries(&gix_object::TreeRef::from_bytes(
             &bytes,
-            gix_hash::Kind::Sha1,
+            objects::oid_kind(tree_oid),
         )?),
         None => Vec::new(),
     };
-    let remove = oid == git2::Oid::ZERO_SHA1 || oid == empty_id();
+    let remove = oid.is_zero() || objects::is_empty_tree(oid);
     let first = out.iter().position(|e| &*e.filename == child);
     out.retain(|e| &*e.filename != child);
     if !remove {
@@ -878,14 +1407,14 @@ pub fn insert_oid(
         let cb = component_bytes(c.as_os_str());
         st = match tree_bytes(odb, st) {
             Some(bytes) => {
-                let tree = gix_object::TreeRef::from_bytes(&bytes, gix_hash::Kind::Sha1)?;
+                let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(st))?;
                 let sorted = entries_canonically_sorted(&tree);
                 match lookup_entry(&tree, cb.into(), sorted) {
                     Some(e) => objects::git2_oid(e.oid),
-                    None => empty_id(),
+                    None => empty_id(objects::oid_kind(full_tree)),
                 }
             }
-            None => empty_id(),
+            None => empty_id(objects::oid_kind(full_tree)),
         };
     }
 
@@ -943,10 +1472,10 @@ pub fn diff_paths(
     let mut buf1 = Vec::new();
     let mut buf2 = Vec::new();
     let tree1 = read_tree_into(src, input1, &mut buf1)
-        .then(|| ParsedTree::from_bytes(&buf1).ok())
+        .then(|| ParsedTree::from_bytes(&buf1, objects::oid_kind(input1)).ok())
         .flatten();
     let tree2 = read_tree_into(src, input2, &mut buf2)
-        .then(|| ParsedTree::from_bytes(&buf2).ok())
+        .then(|| ParsedTree::from_bytes(&buf2, objects::oid_kind(input2)).ok())
         .flatten();
 
     if let (Some(tree1), Some(tree2)) = (&tree1, &tree2) {
@@ -1035,18 +1564,18 @@ fn overlay_inner(
     if input1 == input2 {
         return Ok(input1);
     }
-    if input1 == empty_id() {
+    if objects::is_empty_tree(input1) {
         return Ok(input2);
     }
-    if input2 == empty_id() {
+    if objects::is_empty_tree(input2) {
         return Ok(input1);
     }
 
     let bytes1 = transaction.read_tree_bytes(odb, input1)?;
     let bytes2 = transaction.read_tree_bytes(odb, input2)?;
     if let (Some(bytes1), Some(bytes2)) = (bytes1, bytes2) {
-        let tree1 = gix_object::TreeRef::from_bytes(&bytes1, gix_hash::Kind::Sha1)?;
-        let tree2 = gix_object::TreeRef::from_bytes(&bytes2, gix_hash::Kind::Sha1)?;
+        let tree1 = gix_object::TreeRef::from_bytes(&bytes1, objects::oid_kind(input1))?;
+        let tree2 = gix_object::TreeRef::from_bytes(&bytes2, objects::oid_kind(input2))?;
         let sorted1 = entries_canonically_sorted(&tree1);
         // Start from `tree1` and insert every entry of `tree2`: recursively overlaid where the
         // name exists in `tree1` (with `tree1` winning on blob collisions, keeping its raw
@@ -1109,12 +1638,12 @@ pub fn invert_paths(
         return Ok(cached);
     }
 
-    let mut result = empty_id();
+    let mut result = empty_id(objects::oid_kind(tree));
 
     let bytes = transaction
         .read_tree_bytes(odb, tree)?
         .ok_or_else(|| anyhow!("invert_paths: {} is not a tree", tree))?;
-    let parsed = ParsedTree::from_bytes(&bytes)?;
+    let parsed = ParsedTree::from_bytes(&bytes, objects::oid_kind(tree))?;
 
     for entry in parsed.entries() {
         let name = std::str::from_utf8(entry.filename).map_err(|_| anyhow!("no name"))?;
@@ -1203,7 +1732,7 @@ pub fn populate(
     let paths_kind = odb.read_header(paths).map(|(kind, _)| kind).ok();
     let content_kind = odb.read_header(content).map(|(kind, _)| kind).ok();
 
-    let mut result_tree = empty_id();
+    let mut result_tree = empty_id(objects::oid_kind(paths));
     if let (Some(Kind::Blob), Some(Kind::Blob)) = (paths_kind, content_kind) {
         let paths_bytes = blob_bytes(odb, paths).ok_or_else(|| anyhow!("populate: blob read"))?;
         let ipath = pathline(std::str::from_utf8(&paths_bytes)?)?;
@@ -1215,8 +1744,8 @@ pub fn populate(
         let content_bytes = transaction
             .read_tree_bytes(odb, content)?
             .ok_or_else(|| anyhow!("populate: {} is not a tree", content))?;
-        let paths_tree = ParsedTree::from_bytes(&paths_bytes)?;
-        let content_tree = ParsedTree::from_bytes(&content_bytes)?;
+        let paths_tree = ParsedTree::from_bytes(&paths_bytes, objects::oid_kind(paths))?;
+        let content_tree = ParsedTree::from_bytes(&content_bytes, objects::oid_kind(content))?;
         for entry in content_tree.entries() {
             std::str::from_utf8(entry.filename).map_err(|_| anyhow!("no name"))?;
             if let Some(e) = paths_tree.entry(entry.filename) {
@@ -1243,8 +1772,8 @@ pub fn compose(
     transaction: &cache::Transaction,
     trees: Vec<(&Filter, git2::Oid)>,
 ) -> anyhow::Result<git2::Oid> {
-    let mut result = empty_id();
-    let mut taken = empty_id();
+    let mut result = empty_id(transaction.object_hash());
+    let mut taken = result;
     for (f, applied) in trees {
         let tid = taken;
         // If a filter creates a tree entry that does not exist in the input (Like TreeId and Blob),
@@ -1305,8 +1834,9 @@ pub(crate) fn get_blob_at(
     blob_text(odb, objects::git2_oid(&entry.oid))
 }
 
-pub fn empty_id() -> git2::Oid {
-    git2::Oid::from_str("4b825dc642cb6eb9a060e54bf8d69288fbee4904").unwrap()
+/// The empty tree of a `kind` repository; see [`objects::is_empty_tree`] to test for it.
+pub fn empty_id(kind: gix_hash::Kind) -> git2::Oid {
+    objects::empty_tree(kind)
 }
 
 #[cfg(test)]
@@ -1331,6 +1861,46 @@ mod tests {
         ctx.open().unwrap()
     }
 
+    // Blobs above `core.bigFileThreshold` are never read whole for their text: `:replace`
+    // passes them through, while the binary check streams just their first bytes.
+    #[test]
+    fn replace_passes_big_blobs_through() {
+        let td = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(td.path()).unwrap();
+        repo.config()
+            .unwrap()
+            .set_i64("core.bigFileThreshold", 32)
+            .unwrap();
+        let big = repo
+            .blob(b"a pattern in a blob beyond the threshold")
+            .unwrap();
+        let small = repo.blob(b"a pattern").unwrap();
+        let mut b = repo.treebuilder(None).unwrap();
+        b.insert("big.txt", big, 0o100644).unwrap();
+        b.insert("small.txt", small, 0o100644).unwrap();
+        let input = b.write().unwrap();
+
+        let transaction = open_transaction(&td);
+        let regex = regex::Regex::new("pattern").unwrap();
+        let result = regex_replace(input, &regex, "replacement", &transaction).unwrap();
+
+        let odb = transaction.odb().unwrap();
+        let entry = |name: &str| {
+            let bytes = transaction.read_tree_bytes(&odb, result).unwrap().unwrap();
+            let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(result)).unwrap();
+            objects::git2_oid(
+                tree.entries
+                    .iter()
+                    .find(|e| e.filename == name)
+                    .unwrap()
+                    .oid,
+            )
+        };
+        assert_eq!(entry("big.txt"), big);
+        assert_eq!(blob_text(&odb, entry("small.txt")), "a replacement");
+        assert!(!is_binary_blob(&odb, big));
+    }
+
     // A gitlink (submodule) entry must be dropped from the rebuilt tree -- and must therefore
     // defeat the "unchanged input" fast path -- while a symlink blob accepted by the predicate
     // keeps its 0o120000 filemode.
@@ -1789,7 +2359,7 @@ mod tests {
         .unwrap();
         assert_eq!(
             out,
-            empty_id(),
+            empty_id(gix_hash::Kind::Sha1),
             "a 64-component pattern matches nothing here"
         );
     }
@@ -1886,9 +2456,14 @@ mod tests {
 
         // The empty tree reads through the virtualized disk fallback: no entries, plain miss.
         assert!(
-            get_path_entry(&t, &odb, empty_id(), Path::new("anything"))
-                .unwrap()
-                .is_none()
+            get_path_entry(
+                &t,
+                &odb,
+                empty_id(gix_hash::Kind::Sha1),
+                Path::new("anything")
+            )
+            .unwrap()
+            .is_none()
         );
     }
 
@@ -1997,4 +2572,173 @@ mod tests {
             theirs
         );
     }
+
+    fn make_tree_with_content(repo: &git2::Repository, files: &[(&str, &[u8])]) -> git2::Oid {
+        let mut b = git2::build::TreeUpdateBuilder::new();
+        for (path, content) in files {
+            let oid = repo.blob(content).unwrap();
+            b.upsert(*path, oid, git2::FileMode::Blob);
+        }
+        let base = repo.treebuilder(None).unwrap().write().unwrap();
+        b.create_updated(repo, &repo.find_tree(base).unwrap())
+            .unwrap()
+    }
+
+    fn out_paths(t: &cache::Transaction, oid: git2::Oid) -> Vec<String> {
+        let mut paths = vec![];
+        objects::walk_tree_preorder(&t.odb().unwrap(), oid, &mut |root, entry| {
+            if !entry.mode.is_tree() {
+                let sep = if root.is_empty() { "" } else { "/" };
+                paths.push(format!("{}{}{}", root, sep, entry.filename));
+            }
+            Ok(())
+        })
+        .unwrap();
+        paths.sort();
+        paths
+    }
+
+    // Blob predicates see the content, not the path: a binary blob is selected wherever it sits,
+    // and a tree without any selected blob collapses to empty.
+    #[test]
+    fn remove_blob_pred_selects_binary_blobs() {
+        let td = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(td.path()).unwrap();
+        let input = make_tree_with_content(
+            &repo,
+            &[
+                ("a/image.png", b"\x89PNG\0\0"),
+                ("a/readme.md", b"text"),
+                ("b/data.bin", b"\0"),
+                ("c/src.rs", b"fn main() {}"),
+            ],
+        );
+
+        let t = open_transaction(&td);
+        let key = git2::Oid::from_str("4444444444444444444444444444444444444444").unwrap();
+        let out =
+            remove_blob_pred(&t, input, &|odb, oid| Ok(is_binary_blob(odb, oid)), key).unwrap();
+        assert_eq!(out_paths(&t, out), ["a/image.png", "b/data.bin"]);
+    }
+
+    // Rules of nested `.gitattributes` files override their ancestors', anchored patterns match
+    // relative to the file's directory, and an export-ignored directory is kept whole.
+    #[test]
+    fn remove_export_ignore_honors_nested_gitattributes() {
+        let td = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(td.path()).unwrap();
+        let input = make_tree_with_content(
+            &repo,
+            &[
+                (
+                    ".gitattributes",
+                    b"*.gen export-ignore\n/tests export-ignore\n",
+                ),
+                ("a.gen", b""),
+                ("keep.rs", b""),
+                ("tests/t.rs", b""),
+                (
+                    "sub/.gitattributes",
+                    b"keep.gen -export-ignore\nlocal/*.txt export-ignore\n",
+                ),
+                ("sub/drop.gen", b""),
+                ("sub/keep.gen", b""),
+                ("sub/local/x.txt", b""),
+                ("sub/tests/t.rs", b""),
+            ],
+        );
+
+        let t = open_transaction(&td);
+        let key = git2::Oid::from_str("5555555555555555555555555555555555555555").unwrap();
+        let out = remove_export_ignore(&t, input, key).unwrap();
+        assert_eq!(
+            out_paths(&t, out),
+            ["a.gen", "sub/drop.gen", "sub/local/x.txt", "tests/t.rs"]
+        );
+    }
+
+    fn blob_at(t: &cache::Transaction, tree: git2::Oid, path: &str) -> Vec<u8> {
+        let odb = t.odb().unwrap();
+        let entry = get_path_entry(t, &odb, tree, Path::new(path))
+            .unwrap()
+            .unwrap();
+        blob_bytes(&odb, objects::git2_oid(&entry.oid))
+            .unwrap()
+            .to_vec()
+    }
+
+    // Attributes decide which files are converted and to what, and restoring the normalized tree
+    // gives back the exact input -- mixed line endings included -- even from a fresh transaction.
+    // An edit in the form git stores is kept as it is; anything else has no original and fails.
+    #[test]
+    fn normalize_honors_attributes_and_restores_losslessly() {
+        let td = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(td.path()).unwrap();
+        let input = make_tree_with_content(
+            &repo,
+            &[
+                (".gitattributes", b"*.bat eol=crlf\n*.dat -text\n"),
+                ("a.txt", b"one\r\ntwo\n"),
+                ("b.bat", b"x\ny\n"),
+                ("c.dat", b"p\r\nq\r\n"),
+                ("d.bin", b"\0\r\n"),
+            ],
+        );
+
+        let t = open_transaction(&td);
+        let key = to_filter(Op::Normalize(Normalize::Lf));
+        let out = normalize(&t, input, Normalize::Lf, key).unwrap();
+        assert_eq!(blob_at(&t, out, "a.txt"), b"one\ntwo\n");
+        assert_eq!(blob_at(&t, out, "b.bat"), b"x\r\ny\r\n");
+        assert_eq!(blob_at(&t, out, "c.dat"), b"p\r\nq\r\n");
+        assert_eq!(blob_at(&t, out, "d.bin"), b"\0\r\n");
+
+        drop(t);
+        let t = open_transaction(&td);
+        assert_eq!(
+            restore_normalized(&t, out, Normalize::Lf, key).unwrap(),
+            input
+        );
+
+        let edited = make_tree_with_content(
+            &repo,
+            &[
+                (".gitattributes", b"*.bat eol=crlf\n*.dat -text\n"),
+                ("a.txt", b"one\ntwo\n"),
+                ("b.bat", b"x\nz\n"),
+                ("c.dat", b"p\r\nq\r\n"),
+                ("d.bin", b"\0\r\n"),
+            ],
+        );
+        let restored = restore_normalized(&t, edited, Normalize::Lf, key).unwrap();
+        assert_eq!(blob_at(&t, restored, "a.txt"), b"one\r\ntwo\n");
+        assert_eq!(blob_at(&t, restored, "b.bat"), b"x\nz\n");
+
+        let unnormalized = make_tree_with_content(
+            &repo,
+            &[
+                (".gitattributes", b"*.bat eol=crlf\n*.dat -text\n"),
+                ("e.bat", b"three\r\n"),
+            ],
+        );
+        let err = restore_normalized(&t, unnormalized, Normalize::Lf, key).unwrap_err();
+        assert!(err.to_string().contains("e.bat"), "{err}");
+    }
+
+    #[test]
+    fn convert_text_reencodes_utf16() {
+        let le = [0xff, 0xfe, b'h', 0, b'i', 0, 0x3d, 0xd8, 0x00, 0xde];
+        assert_eq!(
+            convert_text(&le, Normalize::Utf8, false).unwrap(),
+            "hi\u{1f600}".as_bytes()
+        );
+        let be = [0xfe, 0xff, 0, b'h', 0, b'i'];
+        assert_eq!(convert_text(&be, Normalize::Utf8, false).unwrap(), b"hi");
+        // An unpaired surrogate does not decode, so the blob is kept as it is.
+        assert_eq!(
+            convert_text(&[0xff, 0xfe, 0x00, 0xd8], Normalize::Utf8, false),
+            None
+        );
+        assert_eq!(convert_text(b"plain", Normalize::Utf8, false), None);
+    }
 }
@@ -14,30 +14,31 @@ pub fn resolve_snapshot_input(
     transaction: &crate::cache::Transaction,
     input_ref: &str,
 ) -> anyhow::Result<git2::Oid> {
-    let repo = transaction.git2_repo();
     if input_ref == "+" || input_ref == "." {
+        // The index and the worktree are libgit2's; HEAD and the snapshot commit go through
+        // the transaction.
+        let repo = transaction.git2_repo();
+        let odb = transaction.odb()?;
+        let head = transaction.head()?.commit;
         let mut index = repo.index()?;
         let tree_oid = if input_ref == "+" {
             index.write_tree_to(repo)?
         } else {
-            let head_tree = repo.head()?.peel_to_tree()?;
-            index.read_tree(&head_tree)?;
+            let head_tree = crate::objects::CommitData::read(&odb, head)?.tree_id()?;
+            index.read_tree(&repo.find_tree(head_tree)?)?;
             index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
             index.update_all(["*"].iter(), None)?;
             index.write_tree_to(repo)?
         };
-        let tree = repo.find_tree(tree_oid)?;
         let sig = crate::git::josh_commit_signature()?;
-        let head_commit = repo.head()?.peel_to_commit()?;
-        let commit_oid = repo.commit(None, &sig, &sig, "WIP", &tree, &[&head_commit])?;
-        Ok(commit_oid)
-    } else if let Ok(oid) = git2::Oid::from_str(input_ref) {
-        Ok(repo.find_object(oid, None)?.peel_to_commit()?.id())
+        crate::objects::write_commit(&odb, tree_oid, &[head], &sig, &sig, "WIP")
+    } else if let Ok(oid) = crate::objects::parse_oid(input_ref) {
+        crate::objects::peel_to_commit(&transaction.odb()?, oid)
     } else {
-        let obj = repo
-            .revparse_single(input_ref)
+        let oid = transaction
+            .rev_parse(input_ref)?
             .with_context(|| format!("could not resolve input: {:?}", input_ref))?;
-        Ok(obj.peel_to_commit()?.id())
+        crate::objects::peel_to_commit(&transaction.odb()?, oid)
     }
 }
 
@@ -140,6 +141,37 @@ pub fn normalize_repo_path(repo_path: &std::path::Path) -> PathBuf {
     }
 }
 
+/// Set up the process-wide libgit2 options josh relies on. Idempotent; josh-core calls it before
+/// opening a repository for a transaction, and binaries call it first thing in `main` so that
+/// every repository they open, with or without a transaction, gets the same treatment.
+pub fn configure_libgit2() {
+    static GIT2_OPTIONS: std::sync::Once = std::sync::Once::new();
+    GIT2_OPTIONS.call_once(|| {
+        // Don't check per write that referenced objects exist: josh only ever writes
+        // objects whose referenced objects it has just produced or read, so the checks
+        // are pure overhead.
+        git2::opts::strict_object_creation(false);
+        // Don't re-hash objects on every read: libgit2 defaults to verifying each
+        // object against its id with collision-detecting SHA1 on every lookup. josh
+        // only reads objects it wrote itself or that git verified on transfer, so this
+        // costs a full hash pass per object read and buys nothing.
+        git2::opts::strict_hash_verification(false);
+        // Open partial clones and reftable repositories: libgit2 refuses repositories
+        // declaring an extension it does not know, and it knows neither `partialclone` nor
+        // `refStorage`, although neither changes anything about reading objects. Objects a
+        // partial clone left out are fetched by the transaction's promisor (see
+        // `cache::promisor`); refs are read and written by the transaction's ref store, which
+        // knows reftables (see `cache::RefStore`).
+        //
+        // SAFETY: libgit2 reads the extension list when opening a repository. The `Once`
+        // serializes callers, and callers run this before opening repositories.
+        unsafe {
+            git2::opts::set_extensions(&["partialclone", "refstorage"])
+                .expect("failed to register the partialclone and refstorage extensions");
+        }
+    });
+}
+
 /// Spawn a git command. By default, when used in TTY environment,
 /// forwards stdout/stderr to user's TTY
 pub struct GitCommand {
@@ -258,7 +290,7 @@ pub fn read_parent_ids(odb: &josh_memodb::Odb, oid: git2::Oid) -> anyhow::Result
             kind
         ));
     }
-    gix_object::CommitRefIter::from_bytes(&bytes, gix_hash::Kind::Sha1)
+    gix_object::CommitRefIter::from_bytes(&bytes, crate::objects::oid_kind(oid))
         .parent_ids()
         .map(|p| Ok(git2::Oid::from_bytes(p.as_bytes())?))
         .collect()
@@ -276,7 +308,8 @@ pub fn read_tree_id(odb: &josh_memodb::Odb, oid: git2::Oid) -> anyhow::Result<gi
             kind
         ));
     }
-    let tree_id = gix_object::CommitRefIter::from_bytes(&bytes, gix_hash::Kind::Sha1).tree_id()?;
+    let tree_id =
+        gix_object::CommitRefIter::from_bytes(&bytes, crate::objects::oid_kind(oid)).tree_id()?;
     Ok(git2::Oid::from_bytes(tree_id.as_bytes())?)
 }
 
@@ -291,7 +324,11 @@ mod tests {
         let tree = repo.find_tree(tree_id).unwrap();
         let commit_id = repo.commit(None, &sig, &sig, "test", &tree, &[]).unwrap();
 
-        let store = josh_memodb::MemOdb::new(None, josh_memodb::objects_dir(&repo));
+        let store = josh_memodb::MemOdb::new(
+            None,
+            josh_memodb::objects_dir(&repo),
+            josh_memodb::object_hash(&repo),
+        );
         let odb = josh_memodb::Odb::new(store, repo.odb().unwrap());
         assert_eq!(
             super::read_tree_id(&odb, commit_id).unwrap(),
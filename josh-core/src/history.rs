@@ -14,6 +14,10 @@ pub(crate) fn history_flag(value: Option<&str>, flag: &str) -> bool {
     value.is_some_and(|v| v.split(',').any(|f| f.trim() == flag))
 }
 
+/// Below this many commits to filter, a walk stays on one thread even when more are allowed:
+/// forking the workers would cost more than it saves.
+const PARALLEL_WALK_MIN_COMMITS: usize = 1000;
+
 pub fn walk2(
     filter: filter::Filter,
     input: git2::Oid,
@@ -41,8 +45,31 @@ pub fn walk2(
 
     // The prune callback cannot propagate errors, so treat a failed lookup as "not known":
     // the walk then visits the commit and the fallible body reports the same error properly.
-    let sorted = walk.into_topo_vec(|id| transaction.known(filter, id).unwrap_or(false))?;
+    let prune = |id| transaction.known(filter, id).unwrap_or(false);
+
+    let (n_in, n_out) = if transaction.threads() > 1 {
+        let (sorted, parents) = walk.into_topo_graph(prune)?;
+        if sorted.len() >= PARALLEL_WALK_MIN_COMMITS {
+            walk_parallel(filter, transaction, &sorted, &parents)?
+        } else {
+            walk_serial(filter, transaction, &sorted)?
+        }
+    } else {
+        walk_serial(filter, transaction, &walk.into_topo_vec(prune)?)?
+    };
+
+    log::info!("{} commits filtered, {} written", n_in, n_out,);
+
+    Ok(())
+}
 
+/// Filter `sorted` (children before parents) parents-first, stopping at the first commit that
+/// cannot be filtered yet. Returns the number of commits filtered and written.
+fn walk_serial(
+    filter: filter::Filter,
+    transaction: &cache::Transaction,
+    sorted: &[git2::Oid],
+) -> anyhow::Result<(usize, usize)> {
     let mut n_in = 0;
     let mut n_out = 0;
 
@@ -59,9 +86,227 @@ pub fn walk2(
         }
     }
 
-    log::info!("{} commits filtered, {} written", n_in, n_out,);
+    Ok((n_in, n_out))
+}
 
-    Ok(())
+/// Split the commit graph into segments: maximal chains in which each commit is the only child
+/// of its only parent. Returns the segments, each parents-first, and for each segment the
+/// segments holding the parents of its first commit.
+fn walk_segments(parents: &[Vec<usize>]) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+    let mut n_children = vec![0usize; parents.len()];
+    for commit_parents in parents {
+        for &p in commit_parents {
+            n_children[p] += 1;
+        }
+    }
+
+    let mut segment_of = vec![0; parents.len()];
+    let mut segments: Vec<Vec<usize>> = vec![];
+    let mut deps: Vec<Vec<usize>> = vec![];
+    // Higher positions first: parents before children.
+    for i in (0..parents.len()).rev() {
+        if let [p] = parents[i][..]
+            && n_children[p] == 1
+        {
+            segment_of[i] = segment_of[p];
+            segments[segment_of[i]].push(i);
+            continue;
+        }
+        let mut segment_deps: Vec<usize> = parents[i].iter().map(|&p| segment_of[p]).collect();
+        segment_deps.sort_unstable();
+        segment_deps.dedup();
+        segment_of[i] = segments.len();
+        segments.push(vec![i]);
+        deps.push(segment_deps);
+    }
+    (segments, deps)
+}
+
+struct WalkSchedule {
+    ready: std::collections::VecDeque<usize>,
+    // Per segment, the number of segments it waits for.
+    waiting_for: Vec<usize>,
+    remaining: usize,
+    stop: bool,
+    error: Option<anyhow::Error>,
+}
+
+/// [`walk_serial`] on the worker threads of the transaction: segments of the commit graph (see
+/// [`walk_segments`]) are filtered as soon as the segments holding their parents are done. As
+/// filtering a commit only depends on the commit and its filtered parents, the result is the
+/// same as the serial walk's; histories without parallel branches gain nothing.
+fn walk_parallel(
+    filter: filter::Filter,
+    transaction: &cache::Transaction,
+    sorted: &[git2::Oid],
+    parents: &[Vec<usize>],
+) -> anyhow::Result<(usize, usize)> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let (segments, deps) = walk_segments(parents);
+    let mut dependents = vec![vec![]; segments.len()];
+    for (segment, segment_deps) in deps.iter().enumerate() {
+        for &dep in segment_deps {
+            dependents[dep].push(segment);
+        }
+    }
+    let threads = transaction.threads().min(segments.len());
+    log::debug!(
+        "{} commits in {} segments on {} threads",
+        sorted.len(),
+        segments.len(),
+        threads
+    );
+
+    let schedule = std::sync::Mutex::new(WalkSchedule {
+        ready: (0..segments.len())
+            .filter(|&s| deps[s].is_empty())
+            .collect(),
+        waiting_for: deps.iter().map(|d| d.len()).collect(),
+        remaining: segments.len(),
+        stop: false,
+        error: None,
+    });
+    let wakeup = std::sync::Condvar::new();
+    let n_in = AtomicUsize::new(0);
+    let n_out = AtomicUsize::new(0);
+
+    let workers = transaction.fork_workers(threads)?;
+    let workers = std::thread::scope(|scope| {
+        let handles: Vec<_> = workers
+            .into_iter()
+            .map(|worker| {
+                scope.spawn(|| {
+                    let worker = worker;
+                    loop {
+                        let segment = {
+                            let mut state = schedule.lock().unwrap();
+                            loop {
+                                if state.stop || state.remaining == 0 {
+                                    return worker;
+                                }
+                                if let Some(segment) = state.ready.pop_front() {
+                                    break segment;
+                                }
+                                state = wakeup.wait(state).unwrap();
+                            }
+                        };
+
+                        let mut complete = true;
+                        for &i in &segments[segment] {
+                            match filter::apply_to_commit2(filter, sorted[i], &worker) {
+                                Ok(Some(_)) => {
+                                    n_out.fetch_add(1, Ordering::Relaxed);
+                                }
+                                Ok(None) => {
+                                    complete = false;
+                                    break;
+                                }
+                                Err(e) => {
+                                    let mut state = schedule.lock().unwrap();
+                                    state.error.get_or_insert(e);
+                                    complete = false;
+                                    break;
+                                }
+                            }
+                            let n = n_in.fetch_add(1, Ordering::Relaxed) + 1;
+                            if n.is_multiple_of(1000) {
+                                log::debug!("{} commits filtered", n);
+                            }
+                        }
+
+                        let mut state = schedule.lock().unwrap();
+                        if complete {
+                            state.remaining -= 1;
+                            for &dependent in &dependents[segment] {
+                                state.waiting_for[dependent] -= 1;
+                                if state.waiting_for[dependent] == 0 {
+                                    state.ready.push_back(dependent);
+                                }
+                            }
+                        } else {
+                            // Like the serial walk, stop at a commit that cannot be filtered yet.
+                            state.stop = true;
+                        }
+                        wakeup.notify_all();
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("walk worker panicked"))
+            .collect::<Vec<_>>()
+    });
+    transaction.join_workers(workers);
+
+    if let Some(e) = schedule.into_inner().unwrap().error {
+        return Err(e);
+    }
+    Ok((n_in.into_inner(), n_out.into_inner()))
+}
+
+/// The originals of filtered commits as far as an unapply walk has resolved them: commits found
+/// by searching the original history, and the commits the walk rewrote. Backed by the
+/// transaction's cache stack under a key that also covers the target and the walk's options, so
+/// pushing an amended stack onto the same target again only walks the commits that changed.
+struct Originals<'a> {
+    transaction: &'a cache::Transaction,
+    filter: filter::Filter,
+    /// Everything besides the filtered commit that decides its original.
+    context: String,
+    map: HashMap<git2::Oid, git2::Oid>,
+}
+
+impl<'a> Originals<'a> {
+    fn new(
+        transaction: &'a cache::Transaction,
+        filter: filter::Filter,
+        original_target: git2::Oid,
+        orphans_mode: &OrphansMode,
+        reparent_orphans: Option<git2::Oid>,
+    ) -> Self {
+        Originals {
+            transaction,
+            filter,
+            context: format!(
+                "original:{}:{:?}:{:?}",
+                original_target, orphans_mode, reparent_orphans
+            ),
+            map: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, filtered: git2::Oid) -> Option<git2::Oid> {
+        if let Some(original) = self.map.get(&filtered) {
+            return Some(*original);
+        }
+        let original = self
+            .transaction
+            .get_original(self.filter, self.key(filtered).ok()?)?;
+        self.map.insert(filtered, original);
+        Some(original)
+    }
+
+    fn insert(&mut self, filtered: git2::Oid, original: git2::Oid) {
+        self.map.insert(filtered, original);
+        if let Ok(key) = self.key(filtered) {
+            self.transaction.insert_original(self.filter, key, original);
+        }
+    }
+
+    fn key(&self, filtered: git2::Oid) -> Result<git2::Oid, git2::Error> {
+        original_key(&self.context, filtered)
+    }
+}
+
+/// The cache key under which the original of `filtered` is stored. `context` names the search
+/// that found it and everything besides the filtered commit the answer depends on.
+fn original_key(context: &str, filtered: git2::Oid) -> Result<git2::Oid, git2::Error> {
+    git2::Oid::hash_object(
+        git2::ObjectType::Blob,
+        format!("{}:{}", context, filtered).as_bytes(),
+    )
 }
 
 fn find_unapply_base(
@@ -69,7 +314,7 @@ fn find_unapply_base(
     // Used as a cache to avoid re-applying the filter to the same commit -
     // this function is called during revwalk so there be a lot of repeated
     // calls
-    filtered_to_original: &mut HashMap<git2::Oid, git2::Oid>,
+    filtered_to_original: &mut Originals,
     filter: filter::Filter,
     // When building the filtered_to_original mapping use this as a starting point
     // for the search for originals. If there are multiple originals that map to the
@@ -84,18 +329,18 @@ fn find_unapply_base(
     // when there is no `contained_in` hint (e.g. a no-base push of an orphan
     // history). Checking this before the zero guard is what keeps such a push
     // connected instead of collapsing into a single parentless commit.
-    if let Some(original) = filtered_to_original.get(&filtered) {
+    if let Some(original) = filtered_to_original.get(filtered) {
         tracing::info!("Found in filtered_to_original",);
-        return Ok(*original);
+        return Ok(original);
     }
 
-    if contained_in == git2::Oid::ZERO_SHA1 {
+    if contained_in.is_zero() {
         tracing::info!("contained in zero",);
         return Ok(git2::Oid::ZERO_SHA1);
     }
 
     let oid = filter::apply_to_commit(filter, contained_in, transaction)?;
-    if oid != git2::Oid::ZERO_SHA1 {
+    if !oid.is_zero() {
         filtered_to_original.insert(oid, contained_in);
     }
 
@@ -157,6 +402,9 @@ fn find_unapply_base(
     }
 }
 
+/// The commit in the history of `contained_in` that filters to `filtered`, skipping commits whose
+/// parent already filters to it; zero when there is none. A commit found is remembered in the
+/// transaction's cache stack.
 pub fn find_original(
     transaction: &cache::Transaction,
     filter: filter::Filter,
@@ -164,12 +412,19 @@ pub fn find_original(
     filtered: git2::Oid,
     linear: bool,
 ) -> anyhow::Result<git2::Oid> {
-    if contained_in == git2::Oid::ZERO_SHA1 {
+    if contained_in.is_zero() {
         return Ok(git2::Oid::ZERO_SHA1);
     }
     if filter.is_nop() {
         return Ok(filtered);
     }
+    let key = original_key(
+        &format!("find-original:{}:{}", contained_in, linear),
+        filtered,
+    )?;
+    if let Some(original) = transaction.get_original(filter, key) {
+        return Ok(original);
+    }
     let odb = transaction.odb()?;
     let mut walk = objects::RevWalk::new(&odb);
     if linear {
@@ -187,6 +442,7 @@ pub fn find_original(
                     continue;
                 }
             }
+            transaction.insert_original(filter, key, original);
             return Ok(original);
         }
     }
@@ -194,6 +450,96 @@ pub fn find_original(
     Ok(git2::Oid::ZERO_SHA1)
 }
 
+/// The originals of several filtered commits at once, sharing one walk of `contained_in`'s
+/// history, newest first. As with [`find_original`], the first commit that filters to a filtered
+/// commit, unless its parent already does, is its original. Returns an original per filtered
+/// commit, zero where there is none.
+pub fn find_originals(
+    transaction: &cache::Transaction,
+    filter: filter::Filter,
+    contained_in: git2::Oid,
+    filtered: &[git2::Oid],
+) -> anyhow::Result<Vec<git2::Oid>> {
+    Ok(find_originals_from(transaction, filter, contained_in, filtered, None)?.0)
+}
+
+/// [`find_originals`], continuing a walk of `contained_in`'s history that stopped at
+/// `frontier`, the commits it had pending. Returns the originals together with the frontier
+/// where this walk stopped, for the next call to continue from.
+///
+/// Every commit the walk passes is remembered as the original of its filtered commit, unless
+/// one is known already, so originals the earlier walk passed are found in the cache. Filtered
+/// commits the continued walk does not find are searched in the whole history again.
+pub fn find_originals_from(
+    transaction: &cache::Transaction,
+    filter: filter::Filter,
+    contained_in: git2::Oid,
+    filtered: &[git2::Oid],
+    frontier: Option<&[git2::Oid]>,
+) -> anyhow::Result<(Vec<git2::Oid>, Vec<git2::Oid>)> {
+    let start = frontier.map_or_else(|| vec![contained_in], <[_]>::to_vec);
+    if contained_in.is_zero() {
+        return Ok((vec![git2::Oid::ZERO_SHA1; filtered.len()], vec![]));
+    }
+    if filter.is_nop() {
+        return Ok((filtered.to_vec(), start));
+    }
+    let context = format!("find-originals:{}", contained_in);
+    let mut found = HashMap::new();
+    for &id in filtered {
+        if let Some(original) = transaction.get_original(filter, original_key(&context, id)?) {
+            found.insert(id, original);
+        }
+    }
+    let mut wanted: HashSet<_> = filtered
+        .iter()
+        .filter(|id| !found.contains_key(id))
+        .copied()
+        .collect();
+
+    let mut stopped_at = start.clone();
+    let odb = transaction.odb()?;
+    let restart = frontier.is_some().then(|| vec![contained_in]);
+    for (i, tips) in std::iter::once(start).chain(restart).enumerate() {
+        if wanted.is_empty() {
+            break;
+        }
+        let mut walk =
+            objects::HistoryWalk::new(&odb, |oid| cache::compute_sequence_number(transaction, oid));
+        for tip in tips {
+            walk.push(tip)?;
+        }
+        while !wanted.is_empty() {
+            let Some(original) = walk.next_commit()? else {
+                break;
+            };
+            let id = filter::apply_to_commit(filter, original, transaction)?;
+            let parent_ids = git::read_parent_ids(&odb, original)?;
+            if parent_ids.len() == 1
+                && filter::apply_to_commit(filter, parent_ids[0], transaction)? == id
+            {
+                continue;
+            }
+            let key = original_key(&context, id)?;
+            if wanted.remove(&id) {
+                found.insert(id, original);
+                transaction.insert_original(filter, key, original);
+            } else if !found.contains_key(&id) && transaction.get_original(filter, key).is_none() {
+                transaction.insert_original(filter, key, original);
+            }
+        }
+        if i == 0 {
+            stopped_at = walk.frontier();
+        }
+    }
+
+    let originals = filtered
+        .iter()
+        .map(|id| found.get(id).copied().unwrap_or(git2::Oid::ZERO_SHA1))
+        .collect();
+    Ok((originals, stopped_at))
+}
+
 // takes everything from base except its tree and replaces it with the tree
 // given
 pub fn rewrite_commit(
@@ -218,7 +564,7 @@ pub fn rewrite_commit(
     let mut committer = None;
     let message = rewrite_data.message;
 
-    let mut commit = gix_object::CommitRef::from_bytes(base.bytes(), gix_hash::Kind::Sha1)?;
+    let mut commit = gix_object::CommitRef::from_bytes(base.bytes(), objects::oid_kind(base.id()))?;
     commit.tree = tree_id.as_ref();
 
     commit.parents.clear();
@@ -323,7 +669,7 @@ fn find_oldest_similar_commit(
 
 fn find_new_branch_base(
     transaction: &cache::Transaction,
-    filtered_to_original: &mut HashMap<git2::Oid, git2::Oid>,
+    filtered_to_original: &mut Originals,
     filter: filter::Filter,
     // See "contained_in" in find_unapply_base
     contained_in: git2::Oid,
@@ -338,7 +684,7 @@ fn find_new_branch_base(
     for rev in walk.into_topo_vec(|_| false)? {
         if let Ok(base) =
             find_unapply_base(transaction, filtered_to_original, filter, contained_in, rev)
-            && base != git2::Oid::ZERO_SHA1
+            && !base.is_zero()
         {
             tracing::info!("new branch base: {:?} mapping to {:?}", base, rev);
             let base = if let Ok(new_base) = find_oldest_similar_commit(transaction, filter, base) {
@@ -374,10 +720,16 @@ pub fn unapply_filter(
     orphans_mode: OrphansMode,
     reparent_orphans: Option<git2::Oid>,
 ) -> anyhow::Result<git2::Oid> {
-    let mut filtered_to_original = HashMap::new();
+    let mut filtered_to_original = Originals::new(
+        transaction,
+        filter,
+        original_target,
+        &orphans_mode,
+        reparent_orphans,
+    );
     let mut ret = original_target;
 
-    let old_filtered_oid = if old_filtered_oid == git2::Oid::ZERO_SHA1 {
+    let old_filtered_oid = if old_filtered_oid.is_zero() {
         match find_new_branch_base(
             transaction,
             &mut filtered_to_original,
@@ -402,9 +754,9 @@ pub fn unapply_filter(
     if new_filtered_oid == old_filtered_oid {
         tracing::info!("New == old. Pushing a new branch?");
 
-        let unapply_result = if let Some(original) = filtered_to_original.get(&new_filtered_oid) {
+        let unapply_result = if let Some(original) = filtered_to_original.get(new_filtered_oid) {
             tracing::info!("Found in filtered_to_original {}", original);
-            *original
+            original
         } else {
             tracing::info!("Had to go through the whole thing",);
             find_original(
@@ -453,7 +805,9 @@ pub fn unapply_filter(
         tracing::info!("walk commit: {:?}", rev);
         let module_commit = objects::CommitData::read(&odb, rev)?;
 
-        if filtered_to_original.contains_key(&module_commit.id()) {
+        // Known from earlier in this walk, or from an earlier push of the same commit.
+        if let Some(original) = filtered_to_original.get(module_commit.id()) {
+            ret = original;
             continue;
         }
 
@@ -497,7 +851,7 @@ pub fn unapply_filter(
             })
             .filter(|unapply_base| {
                 if let Ok(oid) = unapply_base {
-                    *oid != git2::Oid::ZERO_SHA1
+                    !oid.is_zero()
                 } else {
                     true
                 }
@@ -586,7 +940,7 @@ pub fn unapply_filter(
                     transaction,
                     filter,
                     tree,
-                    filter::tree::empty_id(),
+                    filter::tree::empty_id(objects::oid_kind(tree)),
                     Some((module_commit.id(), module_commit.id())),
                 )?
             }
@@ -610,7 +964,7 @@ pub fn unapply_filter(
                     }
                 }
 
-                if tid == git2::Oid::ZERO_SHA1 && parent_count == 2 {
+                if tid.is_zero() && parent_count == 2 {
                     // If we could not select one of the parents, try to merge them.
                     // We expect conflicts to occur only in the paths that are present in
                     // the filtered commit.
@@ -657,7 +1011,7 @@ pub fn unapply_filter(
                     }
                 }
 
-                if tid == git2::Oid::ZERO_SHA1 {
+                if tid.is_zero() {
                     // We give up. If we see this message again we need to investigate once
                     // more and maybe consider allowing a manual override as last resort.
                     tracing::warn!("rejecting merge");
@@ -803,13 +1157,13 @@ fn create_filtered_commit2(
     let odb = transaction.odb()?;
     let mut filtered_parents: Vec<(git2::Oid, git2::Oid)> = filtered_parent_ids
         .iter()
-        .filter(|x| **x != git2::Oid::ZERO_SHA1)
+        .filter(|x| !x.is_zero())
         .map(|x| Ok((*x, filtered_parent_tree_id(transaction, *x)?)))
         .collect::<anyhow::Result<_>>()?;
 
     if filtered_parents
         .iter()
-        .any(|(_, tree_id)| *tree_id == filter::tree::empty_id())
+        .any(|(_, tree_id)| objects::is_empty_tree(*tree_id))
     {
         // An "initial merge" is a merge whose parents have no common ancestor.
         // Cheaper than `repo.merge_base_many(...).is_err()`: ask whether the
@@ -819,7 +1173,7 @@ fn create_filtered_commit2(
         let nonzero_parent_ids: Vec<_> = filtered_parent_ids
             .iter()
             .copied()
-            .filter(|x| *x != git2::Oid::ZERO_SHA1)
+            .filter(|x| !x.is_zero())
             .collect();
         let is_initial_merge = nonzero_parent_ids.len() > 1
             && !cache::parents_share_root(transaction, &nonzero_parent_ids)?;
@@ -829,9 +1183,9 @@ fn create_filtered_commit2(
         if is_initial_merge
             && filtered_parents
                 .iter()
-                .any(|(_, tree_id)| *tree_id != filter::tree::empty_id())
+                .any(|(_, tree_id)| !objects::is_empty_tree(*tree_id))
         {
-            filtered_parents.retain(|(_, tree_id)| *tree_id != filter::tree::empty_id());
+            filtered_parents.retain(|(_, tree_id)| !objects::is_empty_tree(*tree_id));
         }
     }
 
@@ -874,7 +1228,7 @@ fn create_filtered_commit2(
             // Returning the parent id here means the commit is dropped from the output history
             return Ok((filtered_parents[0].0, false));
         }
-        if rewrite_data.tree_id() == filter::tree::empty_id() {
+        if objects::is_empty_tree(rewrite_data.tree_id()) {
             return Ok((git2::Oid::ZERO_SHA1, false));
         }
     }
@@ -922,14 +1276,14 @@ fn is_empty_root(
     odb: &josh_memodb::Odb,
     oid: git2::Oid,
 ) -> anyhow::Result<bool> {
-    if oid == filter::tree::empty_id() {
+    if objects::is_empty_tree(oid) {
         return Ok(true);
     }
 
     let bytes = transaction
         .read_tree_bytes(odb, oid)?
         .ok_or_else(|| anyhow!("{} is not a tree", oid))?;
-    let tree = gix_object::TreeRef::from_bytes(&bytes, gix_hash::Kind::Sha1)?;
+    let tree = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(oid))?;
     Ok(tree
         .entries
         .iter()
@@ -941,13 +1295,13 @@ fn is_empty_subtree(
     odb: &josh_memodb::Odb,
     oid: git2::Oid,
 ) -> bool {
-    if oid == filter::tree::empty_id() {
+    if objects::is_empty_tree(oid) {
         return true;
     }
     let Ok(Some(bytes)) = transaction.read_tree_bytes(odb, oid) else {
         return false;
     };
-    let Ok(tree) = gix_object::TreeRef::from_bytes(&bytes, gix_hash::Kind::Sha1) else {
+    let Ok(tree) = gix_object::TreeRef::from_bytes(&bytes, objects::oid_kind(oid)) else {
         return false;
     };
     tree.entries
@@ -959,6 +1313,149 @@ fn is_empty_subtree(
 mod tests {
     use super::*;
 
+    #[test]
+    fn walk_segments_split_at_forks_and_merges() {
+        // Positions in walk order (children first): 0 merges 1 and 3; 1-2 and 3-4 are two
+        // branches off 5, the root.
+        let parents = vec![vec![1, 3], vec![2], vec![5], vec![4], vec![5], vec![]];
+        let (segments, deps) = walk_segments(&parents);
+        assert_eq!(segments, vec![vec![5], vec![4, 3], vec![2, 1], vec![0]]);
+        assert_eq!(deps, vec![vec![], vec![0], vec![0], vec![1, 2]]);
+    }
+
+    // Filtering on several threads must produce exactly the commits the serial walk does.
+    #[test]
+    fn parallel_walk_matches_serial() {
+        let td = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(td.path()).unwrap();
+        let sig = git2::Signature::new("t", "t@example.com", &git2::Time::new(0, 0)).unwrap();
+
+        let commit = |parents: &[git2::Oid], path: &str, content: &str| {
+            let parents: Vec<_> = parents
+                .iter()
+                .map(|p| repo.find_commit(*p).unwrap())
+                .collect();
+            let base = match parents.first() {
+                Some(parent) => parent.tree().unwrap(),
+                None => repo
+                    .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+                    .unwrap(),
+            };
+            let mut builder = git2::build::TreeUpdateBuilder::new();
+            builder.upsert(
+                path,
+                repo.blob(content.as_bytes()).unwrap(),
+                git2::FileMode::Blob,
+            );
+            let tree = repo
+                .find_tree(builder.create_updated(&repo, &base).unwrap())
+                .unwrap();
+            let parents: Vec<_> = parents.iter().collect();
+            repo.commit(None, &sig, &sig, content, &tree, &parents)
+                .unwrap()
+        };
+
+        // Eight branches off a common root, merged one after the other. Every third commit
+        // only touches a path the filter drops.
+        let root = commit(&[], "a/file", "root");
+        let mut head = root;
+        for branch in 0..8 {
+            let mut tip = root;
+            for i in 0..130 {
+                let dir = ["a", "b", "c"][i % 3];
+                tip = commit(&[tip], &format!("{dir}/{branch}"), &format!("{branch} {i}"));
+            }
+            head = commit(&[head, tip], "a/merged", &format!("merge {branch}"));
+        }
+
+        let filter = filter::parse(":[x=:/a,:/b]").unwrap();
+        let filtered = |threads| {
+            let cache = std::sync::Arc::new(cache::CacheStack::new());
+            let t = cache::TransactionContext::new(td.path(), cache)
+                .with_threads(threads)
+                .open()
+                .unwrap();
+            walk2(filter, head, &t).unwrap();
+            t.get(filter, head).unwrap().unwrap()
+        };
+        assert_eq!(filtered(4), filtered(1));
+    }
+
+    // Looking up originals page by page, each walk continuing from where the last one stopped,
+    // finds what one walk over all of them finds, also when the cache was lost in between.
+    #[test]
+    fn find_originals_resumes_from_frontier() {
+        let td = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(td.path()).unwrap();
+        let sig = git2::Signature::new("t", "t@example.com", &git2::Time::new(0, 0)).unwrap();
+        let commit = |parents: &[git2::Oid], path: &str, content: &str| {
+            let parents: Vec<_> = parents
+                .iter()
+                .map(|p| repo.find_commit(*p).unwrap())
+                .collect();
+            let mut builder = git2::build::TreeUpdateBuilder::new();
+            builder.upsert(
+                path,
+                repo.blob(content.as_bytes()).unwrap(),
+                git2::FileMode::Blob,
+            );
+            let base = parents[0].tree().unwrap();
+            let tree = repo
+                .find_tree(builder.create_updated(&repo, &base).unwrap())
+                .unwrap();
+            let parents: Vec<_> = parents.iter().collect();
+            repo.commit(None, &sig, &sig, content, &tree, &parents)
+                .unwrap()
+        };
+        let empty = repo
+            .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        let root = repo.commit(None, &sig, &sig, "root", &empty, &[]).unwrap();
+
+        // Two branches, merged, with commits the filter drops in between.
+        let mut head = root;
+        let mut side = root;
+        for i in 0..12 {
+            let dir = ["a", "b"][i % 2];
+            head = commit(&[head], &format!("{dir}/main"), &format!("main {i}"));
+            side = commit(&[side], &format!("{dir}/side"), &format!("side {i}"));
+        }
+        head = commit(&[head, side], "a/merged", "merge");
+
+        let filter = filter::parse(":/a").unwrap();
+        let open = || {
+            let cache = std::sync::Arc::new(cache::CacheStack::new());
+            cache::TransactionContext::new(td.path(), cache)
+                .open()
+                .unwrap()
+        };
+        let t = open();
+        let filtered_head = filter::apply_to_commit(filter, head, &t).unwrap();
+        let odb = t.odb().unwrap();
+        let mut walk =
+            objects::HistoryWalk::new(&odb, |oid| cache::compute_sequence_number(&t, oid));
+        walk.push(filtered_head).unwrap();
+        let filtered: Vec<git2::Oid> = walk.map(Result::unwrap).collect();
+        let expected = find_originals(&t, filter, head, &filtered).unwrap();
+        assert!(expected.iter().all(|original| !original.is_zero()));
+
+        for fresh in [false, true] {
+            let mut t = open();
+            let mut frontier = None;
+            let mut originals = vec![];
+            for page in filtered.chunks(3) {
+                if fresh {
+                    t = open();
+                }
+                let (found, stopped_at) =
+                    find_originals_from(&t, filter, head, page, frontier.as_deref()).unwrap();
+                originals.extend(found);
+                frontier = Some(stopped_at);
+            }
+            assert_eq!(originals, expected, "fresh cache: {fresh}");
+        }
+    }
+
     // A root is "empty" iff it contains nothing but (recursively) empty trees: the empty tree
     // itself and nested empty chains qualify; any blob or gitlink anywhere disqualifies.
     #[test]
@@ -972,7 +1469,7 @@ mod tests {
         let t = ctx.open().unwrap();
         let odb = t.odb().unwrap();
 
-        let empty = filter::tree::empty_id();
+        let empty = filter::tree::empty_id(gix_hash::Kind::Sha1);
         assert!(is_empty_root(&t, &odb, empty).unwrap());
 
         // A chain of trees bottoming out in the (virtualized) empty tree is empty. Built by
@@ -1013,4 +1510,130 @@ mod tests {
         let with_gitlink = b.write().unwrap();
         assert!(!is_empty_root(&t, &odb, with_gitlink).unwrap());
     }
+
+    /// Records of the cache stack, shared with the test so it can see what was written.
+    #[derive(Clone, Default)]
+    struct Recorder(
+        std::sync::Arc<std::sync::Mutex<HashMap<(filter::Filter, git2::Oid), git2::Oid>>>,
+    );
+
+    impl cache::CacheBackend for Recorder {
+        fn read(
+            &self,
+            filter: filter::Filter,
+            from: git2::Oid,
+            _hint: cache::HistoryGraphHint,
+            _tree_keyed: bool,
+        ) -> anyhow::Result<Option<git2::Oid>> {
+            Ok(self.0.lock().unwrap().get(&(filter, from)).cloned())
+        }
+
+        fn write(
+            &self,
+            filter: filter::Filter,
+            from: git2::Oid,
+            to: git2::Oid,
+            _hint: cache::HistoryGraphHint,
+            _tree_keyed: bool,
+        ) -> anyhow::Result<()> {
+            self.0.lock().unwrap().insert((filter, from), to);
+            Ok(())
+        }
+    }
+
+    impl Recorder {
+        /// How many records run a filter backwards.
+        fn reverse_records(&self) -> usize {
+            self.0
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|(filter, _)| filter.get_meta("cache").is_some())
+                .count()
+        }
+    }
+
+    // Pushing an amended stack again reuses what the first push unapplied: only the amended
+    // commit is unapplied anew, and the result is what a cold push computes.
+    #[test]
+    fn repeated_push_reuses_unapplied_commits() {
+        let td = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(td.path()).unwrap();
+        let sig = git2::Signature::new("t", "t@example.com", &git2::Time::new(0, 0)).unwrap();
+        let commit = |parent: Option<git2::Oid>, path: &str, content: &str| {
+            let parents: Vec<_> = parent
+                .iter()
+                .map(|p| repo.find_commit(*p).unwrap())
+                .collect();
+            let base = match parents.first() {
+                Some(parent) => parent.tree().unwrap(),
+                None => repo
+                    .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+                    .unwrap(),
+            };
+            let mut builder = git2::build::TreeUpdateBuilder::new();
+            builder.upsert(
+                path,
+                repo.blob(content.as_bytes()).unwrap(),
+                git2::FileMode::Blob,
+            );
+            let tree = repo
+                .find_tree(builder.create_updated(&repo, &base).unwrap())
+                .unwrap();
+            let parents: Vec<_> = parents.iter().collect();
+            repo.commit(None, &sig, &sig, content, &tree, &parents)
+                .unwrap()
+        };
+
+        let base = commit(None, "a/file", "base");
+        let head = commit(Some(base), "b/file", "outside");
+        let filter = filter::parse(":/a").unwrap();
+
+        let recorder = Recorder::default();
+        let cache = std::sync::Arc::new(cache::CacheStack::new().with_backend(recorder.clone()));
+        let push = |stack_top: &dyn Fn(git2::Oid) -> git2::Oid, cache| {
+            let t = cache::TransactionContext::new(td.path(), cache)
+                .open()
+                .unwrap();
+            let filtered_head = filter::apply_to_commit(filter, head, &t).unwrap();
+            // The stack is built with plain git2 on top of the filtered head.
+            t.flush_mem_odb().unwrap();
+            let tip = stack_top(filtered_head);
+            let unapplied = unapply_filter(
+                &t,
+                filter,
+                head,
+                filtered_head,
+                tip,
+                OrphansMode::Fail,
+                None,
+            )
+            .unwrap();
+            assert_eq!(filter::apply_to_commit(filter, unapplied, &t).unwrap(), tip);
+            unapplied
+        };
+
+        push(
+            &|filtered_head| {
+                let lower = commit(Some(filtered_head), "file", "one");
+                let lower = commit(Some(lower), "file", "two");
+                commit(Some(lower), "file", "three")
+            },
+            cache.clone(),
+        );
+        let after_first = recorder.reverse_records();
+        assert!(after_first > 0);
+
+        let amend = |filtered_head| {
+            let lower = commit(Some(filtered_head), "file", "one");
+            let lower = commit(Some(lower), "file", "two");
+            commit(Some(lower), "file", "three, amended")
+        };
+        let warm = push(&amend, cache.clone());
+        // The amended commit: its tree, and its original.
+        assert_eq!(recorder.reverse_records(), after_first + 2);
+
+        let cold = push(&amend, std::sync::Arc::new(cache::CacheStack::new()));
+        assert_eq!(warm, cold);
+    }
 }
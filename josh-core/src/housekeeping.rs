@@ -7,6 +7,19 @@ use tracing::{Level, info, span};
 
 pub type KnownViews = HashMap<String, (git2::Oid, BTreeSet<String>)>;
 
+/// `git` arguments adding the commits reachable from any ref to a repository's commit-graph,
+/// with changed-path filters, as a new layer of a split graph: each run writes only the commits
+/// added since the last one, and git merges small layers as it goes. josh's walks read the
+/// graph back through `josh_gix_ext::CommitGraph`.
+pub const COMMIT_GRAPH_WRITE: &[&str] = &[
+    "commit-graph",
+    "write",
+    "--reachable",
+    "--changed-paths",
+    "--split",
+    "--no-progress",
+];
+
 static KNOWN_FILTERS: LazyLock<std::sync::Mutex<KnownViews>> =
     LazyLock::new(|| std::sync::Mutex::new(std::collections::HashMap::new()));
 
@@ -118,7 +131,6 @@ regex_parsed!(
  * expensive to build from scratch using heuristics.
  */
 pub fn discover_filter_candidates(transaction: &cache::Transaction) -> anyhow::Result<()> {
-    let repo = transaction.git2_repo();
     let mut known_filters = KNOWN_FILTERS.lock().unwrap();
     let trace_s = span!(Level::TRACE, "discover_filter_candidates");
     let _e = trace_s.enter();
@@ -140,12 +152,8 @@ pub fn discover_filter_candidates(transaction: &cache::Transaction) -> anyhow::R
             .or_insert_with(|| (git2::Oid::ZERO_SHA1, BTreeSet::new()));
 
         if known_f.0 != target {
-            // PORT: mirror-resident fetched target, and `peel` tolerates annotated tags --
-            // stays on git2 until flag day.
-            let tree = repo
-                .find_object(target, None)?
-                .peel(git2::ObjectType::Tree)?;
-            let hs = find_all_workspaces_and_subdirectories(&odb, tree.id())?;
+            let tree = crate::objects::peel_to_tree(&odb, target)?;
+            let hs = find_all_workspaces_and_subdirectories(&odb, tree)?;
             known_f.0 = target;
             for i in hs {
                 known_f.1.insert(i);
@@ -41,7 +41,7 @@ impl Default for Oid {
 impl std::convert::TryFrom<String> for Oid {
     type Error = anyhow::Error;
     fn try_from(s: String) -> anyhow::Result<Oid> {
-        Ok(Oid(git2::Oid::from_str(&s)?))
+        Ok(Oid(objects::parse_oid(&s)?))
     }
 }
 
@@ -0,0 +1,88 @@
+//! Eviction from the sled cache. Lives in its own integration binary because the sled db and
+//! its configured path are process-global.
+
+use std::sync::Arc;
+
+use josh_core::cache::{CacheStack, SledCacheBackend, TransactionContext};
+use josh_core::filter;
+
+/// A new root commit with an empty tree.
+fn empty_commit(transaction: &josh_core::cache::Transaction, message: &str) -> git2::Oid {
+    let git = transaction.git2_repo();
+    let sig = git2::Signature::new("t", "t@example.com", &git2::Time::new(0, 0)).unwrap();
+    let tree = git
+        .find_tree(git.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    git.commit(None, &sig, &sig, message, &tree, &[]).unwrap()
+}
+
+#[test]
+fn gc_evicts_least_recently_used_filters() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    git2::Repository::init_bare(repo).unwrap();
+
+    let cache = Arc::new(CacheStack::new().with_backend(SledCacheBackend::new(repo)));
+    let specs = [":/a", ":/b", ":/c"];
+
+    // One transaction per filter, so that each gets its own (increasing) last access time.
+    for (i, spec) in specs.iter().enumerate() {
+        let transaction = TransactionContext::new(repo, cache.clone()).open().unwrap();
+        let filter = filter::parse(spec).unwrap();
+        // Later filters get more records: `:/c` is both the largest and the most recent.
+        for n in 0..=i {
+            let commit = empty_commit(&transaction, &format!("{spec} {n}"));
+            transaction.insert(filter, commit, commit, true).unwrap();
+        }
+        if i + 1 < specs.len() {
+            // Access times have a resolution of one second.
+            std::thread::sleep(std::time::Duration::from_millis(1100));
+        }
+    }
+
+    // Besides the filters inserted here, the cache holds the history hints computed for them.
+    let path_filters = || {
+        let stats = josh_core::cache::sled_stats().unwrap();
+        let total: u64 = stats.iter().map(|tree| tree.bytes).sum();
+        let stats: Vec<_> = stats
+            .into_iter()
+            .filter(|tree| tree.name.starts_with(":/"))
+            .collect();
+        (stats, total)
+    };
+    let names = |stats: &[josh_core::cache::SledTreeStats]| {
+        stats
+            .iter()
+            .map(|tree| tree.name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let (stats, total) = path_filters();
+    assert_eq!(names(&stats), [":/c", ":/b", ":/a"]);
+    assert_eq!(stats[0].entries, 3);
+    assert_eq!(stats[0].bytes, 3 * 40);
+    assert!(stats.iter().all(|tree| tree.last_access.is_some()));
+
+    // No limits: nothing but empty trees goes.
+    assert!(josh_core::cache::sled_gc(None, None).unwrap().is_empty());
+
+    // Room for all but one record: `:/a`, the least recently used, goes.
+    let evicted = josh_core::cache::sled_gc(Some(total - 40), None).unwrap();
+    assert_eq!(names(&evicted), [":/a"]);
+
+    // Eviction goes by recency, not size: `:/b` goes before the larger `:/c`.
+    let evicted = josh_core::cache::sled_gc(Some(total - 3 * 40), None).unwrap();
+    assert_eq!(names(&evicted), [":/b"]);
+
+    let (stats, _) = path_filters();
+    assert_eq!(names(&stats), [":/c"]);
+
+    // Evicted filters are recomputed, and show up again, on their next use.
+    let transaction = TransactionContext::new(repo, cache.clone()).open().unwrap();
+    let commit = empty_commit(&transaction, "again");
+    transaction
+        .insert(filter::parse(":/a").unwrap(), commit, commit, true)
+        .unwrap();
+    drop(transaction);
+    assert_eq!(names(&path_filters().0), [":/c", ":/a"]);
+}
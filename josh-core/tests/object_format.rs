@@ -0,0 +1,300 @@
+//! Repositories in an object format other than SHA-1, or with refs in a format other than
+//! loose files and `packed-refs`.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use josh_core::cache::{CacheStack, Expected, TransactionContext};
+#[cfg(feature = "sha256")]
+use josh_core::filter;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {:?}: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap().trim().to_owned()
+}
+
+#[cfg(feature = "sha256")]
+fn write(dir: &Path, path: &str, content: &str) {
+    let path = dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+/// The same small history -- a merge, CRLF content, a workspace -- in a repository of the given
+/// object format, returning its head.
+#[cfg(feature = "sha256")]
+fn fixture(dir: &Path, format: &str) -> git2::Oid {
+    std::fs::create_dir(dir).unwrap();
+    let object_format = format!("--object-format={}", format);
+    git(dir, &["init", "-q", "-b", "main", &object_format]);
+    write(dir, "a/x.txt", "one\r\ntwo\r\n");
+    write(dir, "a/README.md", "docs\n");
+    write(dir, "b/y.txt", "b\n");
+    write(dir, "ws/workspace.josh", "lib = :/a\n");
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "initial"]);
+    git(dir, &["checkout", "-q", "-b", "side"]);
+    write(dir, "a/x.txt", "one\r\ntwo\r\nthree\r\n");
+    git(dir, &["commit", "-q", "-am", "side"]);
+    git(dir, &["checkout", "-q", "main"]);
+    write(dir, "b/y.txt", "b, changed\n");
+    git(dir, &["commit", "-q", "-am", "main"]);
+    git(dir, &["merge", "-q", "--no-ff", "-m", "merge", "side"]);
+    write(dir, "c/z.txt", "z\n");
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "add c"]);
+    josh_core::objects::parse_oid(&git(dir, &["rev-parse", "HEAD"])).unwrap()
+}
+
+/// Everything about the history of `commit` but its object ids: per commit in topological
+/// order the subject, the number of parents and every file with its content.
+#[cfg(feature = "sha256")]
+fn describe(dir: &Path, commit: git2::Oid) -> String {
+    let mut out = String::new();
+    for rev in git(
+        dir,
+        &["rev-list", "--topo-order", "--parents", &commit.to_string()],
+    )
+    .lines()
+    {
+        let mut ids = rev.split(' ');
+        let rev = ids.next().unwrap();
+        out.push_str(&format!(
+            "{} ({} parents)\n",
+            git(dir, &["log", "-1", "--format=%s", rev]),
+            ids.count()
+        ));
+        for path in git(dir, &["ls-tree", "-r", "--name-only", rev]).lines() {
+            let content = git(dir, &["cat-file", "-p", &format!("{}:{}", rev, path)]);
+            out.push_str(&format!("  {}: {:?}\n", path, content));
+        }
+    }
+    out
+}
+
+fn open(dir: &Path) -> josh_core::cache::Transaction {
+    TransactionContext::new(dir, Arc::new(CacheStack::new()))
+        .open()
+        .unwrap()
+}
+
+/// Filter `head` with `spec`, in a transaction of its own so the result is on disk afterwards.
+#[cfg(feature = "sha256")]
+fn filter_head(dir: &Path, spec: &str, head: git2::Oid) -> git2::Oid {
+    let transaction = open(dir);
+    josh_core::filter_commit(&transaction, filter::parse(spec).unwrap(), head).unwrap()
+}
+
+/// Push an edit of `path` on top of the `spec` projection of `head` back through the filter.
+#[cfg(feature = "sha256")]
+fn unapply_edit(dir: &Path, spec: &str, head: git2::Oid, path: &str, content: &str) -> git2::Oid {
+    let filtered = filter_head(dir, spec, head);
+    let repo = git2::Repository::open(dir).unwrap();
+    let parent = repo.find_commit(filtered).unwrap();
+    let blob = repo.blob(content.as_bytes()).unwrap();
+    let mut builder = repo.treebuilder(Some(&parent.tree().unwrap())).unwrap();
+    builder.insert(path, blob, 0o100644).unwrap();
+    let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+    let sig = git2::Signature::new("t", "t@example.com", &git2::Time::new(0, 0)).unwrap();
+    let edited = repo
+        .commit(None, &sig, &sig, "edit", &tree, &[&parent])
+        .unwrap();
+
+    let transaction = open(dir);
+    josh_core::history::unapply_filter(
+        &transaction,
+        filter::parse(spec).unwrap(),
+        head,
+        filtered,
+        edited,
+        josh_core::history::OrphansMode::Fail,
+        None,
+    )
+    .unwrap()
+}
+
+// The filter suite on a sha256 repository: every filter gives the same history as it does on
+// the same commits in a sha1 repository, only in sha256 object ids.
+#[cfg(feature = "sha256")]
+#[test]
+fn sha256_repository_filters_like_sha1() {
+    let dir = tempfile::tempdir().unwrap();
+    let sha1 = dir.path().join("sha1");
+    let sha256 = dir.path().join("sha256");
+    let head1 = fixture(&sha1, "sha1");
+    let head256 = fixture(&sha256, "sha256");
+    assert_eq!(head256.object_format(), git2::ObjectFormat::Sha256);
+    assert_eq!(describe(&sha1, head1), describe(&sha256, head256));
+
+    for spec in [
+        ":/a",
+        ":/b",
+        ":/c",
+        ":[x=:/c,y=:/nothing]",
+        ":prefix=p",
+        ":/a:prefix=q",
+        "::a/x.txt",
+        ":exclude[::*.md]",
+        ":[a=:/a,b=:/b]",
+        ":workspace=ws",
+        ":eol=lf",
+        ":empty",
+    ] {
+        let filtered1 = filter_head(&sha1, spec, head1);
+        let filtered256 = filter_head(&sha256, spec, head256);
+        if filtered1.is_zero() {
+            assert!(filtered256.is_zero(), "{}", spec);
+            continue;
+        }
+        assert_eq!(
+            filtered256.object_format(),
+            git2::ObjectFormat::Sha256,
+            "{}",
+            spec
+        );
+        assert_eq!(
+            describe(&sha1, filtered1),
+            describe(&sha256, filtered256),
+            "{}",
+            spec
+        );
+    }
+
+    for (spec, path) in [
+        (":/a", "x.txt"),
+        (":workspace=ws", "new.txt"),
+        (":eol=lf", "top.txt"),
+    ] {
+        let pushed1 = unapply_edit(&sha1, spec, head1, path, "edited\n");
+        let pushed256 = unapply_edit(&sha256, spec, head256, path, "edited\n");
+        assert_eq!(
+            describe(&sha1, pushed1),
+            describe(&sha256, pushed256),
+            "{}",
+            spec
+        );
+    }
+}
+
+// Without the `sha256` feature libgit2 cannot read a sha256 repository, and opening one says
+// what josh is missing.
+#[cfg(not(feature = "sha256"))]
+#[test]
+fn sha256_repository_needs_the_feature() {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "-q", "--object-format=sha256"]);
+    let error = TransactionContext::new(dir.path(), Arc::new(CacheStack::new()))
+        .open()
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("`sha256` feature"), "{}", error);
+}
+
+/// A bare repository declaring `extensions.refStorage = reftable`, laid out the way `git init
+/// --ref-format=reftable` leaves it: refs in `reftable/`, and a `HEAD` file pointing nowhere
+/// for tools that do not know the format.
+#[test]
+fn reftable_repository_keeps_refs_in_reftable() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init_bare(dir.path()).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_i32("core.repositoryformatversion", 1).unwrap();
+    config.set_str("extensions.refStorage", "reftable").unwrap();
+    config.set_str("core.logAllRefUpdates", "true").unwrap();
+    std::fs::write(dir.path().join("HEAD"), "ref: refs/heads/.invalid\n").unwrap();
+    std::fs::create_dir(dir.path().join("reftable")).unwrap();
+    std::fs::write(dir.path().join("reftable/tables.list"), "").unwrap();
+
+    let tree = repo
+        .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    let sig = git2::Signature::new("t", "t@example.com", &git2::Time::new(0, 0)).unwrap();
+    let first = repo.commit(None, &sig, &sig, "first", &tree, &[]).unwrap();
+    let second = repo
+        .commit(
+            None,
+            &sig,
+            &sig,
+            "second",
+            &tree,
+            &[&repo.find_commit(first).unwrap()],
+        )
+        .unwrap();
+
+    let transaction = open(dir.path());
+    transaction
+        .update_ref("refs/heads/main", Expected::Absent, first, "create")
+        .unwrap();
+    transaction
+        .update_ref("refs/heads/main", Expected::At(first), second, "advance")
+        .unwrap();
+    assert!(
+        transaction
+            .update_ref("refs/heads/main", Expected::At(first), first, "stale")
+            .is_err()
+    );
+    transaction
+        .create_symref("HEAD", "refs/heads/main", "checkout")
+        .unwrap();
+    transaction
+        .update_ref("refs/josh/a", Expected::Any, first, "test")
+        .unwrap();
+    transaction
+        .update_ref("refs/josh/b", Expected::Any, second, "test")
+        .unwrap();
+    transaction
+        .delete_ref("refs/josh/a", Expected::At(first))
+        .unwrap();
+    drop(transaction);
+
+    // Nothing went to the files a files repository keeps refs in.
+    assert!(!dir.path().join("refs/heads/main").exists());
+    assert!(!dir.path().join("packed-refs").exists());
+    assert!(!dir.path().join("logs").exists());
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("HEAD")).unwrap(),
+        "ref: refs/heads/.invalid\n"
+    );
+
+    let transaction = open(dir.path());
+    let head = transaction.head().unwrap();
+    assert_eq!(head.reference, "refs/heads/main");
+    assert_eq!(head.commit, second);
+    assert_eq!(transaction.resolve_ref("refs/josh/a").unwrap(), None);
+    let mut refs = vec![];
+    transaction
+        .for_each_ref_prefixed("refs/", |name, oid| {
+            refs.push((name.to_owned(), oid));
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(
+        refs,
+        [
+            ("refs/heads/main".to_owned(), second),
+            ("refs/josh/b".to_owned(), second)
+        ]
+    );
+    assert_eq!(
+        transaction.expand_ref_name("main").unwrap().as_deref(),
+        Some("refs/heads/main")
+    );
+    assert_eq!(transaction.rev_parse("main").unwrap(), Some(second));
+    assert_eq!(transaction.rev_parse("main~1").unwrap(), Some(first));
+    assert_eq!(transaction.rev_parse("HEAD^").unwrap(), Some(first));
+    assert_eq!(
+        transaction.rev_parse(&second.to_string()).unwrap(),
+        Some(second)
+    );
+}
@@ -0,0 +1,110 @@
+//! Filtering a blobless clone: tree-only filters run without fetching anything, content
+//! filters fetch the blobs they read from the promisor remote.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use josh_core::cache::{CacheStack, TransactionContext};
+use josh_core::filter;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {:?}: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap().trim().to_owned()
+}
+
+fn write(dir: &Path, path: &str, content: &str) {
+    let path = dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+/// Packs fetched from the promisor remote; git marks each with a `.promisor` file. Packs josh
+/// writes itself carry no such mark.
+fn promisor_packs(repo: &Path) -> usize {
+    std::fs::read_dir(repo.join("objects/pack"))
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().extension() == Some("promisor".as_ref()))
+        .count()
+}
+
+/// Filter `head` with `spec` in a transaction of its own.
+fn filter_head(repo: &Path, spec: &str, head: git2::Oid) -> git2::Oid {
+    let transaction = TransactionContext::new(repo, Arc::new(CacheStack::new()))
+        .open()
+        .unwrap();
+    josh_core::filter_commit(&transaction, filter::parse(spec).unwrap(), head).unwrap()
+}
+
+#[test]
+fn blobless_clone_fetches_only_what_content_filters_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let upstream = dir.path().join("upstream");
+    std::fs::create_dir(&upstream).unwrap();
+    git(&upstream, &["init", "-q", "-b", "main"]);
+    git(&upstream, &["config", "uploadpack.allowFilter", "true"]);
+    write(&upstream, "a/x.txt", "pattern in a\n");
+    write(&upstream, "a/README.md", "docs\n");
+    write(&upstream, "b/y.txt", "b\n");
+    git(&upstream, &["add", "."]);
+    git(&upstream, &["commit", "-q", "-m", "one"]);
+    write(&upstream, "b/y.txt", "b, changed\n");
+    git(&upstream, &["commit", "-q", "-am", "two"]);
+    let head = git2::Oid::from_str(&git(&upstream, &["rev-parse", "HEAD"])).unwrap();
+    let blob = git2::Oid::from_str(&git(&upstream, &["rev-parse", "HEAD:a/x.txt"])).unwrap();
+
+    let url = format!("file://{}", upstream.display());
+    git(
+        dir.path(),
+        &[
+            "clone",
+            "-q",
+            "--bare",
+            "--filter=blob:none",
+            &url,
+            "partial.git",
+        ],
+    );
+    git(dir.path(), &["clone", "-q", "--bare", &url, "full.git"]);
+    let partial = dir.path().join("partial.git");
+    let full = dir.path().join("full.git");
+    let packs = promisor_packs(&partial);
+
+    // The partial clone goes first each time: filter results are memoized process-wide by
+    // tree, and a hit would hide a blob read.
+    for spec in [":/a", ":prefix=p", ":exclude[::*.md]", ":[a=:/a,b=:/b]"] {
+        let filtered = filter_head(&partial, spec, head);
+        assert_eq!(filtered, filter_head(&full, spec, head), "{}", spec);
+        assert_eq!(promisor_packs(&partial), packs, "{} fetched objects", spec);
+    }
+    assert!(
+        !git2::Repository::open(&partial)
+            .unwrap()
+            .odb()
+            .unwrap()
+            .exists(blob)
+    );
+
+    let spec = r#":/a:replace("pattern":"replacement")"#;
+    let filtered = filter_head(&partial, spec, head);
+    assert_eq!(filtered, filter_head(&full, spec, head));
+    // Both blobs of `a` come in with one fetch.
+    assert_eq!(promisor_packs(&partial), packs + 1);
+    assert!(
+        git2::Repository::open(&partial)
+            .unwrap()
+            .odb()
+            .unwrap()
+            .exists(blob)
+    );
+}
@@ -396,3 +396,8 @@ pub fn reachable_roots() -> Filter {
 pub fn index() -> Filter {
     to_filter(Op::Index)
 }
+
+/// The `:SYMBOLS` symbol-index filter, the cache namespace of the symbol index records.
+pub fn symbols() -> Filter {
+    to_filter(Op::Symbols)
+}
@@ -0,0 +1,480 @@
+//! Static checks for filter specs (`josh filter lint`): problems that can be found from the
+//! spec alone, without a commit to apply it to. Unlike `compute_warnings`, which tells whether a
+//! filter matches anything in a given tree, these are properties of the spec itself.
+
+use std::path::PathBuf;
+
+use pest::Parser;
+use pest::iterators::Pair;
+
+use super::parse::{Grammar, Rule, parse};
+use crate::opt::invert;
+use crate::persist::{to_filter, to_op};
+use crate::{Filter, Op, experimental_features_enabled, record_experimental_features};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The filter will not work as written.
+    Error,
+    /// The filter works, but likely not as intended.
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// One finding of [`lint`], located by a byte range into the linted spec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable name of the check that fired, e.g. `shadowed-entry`.
+    pub code: &'static str,
+    pub message: String,
+    pub span: std::ops::Range<usize>,
+    /// 1-based line and column of the start of `span`.
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, code: &'static str, span: pest::Span, message: String) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        Diagnostic {
+            severity,
+            code,
+            message,
+            span: span.start()..span.end(),
+            line,
+            column,
+        }
+    }
+
+    fn from_pest(error: pest::error::Error<Rule>) -> Self {
+        let span = match error.location {
+            pest::error::InputLocation::Pos(p) => p..p,
+            pest::error::InputLocation::Span((s, e)) => s..e,
+        };
+        let (line, column) = match error.line_col {
+            pest::error::LineColLocation::Pos(p) => p,
+            pest::error::LineColLocation::Span(p, _) => p,
+        };
+        Diagnostic {
+            severity: Severity::Error,
+            code: "syntax",
+            message: error.variant.message().to_string(),
+            span,
+            line,
+            column,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "severity": self.severity.as_str(),
+            "code": self.code,
+            "message": self.message,
+            "line": self.line,
+            "column": self.column,
+            "start": self.span.start,
+            "end": self.span.end,
+        })
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}[{}]: {}",
+            self.line,
+            self.column,
+            self.severity.as_str(),
+            self.code,
+            self.message
+        )
+    }
+}
+
+/// Check `spec` -- a filter chain or the contents of a `workspace.josh` / stored `.josh` file --
+/// and return its diagnostics ordered by position:
+///
+/// * `syntax` / `invalid-filter`: the spec does not parse.
+/// * `experimental`: an experimental op is used while `JOSH_EXPERIMENTAL_FEATURES` is not set.
+/// * `shadowed-entry`: a compose entry selects nothing an earlier entry has not already taken.
+/// * `not-invertible`: an op that cannot be reversed, so pushing through the filter fails.
+/// * `unreachable-rev-arm`: a `:rev(...)` arm after the `_` default, which always matches first.
+///
+/// Each problem is reported once, at the innermost op it can be attributed to.
+pub fn lint(spec: &str) -> Vec<Diagnostic> {
+    let mut linter = Linter::default();
+    match Grammar::parse(Rule::filter_chain, spec) {
+        Ok(pairs) => {
+            for pair in pairs.filter(|p| p.as_rule() == Rule::filter_spec) {
+                linter.visit_spec(pair, Context::default());
+            }
+        }
+        Err(_) => match Grammar::parse(Rule::workspace_file, spec) {
+            Ok(pairs) => {
+                for pair in pairs.flat_map(|p| p.into_inner()) {
+                    if pair.as_rule() == Rule::compose {
+                        let ctx = Context {
+                            in_compose: true,
+                            ..Context::default()
+                        };
+                        linter.visit_compose(pair, ctx, true);
+                    }
+                }
+            }
+            Err(e) => linter.diagnostics.push(Diagnostic::from_pest(e)),
+        },
+    }
+    linter
+        .diagnostics
+        .sort_by_key(|d| (d.span.start, d.span.end));
+    linter.diagnostics
+}
+
+#[derive(Clone, Copy)]
+struct Context {
+    /// Whether changes are pushed back through this part of the filter, so it has to be
+    /// invertible. Off below generative ops like `:#path[...]`.
+    push_capable: bool,
+    /// Whether this part sits inside a compose (or another op that inverts its operand as a
+    /// whole). Per-commit filters like `:rev` or `:workspace` are only reversible outside one.
+    in_compose: bool,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context {
+            push_capable: true,
+            in_compose: false,
+        }
+    }
+}
+
+/// Which kinds of problems were reported somewhere below a node, so that enclosing ops do not
+/// report the same problem again.
+#[derive(Clone, Copy, Default)]
+struct Reported {
+    invalid: bool,
+    experimental: bool,
+    not_invertible: bool,
+}
+
+impl std::ops::BitOrAssign for Reported {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.invalid |= rhs.invalid;
+        self.experimental |= rhs.experimental;
+        self.not_invertible |= rhs.not_invertible;
+    }
+}
+
+#[derive(Default)]
+struct Linter {
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Parse a piece of the spec on its own, allowing experimental ops.
+fn parse_part(text: &str) -> (anyhow::Result<Filter>, Vec<String>) {
+    record_experimental_features(|| parse(text))
+}
+
+impl Linter {
+    /// A `filter_spec`: a chain of ops.
+    fn visit_spec(&mut self, spec: Pair<Rule>, ctx: Context) -> Reported {
+        let mut reported = Reported::default();
+        for item in spec.into_inner() {
+            reported |= self.visit_item(item, ctx);
+        }
+        reported
+    }
+
+    /// A single op of a chain.
+    fn visit_item(&mut self, item: Pair<Rule>, ctx: Context) -> Reported {
+        let span = item.as_span();
+        let text = item.as_str();
+        let (result, features) = parse_part(text);
+
+        let mut reported = Reported::default();
+        let mut inner_ctx = ctx;
+        match item.as_rule() {
+            Rule::filter_group
+            | Rule::filter_scope
+            | Rule::filter_meta
+            | Rule::filter_starlark
+            | Rule::filter_treeid => {
+                let plain_group = item.as_rule() == Rule::filter_group
+                    && !item.clone().into_inner().any(|p| p.as_rule() == Rule::cmd);
+                inner_ctx.in_compose = true;
+                if matches!(item.as_rule(), Rule::filter_starlark | Rule::filter_treeid) {
+                    inner_ctx.push_capable = false;
+                }
+                for child in item.into_inner() {
+                    if child.as_rule() == Rule::compose {
+                        reported |= self.visit_compose(child, inner_ctx, plain_group);
+                    } else if child.as_rule() == Rule::filter_spec {
+                        reported |= self.visit_spec(child, inner_ctx);
+                    }
+                }
+            }
+            Rule::filter_rev => {
+                // Each arm is resolved for the commit at hand and reversed on its own.
+                inner_ctx.in_compose = false;
+                reported |= self.visit_rev(item, inner_ctx);
+            }
+            Rule::filter_squash | Rule::filter_unapply => {
+                inner_ctx.push_capable = false;
+                for child in item.into_inner() {
+                    if child.as_rule() == Rule::filter_spec {
+                        reported |= self.visit_spec(child, inner_ctx);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if !features.is_empty() && !experimental_features_enabled() && !reported.experimental {
+            self.diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                "experimental",
+                span,
+                format!(
+                    "`{}` uses the {}, which requires JOSH_EXPERIMENTAL_FEATURES=1",
+                    text, features[0]
+                ),
+            ));
+            reported.experimental = true;
+        }
+
+        match result {
+            Err(e) if !reported.invalid => {
+                let message = e.to_string();
+                let message = message.trim().lines().next().unwrap_or_default();
+                self.diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    "invalid-filter",
+                    span,
+                    message.to_string(),
+                ));
+                reported.invalid = true;
+            }
+            Err(_) => {}
+            Ok(filter) => {
+                let per_commit = matches!(
+                    to_op(filter),
+                    Op::Rev(_) | Op::Hook(_) | Op::Workspace(_) | Op::Stored(_) | Op::Starlark(..)
+                );
+                if ctx.push_capable
+                    && !reported.not_invertible
+                    && (!per_commit || ctx.in_compose)
+                    && invert(filter).is_err()
+                {
+                    let reason = if per_commit {
+                        "it is resolved per commit, which only works outside of a compose"
+                    } else {
+                        "it has no inverse"
+                    };
+                    self.diagnostics.push(Diagnostic::new(
+                        Severity::Warning,
+                        "not-invertible",
+                        span,
+                        format!("changes cannot be pushed through `{}`: {}", text, reason),
+                    ));
+                    reported.not_invertible = true;
+                }
+            }
+        }
+        reported
+    }
+
+    /// The entries of a compose. With `check_shadowing`, entries that can not select anything an
+    /// earlier entry has not already taken are reported.
+    fn visit_compose(
+        &mut self,
+        compose: Pair<Rule>,
+        ctx: Context,
+        check_shadowing: bool,
+    ) -> Reported {
+        let mut reported = Reported::default();
+        let mut entries: Vec<(pest::Span, Filter)> = vec![];
+        for entry in compose.into_inner() {
+            let span = entry.as_span();
+            let filter = match entry.as_rule() {
+                Rule::filter_spec => {
+                    let text = entry.as_str();
+                    reported |= self.visit_spec(entry, ctx);
+                    parse_part(text).0.ok()
+                }
+                Rule::file_entry => {
+                    let mut inner = entry.into_inner();
+                    let path = inner.next().map(|p| p.as_str()).unwrap_or_default();
+                    match inner.next() {
+                        Some(spec) => {
+                            let text = spec.as_str();
+                            reported |= self.visit_spec(spec, ctx);
+                            parse_part(text)
+                                .0
+                                .ok()
+                                .map(|f| f.chain(to_filter(Op::Prefix(PathBuf::from(path)))))
+                        }
+                        None => None,
+                    }
+                }
+                _ => None,
+            };
+            let Some(filter) = filter else {
+                continue;
+            };
+            if check_shadowing
+                && let Some(scope) = input_scope(filter)
+                && let Some((earlier, _)) = entries.iter().find(|(_, e)| {
+                    *e == filter || consumed_subtree(*e).is_some_and(|c| scope.starts_with(c))
+                })
+            {
+                let (line, _) = earlier.start_pos().line_col();
+                self.diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    "shadowed-entry",
+                    span,
+                    format!(
+                        "`{}` never selects anything: every path it would take is already taken \
+                         by `{}` (line {})",
+                        span.as_str(),
+                        earlier.as_str(),
+                        line
+                    ),
+                ));
+            }
+            entries.push((span, filter));
+        }
+        reported
+    }
+
+    /// The arms of a `:rev(...)`: `_` matches every commit, so later arms are never consulted.
+    fn visit_rev(&mut self, rev: Pair<Rule>, ctx: Context) -> Reported {
+        let mut reported = Reported::default();
+        let mut default: Option<pest::Span> = None;
+        for entry in rev.into_inner() {
+            if entry.as_rule() != Rule::rev_entry {
+                continue;
+            }
+            let span = entry.as_span();
+            if let Some(default) = default {
+                let (line, _) = default.start_pos().line_col();
+                self.diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    "unreachable-rev-arm",
+                    span,
+                    format!(
+                        "`{}` is never used: the `_` arm (line {}) before it matches every commit",
+                        span.as_str(),
+                        line
+                    ),
+                ));
+            }
+            for part in entry.into_inner() {
+                match part.as_rule() {
+                    Rule::rev_default => {
+                        default.get_or_insert(span);
+                        for spec in part.into_inner() {
+                            reported |= self.visit_spec(spec, ctx);
+                        }
+                    }
+                    Rule::filter_spec => reported |= self.visit_spec(part, ctx),
+                    _ => {}
+                }
+            }
+        }
+        reported
+    }
+}
+
+/// The ops of `filter` as a flat list.
+fn chain_ops(filter: Filter) -> Vec<Op> {
+    match to_op(filter) {
+        Op::Chain(filters) => filters.into_iter().flat_map(chain_ops).collect(),
+        op => vec![op],
+    }
+}
+
+/// The input subtree `filter` takes whole: set when it only moves paths around (`:/a`,
+/// `:prefix=b`), so that nothing below that subtree is left for later compose entries.
+fn consumed_subtree(filter: Filter) -> Option<PathBuf> {
+    let mut subtree = PathBuf::new();
+    let mut prefixed = false;
+    for op in chain_ops(filter) {
+        match op {
+            Op::Nop => {}
+            Op::Subdir(path) if !prefixed => subtree.push(path),
+            Op::Prefix(_) => prefixed = true,
+            _ => return None,
+        }
+    }
+    Some(subtree)
+}
+
+/// The input subtree all paths `filter` selects lie in, as far as its leading ops tell; `None`
+/// when it selects nothing from the input (`:$path="..."`) or the ops are not understood.
+fn input_scope(filter: Filter) -> Option<PathBuf> {
+    let mut scope = PathBuf::new();
+    for op in chain_ops(filter) {
+        match op {
+            Op::Nop => {}
+            Op::Subdir(path) => scope.push(path),
+            Op::File(_, source) => {
+                scope.push(source);
+                return Some(scope);
+            }
+            Op::Insert(..) | Op::TreeId(..) | Op::Compose(_) => return None,
+            _ => return Some(scope),
+        }
+    }
+    Some(scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(spec: &str) -> Vec<(&'static str, String)> {
+        lint(spec)
+            .into_iter()
+            .map(|d| (d.code, spec[d.span].to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn lint_reports_shadowed_entries_and_rev_arms() {
+        assert_eq!(
+            codes("a = :/lib\nb = :/lib/sub\nc = :/app\n"),
+            [("shadowed-entry", "b = :/lib/sub".to_string())]
+        );
+        assert_eq!(
+            codes(":rev(_:/a,<=1234:/b)"),
+            [("unreachable-rev-arm", "<=1234:/b".to_string())]
+        );
+        assert!(codes(":[:/a,:/b]").is_empty());
+    }
+
+    #[test]
+    fn lint_reports_innermost_problem() {
+        assert_eq!(
+            codes(":[a=:/x:subtract[:/y,:/z]]"),
+            [("not-invertible", ":subtract[:/y,:/z]".to_string())]
+        );
+        assert_eq!(
+            codes(":/a:bogus"),
+            [("invalid-filter", ":bogus".to_string())]
+        );
+        let syntax = lint(":[a=:/x");
+        assert_eq!(syntax.len(), 1);
+        assert_eq!(syntax[0].code, "syntax");
+    }
+}
@@ -1,10 +1,11 @@
+pub mod lint;
 pub mod parse;
 
 use crate::filter::MESSAGE_MATCH_ALL_REGEX;
 use crate::filter::{reachable_roots, sequence_number};
 use crate::opt;
 use crate::persist::{to_filter, to_op, to_ops};
-use crate::{Filter, InsertContent, Op, RevMatch};
+use crate::{BlobSelect, Filter, InsertContent, Normalize, Op, RevMatch};
 
 /// Pretty print the filter on multiple lines with initial indentation level.
 /// Nested filters will be indented with additional 4 spaces per nesting level.
@@ -156,6 +157,14 @@ pub fn spec(filter: Filter) -> String {
     spec2(&to_op(filter))
 }
 
+fn normalize_spec(n: &Normalize) -> &'static str {
+    match n {
+        Normalize::Lf => ":eol=lf",
+        Normalize::Crlf => ":eol=crlf",
+        Normalize::Utf8 => ":utf8",
+    }
+}
+
 pub(crate) fn spec2(op: &Op) -> String {
     match op {
         Op::Compose(filters) => {
@@ -252,6 +261,7 @@ pub(crate) fn spec2(op: &Op) -> String {
         Op::Paths => ":PATHS".to_string(),
         Op::Invert => ":INVERT".to_string(),
         Op::Index => ":INDEX".to_string(),
+        Op::Symbols => ":SYMBOLS".to_string(),
         Op::Fold => ":FOLD".to_string(),
         Op::Squash(None) => ":SQUASH".to_string(),
         Op::Squash(Some(ids)) => {
@@ -289,6 +299,11 @@ pub(crate) fn spec2(op: &Op) -> String {
         Op::Prune => ":prune=trivial-merge".to_string(),
         Op::Prefix(path) => format!(":prefix={}", parse::quote_if(&path.to_string_lossy())),
         Op::Pattern(glob) => format!("::{}", parse::quote_if(glob.as_str())),
+        Op::Blobs(BlobSelect::Larger(size)) => format!(":larger={}", size),
+        Op::Blobs(BlobSelect::Binary) => ":binary".to_string(),
+        Op::Blobs(BlobSelect::ExportIgnore) => ":export-ignore".to_string(),
+        Op::Normalize(n) => normalize_spec(n).to_string(),
+        Op::Restore(n) => format!(":restore[{}]", normalize_spec(n)),
         Op::Embed(path) => {
             format!(":embed={}", parse::quote_if(&path.to_string_lossy()),)
         }
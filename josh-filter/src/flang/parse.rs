@@ -3,8 +3,8 @@ use crate::check_experimental_features_enabled;
 use crate::filter::Filter;
 use crate::opt;
 use crate::opt::invert;
-use crate::persist::to_filter;
-use crate::{InsertContent, LazyRef, Op, Regex, RevMatch};
+use crate::persist::{to_filter, to_op};
+use crate::{BlobSelect, InsertContent, LazyRef, Normalize, Op, Regex, RevMatch};
 
 use anyhow::{Context, anyhow};
 use indoc::{formatdoc, indoc};
@@ -64,6 +64,39 @@ fn make_filter(args: &[&str]) -> anyhow::Result<Filter> {
             "#
         ))),
         ["unsign"] => Ok(f.unsign()),
+        ["binary"] => Ok(to_filter(Op::Blobs(BlobSelect::Binary))),
+        ["export-ignore"] => Ok(to_filter(Op::Blobs(BlobSelect::ExportIgnore))),
+        ["larger", size] => Ok(to_filter(Op::Blobs(BlobSelect::Larger(
+            BlobSelect::parse_size(size)?,
+        )))),
+        ["utf8"] => Ok(to_filter(Op::Normalize(Normalize::Utf8))),
+        ["eol", "lf"] => Ok(to_filter(Op::Normalize(Normalize::Lf))),
+        ["eol", "crlf"] => Ok(to_filter(Op::Normalize(Normalize::Crlf))),
+        ["eol", other] => Err(anyhow!(
+            "invalid line ending: {:?} (expected \"lf\" or \"crlf\")",
+            other
+        )),
+        ["eol"] => Err(anyhow!(indoc!(
+            r#"
+            Filter ":eol" requires an argument.
+
+            Note: use "=" to provide the argument value:
+
+              :eol=lf
+              :eol=crlf
+            "#
+        ))),
+        ["larger"] => Err(anyhow!(indoc!(
+            r#"
+            Filter ":larger" requires an argument.
+
+            Note: use "=" to provide the argument value:
+
+              :larger=size
+
+            Where `size` is a number of bytes, optionally suffixed with k, M or G
+            "#
+        ))),
 
         ["unlink"] => {
             check_experimental_features_enabled("unlink filter")?;
@@ -95,6 +128,10 @@ fn make_filter(args: &[&str]) -> anyhow::Result<Filter> {
             check_experimental_features_enabled(":INDEX filter")?;
             Ok(to_filter(Op::Index))
         }
+        ["SYMBOLS"] => {
+            check_experimental_features_enabled(":SYMBOLS filter")?;
+            Ok(to_filter(Op::Symbols))
+        }
         ["INVERT"] => Ok(to_filter(Op::Invert)),
         ["FOLD"] => Ok(to_filter(Op::Fold)),
         ["hook", arg] => Ok(f.hook(arg)),
@@ -183,7 +220,9 @@ fn parse_item(pair: pest::iterators::Pair<Rule>) -> anyhow::Result<Filter> {
             let content_pair = inner.next().unwrap();
             let content = match content_pair.as_rule() {
                 Rule::string => InsertContent::Inline(unquote(content_pair.as_str())),
-                Rule::object_oid => InsertContent::Oid(git2::Oid::from_str(content_pair.as_str())?),
+                Rule::object_oid => {
+                    InsertContent::Oid(josh_gix_ext::parse_oid(content_pair.as_str())?)
+                }
                 _ => unreachable!(),
             };
             Ok(to_filter(Op::Insert(path, content)))
@@ -253,6 +292,13 @@ fn parse_item(pair: pest::iterators::Pair<Rule>) -> anyhow::Result<Filter> {
                             invert(filter)
                         }
                         "subtract" if g.len() == 2 => Ok(to_filter(Op::Subtract(g[0], g[1]))),
+                        "restore" => match g.as_slice() {
+                            [f] => match to_op(*f) {
+                                Op::Normalize(n) => Ok(to_filter(Op::Restore(n))),
+                                _ => Err(anyhow!(":restore[...] expects :eol=... or :utf8")),
+                            },
+                            _ => Err(anyhow!(":restore[...] expects :eol=... or :utf8")),
+                        },
                         _ => Err(anyhow!("parse_item: no match {:?}", cmd)),
                     }
                 }
@@ -555,6 +601,24 @@ pub fn get_comments(filter_spec: &str) -> Result<String, String> {
     Err(format!("Invalid workspace:\n----\n{}\n----", filter_spec))
 }
 
+/// The byte ranges of all ops in `filter_spec` -- a filter chain or a workspace file -- including
+/// those nested in groups, outer ops before the ops inside them. Empty when it does not parse.
+pub fn item_spans(filter_spec: &str) -> Vec<std::ops::Range<usize>> {
+    let pairs = match Grammar::parse(Rule::filter_chain, filter_spec) {
+        Ok(pairs) => pairs,
+        Err(_) => match Grammar::parse(Rule::workspace_file, filter_spec) {
+            Ok(pairs) => pairs,
+            Err(_) => return vec![],
+        },
+    };
+    pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::filter_spec)
+        .flat_map(|pair| pair.into_inner())
+        .map(|item| item.as_span().start()..item.as_span().end())
+        .collect()
+}
+
 #[derive(pest_derive::Parser)]
 #[grammar = "flang/grammar.pest"]
-struct Grammar;
+pub(crate) struct Grammar;
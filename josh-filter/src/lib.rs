@@ -9,7 +9,7 @@ pub use filter::{Filter, compose};
 pub use flang::parse;
 pub use flang::{as_file, pretty, spec};
 pub use op::LinkMode;
-pub use op::{InsertContent, LazyRef, Op, Regex, RevMatch};
+pub use op::{BlobSelect, InsertContent, LazyRef, Normalize, Op, Regex, RevMatch};
 
 static EXPERIMENTAL_FEATURES: std::sync::LazyLock<bool> =
     std::sync::LazyLock::new(|| std::env::var("JOSH_EXPERIMENTAL_FEATURES").as_deref() == Ok("1"));
@@ -18,8 +18,39 @@ pub fn experimental_features_enabled() -> bool {
     *EXPERIMENTAL_FEATURES
 }
 
+thread_local! {
+    /// Set while [`record_experimental_features`] runs: experimental features are allowed and
+    /// collected here instead of failing the check.
+    static RECORDED_FEATURES: std::cell::RefCell<Option<Vec<String>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Run `f` as if experimental features were enabled and return, besides its result, the
+/// experimental features it used. Lets the linter tell "experimental" apart from "invalid".
+pub fn record_experimental_features<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let outer = RECORDED_FEATURES.with(|r| r.replace(Some(vec![])));
+    let result = f();
+    let used = RECORDED_FEATURES
+        .with(|r| r.replace(outer))
+        .unwrap_or_default();
+    if !used.is_empty() {
+        RECORDED_FEATURES.with(|r| {
+            if let Some(outer) = r.borrow_mut().as_mut() {
+                outer.extend(used.iter().cloned());
+            }
+        });
+    }
+    (result, used)
+}
+
 pub fn check_experimental_features_enabled(feature: &str) -> anyhow::Result<()> {
-    if experimental_features_enabled() {
+    let recorded = RECORDED_FEATURES.with(|r| {
+        r.borrow_mut()
+            .as_mut()
+            .map(|used| used.push(feature.to_string()))
+            .is_some()
+    });
+    if recorded || experimental_features_enabled() {
         Ok(())
     } else {
         Err(anyhow::anyhow!(
@@ -70,6 +70,70 @@ pub enum InsertContent {
     Oid(git2::Oid),
 }
 
+/// What an `Op::Blobs` selection matches on. Unlike `Op::Pattern` the decision is made from the
+/// blob itself (or the attributes that apply to it) rather than from its path alone.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BlobSelect {
+    /// `:larger=<size>` - blobs strictly larger than the given number of bytes
+    Larger(u64),
+    /// `:binary` - blobs git would diff as binary (a NUL byte in the first 8000 bytes)
+    Binary,
+    /// `:export-ignore` - paths carrying the `export-ignore` attribute in `.gitattributes`
+    ExportIgnore,
+}
+
+impl BlobSelect {
+    /// Parse the argument of `:larger=`: a byte count with an optional `k`, `M` or `G`
+    /// (binary) suffix.
+    pub fn parse_size(s: &str) -> anyhow::Result<u64> {
+        let (digits, factor) = match s.char_indices().last() {
+            Some((i, 'k' | 'K')) => (&s[..i], 1 << 10),
+            Some((i, 'm' | 'M')) => (&s[..i], 1 << 20),
+            Some((i, 'g' | 'G')) => (&s[..i], 1 << 30),
+            _ => (s, 1),
+        };
+        digits
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(factor))
+            .ok_or_else(|| anyhow!("invalid size: {:?}", s))
+    }
+}
+
+/// The content normalization of an `Op::Normalize` (and of its inverse, `Op::Restore`). Only
+/// text files are rewritten: paths marked `-text` or `binary` in `.gitattributes` are never
+/// touched, paths marked `text` always are, and everything else is checked for binary content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Normalize {
+    /// `:eol=lf` - CRLF line endings become LF
+    Lf,
+    /// `:eol=crlf` - LF line endings become CRLF
+    Crlf,
+    /// `:utf8` - UTF-16 files (recognized by their byte order mark) are re-encoded as UTF-8
+    Utf8,
+}
+
+impl Normalize {
+    /// The name used in persisted filters: `lf`, `crlf` or `utf8`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Normalize::Lf => "lf",
+            Normalize::Crlf => "crlf",
+            Normalize::Utf8 => "utf8",
+        }
+    }
+
+    /// Inverse of [`Normalize::as_str`].
+    pub fn from_name(s: &str) -> anyhow::Result<Normalize> {
+        match s {
+            "lf" => Ok(Normalize::Lf),
+            "crlf" => Ok(Normalize::Crlf),
+            "utf8" => Ok(Normalize::Utf8),
+            _ => Err(anyhow!("unknown normalization: {:?}", s)),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum RevMatch {
     /// `<` - matches if is_ancestor_of(commit, tip) && commit != tip (strict)
@@ -98,7 +162,7 @@ impl LazyRef {
             return Ok(LazyRef::Lazy(s));
         }
 
-        if let Ok(oid) = git2::Oid::from_str(&s) {
+        if let Ok(oid) = josh_gix_ext::parse_oid(&s) {
             Ok(LazyRef::Resolved(oid))
         } else {
             Err(anyhow!("invalid ref: {:?}", s))
@@ -134,6 +198,7 @@ pub enum Op {
     Hook(String),
 
     Index,
+    Symbols,
     Invert,
 
     Insert(std::path::PathBuf, InsertContent), // Insert(dest_path, content)
@@ -148,6 +213,9 @@ pub enum Op {
     ObjectRef(std::path::PathBuf),
 
     Pattern(crate::pattern::CompiledPattern),
+    Blobs(BlobSelect),
+    Normalize(Normalize),
+    Restore(Normalize),
     Message(String, Regex),
 
     Unapply(LazyRef, Filter),
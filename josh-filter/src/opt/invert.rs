@@ -26,6 +26,11 @@ pub fn invert(filter: Filter) -> anyhow::Result<Filter> {
             }
             Op::Prefix(path) => Some(Op::Subdir(path.clone())),
             Op::Pattern(glob) => Some(Op::Pattern(glob.clone())),
+            // Like patterns, blob selections keep paths in place, so they are their own inverse.
+            Op::Blobs(select) => Some(Op::Blobs(select.clone())),
+            // Restoring maps unchanged files back to the exact bytes they were normalized from.
+            Op::Normalize(n) => Some(Op::Restore(*n)),
+            Op::Restore(n) => Some(Op::Normalize(*n)),
             Op::RegexReplace(_) => Some(Op::Nop),
             Op::Pin(_) => Some(Op::Nop),
             // Insert and TreeId are generative: they fabricate tree entries and consume no
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::sync::{LazyLock, OnceLock};
 
 use crate::filter::Filter;
-use crate::op::{InsertContent, LazyRef, Op, Regex, RevMatch};
+use crate::op::{BlobSelect, InsertContent, LazyRef, Normalize, Op, Regex, RevMatch};
 
 /// An interned, immutable `Op` together with its lazily-computed content OID.
 /// Nodes are leaked (`&'static`) and live for the process lifetime. A `Filter` is just a
@@ -453,6 +453,24 @@ impl<'a> InMemoryBuilder<'a> {
                 let params_tree = self.build_str_params(&[glob.as_str()]);
                 push_tree_entries(&mut entries, [("pattern", params_tree)]);
             }
+            Op::Blobs(select) => {
+                let params_tree = match select {
+                    BlobSelect::Larger(size) => {
+                        self.build_str_params(&["larger", &size.to_string()])
+                    }
+                    BlobSelect::Binary => self.build_str_params(&["binary"]),
+                    BlobSelect::ExportIgnore => self.build_str_params(&["export-ignore"]),
+                };
+                push_tree_entries(&mut entries, [("blobs", params_tree)]);
+            }
+            Op::Normalize(n) => {
+                let params_tree = self.build_str_params(&[n.as_str()]);
+                push_tree_entries(&mut entries, [("normalize", params_tree)]);
+            }
+            Op::Restore(n) => {
+                let params_tree = self.build_str_params(&[n.as_str()]);
+                push_tree_entries(&mut entries, [("restore", params_tree)]);
+            }
             Op::Workspace(path) => {
                 let params_tree = self.build_str_params(&[path.to_string_lossy().as_ref()]);
                 push_tree_entries(&mut entries, [("workspace", params_tree)]);
@@ -514,6 +532,10 @@ impl<'a> InMemoryBuilder<'a> {
                 let blob = self.write_blob(b"");
                 push_blob_entries(&mut entries, [("index", blob)]);
             }
+            Op::Symbols => {
+                let blob = self.write_blob(b"");
+                push_blob_entries(&mut entries, [("symbols", blob)]);
+            }
             Op::Fold => {
                 let blob = self.write_blob(b"");
                 push_blob_entries(&mut entries, [("fold", blob)]);
@@ -817,6 +839,10 @@ fn from_tree2(src: &impl gix_object::Find, tree_oid: gix_hash::ObjectId) -> anyh
             let _ = Blob::read(src, entry.id())?;
             Ok(Op::Index)
         }
+        "symbols" => {
+            let _ = Blob::read(src, entry.id())?;
+            Ok(Op::Symbols)
+        }
         "fold" => {
             let _ = Blob::read(src, entry.id())?;
             Ok(Op::Fold)
@@ -980,6 +1006,42 @@ fn from_tree2(src: &impl gix_object::Find, tree_oid: gix_hash::ObjectId) -> anyh
             )?;
             Op::pattern(std::str::from_utf8(pattern_blob.content())?)
         }
+        "blobs" => {
+            let inner = PersistedTree::read(src, entry.id())?;
+            let kind_blob = Blob::read(
+                src,
+                inner.get_name("0").context("blobs: missing kind")?.id(),
+            )?;
+            match std::str::from_utf8(kind_blob.content())? {
+                "larger" => {
+                    let size_blob = Blob::read(
+                        src,
+                        inner.get_name("1").context("blobs: missing size")?.id(),
+                    )?;
+                    let size = std::str::from_utf8(size_blob.content())?.parse()?;
+                    Ok(Op::Blobs(BlobSelect::Larger(size)))
+                }
+                "binary" => Ok(Op::Blobs(BlobSelect::Binary)),
+                "export-ignore" => Ok(Op::Blobs(BlobSelect::ExportIgnore)),
+                other => Err(anyhow!("blobs: unknown selection {:?}", other)),
+            }
+        }
+        "normalize" | "restore" => {
+            let inner = PersistedTree::read(src, entry.id())?;
+            let name_blob = Blob::read(
+                src,
+                inner
+                    .get_name("0")
+                    .context("normalize: missing conversion")?
+                    .id(),
+            )?;
+            let n = Normalize::from_name(std::str::from_utf8(name_blob.content())?)?;
+            if name == "normalize" {
+                Ok(Op::Normalize(n))
+            } else {
+                Ok(Op::Restore(n))
+            }
+        }
         "workspace" => {
             let inner = PersistedTree::read(src, entry.id())?;
             let path_blob = Blob::read(
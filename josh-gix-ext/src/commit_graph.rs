@@ -0,0 +1,446 @@
+//! Reading the commit-graph that housekeeping writes into josh-managed repositories
+//! (`git commit-graph write --reachable --changed-paths --split`).
+//!
+//! A graph answers three questions without touching the object database: a commit's parents,
+//! its generation number (topological level: 1 for roots, one more than the highest parent
+//! otherwise), and -- through the changed-path Bloom filters -- whether a path *may* differ
+//! from its first parent. Commits are added by housekeeping only, so a graph covers some prefix
+//! of history at best: every answer is an `Option`, and `None` sends the caller back to the
+//! object database. A graph is closed under ancestry (a listed commit's ancestors are listed
+//! too), which is what makes mixing the two sources sound.
+//!
+//! Object sources advertise a graph through [`FindCommitGraph`]; the walkers in
+//! [`revwalk`](crate::revwalk) and the queries in [`graph`](crate::graph) pick it up from there.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::{git2_oid, gix_oid};
+
+/// An object source that may come with a [`CommitGraph`] for the commits it holds.
+pub trait FindCommitGraph {
+    fn commit_graph(&self) -> Option<&CommitGraph>;
+}
+
+impl<T: FindCommitGraph> FindCommitGraph for &T {
+    fn commit_graph(&self) -> Option<&CommitGraph> {
+        (*self).commit_graph()
+    }
+}
+
+/// A loaded commit-graph: a single `info/commit-graph` file, or a split chain under
+/// `info/commit-graphs/` (the same lookup order git uses). Files are memory mapped by
+/// gix-commitgraph; Bloom filter chunks are read on first use.
+pub struct CommitGraph {
+    graph: gix_commitgraph::Graph,
+    /// The graph's files, base first, as gix numbers their commits.
+    files: Vec<GraphFile>,
+}
+
+struct GraphFile {
+    path: PathBuf,
+    /// Graph position of the file's first commit.
+    first: u32,
+    /// `None` once read if the file was written without `--changed-paths`.
+    bloom: OnceLock<Option<BloomFilters>>,
+}
+
+/// The `BIDX`/`BDAT` chunks of one graph file.
+struct BloomFilters {
+    hash_version: u32,
+    num_hashes: u32,
+    /// End offset in `data` of each commit's filter, in graph file order.
+    index: Vec<u32>,
+    data: Vec<u8>,
+}
+
+impl CommitGraph {
+    /// The graph of the object directory `objects_dir`, or `None` if it has none.
+    pub fn at(objects_dir: &Path) -> anyhow::Result<Option<CommitGraph>> {
+        let info = objects_dir.join("info");
+        let single = info.join("commit-graph");
+        let chain = info.join("commit-graphs");
+        let paths = if single.is_file() {
+            vec![single]
+        } else if chain.join("commit-graph-chain").is_file() {
+            std::fs::read_to_string(chain.join("commit-graph-chain"))?
+                .lines()
+                .map(|hash| chain.join(format!("graph-{hash}.graph")))
+                .collect()
+        } else {
+            return Ok(None);
+        };
+
+        let mut files = Vec::with_capacity(paths.len());
+        let mut graph_files = Vec::with_capacity(paths.len());
+        let mut first = 0;
+        for path in paths {
+            let file = gix_commitgraph::File::at(&path)
+                .map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+            graph_files.push(GraphFile {
+                path,
+                first,
+                bloom: OnceLock::new(),
+            });
+            first += file.num_commits();
+            files.push(file);
+        }
+        let graph = gix_commitgraph::Graph::new(files)
+            .map_err(|e| anyhow::anyhow!("{}: {e}", objects_dir.display()))?;
+        Ok(Some(CommitGraph {
+            graph,
+            files: graph_files,
+        }))
+    }
+
+    /// The underlying graph, for handing to gix traversals.
+    pub fn graph(&self) -> &gix_commitgraph::Graph {
+        &self.graph
+    }
+
+    pub fn contains(&self, commit: git2::Oid) -> bool {
+        self.graph.lookup(gix_oid(commit)).is_some()
+    }
+
+    /// The parents of `commit` in parent order, or `None` if the graph does not list it.
+    pub fn parents(&self, commit: git2::Oid) -> Option<Vec<git2::Oid>> {
+        self.graph
+            .commit_by_id(gix_oid(commit))?
+            .iter_parents()
+            .map(|pos| pos.ok().map(|pos| git2_oid(self.graph.id_at(pos))))
+            .collect()
+    }
+
+    /// The generation number of `commit`, or `None` if the graph does not list it. Strictly
+    /// greater than the generation of each of its parents.
+    pub fn generation(&self, commit: git2::Oid) -> Option<u32> {
+        Some(self.graph.commit_by_id(gix_oid(commit))?.generation())
+    }
+
+    /// Whether `path` (slash-separated, relative to the root tree) may differ between `commit`
+    /// and its first parent. `Some(false)` is definite; `Some(true)` may be a false positive.
+    /// `None` if the graph has no filter for the commit -- it is not listed, the graph was
+    /// written without `--changed-paths`, or the commit changed too many paths to record -- or
+    /// `path` is the root, which filters do not record.
+    pub fn maybe_changed(&self, commit: git2::Oid, path: &str) -> Option<bool> {
+        let mut key = path.trim_matches('/');
+        if key.is_empty() {
+            return None;
+        }
+        let pos = self.graph.lookup(gix_oid(commit))?.0;
+        let file = &self.files[self.files.partition_point(|f| f.first <= pos) - 1];
+        let bloom = file
+            .bloom
+            .get_or_init(|| read_bloom_filters(&file.path).ok().flatten())
+            .as_ref()?;
+        let local = (pos - file.first) as usize;
+        let start = match local {
+            0 => 0,
+            n => *bloom.index.get(n - 1)? as usize,
+        };
+        let filter = bloom.data.get(start..*bloom.index.get(local)? as usize)?;
+        if filter.is_empty() {
+            return None;
+        }
+        // git records a path together with each of its leading directories, and so checks
+        // them all: any one missing rules the path out.
+        loop {
+            if !bloom.contains(filter, key.as_bytes())? {
+                return Some(false);
+            }
+            match key.rsplit_once('/') {
+                Some((parent, _)) => key = parent,
+                None => return Some(true),
+            }
+        }
+    }
+}
+
+impl BloomFilters {
+    /// Whether `filter` may contain `key`; `None` if this filter's hash cannot be reproduced
+    /// for `key`.
+    fn contains(&self, filter: &[u8], key: &[u8]) -> Option<bool> {
+        // Version 1 filters were hashed with sign-extended bytes (git's original bug), which
+        // only agrees with the correct hash on ASCII; version 2 fixed it.
+        match self.hash_version {
+            1 if key.is_ascii() => {}
+            2 => {}
+            _ => return None,
+        }
+        let h0 = murmur3(0x293ae76f, key);
+        let h1 = murmur3(0x7e646e2c, key);
+        let bits = filter.len() as u64 * 8;
+        Some((0..self.num_hashes).all(|i| {
+            let bit = u64::from(h0.wrapping_add(i.wrapping_mul(h1))) % bits;
+            filter[(bit / 8) as usize] & (1 << (bit % 8)) != 0
+        }))
+    }
+}
+
+/// Read the Bloom filter chunks of the graph file at `path`; `None` if it has none.
+fn read_bloom_filters(path: &Path) -> anyhow::Result<Option<BloomFilters>> {
+    let mut file = std::fs::File::open(path)?;
+    let mut header = [0; 8];
+    file.read_exact(&mut header)?;
+    if &header[..4] != b"CGPH" {
+        anyhow::bail!("{}: not a commit-graph file", path.display());
+    }
+    // The table of contents: `(id, offset)` per chunk, then a terminating entry whose offset
+    // ends the last chunk.
+    let mut toc = vec![0; (usize::from(header[6]) + 1) * 12];
+    file.read_exact(&mut toc)?;
+    let entries: Vec<([u8; 4], u64)> = toc
+        .chunks(12)
+        .map(|e| {
+            let id = e[..4].try_into().unwrap();
+            (id, u64::from_be_bytes(e[4..].try_into().unwrap()))
+        })
+        .collect();
+    let mut chunk = |id: &[u8; 4]| -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(n) = entries.iter().position(|(chunk, _)| chunk == id) else {
+            return Ok(None);
+        };
+        let (start, end) = (entries[n].1, entries[n + 1].1);
+        let mut data = vec![0; usize::try_from(end.saturating_sub(start))?];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut data)?;
+        Ok(Some(data))
+    };
+    let (Some(index), Some(data)) = (chunk(b"BIDX")?, chunk(b"BDAT")?) else {
+        return Ok(None);
+    };
+    if data.len() < 12 {
+        anyhow::bail!("{}: truncated BDAT chunk", path.display());
+    }
+    let be32 = |bytes: &[u8]| u32::from_be_bytes(bytes[..4].try_into().unwrap());
+    Ok(Some(BloomFilters {
+        hash_version: be32(&data[0..]),
+        num_hashes: be32(&data[4..]),
+        index: index.chunks_exact(4).map(be32).collect(),
+        data: data[12..].to_vec(),
+    }))
+}
+
+/// 32-bit MurmurHash3, as git's changed-path filters use it (`murmur3_seeded_v2`).
+fn murmur3(seed: u32, data: &[u8]) -> u32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+    let mix = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    let mut h = seed;
+    let mut blocks = data.chunks_exact(4);
+    for block in &mut blocks {
+        h ^= mix(u32::from_le_bytes(block.try_into().unwrap()));
+        h = h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe6546b64);
+    }
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .rev()
+            .fold(0u32, |k, &byte| (k << 8) | u32::from(byte));
+        h ^= mix(k);
+    }
+    h ^= data.len() as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85ebca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2ae35);
+    h ^ (h >> 16)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A git2 odb with the repository's commit-graph, for running the walkers in graph mode.
+    pub(crate) struct GraphOdb<'a> {
+        odb: crate::Git2Odb<'a>,
+        graph: CommitGraph,
+    }
+
+    impl<'a> GraphOdb<'a> {
+        /// Write a commit-graph of everything reachable from `tips` (replacing any earlier
+        /// one) and load it over `odb`.
+        pub(crate) fn write(
+            repo: &git2::Repository,
+            odb: &'a git2::Odb<'a>,
+            tips: &[git2::Oid],
+        ) -> Self {
+            use std::io::Write;
+            let mut git = std::process::Command::new("git")
+                .arg("-C")
+                .arg(repo.path())
+                .args(["commit-graph", "write", "--stdin-commits", "--no-progress"])
+                .stdin(std::process::Stdio::piped())
+                .spawn()
+                .unwrap();
+            for tip in tips {
+                writeln!(git.stdin.as_mut().unwrap(), "{tip}").unwrap();
+            }
+            assert!(git.wait().unwrap().success());
+            let graph = CommitGraph::at(&repo.path().join("objects"))
+                .unwrap()
+                .unwrap();
+            GraphOdb {
+                odb: crate::Git2Odb(odb),
+                graph,
+            }
+        }
+    }
+
+    impl gix_object::Find for GraphOdb<'_> {
+        fn try_find<'b>(
+            &self,
+            id: &gix_hash::oid,
+            buffer: &'b mut Vec<u8>,
+        ) -> Result<Option<gix_object::Data<'b>>, gix_object::find::Error> {
+            self.odb.try_find(id, buffer)
+        }
+    }
+
+    impl gix_object::FindHeader for GraphOdb<'_> {
+        fn try_header(
+            &self,
+            id: &gix_hash::oid,
+        ) -> Result<Option<gix_object::Header>, gix_object::find::Error> {
+            self.odb.try_header(id)
+        }
+    }
+
+    impl FindCommitGraph for GraphOdb<'_> {
+        fn commit_graph(&self) -> Option<&CommitGraph> {
+            Some(&self.graph)
+        }
+    }
+
+    fn git(repo: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    fn commit(repo: &git2::Repository, files: &[(&str, &str)], parents: &[git2::Oid]) -> git2::Oid {
+        let mut index = git2::Index::new().unwrap();
+        for (path, content) in files {
+            let blob = repo.blob(content.as_bytes()).unwrap();
+            let mut entry = git2::IndexEntry {
+                ctime: git2::IndexTime::new(0, 0),
+                mtime: git2::IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: 0o100644,
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id: blob,
+                flags: 0,
+                flags_extended: 0,
+                path: Vec::new(),
+            };
+            entry.path = path.as_bytes().to_vec();
+            index.add(&entry).unwrap();
+        }
+        let tree = repo.find_tree(index.write_tree_to(repo).unwrap()).unwrap();
+        let sig =
+            git2::Signature::new("Test", "test@example.com", &git2::Time::new(1000, 0)).unwrap();
+        let parents: Vec<_> = parents
+            .iter()
+            .map(|&p| repo.find_commit(p).unwrap())
+            .collect();
+        let parents: Vec<_> = parents.iter().collect();
+        repo.commit(None, &sig, &sig, "c", &tree, &parents).unwrap()
+    }
+
+    /// Reference values from git's own test suite (`t0095-bloom.sh`).
+    #[test]
+    fn murmur3_matches_git() {
+        assert_eq!(murmur3(0, b""), 0x00000000);
+        assert_eq!(murmur3(0, b"Hello world!"), 0x627b0c2c);
+        assert_eq!(
+            murmur3(0, b"The quick brown fox jumps over the lazy dog"),
+            0x2e4ff723
+        );
+    }
+
+    #[test]
+    fn reads_parents_generations_and_changed_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(dir.path()).unwrap();
+        let root = commit(&repo, &[("a/x", "1"), ("b/y", "1")], &[]);
+        let left = commit(&repo, &[("a/x", "2"), ("b/y", "1")], &[root]);
+        let right = commit(&repo, &[("a/x", "1"), ("b/y", "2")], &[root]);
+        let merge = commit(&repo, &[("a/x", "2"), ("b/y", "2")], &[left, right]);
+        let objects = dir.path().join("objects");
+        assert!(CommitGraph::at(&objects).unwrap().is_none());
+
+        repo.reference("refs/heads/main", merge, true, "").unwrap();
+        git(
+            dir.path(),
+            &[
+                "commit-graph",
+                "write",
+                "--reachable",
+                "--changed-paths",
+                "--no-progress",
+            ],
+        );
+        let graph = CommitGraph::at(&objects).unwrap().unwrap();
+
+        assert_eq!(graph.parents(merge), Some(vec![left, right]));
+        assert_eq!(graph.parents(root), Some(vec![]));
+        assert_eq!(graph.generation(root), Some(1));
+        assert_eq!(graph.generation(left), Some(2));
+        assert_eq!(graph.generation(merge), Some(3));
+        let unlisted = commit(&repo, &[], &[merge]);
+        assert!(!graph.contains(unlisted));
+        assert_eq!(graph.parents(unlisted), None);
+
+        assert_eq!(graph.maybe_changed(left, "a/x"), Some(true));
+        assert_eq!(graph.maybe_changed(left, "a"), Some(true));
+        assert_eq!(graph.maybe_changed(left, "b/y"), Some(false));
+        assert_eq!(graph.maybe_changed(left, "b"), Some(false));
+        // Against the first parent only: the merge brings in `b/y` from its second parent.
+        assert_eq!(graph.maybe_changed(merge, "b/y"), Some(true));
+        assert_eq!(graph.maybe_changed(merge, "a/x"), Some(false));
+        assert_eq!(graph.maybe_changed(unlisted, "a/x"), None);
+        assert_eq!(graph.maybe_changed(left, ""), None);
+    }
+
+    /// Incremental writes append files to a split chain; positions keep resolving across it.
+    #[test]
+    fn reads_split_chains() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(dir.path()).unwrap();
+        let write = [
+            "commit-graph",
+            "write",
+            "--reachable",
+            "--changed-paths",
+            "--split=no-merge",
+            "--no-progress",
+        ];
+
+        let root = commit(&repo, &[("a", "1")], &[]);
+        repo.reference("refs/heads/main", root, true, "").unwrap();
+        git(dir.path(), &write);
+        let tip = commit(&repo, &[("a", "1"), ("b", "1")], &[root]);
+        repo.reference("refs/heads/main", tip, true, "").unwrap();
+        git(dir.path(), &write);
+
+        let objects = dir.path().join("objects");
+        assert!(!objects.join("info/commit-graph").exists());
+        let graph = CommitGraph::at(&objects).unwrap().unwrap();
+        assert_eq!(graph.files.len(), 2);
+        assert_eq!(graph.parents(tip), Some(vec![root]));
+        assert_eq!(graph.generation(tip), Some(2));
+        assert_eq!(graph.maybe_changed(tip, "b"), Some(true));
+        assert_eq!(graph.maybe_changed(tip, "a"), Some(false));
+        assert_eq!(graph.maybe_changed(root, "a"), Some(true));
+    }
+}
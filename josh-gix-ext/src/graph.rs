@@ -2,8 +2,11 @@
 //! a transaction -- including the filtered commits it has not written out yet.
 //!
 //! Both queries are merge-base computations: `gix_revision` walks the commits it reads from
-//! `objects`, keeping the answers consistent with what the caller can see.
+//! `objects`, keeping the answers consistent with what the caller can see. Where `objects` has a
+//! commit-graph, the walk takes parents, dates and generation numbers from it for the commits
+//! it lists, and stops early once generations rule out further candidates.
 
+use crate::commit_graph::{CommitGraph, FindCommitGraph};
 use crate::{git2_oid, gix_oid};
 
 /// A commit the walk cannot read is indistinguishable from unrelated history, so the inputs
@@ -28,7 +31,7 @@ fn ensure_commit(objects: &impl gix_object::Find, oid: git2::Oid) -> anyhow::Res
 /// Whether `ancestor` is reachable from `commit` by following parents. A commit does not
 /// descend from itself.
 pub fn is_descendant_of(
-    objects: &impl gix_object::Find,
+    objects: &(impl gix_object::Find + FindCommitGraph),
     commit: git2::Oid,
     ancestor: git2::Oid,
 ) -> anyhow::Result<bool> {
@@ -38,7 +41,8 @@ pub fn is_descendant_of(
     ensure_commit(objects, commit)?;
     ensure_commit(objects, ancestor)?;
     let ancestor = gix_oid(ancestor);
-    let mut graph = gix_revision::Graph::new(objects, None);
+    let mut graph =
+        gix_revision::Graph::new(objects, objects.commit_graph().map(CommitGraph::graph));
     // Reachable is exactly "is one of the best common ancestors of the two".
     let bases = gix_revision::merge_base(gix_oid(commit), &[ancestor], &mut graph)
         .map_err(|e| anyhow::anyhow!("is_descendant_of: {e}"))?;
@@ -48,13 +52,14 @@ pub fn is_descendant_of(
 /// The best common ancestor of `a` and `b`, erroring when they share no history. With
 /// several equally good candidates the choice among them is arbitrary.
 pub fn merge_base(
-    objects: &impl gix_object::Find,
+    objects: &(impl gix_object::Find + FindCommitGraph),
     a: git2::Oid,
     b: git2::Oid,
 ) -> anyhow::Result<git2::Oid> {
     ensure_commit(objects, a)?;
     ensure_commit(objects, b)?;
-    let mut graph = gix_revision::Graph::new(objects, None);
+    let mut graph =
+        gix_revision::Graph::new(objects, objects.commit_graph().map(CommitGraph::graph));
     gix_revision::merge_base(gix_oid(a), &[gix_oid(b)], &mut graph)
         .map_err(|e| anyhow::anyhow!("merge_base: {e}"))?
         .map(|bases| git2_oid(bases.first()))
@@ -64,7 +69,7 @@ pub fn merge_base(
 /// The best common ancestor of every commit in `commits`, or `None` when they do not all
 /// share history.
 pub fn merge_base_octopus(
-    objects: &impl gix_object::Find,
+    objects: &(impl gix_object::Find + FindCommitGraph),
     commits: &[git2::Oid],
 ) -> anyhow::Result<Option<git2::Oid>> {
     let (first, rest) = commits
@@ -74,7 +79,8 @@ pub fn merge_base_octopus(
         ensure_commit(objects, *commit)?;
     }
     let rest: Vec<_> = rest.iter().map(|c| gix_oid(*c)).collect();
-    let mut graph = gix_revision::Graph::new(objects, None);
+    let mut graph =
+        gix_revision::Graph::new(objects, objects.commit_graph().map(CommitGraph::graph));
     let base = gix_revision::merge_base::octopus(gix_oid(*first), &rest, &mut graph)
         .map_err(|e| anyhow::anyhow!("merge_base_octopus: {e}"))?;
     Ok(base.map(|id| git2_oid(&id)))
@@ -216,4 +222,45 @@ mod tests {
         assert!(is_descendant_of(&objects, missing, root).is_err());
         assert!(merge_base_octopus(&objects, &[root, missing]).is_err());
     }
+
+    /// With a commit-graph covering part of the history, every query answers exactly as it
+    /// does from the odb alone -- for pairs inside the graph, outside it, and across the edge.
+    #[test]
+    fn answers_agree_with_a_commit_graph() {
+        let t = TestRepo::new();
+        let root = t.commit(1000, &[]);
+        let a = t.commit(1001, &[root]);
+        let b = t.commit(1002, &[root]);
+        let m = t.commit(1003, &[a, b]);
+        let odb = t.repo.odb().unwrap();
+        let with_graph = crate::commit_graph::tests::GraphOdb::write(&t.repo, &odb, &[m]);
+        let c = t.commit(1004, &[m]);
+        let d = t.commit(1005, &[b]);
+        let unrelated = t.commit(1006, &[]);
+        let objects = crate::Git2Odb(&odb);
+
+        let all = [root, a, b, m, c, d, unrelated];
+        for x in all {
+            for y in all {
+                assert_eq!(
+                    is_descendant_of(&with_graph, x, y).unwrap(),
+                    is_descendant_of(&objects, x, y).unwrap(),
+                    "is_descendant_of({x}, {y})"
+                );
+                assert_eq!(
+                    merge_base(&with_graph, x, y).ok(),
+                    merge_base(&objects, x, y).ok(),
+                    "merge_base({x}, {y})"
+                );
+            }
+        }
+        assert_eq!(
+            merge_base_octopus(&with_graph, &[c, d, a]).unwrap(),
+            Some(root)
+        );
+        assert_eq!(
+            merge_base_octopus(&with_graph, &[c, unrelated]).unwrap(),
+            None
+        );
+    }
 }
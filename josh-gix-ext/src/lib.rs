@@ -23,13 +23,15 @@ use std::collections::HashMap;
 
 use gix_object::WriteTo;
 
+pub mod commit_graph;
 pub mod graph;
 pub mod merge;
 pub mod revwalk;
 
+pub use commit_graph::{CommitGraph, FindCommitGraph};
 pub use graph::{is_descendant_of, merge_base, merge_base_octopus};
 pub use merge::{merge_commits, merge_trees};
-pub use revwalk::{RangeWalk, RevWalk};
+pub use revwalk::{HistoryWalk, RangeWalk, RevWalk};
 
 /// Map the kind of a raw object between the two libraries. Infallible: both enums cover exactly
 /// the four git object kinds.
@@ -53,7 +55,47 @@ pub fn gix_kind(kind: git2::ObjectType) -> Option<gix_object::Kind> {
     }
 }
 
-/// Zero-cost oid conversion: both libraries use the same 20-byte binary representation.
+/// Map a repository's object format to the hash kind gix parses and hashes with.
+pub fn hash_kind(format: git2::ObjectFormat) -> gix_hash::Kind {
+    format
+        .str()
+        .parse()
+        .expect("gix supports every object format git2 does")
+}
+
+/// The hash kind of `oid` -- and so of the object it names: raw objects are parsed with the
+/// kind of the id they were read by, since their embedded ids use the same hash.
+pub fn oid_kind(oid: git2::Oid) -> gix_hash::Kind {
+    hash_kind(oid.object_format())
+}
+
+/// Parse a hex object id of either format, telling them apart by length: 64 digits are
+/// SHA-256 (with the `sha256` feature), anything up to 40 SHA-1.
+pub fn parse_oid(hex: &str) -> Result<git2::Oid, git2::Error> {
+    #[cfg(feature = "sha256")]
+    if hex.len() == gix_hash::Kind::Sha256.len_in_hex() {
+        return git2::Oid::from_str_ext(hex, git2::ObjectFormat::Sha256);
+    }
+    git2::Oid::from_str(hex)
+}
+
+/// The all-zero id of a `kind` repository.
+pub fn zero_oid(kind: gix_hash::Kind) -> git2::Oid {
+    git2_oid(&gix_hash::ObjectId::null(kind))
+}
+
+/// The id of the empty tree of a `kind` repository.
+pub fn empty_tree(kind: gix_hash::Kind) -> git2::Oid {
+    git2_oid(&gix_hash::ObjectId::empty_tree(kind))
+}
+
+/// Whether `oid` is the empty tree of its object format.
+pub fn is_empty_tree(oid: git2::Oid) -> bool {
+    oid == empty_tree(oid_kind(oid))
+}
+
+/// Zero-cost oid conversion: both libraries use the same binary representation (20 bytes for
+/// SHA-1, 32 for SHA-256).
 pub fn gix_oid(oid: git2::Oid) -> gix_hash::ObjectId {
     gix_hash::ObjectId::from_bytes_or_panic(oid.as_bytes())
 }
@@ -63,10 +105,10 @@ pub fn git2_oid(oid: &gix_hash::oid) -> git2::Oid {
     git2::Oid::from_bytes(oid.as_bytes()).expect("oid sizes match")
 }
 
-/// Hash `data` as a blob without writing it anywhere.
-pub fn hash_blob(data: &[u8]) -> git2::Oid {
+/// Hash `data` as a blob of a `kind` repository without writing it anywhere.
+pub fn hash_blob(kind: gix_hash::Kind, data: &[u8]) -> git2::Oid {
     git2_oid(
-        &gix_object::compute_hash(gix_hash::Kind::Sha1, gix_object::Kind::Blob, data)
+        &gix_object::compute_hash(kind, gix_object::Kind::Blob, data)
             .expect("failed to compute hash"),
     )
 }
@@ -87,8 +129,8 @@ pub fn peel_to_commit(
         match data.kind {
             gix_object::Kind::Commit => return Ok(git2_oid(&current)),
             gix_object::Kind::Tag => {
-                current = gix_object::TagRefIter::from_bytes(&buffer, gix_hash::Kind::Sha1)
-                    .target_id()?;
+                current =
+                    gix_object::TagRefIter::from_bytes(&buffer, current.kind()).target_id()?;
             }
             kind => {
                 return Err(anyhow::anyhow!(
@@ -101,6 +143,36 @@ pub fn peel_to_commit(
     }
 }
 
+/// Follow `oid` to the tree it names: a commit's root tree, through any annotated tags, or
+/// `oid` itself when it is a tree. Errors when the object is missing or is a blob.
+pub fn peel_to_tree(
+    src: &(impl gix_object::Find + ?Sized),
+    oid: git2::Oid,
+) -> anyhow::Result<git2::Oid> {
+    let mut current = gix_oid(oid);
+    let mut buffer = Vec::new();
+    loop {
+        let data = src
+            .try_find(&current, &mut buffer)
+            .map_err(|e| anyhow::anyhow!("peel {}: {}", current, e))?
+            .ok_or_else(|| anyhow::anyhow!("object {} not found", current))?;
+        match data.kind {
+            gix_object::Kind::Tree => return Ok(git2_oid(&current)),
+            gix_object::Kind::Commit => {
+                current =
+                    gix_object::CommitRefIter::from_bytes(&buffer, current.kind()).tree_id()?;
+            }
+            gix_object::Kind::Tag => {
+                current =
+                    gix_object::TagRefIter::from_bytes(&buffer, current.kind()).target_id()?;
+            }
+            gix_object::Kind::Blob => {
+                return Err(anyhow::anyhow!("object {} is a blob, not a tree", current));
+            }
+        }
+    }
+}
+
 /// The entries of the tree `oid`, owned so several trees can be walked side by side.
 /// Errors when the object is missing or is not a tree.
 pub fn read_tree_entries(
@@ -115,11 +187,9 @@ pub fn read_tree_entries(
     if data.kind != gix_object::Kind::Tree {
         return Err(anyhow::anyhow!("object {} is not a tree", oid));
     }
-    Ok(
-        gix_object::TreeRef::from_bytes(&buffer, gix_hash::Kind::Sha1)?
-            .into_owned()
-            .entries,
-    )
+    Ok(gix_object::TreeRef::from_bytes(&buffer, oid_kind(oid))?
+        .into_owned()
+        .entries)
 }
 
 /// Descend `path` from the tree `oid`. `None` covers a missing component and a non-tree
@@ -142,7 +212,7 @@ pub fn path_entry(
         if data.kind != gix_object::Kind::Tree {
             return Ok(None);
         }
-        let parsed = gix_object::TreeRef::from_bytes(&buffer, gix_hash::Kind::Sha1)?;
+        let parsed = gix_object::TreeRef::from_bytes(&buffer, oid_kind(current))?;
         let name = std::os::unix::ffi::OsStrExt::as_bytes(component.as_os_str());
         let Some(entry) = parsed.entries.iter().find(|e| e.filename == name) else {
             return Ok(None);
@@ -155,6 +225,44 @@ pub fn path_entry(
     Ok(None)
 }
 
+/// The blob holding the note on `annotated` in the notes commit `notes`, found the way git
+/// stores notes: named by the annotated object's hex id, possibly fanned out into two-digit
+/// directories (`ab/cdef...`, `ab/cd/ef...`). `None` when `annotated` has no note.
+pub fn note_blob(
+    src: &impl gix_object::Find,
+    notes: git2::Oid,
+    annotated: git2::Oid,
+) -> anyhow::Result<Option<git2::Oid>> {
+    let hex = annotated.to_string();
+    let mut rest = hex.as_str();
+    let mut tree = CommitData::read(src, notes)?.tree_id()?;
+    loop {
+        let entries = read_tree_entries(src, tree)?;
+        if let Some(entry) = entries
+            .iter()
+            .find(|e| e.filename == rest && !e.mode.is_tree())
+        {
+            return Ok(Some(git2_oid(&entry.oid)));
+        }
+        let Some((dir, tail)) = rest
+            .split_at_checked(2)
+            .filter(|(_, tail)| !tail.is_empty())
+        else {
+            return Ok(None);
+        };
+        match entries
+            .iter()
+            .find(|e| e.filename == dir && e.mode.is_tree())
+        {
+            Some(entry) => {
+                tree = git2_oid(&entry.oid);
+                rest = tail;
+            }
+            None => return Ok(None),
+        }
+    }
+}
+
 /// The text of the blob `oid`, or `""` when it is missing, is not a blob, holds a NUL byte or
 /// is not valid UTF-8 -- the tolerance the display and script paths want, where a file that
 /// cannot be shown is the same as a file that is not there.
@@ -204,7 +312,7 @@ fn walk_tree_preorder_inner(
             data.kind
         ));
     }
-    let parsed = gix_object::TreeRef::from_bytes(&buf, gix_hash::Kind::Sha1)?;
+    let parsed = gix_object::TreeRef::from_bytes(&buf, oid_kind(tree))?;
     for entry in &parsed.entries {
         cb(path, entry)?;
         if entry.mode.is_tree() {
@@ -234,11 +342,11 @@ pub fn write_tree_now(
     out: &impl gix_object::Write,
     entries: Vec<gix_object::tree::Entry>,
 ) -> anyhow::Result<git2::Oid> {
-    // Exact-fit upper bound: mode (<= 6 octal digits) + space + name + NUL + 20 oid bytes.
+    // Exact-fit upper bound: mode (<= 6 octal digits) + space + name + NUL + oid bytes.
     let mut buffer = Vec::with_capacity(
         entries
             .iter()
-            .map(|e| 6 + 1 + e.filename.len() + 1 + 20)
+            .map(|e| 6 + 1 + e.filename.len() + 1 + e.oid.kind().len_in_bytes())
             .sum(),
     );
     let mut mode_buf = Default::default();
@@ -324,7 +432,8 @@ pub fn write_commit_with_signatures_of(
     Ok(git2_oid(&id))
 }
 
-fn gix_signature(sig: &git2::Signature<'_>) -> anyhow::Result<gix_actor::Signature> {
+/// A git2 signature as gix writes it into commits and reflogs.
+pub fn gix_signature(sig: &git2::Signature<'_>) -> anyhow::Result<gix_actor::Signature> {
     let when = sig.when();
     Ok(gix_actor::Signature {
         name: sig.name_bytes().into(),
@@ -378,13 +487,12 @@ impl CommitData {
     pub fn parsed(&self) -> anyhow::Result<gix_object::CommitRef<'_>> {
         Ok(gix_object::CommitRef::from_bytes(
             &self.bytes,
-            gix_hash::Kind::Sha1,
+            oid_kind(self.id),
         )?)
     }
 
     pub fn tree_id(&self) -> anyhow::Result<git2::Oid> {
-        let id =
-            gix_object::CommitRefIter::from_bytes(&self.bytes, gix_hash::Kind::Sha1).tree_id()?;
+        let id = gix_object::CommitRefIter::from_bytes(&self.bytes, oid_kind(self.id)).tree_id()?;
         Ok(git2_oid(&id))
     }
 
@@ -412,7 +520,7 @@ impl CommitData {
     /// Binary parent ids read from the parsed commit header id array via
     /// `CommitRefIter::parent_ids`; never does odb lookups.
     pub fn parent_ids(&self) -> impl Iterator<Item = git2::Oid> + '_ {
-        gix_object::CommitRefIter::from_bytes(&self.bytes, gix_hash::Kind::Sha1)
+        gix_object::CommitRefIter::from_bytes(&self.bytes, oid_kind(self.id))
             .parent_ids()
             .map(|p| git2_oid(&p))
     }
@@ -592,6 +700,13 @@ impl gix_object::FindHeader for Git2Odb<'_> {
     }
 }
 
+/// A bare git2 odb handle does not know its object directory, so it never has a graph.
+impl FindCommitGraph for Git2Odb<'_> {
+    fn commit_graph(&self) -> Option<&CommitGraph> {
+        None
+    }
+}
+
 impl gix_object::Exists for Git2Odb<'_> {
     fn exists(&self, id: &gix_hash::oid) -> bool {
         self.0.exists(git2_oid(id))
@@ -648,12 +763,12 @@ mod tests {
     fn hash_blob_matches_git2() {
         for data in [&b""[..], b"x", b"1:{\"a\":1}\n"] {
             assert_eq!(
-                super::hash_blob(data),
+                super::hash_blob(hash_kind(git2::ObjectFormat::Sha1), data),
                 git2::Oid::hash_object(git2::ObjectType::Blob, data).unwrap()
             );
         }
         assert_eq!(
-            super::hash_blob(b"").to_string(),
+            super::hash_blob(gix_hash::Kind::Sha1, b"").to_string(),
             "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
         );
     }
@@ -794,6 +909,46 @@ mod tests {
         );
     }
 
+    /// Notes are found whether git stored them flat, as libgit2 does for small notes trees,
+    /// or fanned out into two-digit directories, as git does for large ones.
+    #[test]
+    fn note_blob_finds_flat_and_fanned_out_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(dir.path()).unwrap();
+        let odb = repo.odb().unwrap();
+        let sig = git2::Signature::new("t", "t@e", &git2::Time::new(0, 0)).unwrap();
+        let flat = commit_with_message(&repo, b"flat");
+        let fanned = commit_with_message(&repo, b"fanned");
+        let bare = commit_with_message(&repo, b"no note");
+
+        repo.note(&sig, &sig, Some("refs/notes/test"), flat, "a note", false)
+            .unwrap();
+        let notes = repo.refname_to_id("refs/notes/test").unwrap();
+        assert_eq!(
+            note_blob(&Git2Odb(&odb), notes, flat).unwrap(),
+            Some(repo.find_note(Some("refs/notes/test"), flat).unwrap().id())
+        );
+        assert_eq!(note_blob(&Git2Odb(&odb), notes, bare).unwrap(), None);
+
+        let hex = fanned.to_string();
+        let blob = repo.blob(b"fanned note").unwrap();
+        let mut inner = repo.treebuilder(None).unwrap();
+        inner.insert(&hex[4..], blob, 0o100644).unwrap();
+        let inner = inner.write().unwrap();
+        let mut middle = repo.treebuilder(None).unwrap();
+        middle.insert(&hex[2..4], inner, 0o040000).unwrap();
+        let middle = middle.write().unwrap();
+        let mut root = repo.treebuilder(None).unwrap();
+        root.insert(&hex[..2], middle, 0o040000).unwrap();
+        let root = repo.find_tree(root.write().unwrap()).unwrap();
+        let notes = repo.commit(None, &sig, &sig, "notes", &root, &[]).unwrap();
+        assert_eq!(
+            note_blob(&Git2Odb(&odb), notes, fanned).unwrap(),
+            Some(blob)
+        );
+        assert_eq!(note_blob(&Git2Odb(&odb), notes, bare).unwrap(), None);
+    }
+
     /// The walk yields entries in stored tree order (never canonically sorted), fires for a
     /// tree entry before descending into it, and hands the callback the containing
     /// directory's slash-separated path.
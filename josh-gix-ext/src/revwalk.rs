@@ -1,5 +1,6 @@
 //! josh's revision walkers, working over raw odb bytes: [`RevWalk`] for
-//! pruned topological walks and [`RangeWalk`] for `base..tip` deltas.
+//! pruned topological walks, [`RangeWalk`] for `base..tip` deltas and
+//! [`HistoryWalk`] for lazy, resumable newest-first history.
 //!
 //! josh's walks need two things no gitoxide traversal provides:
 //!
@@ -7,25 +8,32 @@
 //!   cheap by skipping already-processed subgraphs (the prune callback).
 //!   `gix_traverse::commit::topo::Builder` cannot do this: `with_predicate`
 //!   only filters yields after parent expansion.
-//! - Laziness without a commit-graph. josh repos have no commit-graph file, and
-//!   without one the gix topo builder assigns every commit generation INFINITY
-//!   and drains the entire reachable graph before the first yield.
-//!   [`RevWalk::discover`] streams visits with early exit.
+//! - Laziness without a commit-graph. Commits josh has just written are in no
+//!   commit-graph file (housekeeping adds them later, see
+//!   [`commit_graph`](crate::commit_graph)), and without one the gix topo
+//!   builder assigns every commit generation INFINITY and drains the entire
+//!   reachable graph before the first yield. [`RevWalk::discover`] streams
+//!   visits with early exit.
 //!
-//! Commits are parsed only up to their parent headers (one raw odb read, one
-//! `CommitRefIter` pass; committer lines are never read), so malformed
-//! author/committer lines (fsck-invalid objects) do not affect a walk. Tags
+//! Parents come from the source's [`CommitGraph`](crate::CommitGraph) when it lists the commit,
+//! and otherwise from the commit itself, parsed only up to its parent headers
+//! (one raw odb read, one `CommitRefIter` pass; committer lines are never
+//! read), so malformed author/committer lines (fsck-invalid objects) do not
+//! affect a walk. Tags
 //! are NOT peeled: any non-commit input is an error (josh only ever walks
 //! commit oids; the git2 walks this module replaces peeled).
 //!
 //! Differential tests below pin the yield sets against an independent
 //! reference model (and against `git2::Revwalk` for [`RevWalk`], where the
 //! sets provably coincide), and pin topological validity and order
-//! determinism, over fixed shapes and seeded random DAGs.
+//! determinism, over fixed shapes and seeded random DAGs -- each once reading
+//! the odb only and once with a commit-graph, which must not change a single
+//! yield.
 
 use std::collections::HashMap;
 use std::ops::ControlFlow;
 
+use crate::commit_graph::FindCommitGraph;
 use crate::git2_oid;
 
 type Visit<'v> = &'v mut dyn FnMut(git2::Oid) -> anyhow::Result<ControlFlow<()>>;
@@ -47,7 +55,7 @@ fn read_parent_oids(
         anyhow::bail!("object {} is not a commit", oid);
     }
     let mut parents = Vec::new();
-    for token in gix_object::CommitRefIter::from_bytes(data.data, gix_hash::Kind::Sha1) {
+    for token in gix_object::CommitRefIter::from_bytes(data.data, crate::oid_kind(oid)) {
         use gix_object::commit::ref_iter::Token;
         match token? {
             Token::Tree { .. } => {}
@@ -58,6 +66,19 @@ fn read_parent_oids(
     Ok(parents)
 }
 
+/// A commit's parent ids: from the commit-graph when it lists the commit, otherwise via
+/// [`read_parent_oids`].
+fn parent_oids(
+    src: &(impl gix_object::Find + FindCommitGraph),
+    oid: git2::Oid,
+    buf: &mut Vec<u8>,
+) -> anyhow::Result<Vec<git2::Oid>> {
+    match src.commit_graph().and_then(|graph| graph.parents(oid)) {
+        Some(parents) => Ok(parents),
+        None => read_parent_oids(src, oid, buf),
+    }
+}
+
 /// Verify `oid` names a commit without decompressing it.
 fn ensure_commit(src: &impl gix_object::FindHeader, oid: git2::Oid) -> anyhow::Result<()> {
     let header = src
@@ -115,7 +136,7 @@ pub struct RevWalk<'a, S> {
     scratch: Vec<u8>,
 }
 
-impl<'a, S: gix_object::Find + gix_object::FindHeader> RevWalk<'a, S> {
+impl<'a, S: gix_object::Find + gix_object::FindHeader + FindCommitGraph> RevWalk<'a, S> {
     pub fn new(odb: &'a S) -> Self {
         RevWalk {
             odb,
@@ -151,6 +172,37 @@ impl<'a, S: gix_object::Find + gix_object::FindHeader> RevWalk<'a, S> {
         Ok(post.into_iter().rev().map(|i| self.nodes[i].oid).collect())
     }
 
+    /// [`RevWalk::into_topo_vec`] together with the commit graph among the yielded commits: for
+    /// each commit, the positions of its parents within the result. Parents that were pruned (or
+    /// not followed, with [`RevWalk::simplify_first_parent`]) are left out, so every listed
+    /// parent comes after its child.
+    pub fn into_topo_graph(
+        mut self,
+        mut prune: impl FnMut(git2::Oid) -> bool,
+    ) -> anyhow::Result<(Vec<git2::Oid>, Vec<Vec<usize>>)> {
+        let post = self.dfs(&mut prune, &mut None)?;
+        let mut position = vec![usize::MAX; self.nodes.len()];
+        for (i, &node) in post.iter().rev().enumerate() {
+            position[node] = i;
+        }
+        let n = if self.first_parent { 1 } else { usize::MAX };
+        let parents = post
+            .iter()
+            .rev()
+            .map(|&node| {
+                self.nodes[node]
+                    .parents
+                    .iter()
+                    .take(n)
+                    .map(|&p| position[p])
+                    .filter(|&p| p != usize::MAX)
+                    .collect()
+            })
+            .collect();
+        let commits = post.into_iter().rev().map(|i| self.nodes[i].oid).collect();
+        Ok((commits, parents))
+    }
+
     /// Lazy discovery iteration: streams oids in DFS pre-order (each commit
     /// visited before its parents are explored). `visit` returns
     /// [`ControlFlow::Break`] to abort the walk.
@@ -184,7 +236,7 @@ impl<'a, S: gix_object::Find + gix_object::FindHeader> RevWalk<'a, S> {
         if self.nodes[commit].parsed {
             return Ok(());
         }
-        let parents = read_parent_oids(self.odb, self.nodes[commit].oid, &mut self.scratch)?
+        let parents = parent_oids(self.odb, self.nodes[commit].oid, &mut self.scratch)?
             .into_iter()
             .map(|p| self.intern(p))
             .collect();
@@ -286,7 +338,9 @@ struct RangeNode {
 ///   undiscovered is then an ancestor of hidden commits only. The walk stops
 ///   at the merge-base level: O(delta + boundary). Emission order (highest
 ///   number first) is already forward topological order. Lookup failures
-///   fail the walk.
+///   fail the walk. When the source's commit-graph lists both tip and base,
+///   it lists everything the walk can reach, and its generation numbers --
+///   the same `1 + max(parents)` rule -- rank instead, without a lookup.
 ///
 /// The two strategies yield different topological orders, which is fine
 /// because the callers consume the whole yield set parents-first.
@@ -299,7 +353,7 @@ pub struct RangeWalk<'a, S> {
     scratch: Vec<u8>,
 }
 
-impl<'a, S: gix_object::Find + gix_object::FindHeader> RangeWalk<'a, S> {
+impl<'a, S: gix_object::Find + gix_object::FindHeader + FindCommitGraph> RangeWalk<'a, S> {
     pub fn new(
         odb: &'a S,
         sequence_numbers: impl Fn(git2::Oid) -> anyhow::Result<u64> + 'a,
@@ -359,7 +413,7 @@ impl<'a, S: gix_object::Find + gix_object::FindHeader> RangeWalk<'a, S> {
         if self.nodes[commit].parsed {
             return Ok(());
         }
-        let parents = read_parent_oids(self.odb, self.nodes[commit].oid, &mut self.scratch)?
+        let parents = parent_oids(self.odb, self.nodes[commit].oid, &mut self.scratch)?
             .into_iter()
             .map(|p| self.intern(p))
             .collect();
@@ -400,7 +454,17 @@ impl<'a, S: gix_object::Find + gix_object::FindHeader> RangeWalk<'a, S> {
     /// sequence number, ties resolved by insertion order (equal numbers are
     /// never ancestor-related, so any deterministic order is correct).
     fn ranked_walk(&mut self, tip: usize, base: usize) -> anyhow::Result<Vec<git2::Oid>> {
-        let seq_of = std::mem::replace(&mut self.sequence_numbers, Box::new(|_| unreachable!()));
+        let lookup = std::mem::replace(&mut self.sequence_numbers, Box::new(|_| unreachable!()));
+        let graph = self.odb.commit_graph().filter(|graph| {
+            graph.contains(self.nodes[tip].oid) && graph.contains(self.nodes[base].oid)
+        });
+        let seq_of = |oid| match graph {
+            Some(graph) => graph
+                .generation(oid)
+                .map(u64::from)
+                .ok_or_else(|| anyhow::anyhow!("commit {oid} missing from the commit-graph")),
+            None => lookup(oid),
+        };
         let mut heap: std::collections::BinaryHeap<(u64, std::cmp::Reverse<u64>, usize)> =
             std::collections::BinaryHeap::new();
         let mut seq = 0u64;
@@ -463,9 +527,86 @@ impl<'a, S: gix_object::Find + gix_object::FindHeader> RangeWalk<'a, S> {
     }
 }
 
+/// A lazy walk of everything reachable from the pushed tips, newest first:
+/// commits pop highest sequence number first, so every commit comes after all
+/// of its children, like `git log --topo-order` without the up-front walk.
+/// Unlike [`RevWalk`], nothing is read beyond what has been yielded plus the
+/// pending frontier, so a caller that stops early pays for what it consumed.
+///
+/// The walk is resumable: [`frontier`](HistoryWalk::frontier) lists the
+/// commits pending when it stopped, and a new walk with those pushed yields
+/// exactly the commits the old one had not yet yielded, in the same order.
+/// Paging callers keep the frontier in their cursor instead of rewalking.
+pub struct HistoryWalk<'a, S> {
+    odb: &'a S,
+    sequence_numbers: Box<dyn Fn(git2::Oid) -> anyhow::Result<u64> + 'a>,
+    queue: std::collections::BinaryHeap<(u64, git2::Oid)>,
+    /// Reused per-commit read buffer, so a walk does one allocation, not one per commit.
+    scratch: Vec<u8>,
+}
+
+impl<'a, S: gix_object::Find + gix_object::FindHeader + FindCommitGraph> HistoryWalk<'a, S> {
+    pub fn new(
+        odb: &'a S,
+        sequence_numbers: impl Fn(git2::Oid) -> anyhow::Result<u64> + 'a,
+    ) -> Self {
+        HistoryWalk {
+            odb,
+            sequence_numbers: Box::new(sequence_numbers),
+            queue: std::collections::BinaryHeap::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Mark a commit to start traversal from. A missing oid or a non-commit
+    /// object errors immediately. Pushing after the walk has started is only
+    /// sound for commits that are not ancestors of already yielded ones.
+    pub fn push(&mut self, tip: git2::Oid) -> anyhow::Result<()> {
+        ensure_commit(self.odb, tip)?;
+        self.queue.push(((self.sequence_numbers)(tip)?, tip));
+        Ok(())
+    }
+
+    /// The next commit, or `None` once the history is exhausted.
+    pub fn next_commit(&mut self) -> anyhow::Result<Option<git2::Oid>> {
+        let Some((_, oid)) = self.queue.pop() else {
+            return Ok(None);
+        };
+        // A commit is queued once per child. All of them rank above it, so
+        // its duplicates are queued by the time it pops, and pop right after.
+        while self.queue.peek().is_some_and(|&(_, next)| next == oid) {
+            self.queue.pop();
+        }
+        for parent in parent_oids(self.odb, oid, &mut self.scratch)? {
+            self.queue.push(((self.sequence_numbers)(parent)?, parent));
+        }
+        Ok(Some(oid))
+    }
+
+    /// The commits pending to be yielded, without duplicates. Pushing these
+    /// into a new walk resumes this one.
+    pub fn frontier(&self) -> Vec<git2::Oid> {
+        let mut pending: Vec<(u64, git2::Oid)> = self.queue.iter().copied().collect();
+        pending.sort_unstable_by(|a, b| b.cmp(a));
+        pending.dedup();
+        pending.into_iter().map(|(_, oid)| oid).collect()
+    }
+}
+
+impl<S: gix_object::Find + gix_object::FindHeader + FindCommitGraph> Iterator
+    for HistoryWalk<'_, S>
+{
+    type Item = anyhow::Result<git2::Oid>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_commit().transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::commit_graph::tests::GraphOdb;
     use std::collections::HashSet;
 
     struct TestRepo {
@@ -580,6 +721,32 @@ mod tests {
         pruned: &HashSet<git2::Oid>,
         first_parent: bool,
     ) -> anyhow::Result<Vec<git2::Oid>> {
+        let odb = repo.odb().unwrap();
+        rev_walk_in(&crate::Git2Odb(&odb), tips, pruned, first_parent)
+    }
+
+    fn rev_walk_in(
+        odb: &(impl gix_object::Find + gix_object::FindHeader + FindCommitGraph),
+        tips: &[git2::Oid],
+        pruned: &HashSet<git2::Oid>,
+        first_parent: bool,
+    ) -> anyhow::Result<Vec<git2::Oid>> {
+        let mut walk = RevWalk::new(odb);
+        if first_parent {
+            walk.simplify_first_parent();
+        }
+        for &t in tips {
+            walk.push(t)?;
+        }
+        walk.into_topo_vec(|oid| pruned.contains(&oid))
+    }
+
+    fn rev_walk_graph(
+        repo: &git2::Repository,
+        tips: &[git2::Oid],
+        pruned: &HashSet<git2::Oid>,
+        first_parent: bool,
+    ) -> anyhow::Result<(Vec<git2::Oid>, Vec<Vec<usize>>)> {
         let odb = repo.odb().unwrap();
         let odb = crate::Git2Odb(&odb);
         let mut walk = RevWalk::new(&odb);
@@ -589,7 +756,7 @@ mod tests {
         for &t in tips {
             walk.push(t)?;
         }
-        walk.into_topo_vec(|oid| pruned.contains(&oid))
+        walk.into_topo_graph(|oid| pruned.contains(&oid))
     }
 
     fn range_walk(
@@ -597,10 +764,18 @@ mod tests {
         tip: git2::Oid,
         base: git2::Oid,
     ) -> anyhow::Result<Vec<git2::Oid>> {
-        let memo = std::cell::RefCell::new(HashMap::new());
         let odb = repo.odb().unwrap();
-        let odb = crate::Git2Odb(&odb);
-        let walk = RangeWalk::new(&odb, |oid| exact_seq(repo, &memo, oid));
+        range_walk_in(repo, &crate::Git2Odb(&odb), tip, base)
+    }
+
+    fn range_walk_in(
+        repo: &git2::Repository,
+        odb: &(impl gix_object::Find + gix_object::FindHeader + FindCommitGraph),
+        tip: git2::Oid,
+        base: git2::Oid,
+    ) -> anyhow::Result<Vec<git2::Oid>> {
+        let memo = std::cell::RefCell::new(HashMap::new());
+        let walk = RangeWalk::new(odb, |oid| exact_seq(repo, &memo, oid));
         walk.into_topo_vec(tip, base)
     }
 
@@ -625,13 +800,16 @@ mod tests {
 
     /// Assert [`RevWalk`] against the reference model: exact yield set, valid
     /// topological order, run-to-run determinism, and (with no pruning) set
-    /// equality with `git2::Revwalk`.
+    /// equality with `git2::Revwalk`. Reading parents from a commit-graph
+    /// must reproduce the walk exactly.
     fn assert_rev_walk(
         repo: &git2::Repository,
         tips: &[git2::Oid],
         pruned: &HashSet<git2::Oid>,
         label: &str,
     ) {
+        let odb = repo.odb().unwrap();
+        let with_graph = GraphOdb::write(repo, &odb, tips);
         for first_parent in [false, true] {
             let mode = format!("{label} first_parent={first_parent}");
             let got = rev_walk(repo, tips, pruned, first_parent).unwrap();
@@ -641,6 +819,22 @@ mod tests {
             assert_topo(repo, &got, first_parent, &mode);
             let again = rev_walk(repo, tips, pruned, first_parent).unwrap();
             assert_eq!(got, again, "non-deterministic order: {mode}");
+            let graph_mode = rev_walk_in(&with_graph, tips, pruned, first_parent).unwrap();
+            assert_eq!(got, graph_mode, "commit-graph walk differs: {mode}");
+
+            // The graph variant yields the same order, and exactly the followed edges between
+            // yielded commits.
+            let (commits, parents) = rev_walk_graph(repo, tips, pruned, first_parent).unwrap();
+            assert_eq!(commits, got, "graph order mismatch: {mode}");
+            for (i, &c) in commits.iter().enumerate() {
+                let mut want: Vec<git2::Oid> = repo.find_commit(c).unwrap().parent_ids().collect();
+                if first_parent {
+                    want.truncate(1);
+                }
+                want.retain(|p| got_set.contains(p));
+                let got_parents: Vec<git2::Oid> = parents[i].iter().map(|&p| commits[p]).collect();
+                assert_eq!(got_parents, want, "graph edge mismatch at {c}: {mode}");
+            }
 
             if pruned.is_empty() {
                 let mut g2 = repo.revwalk().unwrap();
@@ -658,7 +852,11 @@ mod tests {
     }
 
     /// Assert [`RangeWalk`] against the reference model: exact yield set,
-    /// valid topological order, run-to-run determinism.
+    /// valid topological order, run-to-run determinism. The same walk over a
+    /// commit-graph listing only the base's history (ranked by sequence
+    /// numbers, parents from both sources) and listing both ends (ranked by
+    /// generation numbers, which coincide with exact sequence numbers) must
+    /// yield the same order.
     fn assert_range_walk(repo: &git2::Repository, tip: git2::Oid, base: git2::Oid, label: &str) {
         let got = range_walk(repo, tip, base).unwrap();
         let want = reference_set(repo, &[tip], Some(base), &HashSet::new(), false);
@@ -667,6 +865,46 @@ mod tests {
         assert_topo(repo, &got, false, label);
         let again = range_walk(repo, tip, base).unwrap();
         assert_eq!(got, again, "non-deterministic order: {label}");
+
+        let odb = repo.odb().unwrap();
+        for covered in [&[base][..], &[tip, base]] {
+            let with_graph = GraphOdb::write(repo, &odb, covered);
+            let graph_mode = range_walk_in(repo, &with_graph, tip, base).unwrap();
+            assert_eq!(
+                got, graph_mode,
+                "commit-graph walk differs: {label} {covered:?}"
+            );
+        }
+    }
+
+    /// Check a [`HistoryWalk`] from `tips`: the yield set is the reachable
+    /// set, in topological order, and stopping after any prefix and resuming
+    /// from the frontier yields exactly the rest.
+    fn assert_history_walk(repo: &git2::Repository, tips: &[git2::Oid], label: &str) {
+        let odb = repo.odb().unwrap();
+        let odb = crate::Git2Odb(&odb);
+        let memo = std::cell::RefCell::new(HashMap::new());
+        let walk = |starts: &[git2::Oid]| {
+            let mut walk = HistoryWalk::new(&odb, |oid| exact_seq(repo, &memo, oid));
+            for &t in starts {
+                walk.push(t).unwrap();
+            }
+            walk
+        };
+        let all: Vec<git2::Oid> = walk(tips).map(Result::unwrap).collect();
+        let expect = reference_set(repo, tips, None, &no_prune(), false);
+        assert_eq!(
+            all.iter().copied().collect::<HashSet<_>>(),
+            expect,
+            "{label}"
+        );
+        assert_topo(repo, &all, false, label);
+        for split in 0..=all.len() {
+            let mut first = walk(tips);
+            let head: Vec<git2::Oid> = first.by_ref().take(split).map(Result::unwrap).collect();
+            let rest: Vec<git2::Oid> = walk(&first.frontier()).map(Result::unwrap).collect();
+            assert_eq!([head, rest].concat(), all, "{label} resumed after {split}");
+        }
     }
 
     fn no_prune() -> HashSet<git2::Oid> {
@@ -1013,6 +1251,36 @@ mod tests {
         assert_eq!(w.into_topo_vec(|_| false).unwrap(), vec![tip, trunc]);
     }
 
+    #[test]
+    fn history_walk_is_lazy_and_resumable() {
+        let t = TestRepo::new();
+        let root = t.commit("root", &[]);
+        let a = t.commit("a", &[root]);
+        let b = t.commit("b", &[root]);
+        let x = t.commit("x", &[a, b]);
+        let y = t.commit("y", &[b, a]);
+        let m = t.commit("m", &[x, y]);
+        assert_history_walk(&t.repo, &[m], "criss-cross");
+        assert_history_walk(&t.repo, &[x, y], "criss-cross two tips");
+        assert_history_walk(&t.repo, &[a, a], "repeated tip");
+
+        // Nothing below the frontier is read: a missing ancestor only fails
+        // the walk once it is due.
+        let missing = git2::Oid::from_str("1111111111111111111111111111111111111111").unwrap();
+        let orphan = t.raw_commit("orphan", &[missing]);
+        let tip = t.commit("tip", &[orphan]);
+        let odb = t.repo.odb().unwrap();
+        let odb = crate::Git2Odb(&odb);
+        let seqs = HashMap::from([(tip, 3), (orphan, 2), (missing, 1)]);
+        let mut walk = HistoryWalk::new(&odb, |oid| Ok(seqs[&oid]));
+        walk.push(tip).unwrap();
+        assert_eq!(walk.next().unwrap().unwrap(), tip);
+        assert_eq!(walk.frontier(), vec![orphan]);
+        assert_eq!(walk.next().unwrap().unwrap(), orphan);
+        assert!(walk.next().unwrap().is_err());
+        assert!(walk.push(missing).is_err());
+    }
+
     #[test]
     fn discover_streams_dfs_preorder() {
         let t = TestRepo::new();
@@ -1123,6 +1391,7 @@ mod tests {
                 .collect();
             assert_rev_walk(&t.repo, &tips, &no_prune(), &format!("fuzz {seed}"));
             assert_rev_walk(&t.repo, &tips, &pruned, &format!("fuzz {seed} pruned"));
+            assert_history_walk(&t.repo, &tips, &format!("fuzz {seed} history"));
 
             let tip = commits[rng.below(commits.len())];
             let base = commits[rng.below(commits.len())];
@@ -1,12 +1,15 @@
 #![allow(unused_variables)]
 
 use anyhow::anyhow;
+use futures::StreamExt;
 use josh_core::filter::Rewrite;
 use josh_core::filter::tree;
 use josh_core::objects;
-use josh_core::objects::CommitData;
+use josh_core::objects::{CommitData, FindCommitGraph};
 use josh_core::{cache, filter, history};
-use juniper::{EmptyMutation, EmptySubscription, FieldResult, graphql_object};
+use juniper::{
+    EmptyMutation, EmptySubscription, FieldResult, graphql_object, graphql_subscription,
+};
 
 pub struct Revision {
     filter: filter::Filter,
@@ -94,6 +97,329 @@ fn filtered_commit(
     CommitData::read(&transaction.odb()?, filtered)
 }
 
+/// Whether the filtered commit `id` changes `path` (any path when `None`): the entry there
+/// differs from that of every (followed) parent, like `git log -- path` shows merges only when
+/// they differ from all parents. A commit-graph's changed-path filter rules most commits out
+/// without reading a tree: unchanged from the first parent means untouched in either mode.
+fn touches_path(
+    transaction: &cache::Transaction,
+    odb: &josh_core::memodb::Odb,
+    id: git2::Oid,
+    path: Option<&std::path::Path>,
+    first_parent: bool,
+) -> anyhow::Result<bool> {
+    let Some(path) = path else {
+        return Ok(true);
+    };
+    if let (Some(graph), Some(key)) = (odb.commit_graph(), path.to_str())
+        && graph.maybe_changed(id, key) == Some(false)
+    {
+        return Ok(false);
+    }
+    let entry = |commit: git2::Oid| -> anyhow::Result<Option<git2::Oid>> {
+        let tree = josh_core::git::read_tree_id(odb, commit)?;
+        Ok(tree::get_path_entry(transaction, odb, tree, path)?
+            .map(|entry| objects::git2_oid(&entry.oid)))
+    };
+    let own = entry(id)?;
+    let mut parents = josh_core::git::read_parent_ids(odb, id)?;
+    if first_parent {
+        parents.truncate(1);
+    }
+    if parents.is_empty() {
+        return Ok(own.is_some());
+    }
+    for parent in parents {
+        if entry(parent)? == own {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Visit the first-parent history of `head` that [`touches_path`], newest first, until `visit`
+/// breaks. With `after`, the walk starts behind that commit.
+fn walk_history(
+    transaction: &cache::Transaction,
+    head: git2::Oid,
+    path: Option<&std::path::Path>,
+    after: Option<git2::Oid>,
+    mut visit: impl FnMut(git2::Oid) -> bool,
+) -> anyhow::Result<()> {
+    let odb = transaction.odb()?;
+    let mut next = match after {
+        Some(after) => josh_core::git::read_parent_ids(&odb, after)?
+            .into_iter()
+            .next(),
+        None => Some(head),
+    };
+    while let Some(id) = next {
+        if touches_path(transaction, &odb, id, path, true)? && !visit(id) {
+            break;
+        }
+        next = josh_core::git::read_parent_ids(&odb, id)?
+            .into_iter()
+            .next();
+    }
+    Ok(())
+}
+
+/// Visit the whole filtered history from `start` that [`touches_path`], newest first, until
+/// `visit` breaks. `visit` also gets the frontier of the walk behind the commit, from where a
+/// later walk continues.
+fn walk_dag_history(
+    transaction: &cache::Transaction,
+    start: &[git2::Oid],
+    path: Option<&std::path::Path>,
+    mut visit: impl FnMut(git2::Oid, Vec<git2::Oid>) -> bool,
+) -> anyhow::Result<()> {
+    let odb = transaction.odb()?;
+    let mut walk =
+        objects::HistoryWalk::new(&odb, |oid| cache::compute_sequence_number(transaction, oid));
+    for &id in start {
+        walk.push(id)?;
+    }
+    while let Some(id) = walk.next_commit()? {
+        if touches_path(transaction, &odb, id, path, false)? && !visit(id, walk.frontier()) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// History cursors are opaque to clients; inside they are the filtered commit of an edge and
+/// its original, so a first-parent walk resumes right there.
+fn history_cursor(filtered: git2::Oid, original: git2::Oid) -> String {
+    format!("{}:{}", filtered, original)
+}
+
+fn parse_history_cursor(cursor: &str) -> anyhow::Result<(git2::Oid, git2::Oid)> {
+    cursor
+        .split_once(':')
+        .and_then(|(filtered, original)| {
+            Some((
+                git2::Oid::from_str(filtered).ok()?,
+                git2::Oid::from_str(original).ok()?,
+            ))
+        })
+        .ok_or_else(|| anyhow!("invalid history cursor: {}", cursor))
+}
+
+/// Cursors of the whole history hold the frontiers of both walks, the filtered one behind the
+/// edge and the original one behind the page, so the next page continues both.
+fn dag_history_cursor(filtered: &[git2::Oid], original: &[git2::Oid]) -> String {
+    let join = |ids: &[git2::Oid]| {
+        ids.iter()
+            .map(git2::Oid::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    format!("{};{}", join(filtered), join(original))
+}
+
+fn parse_dag_history_cursor(cursor: &str) -> anyhow::Result<(Vec<git2::Oid>, Vec<git2::Oid>)> {
+    let split = |ids: &str| -> Option<Vec<git2::Oid>> {
+        ids.split(',')
+            .filter(|id| !id.is_empty())
+            .map(|id| git2::Oid::from_str(id).ok())
+            .collect()
+    };
+    cursor
+        .split_once(';')
+        .and_then(|(filtered, original)| Some((split(filtered)?, split(original)?)))
+        .ok_or_else(|| anyhow!("invalid history cursor: {}", cursor))
+}
+
+/// The options of a search from the arguments of the `search` fields, without paging.
+fn search_options(
+    regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    context_before: Option<i32>,
+    context_after: Option<i32>,
+) -> anyhow::Result<josh_search::SearchOptions> {
+    let globs = |globs: Option<Vec<String>>| -> anyhow::Result<Vec<_>> {
+        globs
+            .into_iter()
+            .flatten()
+            .map(|glob| Ok(tree::CompiledPattern::compile(&glob)?))
+            .collect()
+    };
+    let count = |n: Option<i32>| -> anyhow::Result<usize> {
+        n.map(|n| usize::try_from(n).map_err(|_| anyhow!("negative count: {}", n)))
+            .transpose()
+            .map(Option::unwrap_or_default)
+    };
+    Ok(josh_search::SearchOptions {
+        regex: regex.unwrap_or(false),
+        ignore_case: !case_sensitive.unwrap_or(true),
+        include: globs(include)?,
+        exclude: globs(exclude)?,
+        before: count(context_before)?,
+        after: count(context_after)?,
+        max_results: None,
+        cursor: None,
+    })
+}
+
+/// The originals of `ids`, a first-parent sequence of filtered commits found in the history of
+/// `contained_in`. Each lookup continues below the previous original; the sequence ends at the
+/// first commit without one.
+fn first_parent_originals(
+    transaction: &cache::Transaction,
+    filter: filter::Filter,
+    mut contained_in: git2::Oid,
+    ids: &[git2::Oid],
+) -> anyhow::Result<Vec<git2::Oid>> {
+    let mut originals = vec![];
+    for &id in ids {
+        let orig = history::find_original(transaction, filter, contained_in, id, true)?;
+        if orig.is_zero() {
+            break;
+        }
+        originals.push(orig);
+        contained_in = josh_core::git::read_parent_ids(&transaction.odb()?, orig)?
+            .into_iter()
+            .next()
+            .unwrap_or(orig);
+    }
+    Ok(originals)
+}
+
+/// The blob at `path` in the tree `tree`, if there is one.
+fn blob_at(
+    transaction: &cache::Transaction,
+    odb: &josh_core::memodb::Odb,
+    tree: git2::Oid,
+    path: &std::path::Path,
+) -> anyhow::Result<Option<git2::Oid>> {
+    Ok(tree::get_path_entry(transaction, odb, tree, path)?
+        .filter(|entry| !entry.mode.is_tree() && !entry.mode.is_commit())
+        .map(|entry| objects::git2_oid(&entry.oid)))
+}
+
+fn blob_content(odb: &josh_core::memodb::Odb, blob: Option<git2::Oid>) -> Vec<u8> {
+    blob.and_then(|blob| tree::blob_bytes(odb, blob))
+        .map(|bytes| bytes.to_vec())
+        .unwrap_or_default()
+}
+
+/// Compare two versions of a file in memory: the filtered objects may not be in the repository
+/// yet, so libgit2 can only diff them as buffers.
+fn diff_buffers<'a>(
+    old: &'a [u8],
+    new: &'a [u8],
+    path: &std::path::Path,
+    context_lines: u32,
+) -> anyhow::Result<git2::Patch<'a>> {
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(context_lines);
+    Ok(git2::Patch::from_buffers(
+        old,
+        Some(path),
+        new,
+        Some(path),
+        Some(&mut opts),
+    )?)
+}
+
+/// For every line of `new`, the line of `old` it was kept from, or `None` when it was added.
+fn line_origins(
+    old: &[u8],
+    new: &[u8],
+    path: &std::path::Path,
+) -> anyhow::Result<Vec<Option<usize>>> {
+    let count = new.split_inclusive(|&b| b == b'\n').count();
+    let patch = diff_buffers(old, new, path, 0)?;
+    if patch.delta().flags().is_binary() {
+        return Ok(vec![None; count]);
+    }
+
+    let mut added = std::collections::HashSet::new();
+    let mut deleted = std::collections::HashSet::new();
+    for hunk in 0..patch.num_hunks() {
+        for line in 0..patch.num_lines_in_hunk(hunk)? {
+            let line = patch.line_in_hunk(hunk, line)?;
+            match line.origin() {
+                '+' => added.extend(line.new_lineno()),
+                '-' => deleted.extend(line.old_lineno()),
+                _ => {}
+            }
+        }
+    }
+
+    let mut old_line = 1;
+    Ok((1..=count as u32)
+        .map(|new_line| {
+            if added.contains(&new_line) {
+                return None;
+            }
+            while deleted.contains(&old_line) {
+                old_line += 1;
+            }
+            old_line += 1;
+            Some(old_line as usize - 1)
+        })
+        .collect())
+}
+
+/// For every line of `path` in the filtered commit `head`, the filtered commit that last
+/// changed it, following first parents like `git blame --first-parent`.
+fn blame_lines(
+    transaction: &cache::Transaction,
+    head: git2::Oid,
+    path: &std::path::Path,
+) -> anyhow::Result<Vec<git2::Oid>> {
+    let odb = transaction.odb()?;
+    let blob = |commit: git2::Oid| -> anyhow::Result<Option<git2::Oid>> {
+        let tree = josh_core::git::read_tree_id(&odb, commit)?;
+        blob_at(transaction, &odb, tree, path)
+    };
+
+    let mut commit = head;
+    let mut current = blob(head)?.ok_or_else(|| anyhow!("no such file: {}", path.display()))?;
+    let mut content = blob_content(&odb, Some(current));
+    let count = content.split_inclusive(|&b| b == b'\n').count();
+    let mut owners = vec![git2::Oid::ZERO_SHA1; count];
+    // Lines not attributed yet: their index in `head` and their line in `current`.
+    let mut pending: Vec<(usize, usize)> = (0..count).map(|i| (i, i + 1)).collect();
+
+    while !pending.is_empty() {
+        let parent = josh_core::git::read_parent_ids(&odb, commit)?
+            .into_iter()
+            .next();
+        let parent_blob = match parent {
+            Some(parent) => blob(parent)?,
+            None => None,
+        };
+        let (Some(parent), Some(parent_blob)) = (parent, parent_blob) else {
+            for (index, _) in pending.drain(..) {
+                owners[index] = commit;
+            }
+            break;
+        };
+        if parent_blob != current {
+            let parent_content = blob_content(&odb, Some(parent_blob));
+            let origins = line_origins(&parent_content, &content, path)?;
+            pending.retain_mut(|(index, line)| match origins[*line - 1] {
+                Some(old_line) => {
+                    *line = old_line;
+                    true
+                }
+                None => {
+                    owners[*index] = commit;
+                    false
+                }
+            });
+            current = parent_blob;
+            content = parent_content;
+        }
+        commit = parent;
+    }
+    Ok(owners)
+}
+
 pub struct DiffPath {
     a: Option<Path>,
     b: Option<Path>,
@@ -110,6 +436,101 @@ impl DiffPath {
     }
 }
 
+pub struct FileDiff {
+    path: String,
+    a: Option<Path>,
+    b: Option<Path>,
+    binary: bool,
+    hunks: Vec<Hunk>,
+}
+
+#[graphql_object(context = Context)]
+impl FileDiff {
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn from(&self) -> Option<Path> {
+        self.a.clone()
+    }
+
+    fn to(&self) -> Option<Path> {
+        self.b.clone()
+    }
+
+    fn binary(&self) -> bool {
+        self.binary
+    }
+
+    fn hunks(&self) -> Vec<Hunk> {
+        self.hunks.clone()
+    }
+}
+
+#[derive(Clone)]
+pub struct Hunk {
+    header: String,
+    old_start: i32,
+    old_lines: i32,
+    new_start: i32,
+    new_lines: i32,
+    lines: Vec<DiffLine>,
+}
+
+#[graphql_object(context = Context)]
+impl Hunk {
+    fn header(&self) -> &str {
+        &self.header
+    }
+
+    fn old_start(&self) -> i32 {
+        self.old_start
+    }
+
+    fn old_lines(&self) -> i32 {
+        self.old_lines
+    }
+
+    fn new_start(&self) -> i32 {
+        self.new_start
+    }
+
+    fn new_lines(&self) -> i32 {
+        self.new_lines
+    }
+
+    fn lines(&self) -> Vec<DiffLine> {
+        self.lines.clone()
+    }
+}
+
+#[derive(Clone)]
+pub struct DiffLine {
+    origin: char,
+    content: String,
+    old_line: Option<i32>,
+    new_line: Option<i32>,
+}
+
+#[graphql_object(context = Context)]
+impl DiffLine {
+    fn origin(&self) -> String {
+        self.origin.to_string()
+    }
+
+    fn content(&self) -> &str {
+        &self.content
+    }
+
+    fn old_line(&self) -> Option<i32> {
+        self.old_line
+    }
+
+    fn new_line(&self) -> Option<i32> {
+        self.new_line
+    }
+}
+
 impl Revision {
     fn files_or_dirs(
         &self,
@@ -139,6 +560,88 @@ impl Revision {
         }
         Ok(Some(ws))
     }
+
+    /// Search the filtered tree for `string`: the files found, and the cursor to resume from
+    /// when `options` cut the results short.
+    fn search_page(
+        &self,
+        string: &str,
+        options: &josh_search::SearchOptions,
+        context: &Context,
+    ) -> FieldResult<(Vec<SearchResult>, Option<String>)> {
+        let transaction = context.transaction.lock().unwrap();
+        let odb = transaction.odb()?;
+        let tree = CommitData::read(&odb, self.commit_id)?.tree_id()?;
+
+        let x = filter::apply(&transaction, self.filter, Rewrite::from_tree(tree))?;
+
+        // The trigram index is experimental; without it every file in scope is read, so
+        // results are identical, just slower.
+        let index_tree = if filter::experimental_features_enabled() {
+            let ifilterobj = filter::parse(":SQUASH:INDEX")?;
+            Some(filter::apply(&transaction, ifilterobj, x.clone())?.tree_id())
+        } else {
+            None
+        };
+
+        let page = josh_search::search(&odb, index_tree, x.tree_id(), string, options)?;
+
+        let mut r = vec![];
+        for file in page.files {
+            let matches = file
+                .lines
+                .into_iter()
+                .map(|line| SearchMatch {
+                    line: line.number as i32,
+                    text: line.text,
+                    context: !line.is_match,
+                })
+                .collect();
+            let path = Path {
+                path: std::path::PathBuf::from(file.path),
+                commit_id: self.commit_id,
+                filter: self.filter,
+                tree: x.tree_id(),
+            };
+            r.push(SearchResult {
+                path,
+                matches,
+                cursor: file.cursor,
+            });
+        }
+        Ok((r, page.next))
+    }
+
+    /// Look `symbol` up in the symbol index of the filtered tree with `lookup`.
+    fn symbol_locations(
+        &self,
+        symbol: &str,
+        context: &Context,
+        lookup: fn(
+            &dyn josh_search::Objects,
+            git2::Oid,
+            &str,
+        ) -> anyhow::Result<Vec<josh_search::SymbolLocation>>,
+    ) -> FieldResult<Vec<SymbolMatch>> {
+        let transaction = context.transaction.lock().unwrap();
+        let odb = transaction.odb()?;
+        let tree = CommitData::read(&odb, self.commit_id)?.tree_id()?;
+        let x = filter::apply(&transaction, self.filter, Rewrite::from_tree(tree))?;
+        let index = filter::apply(&transaction, filter::parse(":SYMBOLS")?, x.clone())?;
+
+        Ok(lookup(&odb, index.tree_id(), symbol)?
+            .into_iter()
+            .map(|location| SymbolMatch {
+                path: Path {
+                    path: std::path::PathBuf::from(location.path),
+                    commit_id: self.commit_id,
+                    filter: self.filter,
+                    tree: x.tree_id(),
+                },
+                line: location.line as i32,
+            })
+            .collect())
+    }
 }
 
 #[graphql_object(context = Context)]
@@ -240,6 +743,7 @@ impl Revision {
         &self,
         limit: Option<i32>,
         offset: Option<i32>,
+        path: Option<String>,
         context: &Context,
     ) -> FieldResult<Vec<Revision>> {
         let limit = limit.unwrap_or(1) as usize;
@@ -248,40 +752,22 @@ impl Revision {
         let filter_commit = filtered_commit(&transaction, self.filter, self.commit_id)?;
 
         // First parents only, followed just far enough to fill the requested window.
-        let odb = transaction.odb()?;
+        let path = path.as_ref().map(std::path::Path::new);
         let mut ids = vec![];
-        let mut next = Some(filter_commit.id());
-        for i in 0..offset + limit {
-            let Some(id) = next else { break };
-            if i >= offset {
-                ids.push(id);
-            }
-            next = josh_core::git::read_parent_ids(&odb, id)?
-                .into_iter()
-                .next();
+        let mut seen = 0;
+        if limit == 0 {
+            return Ok(vec![]);
         }
-
-        let mut contained_in = self.commit_id;
-
-        {
-            for i in 0..ids.len() {
-                let orig =
-                    history::find_original(&transaction, self.filter, contained_in, ids[i], true)?;
-
-                if orig != git2::Oid::ZERO_SHA1 {
-                    ids[i] = orig;
-                    contained_in = josh_core::git::read_parent_ids(&transaction.odb()?, ids[i])?
-                        .into_iter()
-                        .next()
-                        .unwrap_or(ids[i]);
-                } else {
-                    ids.truncate(i);
-                    break;
-                }
+        walk_history(&transaction, filter_commit.id(), path, None, |id| {
+            if seen >= offset {
+                ids.push(id);
             }
-        }
+            seen += 1;
+            seen < offset + limit
+        })?;
 
-        Ok(ids
+        let originals = first_parent_originals(&transaction, self.filter, self.commit_id, &ids)?;
+        Ok(originals
             .into_iter()
             .map(|id| Revision {
                 filter: self.filter,
@@ -290,6 +776,106 @@ impl Revision {
             .collect())
     }
 
+    fn history_connection(
+        &self,
+        first: Option<i32>,
+        after: Option<String>,
+        path: Option<String>,
+        first_parent: Option<bool>,
+        context: &Context,
+    ) -> FieldResult<RevisionConnection> {
+        let first = match first {
+            Some(n) => usize::try_from(n).map_err(|_| anyhow!("negative count: {}", n))?,
+            None => 10,
+        };
+        let first_parent = first_parent.unwrap_or(true);
+        let transaction = context.transaction.lock().unwrap();
+        let filter_commit = filtered_commit(&transaction, self.filter, self.commit_id)?;
+        let path = path.as_ref().map(std::path::Path::new);
+
+        // One commit past the page tells whether there is a next one.
+        let mut ids = vec![];
+        let edges: Vec<RevisionEdge> = if first_parent {
+            let after = after.as_deref().map(parse_history_cursor).transpose()?;
+            walk_history(
+                &transaction,
+                filter_commit.id(),
+                path,
+                after.map(|(filtered, _)| filtered),
+                |id| {
+                    ids.push(id);
+                    ids.len() <= first
+                },
+            )?;
+            let page = &ids[..ids.len().min(first)];
+
+            // Resume below the original of the cursor instead of searching from the tip again.
+            let contained_in = match after {
+                Some((_, original)) => {
+                    josh_core::git::read_parent_ids(&transaction.odb()?, original)?
+                        .into_iter()
+                        .next()
+                        .unwrap_or(git2::Oid::ZERO_SHA1)
+                }
+                None => self.commit_id,
+            };
+            let originals = first_parent_originals(&transaction, self.filter, contained_in, page)?;
+            page.iter()
+                .copied()
+                .zip(originals)
+                .map(|(id, original)| (history_cursor(id, original), original))
+                .collect::<Vec<_>>()
+        } else {
+            // Both walks continue from the frontiers in the cursor instead of from the tips.
+            let (start, resume) = match after.as_deref().map(parse_dag_history_cursor) {
+                Some(cursor) => {
+                    let (filtered, original) = cursor?;
+                    (filtered, Some(original))
+                }
+                None => (vec![filter_commit.id()], None),
+            };
+            let mut frontiers = vec![];
+            walk_dag_history(&transaction, &start, path, |id, frontier| {
+                ids.push(id);
+                frontiers.push(frontier);
+                ids.len() <= first
+            })?;
+            let page = &ids[..ids.len().min(first)];
+            let (originals, original_frontier) = history::find_originals_from(
+                &transaction,
+                self.filter,
+                self.commit_id,
+                page,
+                resume.as_deref(),
+            )?;
+            frontiers
+                .iter()
+                .zip(originals)
+                .map(|(frontier, original)| {
+                    (dag_history_cursor(frontier, &original_frontier), original)
+                })
+                .collect::<Vec<_>>()
+        }
+        .into_iter()
+        .filter(|(_, original)| !original.is_zero())
+        .map(|(cursor, original)| RevisionEdge {
+            cursor,
+            filter: self.filter,
+            commit_id: original,
+        })
+        .collect();
+        let has_next_page = ids.len() > first;
+        Ok(RevisionConnection {
+            page_info: PageInfo {
+                has_next_page,
+                has_previous_page: after.is_some(),
+                start_cursor: edges.first().map(|edge| edge.cursor.clone()),
+                end_cursor: edges.last().map(|edge| edge.cursor.clone()),
+            },
+            edges,
+        })
+    }
+
     fn files(
         &self,
         at: Option<String>,
@@ -367,6 +953,120 @@ impl Revision {
         Ok(Some(df))
     }
 
+    fn diff(
+        &self,
+        against: Option<String>,
+        paths: Option<Vec<String>>,
+        context_lines: Option<i32>,
+        context: &Context,
+    ) -> FieldResult<Vec<FileDiff>> {
+        let context_lines = match context_lines {
+            Some(n) => u32::try_from(n).map_err(|_| anyhow!("negative context: {}", n))?,
+            None => 3,
+        };
+        let transaction = context.transaction.lock().unwrap();
+        let filter_commit = filtered_commit(&transaction, self.filter, self.commit_id)?;
+        let odb = transaction.odb()?;
+
+        // Without `against` the commit is compared with its (filtered) first parent, like
+        // `changedFiles`; otherwise with `against` seen through the same filter.
+        let (base_id, base_tree) = match against {
+            Some(against) => {
+                let id = match git2::Oid::from_str(&against) {
+                    Ok(id) => id,
+                    Err(_) => transaction
+                        .rev_parse(&against)?
+                        .ok_or_else(|| anyhow!("no such revision: {}", against))?,
+                };
+                let filtered = filter::apply_to_commit(self.filter, id, &transaction)?;
+                let tree = if filtered.is_zero() {
+                    git2::Oid::ZERO_SHA1
+                } else {
+                    josh_core::git::read_tree_id(&odb, filtered)?
+                };
+                (id, tree)
+            }
+            None => match filter_commit.first_parent_id() {
+                Some(parent) => (parent, josh_core::git::read_tree_id(&odb, parent)?),
+                None => (git2::Oid::ZERO_SHA1, git2::Oid::ZERO_SHA1),
+            },
+        };
+        let filter_tree_id = filter_commit.tree_id()?;
+
+        let selected = |path: &str| match &paths {
+            Some(paths) => paths
+                .iter()
+                .map(|p| p.trim_end_matches('/'))
+                .any(|p| p.is_empty() || path == p || path.starts_with(&format!("{}/", p))),
+            None => true,
+        };
+        let side = |tree: git2::Oid, commit_id: git2::Oid, path: &std::path::Path| {
+            let blob = if tree.is_zero() {
+                None
+            } else {
+                blob_at(&transaction, &odb, tree, path)?
+            };
+            anyhow::Ok(blob.map(|blob| {
+                (
+                    blob,
+                    Path {
+                        path: path.to_owned(),
+                        commit_id,
+                        filter: self.filter,
+                        tree,
+                    },
+                )
+            }))
+        };
+
+        let mut diffs = vec![];
+        for (path, _) in filter::tree::diff_paths(&odb, base_tree, filter_tree_id, "")? {
+            if !selected(&path) {
+                continue;
+            }
+            let file = std::path::Path::new(&path);
+            let a = side(base_tree, base_id, file)?;
+            let b = side(filter_tree_id, self.commit_id, file)?;
+            let old = blob_content(&odb, a.as_ref().map(|(blob, _)| *blob));
+            let new = blob_content(&odb, b.as_ref().map(|(blob, _)| *blob));
+            let patch = diff_buffers(&old, &new, file, context_lines)?;
+
+            let mut hunks = vec![];
+            for index in 0..patch.num_hunks() {
+                let (hunk, count) = patch.hunk(index)?;
+                let mut lines = vec![];
+                for line in 0..count {
+                    let line = patch.line_in_hunk(index, line)?;
+                    let content = String::from_utf8_lossy(line.content());
+                    lines.push(DiffLine {
+                        origin: line.origin(),
+                        content: content.strip_suffix('\n').unwrap_or(&content).to_string(),
+                        old_line: line.old_lineno().map(|n| n as i32),
+                        new_line: line.new_lineno().map(|n| n as i32),
+                    });
+                }
+                hunks.push(Hunk {
+                    header: String::from_utf8_lossy(hunk.header())
+                        .trim_end()
+                        .to_string(),
+                    old_start: hunk.old_start() as i32,
+                    old_lines: hunk.old_lines() as i32,
+                    new_start: hunk.new_start() as i32,
+                    new_lines: hunk.new_lines() as i32,
+                    lines,
+                });
+            }
+            diffs.push(FileDiff {
+                path,
+                a: a.map(|(_, path)| path),
+                b: b.map(|(_, path)| path),
+                binary: patch.delta().flags().is_binary(),
+                hunks,
+            });
+        }
+        Ok(diffs)
+    }
+
     fn file(&self, path: String, context: &Context) -> FieldResult<Option<Path>> {
         let transaction = context.transaction.lock().unwrap();
         let path = std::path::Path::new(&path).to_owned();
@@ -426,65 +1126,295 @@ impl Revision {
         }
     }
 
-    fn warnings(&self, context: &Context) -> FieldResult<Option<Vec<Warning>>> {
-        let transaction = context.transaction.lock().unwrap();
-        let commit = CommitData::read(&transaction.odb()?, self.commit_id)?;
+    fn documents(
+        &self,
+        glob: String,
+        format: Option<String>,
+        pointer: Option<String>,
+        context: &Context,
+    ) -> FieldResult<Vec<DocumentMatch>> {
+        let transaction = context.transaction.lock().unwrap();
+        let odb = transaction.odb()?;
+        let tree = CommitData::read(&odb, self.commit_id)?.tree_id()?;
+        let tree = filter::apply(&transaction, self.filter, Rewrite::from_tree(tree))?.tree_id();
+
+        let documents = find_documents(
+            &transaction,
+            tree,
+            &glob,
+            format.as_deref(),
+            pointer.as_deref(),
+        )?;
+
+        Ok(documents
+            .into_iter()
+            .map(|found| DocumentMatch {
+                path: Path {
+                    path: found.path,
+                    commit_id: self.commit_id,
+                    filter: self.filter,
+                    tree,
+                },
+                id: found.id,
+                value: found.value,
+            })
+            .collect())
+    }
+
+    fn warnings(&self, context: &Context) -> FieldResult<Option<Vec<Warning>>> {
+        let transaction = context.transaction.lock().unwrap();
+        let commit = CommitData::read(&transaction.odb()?, self.commit_id)?;
+
+        let warnings = filter::compute_warnings(&transaction, self.filter, commit.tree_id()?)
+            .into_iter()
+            .map(|text| Warning { text })
+            .collect();
+
+        Ok(Some(warnings))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        string: String,
+        regex: Option<bool>,
+        case_sensitive: Option<bool>,
+        include: Option<Vec<String>>,
+        exclude: Option<Vec<String>>,
+        context_before: Option<i32>,
+        context_after: Option<i32>,
+        context: &Context,
+    ) -> FieldResult<Option<Vec<SearchResult>>> {
+        let options = search_options(
+            regex,
+            case_sensitive,
+            include,
+            exclude,
+            context_before,
+            context_after,
+        )?;
+        let (results, _) = self.search_page(&string, &options, context)?;
+        Ok(Some(results))
+    }
+
+    // `search` a page at a time: up to `first` matching lines, resumed after the cursor of the
+    // last file of the previous page.
+    #[allow(clippy::too_many_arguments)]
+    fn search_connection(
+        &self,
+        string: String,
+        regex: Option<bool>,
+        case_sensitive: Option<bool>,
+        include: Option<Vec<String>>,
+        exclude: Option<Vec<String>>,
+        context_before: Option<i32>,
+        context_after: Option<i32>,
+        first: Option<i32>,
+        after: Option<String>,
+        context: &Context,
+    ) -> FieldResult<SearchConnection> {
+        let first = match first {
+            Some(n) => usize::try_from(n).map_err(|_| anyhow!("negative count: {}", n))?,
+            None => 10,
+        };
+        let options = josh_search::SearchOptions {
+            max_results: Some(first),
+            cursor: after.clone(),
+            ..search_options(
+                regex,
+                case_sensitive,
+                include,
+                exclude,
+                context_before,
+                context_after,
+            )?
+        };
+        let (edges, next) = self.search_page(&string, &options, context)?;
+        Ok(SearchConnection {
+            page_info: PageInfo {
+                has_next_page: next.is_some(),
+                has_previous_page: after.is_some(),
+                start_cursor: edges.first().map(|edge| edge.cursor.clone()),
+                end_cursor: edges.last().map(|edge| edge.cursor.clone()),
+            },
+            edges,
+        })
+    }
+
+    fn search_history(
+        &self,
+        string: String,
+        first: Option<i32>,
+        context: &Context,
+    ) -> FieldResult<Vec<HistoryMatch>> {
+        let first = first
+            .map(|n| usize::try_from(n).map_err(|_| anyhow!("negative count: {}", n)))
+            .transpose()?;
+        let transaction = context.transaction.lock().unwrap();
+        let odb = transaction.odb()?;
+        let head = filter::apply_to_commit(self.filter, self.commit_id, &transaction)?;
+
+        // As with search, the index only saves reading files that cannot have changed.
+        let ifilterobj = filter::experimental_features_enabled()
+            .then(|| filter::parse(":SQUASH:INDEX"))
+            .transpose()?;
+        let mut index = |tree: git2::Oid| -> anyhow::Result<Option<git2::Oid>> {
+            let Some(ifilterobj) = ifilterobj else {
+                return Ok(None);
+            };
+            Ok(Some(
+                filter::apply(&transaction, ifilterobj, Rewrite::from_tree(tree))?.tree_id(),
+            ))
+        };
+        let mut walk = objects::HistoryWalk::new(&odb, |oid| {
+            cache::compute_sequence_number(&transaction, oid)
+        });
+        walk.push(head)?;
+        let matches = josh_search::search_history(&odb, walk, &string, &mut index, first)?;
+
+        let filtered: Vec<git2::Oid> = matches.iter().map(|m| m.commit).collect();
+        let originals =
+            history::find_originals(&transaction, self.filter, self.commit_id, &filtered)?;
+        let mut r = vec![];
+        for (m, commit_id) in matches.into_iter().zip(originals) {
+            let changes = m
+                .changes
+                .into_iter()
+                .map(|change| OccurrenceChange {
+                    path: change.path,
+                    before: change.before as i32,
+                    after: change.after as i32,
+                })
+                .collect();
+            r.push(HistoryMatch {
+                rev: Revision {
+                    filter: self.filter,
+                    commit_id,
+                },
+                changes,
+            });
+        }
+        Ok(r)
+    }
+
+    fn definitions(&self, symbol: String, context: &Context) -> FieldResult<Vec<SymbolMatch>> {
+        self.symbol_locations(&symbol, context, josh_search::definitions)
+    }
+
+    fn references(&self, symbol: String, context: &Context) -> FieldResult<Vec<SymbolMatch>> {
+        self.symbol_locations(&symbol, context, josh_search::references)
+    }
+}
+
+pub struct SymbolMatch {
+    path: Path,
+    line: i32,
+}
+
+#[graphql_object(context = Context)]
+impl SymbolMatch {
+    pub fn path(&self) -> Path {
+        self.path.clone()
+    }
+    pub fn line(&self) -> i32 {
+        self.line
+    }
+}
+
+pub struct HistoryMatch {
+    rev: Revision,
+    changes: Vec<OccurrenceChange>,
+}
 
-        let warnings = filter::compute_warnings(&transaction, self.filter, commit.tree_id()?)
-            .into_iter()
-            .map(|text| Warning { text })
-            .collect();
+#[graphql_object(context = Context)]
+impl HistoryMatch {
+    pub fn rev(&self) -> Revision {
+        Revision {
+            filter: self.rev.filter,
+            commit_id: self.rev.commit_id,
+        }
+    }
+    pub fn changes(&self) -> Vec<OccurrenceChange> {
+        self.changes.clone()
+    }
+}
 
-        Ok(Some(warnings))
+#[derive(Clone)]
+pub struct OccurrenceChange {
+    path: String,
+    before: i32,
+    after: i32,
+}
+
+#[graphql_object(context = Context)]
+impl OccurrenceChange {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn before(&self) -> i32 {
+        self.before
     }
+    pub fn after(&self) -> i32 {
+        self.after
+    }
+}
 
-    fn search(&self, string: String, context: &Context) -> FieldResult<Option<Vec<SearchResult>>> {
-        let transaction = context.transaction.lock().unwrap();
-        let odb = transaction.odb()?;
-        let tree = CommitData::read(&odb, self.commit_id)?.tree_id()?;
+pub struct RevisionConnection {
+    edges: Vec<RevisionEdge>,
+    page_info: PageInfo,
+}
 
-        let x = filter::apply(&transaction, self.filter, Rewrite::from_tree(tree))?;
+#[graphql_object(context = Context)]
+impl RevisionConnection {
+    pub fn edges(&self) -> Vec<RevisionEdge> {
+        self.edges.clone()
+    }
+    pub fn page_info(&self) -> PageInfo {
+        self.page_info.clone()
+    }
+}
 
-        // The trigram index is experimental; without it every file is a candidate and
-        // search_matches does all the filtering, so results are identical, just slower.
-        let candidates = if filter::experimental_features_enabled() {
-            let ifilterobj = filter::parse(":SQUASH:INDEX")?;
-            let index_tree = filter::apply(&transaction, ifilterobj, x.clone())?;
-            josh_search::search_candidates(&odb, index_tree.tree_id(), x.tree_id(), &string)?
-        } else {
-            let mut scan = vec![];
-            objects::walk_tree_preorder(&odb, x.tree_id(), &mut |parent, entry| {
-                if !entry.mode.is_tree()
-                    && !entry.mode.is_commit()
-                    && let Ok(name) = std::str::from_utf8(entry.filename)
-                {
-                    let separator = if parent.is_empty() { "" } else { "/" };
-                    scan.push(format!("{}{}{}", parent, separator, name));
-                }
-                Ok(())
-            })?;
-            scan
-        };
-        let results = josh_search::search_matches(&odb, x.tree_id(), &string, &candidates)?;
+#[derive(Clone)]
+pub struct RevisionEdge {
+    cursor: String,
+    filter: filter::Filter,
+    commit_id: git2::Oid,
+}
 
-        let mut r = vec![];
-        for m in results {
-            let mut matches = vec![];
-            for l in m.1 {
-                matches.push(SearchMatch {
-                    line: l.0 as i32,
-                    text: l.1,
-                });
-            }
-            let path = Path {
-                path: std::path::PathBuf::from(m.0),
-                commit_id: self.commit_id,
-                filter: self.filter,
-                tree: x.tree_id(),
-            };
-            r.push(SearchResult { path, matches });
+#[graphql_object(context = Context)]
+impl RevisionEdge {
+    pub fn cursor(&self) -> &str {
+        &self.cursor
+    }
+    pub fn node(&self) -> Revision {
+        Revision {
+            filter: self.filter,
+            commit_id: self.commit_id,
         }
-        Ok(Some(r))
+    }
+}
+
+#[derive(Clone)]
+pub struct PageInfo {
+    has_next_page: bool,
+    has_previous_page: bool,
+    start_cursor: Option<String>,
+    end_cursor: Option<String>,
+}
+
+#[graphql_object(context = Context)]
+impl PageInfo {
+    pub fn has_next_page(&self) -> bool {
+        self.has_next_page
+    }
+    pub fn has_previous_page(&self) -> bool {
+        self.has_previous_page
+    }
+    pub fn start_cursor(&self) -> Option<String> {
+        self.start_cursor.clone()
+    }
+    pub fn end_cursor(&self) -> Option<String> {
+        self.end_cursor.clone()
     }
 }
 
@@ -507,10 +1437,38 @@ pub struct Path {
     tree: git2::Oid,
 }
 
+/// Consecutive lines of a file last changed by the same filtered commit.
+pub struct BlameRange {
+    start_line: i32,
+    end_line: i32,
+    filtered: git2::Oid,
+    filter: filter::Filter,
+    commit_id: git2::Oid,
+}
+
+#[graphql_object(context = Context)]
+impl BlameRange {
+    fn start_line(&self) -> i32 {
+        self.start_line
+    }
+
+    fn end_line(&self) -> i32 {
+        self.end_line
+    }
+
+    fn rev(&self) -> Revision {
+        Revision {
+            filter: self.filter,
+            commit_id: self.commit_id,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SearchMatch {
     line: i32,
     text: String,
+    context: bool,
 }
 
 #[graphql_object(context = Context)]
@@ -521,11 +1479,31 @@ impl SearchMatch {
     pub fn text(&self) -> String {
         self.text.clone()
     }
+    pub fn context(&self) -> bool {
+        self.context
+    }
+}
+
+pub struct SearchConnection {
+    edges: Vec<SearchResult>,
+    page_info: PageInfo,
+}
+
+#[graphql_object(context = Context)]
+impl SearchConnection {
+    pub fn edges(&self) -> Vec<SearchResult> {
+        self.edges.clone()
+    }
+    pub fn page_info(&self) -> PageInfo {
+        self.page_info.clone()
+    }
 }
 
+#[derive(Clone)]
 pub struct SearchResult {
     path: Path,
     matches: Vec<SearchMatch>,
+    cursor: String,
 }
 
 #[graphql_object(context = Context)]
@@ -536,6 +1514,9 @@ impl SearchResult {
     pub fn matches(&self) -> Vec<SearchMatch> {
         self.matches.clone()
     }
+    pub fn cursor(&self) -> &str {
+        &self.cursor
+    }
 }
 
 pub fn linecount(
@@ -577,7 +1558,7 @@ impl Markers {
         let tree = if let Some(id) = transaction_mirror.resolve_ref(&refname)? {
             CommitData::read(&odb, id)?.tree_id()?
         } else {
-            filter::tree::empty_id()
+            filter::tree::empty_id(odb.object_hash())
         };
 
         let commit = self.commit_id.to_string();
@@ -630,7 +1611,7 @@ impl Markers {
         let mtree = if let Some(id) = transaction_mirror.resolve_ref(&refname)? {
             CommitData::read(&odb, id)?.tree_id()?
         } else {
-            filter::tree::empty_id()
+            filter::tree::empty_id(odb.object_hash())
         };
 
         let commit = self.commit_id.to_string();
@@ -644,7 +1625,7 @@ impl Markers {
         .flatten()
         .filter(|entry| entry.mode.is_tree())
         .map(|entry| objects::git2_oid(&entry.oid))
-        .unwrap_or_else(filter::tree::empty_id);
+        .unwrap_or_else(|| filter::tree::empty_id(odb.object_hash()));
 
         let mtree = if self.filter.is_nop() {
             mtree
@@ -746,6 +1727,42 @@ impl Path {
         })
     }
 
+    fn blame(&self, context: &Context) -> FieldResult<Vec<BlameRange>> {
+        let transaction = context.transaction.lock().unwrap();
+        let head = filter::apply_to_commit(self.filter, self.commit_id, &transaction)?;
+        let owners = blame_lines(&transaction, head, &self.path)?;
+
+        let mut commits = owners.clone();
+        commits.sort();
+        commits.dedup();
+        let originals: std::collections::HashMap<_, _> = commits
+            .iter()
+            .copied()
+            .zip(history::find_originals(
+                &transaction,
+                self.filter,
+                self.commit_id,
+                &commits,
+            )?)
+            .collect();
+
+        let mut ranges: Vec<BlameRange> = vec![];
+        for (index, owner) in owners.iter().enumerate() {
+            let line = index as i32 + 1;
+            match ranges.last_mut() {
+                Some(range) if range.filtered == *owner => range.end_line = line,
+                _ => ranges.push(BlameRange {
+                    start_line: line,
+                    end_line: line,
+                    filtered: *owner,
+                    filter: self.filter,
+                    commit_id: originals[owner],
+                }),
+            }
+        }
+        Ok(ranges)
+    }
+
     fn toml(&self, context: &Context) -> FieldResult<Document> {
         self.serialize_to_serde_value(context, |blob| {
             toml::de::from_str::<serde_json::Value>(blob)
@@ -836,6 +1853,108 @@ impl Document {
     }
 }
 
+/// A file found by `find_documents`, with its parsed (and projected) content.
+pub struct FoundDocument {
+    pub path: std::path::PathBuf,
+    pub id: git2::Oid,
+    pub value: serde_json::Value,
+}
+
+/// Parse `text` as a document of `format`: "toml", "json" or "yaml".
+fn parse_document(format: &str, text: &str) -> anyhow::Result<serde_json::Value> {
+    Ok(match format {
+        "toml" => toml::de::from_str(text)?,
+        "json" => serde_json::from_str(text)?,
+        "yaml" | "yml" => serde_yaml::from_str(text)?,
+        _ => return Err(anyhow!("unknown document format: {}", format)),
+    })
+}
+
+/// Parse the files in `tree` whose path matches `glob` and, with `pointer`, project each to the
+/// value at that JSON pointer, leaving out files without one. Without `format` it is taken from
+/// the file extension. `glob` matches like a `::` pattern filter: `*` does not match `/`, `**`
+/// matches any number of directories, and neither matches names starting with a dot. Files
+/// with no known format or that don't parse are left out, so one stray file doesn't fail the
+/// whole lookup.
+pub fn find_documents(
+    transaction: &cache::Transaction,
+    tree: git2::Oid,
+    glob: &str,
+    format: Option<&str>,
+    pointer: Option<&str>,
+) -> anyhow::Result<Vec<FoundDocument>> {
+    let pattern = tree::CompiledPattern::compile(glob)?;
+    let odb = transaction.odb()?;
+
+    // Only the directory named by the pattern's literal part needs to be listed.
+    let literal = &glob[..glob.find(['*', '?', '[']).unwrap_or(glob.len())];
+    let at = literal.rfind('/').map(|end| literal[..end].to_string());
+    if let Some(at) = &at {
+        match tree::get_path_entry(transaction, &odb, tree, std::path::Path::new(at))? {
+            Some(entry) if entry.mode.is_tree() => {}
+            _ => return Ok(vec![]),
+        }
+    }
+
+    let mut documents = vec![];
+    for path in find_paths(transaction, &odb, tree, at, None, git2::ObjectType::Blob)? {
+        let matches = path
+            .to_str()
+            .is_some_and(|path| pattern.full.matches_with(path, tree::PATTERN_MATCH_OPTIONS));
+        if !matches {
+            continue;
+        }
+
+        let Some(format) = format.or_else(|| path.extension().and_then(|e| e.to_str())) else {
+            continue;
+        };
+        let entry = tree::get_path_entry(transaction, &odb, tree, &path)?
+            .ok_or_else(|| anyhow!("no such path: {}", path.display()))?;
+        let id = objects::git2_oid(&entry.oid);
+        let blob = tree::blob_bytes(&odb, id).ok_or_else(|| anyhow!("not a blob: {}", id))?;
+        let parsed = std::str::from_utf8(&blob)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| parse_document(format, text));
+        let value = match parsed {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::debug!("skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        let value = match pointer {
+            Some(pointer) => match value.pointer(pointer) {
+                Some(value) => value.to_owned(),
+                None => continue,
+            },
+            None => value,
+        };
+        documents.push(FoundDocument { path, id, value });
+    }
+    Ok(documents)
+}
+
+pub struct DocumentMatch {
+    path: Path,
+    id: git2::Oid,
+    value: serde_json::Value,
+}
+
+#[graphql_object(context = Context)]
+impl DocumentMatch {
+    fn path(&self) -> Path {
+        self.path.clone()
+    }
+
+    fn document(&self) -> Document {
+        Document {
+            id: self.id,
+            value: self.value.clone(),
+        }
+    }
+}
+
 pub struct Reference {
     refname: String,
 }
@@ -863,8 +1982,106 @@ impl Reference {
     }
 }
 
+/// A ref of the upstream mirror that was created, moved or deleted by a fetch. `refname` is the
+/// full mirror ref, namespace included.
+#[derive(Clone, Debug)]
+pub struct RefChange {
+    pub refname: String,
+    pub old: Option<git2::Oid>,
+    pub new: Option<git2::Oid>,
+}
+
+pub type RefChanges = std::pin::Pin<Box<dyn futures::Stream<Item = RefChange> + Send>>;
+
+/// The ref changes a subscription sees, and how to open the transaction each one is filtered
+/// in: a fresh one per change, so a long-lived subscription neither holds a transaction nor
+/// reads through a stale one.
+pub struct RefChangeFeed {
+    pub changes: RefChanges,
+    pub open_transaction:
+        std::sync::Arc<dyn Fn() -> anyhow::Result<cache::Transaction> + Send + Sync>,
+}
+
+pub struct RefUpdate {
+    name: String,
+    filter: filter::Filter,
+    upstream: Option<git2::Oid>,
+    previous_upstream: Option<git2::Oid>,
+    filtered: Option<git2::Oid>,
+    previous_filtered: Option<git2::Oid>,
+}
+
+#[graphql_object(context = Context)]
+impl RefUpdate {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn upstream(&self) -> Option<String> {
+        self.upstream.map(|id| id.to_string())
+    }
+
+    fn previous_upstream(&self) -> Option<String> {
+        self.previous_upstream.map(|id| id.to_string())
+    }
+
+    fn filtered(&self) -> Option<String> {
+        self.filtered.map(|id| id.to_string())
+    }
+
+    fn previous_filtered(&self) -> Option<String> {
+        self.previous_filtered.map(|id| id.to_string())
+    }
+
+    fn rev(&self) -> Option<Revision> {
+        self.upstream.map(|commit_id| Revision {
+            filter: self.filter,
+            commit_id,
+        })
+    }
+}
+
+/// Turn a mirror ref change into an update of the filtered projection, or `None` when the ref is
+/// outside `ns`, does not match `pattern` or the filtered commit stayed the same.
+fn ref_update(
+    transaction: &cache::Transaction,
+    ns: &str,
+    pattern: &glob::Pattern,
+    filter: filter::Filter,
+    change: &RefChange,
+) -> anyhow::Result<Option<RefUpdate>> {
+    let Some(name) = change.refname.strip_prefix(ns) else {
+        return Ok(None);
+    };
+    if !pattern.matches(name) {
+        return Ok(None);
+    }
+
+    let apply = |id: Option<git2::Oid>| -> anyhow::Result<Option<git2::Oid>> {
+        Ok(match id {
+            Some(id) => Some(filter::apply_to_commit(filter, id, transaction)?)
+                .filter(|filtered| !filtered.is_zero()),
+            None => None,
+        })
+    };
+    let filtered = apply(change.new)?;
+    let previous_filtered = apply(change.old)?;
+    if filtered == previous_filtered {
+        return Ok(None);
+    }
+
+    Ok(Some(RefUpdate {
+        name: name.to_string(),
+        filter,
+        upstream: change.new,
+        previous_upstream: change.old,
+        filtered,
+        previous_filtered,
+    }))
+}
+
 type ToPushSet = std::sync::Arc<
-    std::sync::Mutex<std::collections::HashSet<(git2::Oid, String, Option<String>)>>,
+    std::sync::Mutex<std::collections::HashSet<(git2::Oid, String, Option<String>, bool)>>,
 >;
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -904,6 +2121,10 @@ pub struct Context {
     >,
     pub to_push: ToPushSet,
     pub fetch_state: FetchState,
+    // Only set where subscriptions are served; taken by the first subscription.
+    pub ref_changes: std::sync::Mutex<Option<RefChangeFeed>>,
+    // The authenticated user of the request, the author of commits made without one.
+    pub user: Option<String>,
 }
 
 impl juniper::Context for Context {}
@@ -915,6 +2136,10 @@ pub struct Repository {
 
 pub struct RepositoryMut {}
 
+pub struct RepositorySubscription {
+    ns: String,
+}
+
 fn marker_path(commit: &str, topic: &str) -> std::path::PathBuf {
     std::path::Path::new(topic)
         .join("~")
@@ -930,10 +2155,97 @@ struct MarkersInput {
     data: Vec<String>,
 }
 
+// A file to write in a `commit` mutation; without `content` the file is deleted.
+#[derive(juniper::GraphQLInputObject)]
+struct FileEditInput {
+    path: String,
+    content: Option<String>,
+}
+
+// The options of a proxy push (`git push -o ...`), for a `commit` mutation.
+#[derive(juniper::GraphQLInputObject, Default)]
+struct PushOptionsInput {
+    merge: Option<bool>,
+    allow_orphans: Option<bool>,
+    edit: Option<bool>,
+    create: Option<bool>,
+    force: Option<bool>,
+    base: Option<String>,
+    author: Option<String>,
+}
+
+impl From<PushOptionsInput> for josh_changes::PushOptions {
+    fn from(input: PushOptionsInput) -> Self {
+        josh_changes::PushOptions {
+            merge: input.merge.unwrap_or_default(),
+            allow_orphans: input.allow_orphans.unwrap_or_default(),
+            edit: input.edit.unwrap_or_default(),
+            create: input.create.unwrap_or_default(),
+            force: input.force.unwrap_or_default(),
+            base: input.base,
+            author: input.author,
+        }
+    }
+}
+
+// The author of the commit of a `commit` mutation.
+#[derive(juniper::GraphQLInputObject)]
+struct AuthorInput {
+    name: String,
+    email: String,
+}
+
+/// The path of a file edit, relative to the root of the tree: `/`-separated names, none of them
+/// empty, `.`, `..` or `.git`, so the edit can't produce a tree git would refuse.
+fn edit_path(path: &str) -> anyhow::Result<std::path::PathBuf> {
+    let invalid = |reason: &str| anyhow!("invalid path {:?}: {}", path, reason);
+    if path.is_empty() {
+        return Err(invalid("empty"));
+    }
+    if path.starts_with('/') {
+        return Err(invalid("absolute"));
+    }
+    for name in path.split('/') {
+        match name {
+            "" | "." => return Err(invalid("empty component")),
+            ".." => return Err(invalid("parent component")),
+            _ if name.eq_ignore_ascii_case(".git") => return Err(invalid(".git component")),
+            _ if name.contains('\0') => return Err(invalid("NUL character")),
+            _ => {}
+        }
+    }
+    Ok(std::path::PathBuf::from(path))
+}
+
+pub struct CommitResult {
+    commit_id: git2::Oid,
+    warnings: Vec<String>,
+}
+
+#[graphql_object(context = Context)]
+impl CommitResult {
+    // The commit as pushed upstream.
+    pub fn rev(&self) -> Revision {
+        Revision {
+            filter: filter::Filter::new(),
+            commit_id: self.commit_id,
+        }
+    }
+    // What a push to the proxy would warn about, such as filters matching nothing.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+}
+
 fn format_marker(input: &str) -> anyhow::Result<String> {
     let value = serde_json::from_str::<serde_json::Value>(input)?;
     let line = serde_json::to_string(&value)?;
-    let hash = josh_core::objects::hash_blob(line.as_bytes());
+    // Marker labels are part of the stored format, so they stay SHA-1 whatever the repository's
+    // object format.
+    let hash = josh_core::objects::hash_blob(
+        josh_core::objects::hash_kind(git2::ObjectFormat::Sha1),
+        line.as_bytes(),
+    );
     Ok(format!("{}:{}", &hash, &line))
 }
 
@@ -951,12 +2263,96 @@ impl RevMut {
             filtered_commit(&transaction, self.filter, git2::Oid::from_str(&self.at)?)?;
 
         if let Ok(mut to_push) = context.to_push.lock() {
-            to_push.insert((filter_commit.id(), target, repo));
+            to_push.insert((filter_commit.id(), target, repo, false));
         }
 
         Ok(true)
     }
 
+    fn commit(
+        &self,
+        message: String,
+        edits: Vec<FileEditInput>,
+        target: String,
+        author: Option<AuthorInput>,
+        options: Option<PushOptionsInput>,
+        context: &Context,
+    ) -> FieldResult<CommitResult> {
+        let options = josh_changes::PushOptions::from(options.unwrap_or_default());
+        // Without an author the commit is the authenticated user's, with the change author's
+        // email from the push options.
+        let signature = match (author, &context.user, &options.author) {
+            (Some(author), _, _) => git2::Signature::now(&author.name, &author.email)?,
+            (None, Some(user), Some(email)) => git2::Signature::now(user, email)?,
+            (None, _, _) => {
+                return Err(anyhow!("commit mutation needs an author with an email").into());
+            }
+        };
+        let edits = edits
+            .into_iter()
+            .map(|edit| Ok((edit_path(&edit.path)?, edit)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let transaction = context.transaction.lock().unwrap();
+        let transaction_mirror = context.transaction_mirror.lock().unwrap();
+        let odb = transaction.odb()?;
+
+        let base = filtered_commit(&transaction, self.filter, git2::Oid::from_str(&self.at)?)?;
+        let base_tree = base.tree_id()?;
+        let mut tree_id = base_tree;
+        for (path, edit) in edits {
+            let existing = tree::get_path_entry(&transaction, &odb, tree_id, &path)?
+                .filter(|entry| !entry.mode.is_tree() && !entry.mode.is_commit());
+            let blob = match edit.content {
+                Some(content) => objects::write_blob(&odb, content.as_bytes())?,
+                None if existing.is_none() => {
+                    return Err(anyhow!("no such file: {}", edit.path).into());
+                }
+                None => git2::Oid::ZERO_SHA1,
+            };
+            // Edited files keep their mode, executable or not.
+            let mode = existing.map_or(0o0100644, |entry| entry.mode.value() as i32);
+            tree_id = tree::insert_oid(&odb, tree_id, &path, blob, mode)?;
+        }
+        if tree_id == base_tree {
+            return Err(anyhow!("the edits do not change any file").into());
+        }
+
+        let new_oid = objects::write_commit(
+            &odb,
+            tree_id,
+            &[base.id()],
+            &signature,
+            &signature,
+            &message,
+        )?;
+
+        // From here on this is what a push of `new_oid` to the proxy does.
+        let plan = josh_changes::plan_push(
+            &transaction,
+            &transaction_mirror,
+            self.filter,
+            &target,
+            base.id(),
+            new_oid,
+            &options,
+        )?;
+        let mut warnings = vec![];
+        for push_ref in &plan.refs {
+            let tree = CommitData::read(&odb, push_ref.oid)?.tree_id()?;
+            warnings.extend(filter::compute_warnings(&transaction, self.filter, tree));
+        }
+        if let Ok(mut to_push) = context.to_push.lock() {
+            for push_ref in plan.refs {
+                to_push.insert((push_ref.oid, push_ref.ref_name, None, plan.force));
+            }
+        }
+
+        Ok(CommitResult {
+            commit_id: plan.oid,
+            warnings,
+        })
+    }
+
     fn meta(&self, topic: String, add: Vec<MarkersInput>, context: &Context) -> FieldResult<bool> {
         if !self.filter.is_nop() {
             return Err(anyhow!("meta mutation for filtered revs is not implemented").into());
@@ -1084,6 +2480,49 @@ impl Repository {
     }
 }
 
+type RefUpdateStream =
+    std::pin::Pin<Box<dyn futures::Stream<Item = FieldResult<RefUpdate>> + Send>>;
+
+#[graphql_subscription(context = Context)]
+impl RepositorySubscription {
+    async fn ref_updates(
+        &self,
+        context: &Context,
+        pattern: Option<String>,
+        filter: Option<String>,
+    ) -> FieldResult<RefUpdateStream> {
+        let RefChangeFeed {
+            changes,
+            open_transaction,
+        } = context
+            .ref_changes
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| anyhow!("refUpdates(): not available"))?;
+        let pattern = glob::Pattern::new(&pattern.unwrap_or_else(|| "refs/heads/*".to_string()))?;
+        let filter = filter::parse(&filter.unwrap_or_else(|| ":/".to_string()))?;
+        let ns = self.ns.clone();
+
+        // Filtering blocks on the object database, so it runs off the async runtime.
+        let updates = changes.then(move |change| {
+            let open_transaction = open_transaction.clone();
+            let ns = ns.clone();
+            let pattern = pattern.clone();
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    let transaction = open_transaction()?;
+                    ref_update(&transaction, &ns, &pattern, filter, &change)
+                })
+                .await?
+            }
+        });
+        Ok(Box::pin(updates.filter_map(|update| {
+            futures::future::ready(update.map_err(Into::into).transpose())
+        })))
+    }
+}
+
 josh_core::regex_parsed!(
     UpstreamRef,
     r"refs/josh/upstream/.*[.]git/(?P<reference>refs/heads/.*)",
@@ -1097,6 +2536,8 @@ pub fn context(transaction: cache::Transaction, transaction_mirror: cache::Trans
         meta_add: std::sync::Arc::new(std::sync::Mutex::new(Default::default())),
         to_push: std::sync::Arc::new(std::sync::Mutex::new(Default::default())),
         fetch_state: FetchState::default(),
+        ref_changes: Default::default(),
+        user: None,
     }
 }
 
@@ -1114,7 +2555,7 @@ pub fn commit_schema(commit_id: git2::Oid) -> CommitSchema {
     )
 }
 
-pub type RepoSchema = juniper::RootNode<Repository, RepositoryMut, EmptySubscription<Context>>;
+pub type RepoSchema = juniper::RootNode<Repository, RepositoryMut, RepositorySubscription>;
 
 pub fn repo_schema(name: String, local: bool) -> RepoSchema {
     let ns = if local {
@@ -1123,8 +2564,11 @@ pub fn repo_schema(name: String, local: bool) -> RepoSchema {
         format!("refs/josh/upstream/{}.git/", josh_core::to_ns(&name))
     };
     RepoSchema::new(
-        Repository { name, ns },
+        Repository {
+            name,
+            ns: ns.clone(),
+        },
         RepositoryMut {},
-        EmptySubscription::new(),
+        RepositorySubscription { ns },
     )
 }
@@ -362,7 +362,7 @@ pub fn load_detail(sha: &str, scope: &josh_changes::ChangesRef) -> anyhow::Resul
         if let Ok(all) = josh_changes::list_changes(&transaction, scope) {
             if let Some(c) = all.iter().find(|c| c.id() == Some(cid.as_str())) {
                 let base = c.base();
-                if base != git2::Oid::ZERO_SHA1 {
+                if !base.is_zero() {
                     change.set_base(base);
                 }
             }
@@ -0,0 +1,315 @@
+//! The language features, as functions of the document text. Positions are byte offsets here;
+//! converting from and to LSP positions happens in [`offset_at`] and [`position_at`].
+
+use josh_core::filter::{self, Op, lint};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, NumberOrString, Position,
+    Range, TextEdit,
+};
+
+/// The byte offset of `position` in `text`. LSP columns count UTF-16 code units; positions past
+/// the end of a line or of the text are clamped.
+pub fn offset_at(text: &str, position: Position) -> usize {
+    let mut offset = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        if index == position.line as usize {
+            let mut units = 0;
+            for (i, c) in line.char_indices() {
+                if units >= position.character as usize || c == '\n' {
+                    return offset + i;
+                }
+                units += c.len_utf16();
+            }
+            return offset + line.len();
+        }
+        offset += line.len();
+    }
+    text.len()
+}
+
+/// The LSP position of the byte `offset` in `text`.
+pub fn position_at(text: &str, offset: usize) -> Position {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: line as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+fn range_of(text: &str, span: &std::ops::Range<usize>) -> Range {
+    Range {
+        start: position_at(text, span.start),
+        end: position_at(text, span.end),
+    }
+}
+
+/// The findings of `josh filter lint` for the document.
+pub fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    lint::lint(text)
+        .into_iter()
+        .map(|d| Diagnostic {
+            range: range_of(text, &d.span),
+            severity: Some(match d.severity {
+                lint::Severity::Error => DiagnosticSeverity::ERROR,
+                lint::Severity::Warning => DiagnosticSeverity::WARNING,
+            }),
+            code: Some(NumberOrString::String(d.code.to_string())),
+            source: Some("josh".to_string()),
+            message: d.message,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Markdown describing the innermost op at `offset`, and the range of that op.
+pub fn hover(text: &str, offset: usize) -> Option<(String, Range)> {
+    let span = filter::item_spans(text)
+        .into_iter()
+        .rfind(|span| span.start <= offset && offset < span.end)?;
+    let parsed = filter::parse(&text[span.clone()]).ok()?;
+    let op = match filter::to_op(parsed) {
+        // A single op like `:/a/b` may parse into a chain; describe what it starts with.
+        Op::Chain(filters) => filters.first().map(|f| filter::to_op(*f))?,
+        op => op,
+    };
+    let markdown = format!("```\n{}\n```\n\n{}", filter::spec(parsed), op_doc(&op));
+    Some((markdown, range_of(text, &span)))
+}
+
+/// One line on what `op` does, in the words of the filter reference.
+fn op_doc(op: &Op) -> &'static str {
+    match op {
+        Op::Meta(..) => "Applies the filter with the given options, e.g. `history` or `gpgsig`.",
+        Op::Nop => "Leaves the tree unchanged.",
+        Op::Empty => "Selects nothing: the result is the empty tree.",
+        Op::Fold => "Merges the trees of all ancestors into each commit.",
+        Op::Paths => "Replaces the content of every file with its path.",
+        Op::Adapt(_) => "Adapts the tree to the layout of another tool (experimental).",
+        Op::Link(_) => "Resolves `.link.josh` files into the linked repositories' content.",
+        Op::Unlink => "Removes the content of `.link.josh` links, keeping the link files.",
+        Op::Export => "Exports linked content back to the linked repositories (experimental).",
+        Op::Embed(_) => "Embeds the linked repository at the given path (experimental).",
+        Op::Squash(_) => {
+            "Squashes history: only the commits listed keep their identity, or none at all."
+        }
+        Op::Author(..) => "Rewrites the author of every commit.",
+        Op::Committer(..) => "Rewrites the committer of every commit.",
+        Op::Rev(_) => {
+            "Applies different filters to different parts of history. Arms are tried in order; \
+             `_` matches every commit."
+        }
+        Op::Prune => "Drops merge commits that do not change the tree of their first parent.",
+        Op::RegexReplace(_) => "Replaces regex matches in the content of every file.",
+        Op::Hook(_) => "Applies the filter a hook returns for each commit.",
+        Op::Index => "Replaces the tree with a trigram search index of it.",
+        Op::Symbols => "Replaces the tree with a symbol index of its definitions and references.",
+        Op::Invert => "Swaps the roles of input and output paths of the filter.",
+        Op::Insert(..) => "Inserts a file or tree with the given content at the path.",
+        Op::File(..) => "Selects a single file, optionally placing it at a different path.",
+        Op::Prefix(_) => "Moves the whole tree into the given directory.",
+        Op::Subdir(_) => "Selects the given directory and makes it the root of the tree.",
+        Op::Workspace(_) => {
+            "Applies the `workspace.josh` in the given directory, keeping the directory itself."
+        }
+        Op::Stored(_) => "Applies the filter stored in the given `.josh` file.",
+        Op::Starlark(..) => "Applies the filter computed by a Starlark script (experimental).",
+        Op::TreeId(..) => {
+            "Inserts a file at the path holding the tree id of the filter's result (experimental)."
+        }
+        Op::ObjectDeref(_) => {
+            "Keeps only the object whose id the file at the path holds, placed at that path."
+        }
+        Op::ObjectRef(_) => {
+            "Keeps only a file at the path holding the id of the object found there."
+        }
+        Op::Pattern(_) => {
+            "Selects the files or directories matching the pattern, keeping their paths."
+        }
+        Op::Blobs(_) => "Selects files by their content or attributes.",
+        Op::Normalize(_) => "Normalizes the content of text files; reversed on push.",
+        Op::Restore(_) => "Undoes content normalization, restoring the original bytes.",
+        Op::Message(..) => "Rewrites commit messages with a template.",
+        Op::Unapply(..) => "Reverses the filter against the given commit.",
+        Op::Compose(_) => {
+            "Combines the results of several filters. Each filter only sees the paths earlier \
+             ones have not taken; on conflicting output the later one wins."
+        }
+        Op::Chain(_) => "Applies the filters one after the other.",
+        Op::Subtract(..) => "Removes what the second filter selects from the first one's result.",
+        Op::Exclude(_) => "Removes the paths the filter selects.",
+        Op::Select(_) => "Keeps only the paths the filter selects, without moving them.",
+        Op::Pin(_) => "Keeps the selected paths at their state in the first parent commit.",
+        Op::Downstack(_) => {
+            "Rebuilds a change onto the given base, dropping unrelated commits below it."
+        }
+    }
+}
+
+/// The path typed before `offset`, if the cursor is in the source path of `:/dir` or `::file`:
+/// the byte offset the path starts at, and the path so far.
+pub fn path_prefix(text: &str, offset: usize) -> Option<(usize, &str)> {
+    let before = &text[..offset];
+    let start = before
+        .char_indices()
+        .rfind(|(_, c)| c.is_whitespace() || ":[](),=\"'!".contains(*c))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let token = &before[start..];
+    let head = &before[..start];
+    if head.ends_with("::") {
+        Some((start, token))
+    } else if head.ends_with(':') {
+        token.strip_prefix('/').map(|path| (start + 1, path))
+    } else {
+        None
+    }
+}
+
+/// Completions for the path at `offset` from the tree of HEAD of the repository containing
+/// `dir`. Directories complete with a trailing '/' so that typing can go on below them.
+pub fn complete_paths(
+    text: &str,
+    offset: usize,
+    dir: &std::path::Path,
+) -> anyhow::Result<Vec<CompletionItem>> {
+    let Some((start, path)) = path_prefix(text, offset) else {
+        return Ok(vec![]);
+    };
+    let (parent, partial) = path.rsplit_once('/').unwrap_or(("", path));
+    let replace = Range {
+        start: position_at(text, start + path.len() - partial.len()),
+        end: position_at(text, offset),
+    };
+
+    let repo = git2::Repository::discover(dir)?;
+    let head = josh_core::cache::RefStore::open(&repo)
+        .resolve("HEAD")?
+        .ok_or_else(|| anyhow::anyhow!("HEAD does not point at a commit"))?;
+    let head = repo.find_object(head, None)?.peel_to_commit()?.tree_id();
+    let odb = josh_core::memodb::Odb::new(
+        josh_core::memodb::MemOdb::new(
+            None,
+            josh_core::memodb::objects_dir(&repo),
+            josh_core::memodb::object_hash(&repo),
+        ),
+        repo.odb()?,
+    );
+    let tree = if parent.is_empty() {
+        head
+    } else {
+        match josh_core::objects::path_entry(&odb, head, std::path::Path::new(parent))? {
+            Some(entry) if entry.mode.is_tree() => josh_core::objects::git2_oid(&entry.oid),
+            _ => return Ok(vec![]),
+        }
+    };
+
+    let mut items = vec![];
+    for entry in josh_core::objects::read_tree_entries(&odb, tree)? {
+        let name = entry.filename.to_string();
+        if !name.starts_with(partial) {
+            continue;
+        }
+        let is_dir = entry.mode.is_tree();
+        let insert = if is_dir {
+            format!("{}/", name)
+        } else {
+            name.clone()
+        };
+        items.push(CompletionItem {
+            label: insert.clone(),
+            kind: Some(if is_dir {
+                CompletionItemKind::FOLDER
+            } else {
+                CompletionItemKind::FILE
+            }),
+            text_edit: Some(lsp_types::CompletionTextEdit::Edit(TextEdit {
+                range: replace,
+                new_text: insert,
+            })),
+            ..Default::default()
+        });
+    }
+    Ok(items)
+}
+
+/// The document printed by the pretty printer, as `josh` itself writes `.josh` files. `None`
+/// when it does not parse, or when it has comments other than the leading ones, which the
+/// printer would drop.
+pub fn format(text: &str) -> Option<String> {
+    let parsed = filter::parse(text).ok()?;
+    let comments = filter::get_comments(text).unwrap_or_default();
+    let comment_lines = |s: &str| {
+        s.lines()
+            .filter(|line| line.trim_start().starts_with('#'))
+            .count()
+    };
+    if comment_lines(text) != comment_lines(&comments) {
+        return None;
+    }
+    let mut formatted = comments;
+    if !formatted.is_empty() && !formatted.ends_with('\n') {
+        formatted.push('\n');
+    }
+    formatted.push_str(&filter::as_file(parsed, 0));
+    Some(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_count_utf16_units() {
+        let text = "a = :/ä\nb = :/𝄞x\n";
+        let offset = text.find('x').unwrap();
+        let position = position_at(text, offset);
+        assert_eq!(
+            position,
+            Position {
+                line: 1,
+                character: 8
+            }
+        );
+        assert_eq!(offset_at(text, position), offset);
+        assert_eq!(
+            offset_at(
+                text,
+                Position {
+                    line: 0,
+                    character: 99
+                }
+            ),
+            text.find('\n').unwrap()
+        );
+    }
+
+    #[test]
+    fn hover_describes_innermost_op() {
+        let text = "a = :/lib:[::x,:prefix=p]\n";
+        let (markdown, range) = hover(text, text.find("prefix").unwrap()).unwrap();
+        assert!(markdown.starts_with("```\n:prefix=p\n```"), "{}", markdown);
+        assert_eq!(range.start.character, 15);
+        let (markdown, _) = hover(text, text.find("lib").unwrap()).unwrap();
+        assert!(markdown.contains("directory"), "{}", markdown);
+    }
+
+    #[test]
+    fn path_prefix_finds_source_paths() {
+        let text = "a = :/lib/su\nb = ::docs/RE\nc = :prefix=x";
+        let end = |s: &str| text.find(s).unwrap() + s.len();
+        assert_eq!(path_prefix(text, end("su")), Some((6, "lib/su")));
+        assert_eq!(path_prefix(text, end("RE")), Some((19, "docs/RE")));
+        assert_eq!(path_prefix(text, end("=x")), None);
+    }
+
+    #[test]
+    fn format_keeps_leading_comments() {
+        assert_eq!(
+            format("# libs\nb = :/y\na = :/x").as_deref(),
+            Some("# libs\na = :/x\nb = :/y\n")
+        );
+        assert_eq!(format("a = :/a\n# trailing\nb = :/b\n"), None);
+        assert_eq!(format("a = :/a:bogus"), None);
+    }
+}
@@ -0,0 +1,195 @@
+//! `josh-lsp`: a language server for `.josh` filter files (`workspace.josh`, stored filters),
+//! speaking LSP over stdio. It provides the diagnostics of `josh filter lint`, hover docs for
+//! each op, completion of paths from the tree of HEAD, and formatting with the pretty printer.
+
+mod analysis;
+
+use std::collections::HashMap;
+
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::Notification as _;
+use lsp_types::request::Request as _;
+use lsp_types::{
+    CompletionOptions, Hover, HoverContents, HoverProviderCapability, MarkupContent, MarkupKind,
+    OneOf, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Uri,
+    notification, request,
+};
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    josh_core::git::configure_libgit2();
+
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["/".to_string(), ":".to_string()]),
+            ..Default::default()
+        }),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    // The server owns the connection, so that it is closed when the server is done and the
+    // writer thread can finish.
+    Server {
+        connection,
+        documents: HashMap::new(),
+    }
+    .run()?;
+
+    io_threads.join()?;
+    Ok(())
+}
+
+struct Server {
+    connection: Connection,
+    /// The text of every open document; documents are always synced in full.
+    documents: HashMap<Uri, String>,
+}
+
+impl Server {
+    fn run(mut self) -> anyhow::Result<()> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(req) => {
+                    if self.connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    let id = req.id.clone();
+                    let response = match self.handle_request(req) {
+                        Ok(result) => Response::new_ok(id, result),
+                        Err(e) => Response::new_err(
+                            id,
+                            lsp_server::ErrorCode::RequestFailed as i32,
+                            e.to_string(),
+                        ),
+                    };
+                    self.connection.sender.send(Message::Response(response))?;
+                }
+                // A notification has no response to carry an error, and one bad message is
+                // no reason to take the server down.
+                Message::Notification(not) => {
+                    let method = not.method.clone();
+                    if let Err(e) = self.handle_notification(not) {
+                        log::error!("{}: {}", method, e);
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn document(&self, uri: &Uri) -> anyhow::Result<&str> {
+        self.documents
+            .get(uri)
+            .map(|text| text.as_str())
+            .ok_or_else(|| anyhow::anyhow!("document not open: {}", uri.as_str()))
+    }
+
+    fn handle_request(&self, req: Request) -> anyhow::Result<serde_json::Value> {
+        match req.method.as_str() {
+            request::HoverRequest::METHOD => {
+                let params: lsp_types::HoverParams = serde_json::from_value(req.params)?;
+                let position = params.text_document_position_params;
+                let text = self.document(&position.text_document.uri)?;
+                let offset = analysis::offset_at(text, position.position);
+                let hover = analysis::hover(text, offset).map(|(markdown, range)| Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: markdown,
+                    }),
+                    range: Some(range),
+                });
+                Ok(serde_json::to_value(hover)?)
+            }
+            request::Completion::METHOD => {
+                let params: lsp_types::CompletionParams = serde_json::from_value(req.params)?;
+                let position = params.text_document_position;
+                let text = self.document(&position.text_document.uri)?;
+                let offset = analysis::offset_at(text, position.position);
+                // Paths are completed from the repository the document lives in, so documents
+                // that are not files on disk get no completions.
+                let items = match file_dir(&position.text_document.uri) {
+                    Some(dir) => analysis::complete_paths(text, offset, &dir).unwrap_or_else(|e| {
+                        log::debug!("no path completion: {}", e);
+                        vec![]
+                    }),
+                    None => vec![],
+                };
+                Ok(serde_json::to_value(items)?)
+            }
+            request::Formatting::METHOD => {
+                let params: lsp_types::DocumentFormattingParams =
+                    serde_json::from_value(req.params)?;
+                let text = self.document(&params.text_document.uri)?;
+                let edits = analysis::format(text)
+                    .filter(|formatted| formatted != text)
+                    .map(|formatted| {
+                        vec![TextEdit {
+                            range: lsp_types::Range {
+                                start: lsp_types::Position::default(),
+                                end: analysis::position_at(text, text.len()),
+                            },
+                            new_text: formatted,
+                        }]
+                    });
+                Ok(serde_json::to_value(edits)?)
+            }
+            method => Err(anyhow::anyhow!("unsupported request: {}", method)),
+        }
+    }
+
+    fn handle_notification(&mut self, not: Notification) -> anyhow::Result<()> {
+        match not.method.as_str() {
+            notification::DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams =
+                    serde_json::from_value(not.params)?;
+                let document = params.text_document;
+                self.documents.insert(document.uri.clone(), document.text);
+                self.publish_diagnostics(document.uri)?;
+            }
+            notification::DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams =
+                    serde_json::from_value(not.params)?;
+                let uri = params.text_document.uri;
+                if let Some(change) = params.content_changes.into_iter().next_back() {
+                    self.documents.insert(uri.clone(), change.text);
+                    self.publish_diagnostics(uri)?;
+                }
+            }
+            notification::DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams =
+                    serde_json::from_value(not.params)?;
+                self.documents.remove(&params.text_document.uri);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn publish_diagnostics(&self, uri: Uri) -> anyhow::Result<()> {
+        let diagnostics = analysis::diagnostics(self.document(&uri)?);
+        let params = lsp_types::PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        self.connection
+            .sender
+            .send(Message::Notification(Notification::new(
+                notification::PublishDiagnostics::METHOD.to_string(),
+                params,
+            )))?;
+        Ok(())
+    }
+}
+
+/// The directory of the file `uri` names, for `file://` URIs.
+fn file_dir(uri: &Uri) -> Option<std::path::PathBuf> {
+    let path = url::Url::parse(uri.as_str()).ok()?.to_file_path().ok()?;
+    path.parent().map(|dir| dir.to_path_buf())
+}
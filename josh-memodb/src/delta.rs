@@ -0,0 +1,248 @@
+//! Encoding git's binary delta format, the payload of an `OFS_DELTA` pack entry.
+//!
+//! A delta is the source and target sizes (little-endian base-128 varints) followed by
+//! instructions rebuilding the target: *copy* `(offset, size)` from the source, or *insert* up to
+//! 127 literal bytes. The encoder follows git's `diff-delta.c` in spirit: the source is indexed
+//! at every [`BLOCK`]-byte boundary, and the target is scanned for blocks found in that index,
+//! each hit extended as far as the bytes keep matching in both directions. It is deterministic:
+//! the same source and target always encode to the same bytes.
+
+use std::collections::HashMap;
+
+/// Granularity of the source index, and so the shortest match the encoder looks for.
+const BLOCK: usize = 16;
+
+/// Source offsets remembered per block hash. Bounds the work on highly repetitive sources
+/// (where every block hashes alike) at the cost of missing some matches there.
+const MAX_BUCKET: usize = 64;
+
+/// Largest size a single copy instruction encodes; longer matches take several. The same cap
+/// git's encoder uses.
+const MAX_COPY: usize = 0x10000;
+
+/// Largest run of literal bytes a single insert instruction carries.
+const MAX_INSERT: usize = 0x7f;
+
+/// Encode `target` as a delta against `source`. Returns `None` as soon as the delta would
+/// exceed `max_size` bytes, so callers probing several sources pay little for the poor ones.
+pub(crate) fn encode(source: &[u8], target: &[u8], max_size: usize) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    push_size(&mut out, source.len());
+    push_size(&mut out, target.len());
+
+    let index = index_blocks(source);
+    let mut literal_start = 0;
+    let mut pos = 0;
+    while pos + BLOCK <= target.len() {
+        let Some((src_start, len)) = best_match(&index, source, target, pos) else {
+            pos += 1;
+            continue;
+        };
+        // Grow the match backwards over literal bytes not yet emitted.
+        let mut back = 0;
+        while src_start > back
+            && pos - back > literal_start
+            && source[src_start - back - 1] == target[pos - back - 1]
+        {
+            back += 1;
+        }
+        push_inserts(&mut out, &target[literal_start..pos - back]);
+        push_copies(&mut out, src_start - back, len + back);
+        pos += len;
+        literal_start = pos;
+        if out.len() > max_size {
+            return None;
+        }
+    }
+    push_inserts(&mut out, &target[literal_start..]);
+    (out.len() <= max_size).then_some(out)
+}
+
+fn block_hash(block: &[u8]) -> u64 {
+    // FNV-1a: the index only needs to spread blocks, not resist adversaries.
+    block.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Offsets of the source's aligned blocks by hash, in increasing order per bucket.
+fn index_blocks(source: &[u8]) -> HashMap<u64, Vec<usize>> {
+    let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+    for offset in (0..source.len().saturating_sub(BLOCK - 1)).step_by(BLOCK) {
+        let bucket = index
+            .entry(block_hash(&source[offset..offset + BLOCK]))
+            .or_default();
+        if bucket.len() < MAX_BUCKET {
+            bucket.push(offset);
+        }
+    }
+    index
+}
+
+/// The longest forward match of `target[pos..]` starting at an indexed source block, as
+/// `(source offset, length)`; the earliest source offset wins ties.
+fn best_match(
+    index: &HashMap<u64, Vec<usize>>,
+    source: &[u8],
+    target: &[u8],
+    pos: usize,
+) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    for &offset in index.get(&block_hash(&target[pos..pos + BLOCK]))? {
+        let len = source[offset..]
+            .iter()
+            .zip(&target[pos..])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if len >= BLOCK && best.is_none_or(|(_, best_len)| len > best_len) {
+            best = Some((offset, len));
+        }
+    }
+    best
+}
+
+fn push_size(out: &mut Vec<u8>, mut size: usize) {
+    loop {
+        let byte = (size & 0x7f) as u8;
+        size >>= 7;
+        if size == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn push_inserts(out: &mut Vec<u8>, literal: &[u8]) {
+    for chunk in literal.chunks(MAX_INSERT) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+fn push_copies(out: &mut Vec<u8>, mut offset: usize, mut len: usize) {
+    while len > 0 {
+        let size = len.min(MAX_COPY);
+        let at = out.len();
+        let mut cmd = 0x80u8;
+        out.push(0);
+        // Offset in up to four bytes, size in up to three; zero bytes are omitted and flagged
+        // absent. A size of exactly 0x10000 is encoded as no size bytes at all.
+        for i in 0..4 {
+            let byte = (offset >> (8 * i)) as u8;
+            if byte != 0 {
+                cmd |= 1 << i;
+                out.push(byte);
+            }
+        }
+        if size != MAX_COPY {
+            for i in 0..3 {
+                let byte = (size >> (8 * i)) as u8;
+                if byte != 0 {
+                    cmd |= 0x10 << i;
+                    out.push(byte);
+                }
+            }
+        }
+        out[at] = cmd;
+        offset += size;
+        len -= size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal decoder, mirroring what git (and gix-pack) do when reading a delta entry.
+    fn apply(source: &[u8], delta: &[u8]) -> Vec<u8> {
+        fn size(delta: &[u8], i: &mut usize) -> usize {
+            let mut size = 0;
+            let mut shift = 0;
+            loop {
+                let byte = delta[*i];
+                *i += 1;
+                size |= usize::from(byte & 0x7f) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    return size;
+                }
+            }
+        }
+        let mut i = 0;
+        assert_eq!(size(delta, &mut i), source.len());
+        let target_len = size(delta, &mut i);
+        let mut out = Vec::new();
+        while i < delta.len() {
+            let cmd = delta[i];
+            i += 1;
+            if cmd & 0x80 != 0 {
+                let mut field = |bits: std::ops::Range<u8>| {
+                    let mut value = 0;
+                    for (n, bit) in bits.enumerate() {
+                        if cmd & (1 << bit) != 0 {
+                            value |= usize::from(delta[i]) << (8 * n);
+                            i += 1;
+                        }
+                    }
+                    value
+                };
+                let offset = field(0..4);
+                let len = match field(4..7) {
+                    0 => MAX_COPY,
+                    len => len,
+                };
+                out.extend_from_slice(&source[offset..offset + len]);
+            } else {
+                out.extend_from_slice(&delta[i..i + usize::from(cmd)]);
+                i += usize::from(cmd);
+            }
+        }
+        assert_eq!(out.len(), target_len);
+        out
+    }
+
+    fn pseudo_random(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (state >> 33) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips() {
+        let source = pseudo_random(1, 100_000);
+        let mut edited = source.clone();
+        edited[500..520].copy_from_slice(b"twenty inserted byte");
+        edited.drain(40_000..41_000);
+        edited.splice(70_000..70_000, pseudo_random(2, 300));
+
+        let cases: [(&[u8], &[u8]); 5] = [
+            (&source, &edited),
+            (&source, &source),
+            (&source, b""),
+            (b"", &source[..1000]),
+            (b"short", b"shorter"),
+        ];
+        for (source, target) in cases {
+            let delta = encode(source, target, usize::MAX).unwrap();
+            assert_eq!(apply(source, &delta), target);
+        }
+    }
+
+    /// Similar inputs delta to a fraction of the target; the size limit cuts encoding short.
+    #[test]
+    fn similar_inputs_delta_small() {
+        let source = pseudo_random(3, 10_000);
+        let mut target = source.clone();
+        target[5_000] ^= 0xff;
+
+        let delta = encode(&source, &target, usize::MAX).unwrap();
+        assert!(delta.len() < 100, "delta of {} bytes", delta.len());
+        assert!(encode(&source, &pseudo_random(4, 10_000), 1_000).is_none());
+        assert_eq!(encode(&source, &target, usize::MAX), Some(delta));
+    }
+}
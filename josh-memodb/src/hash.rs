@@ -3,7 +3,8 @@ use std::mem::size_of;
 
 /// A hasher that uses an already-uniform key directly as the hash value, avoiding double-hashing.
 ///
-/// Two key shapes are supported: the first 8 bytes of a 20-byte SHA1 (e.g. a git `Oid` key) via
+/// Two key shapes are supported: the first 8 bytes of a 20-byte SHA-1 or 32-byte SHA-256 digest
+/// (e.g. a git `Oid` key) via
 /// [`write`](Hasher::write), or a single pointer-sized integer (e.g. an interned handle) via
 /// [`write_usize`](Hasher::write_usize). Anything else panics: the hasher assumes its input is
 /// already a cryptographic digest or a unique integer, so mixing it further would be wasted work.
@@ -31,7 +32,7 @@ impl Hasher for PassthroughHasher {
         self.done = true;
     }
 
-    // hyper-specialized: reject everything that's not sha1 length
+    // hyper-specialized: reject everything that's not sha1 or sha256 length
     fn write(&mut self, bytes: &[u8]) {
         if self.done {
             panic!("hasher data already written");
@@ -43,7 +44,7 @@ impl Hasher for PassthroughHasher {
             return;
         }
 
-        if bytes.len() != 20 {
+        if bytes.len() != 20 && bytes.len() != 32 {
             panic!("unexpected data length {} in hasher", bytes.len())
         }
 
@@ -69,5 +70,10 @@ mod tests {
         // First 8 bytes as little-endian u64
         let expected = u64::from_le_bytes([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
         assert_eq!(hasher.finish(), expected);
+
+        let mut hasher = PassthroughHasher::default();
+        let sha256: Vec<u8> = (1..=32).collect();
+        hasher.write(&sha256);
+        assert_eq!(hasher.finish(), expected);
     }
 }
@@ -4,6 +4,7 @@
 //! flushes them to a packfile at transaction and external-git boundaries. [`Odb`] is the facade
 //! every reader and writer goes through: memory first, then the repository's objects on disk.
 
+mod delta;
 mod flusher;
 pub mod hash;
 pub mod mem_odb;
@@ -12,5 +13,5 @@ pub mod pack;
 
 pub use hash::PassthroughHasher;
 pub use mem_odb::MemOdb;
-pub use odb::{Bytes, Odb};
-pub use pack::objects_dir;
+pub use odb::{Bytes, DEFAULT_BIG_OBJECT_THRESHOLD, Odb, Promisor, Reader};
+pub use pack::{object_hash, objects_dir};
@@ -13,7 +13,7 @@
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 
 use gix_hash::ObjectId;
 use gix_object::Kind;
@@ -45,6 +45,9 @@ pub struct MemOdb {
     /// [`crate::pack::objects_dir`]), captured at construction while the caller holds a repository
     /// handle; the background flusher packs straight into it without opening one.
     objects_dir: PathBuf,
+    /// The hash of the registered repository's object format (see [`crate::pack::object_hash`]):
+    /// what writes are hashed with and what flushed packs are indexed by.
+    object_hash: gix_hash::Kind,
     /// Set while an overflow chunk is queued on or running in the background flusher, so the write
     /// path does not pile up redundant chunk requests every time the store crosses its limit.
     /// Cleared by the flusher once the chunk has packed and evicted.
@@ -58,15 +61,28 @@ pub struct MemOdb {
     /// are dropped at pack time instead, and with no alternates registered the probe is a
     /// `None` check, no filesystem I/O.
     alternates: Mutex<Option<git2::Odb<'static>>>,
+    /// The directories of the runtime alternates, for reads that go to their packs directly
+    /// (see [`MemOdb::object_dirs`]).
+    alternate_dirs: Mutex<Vec<PathBuf>>,
+    /// The repository's commit-graph, loaded on first use (see [`MemOdb::commit_graph`]).
+    commit_graph: OnceLock<Option<josh_gix_ext::CommitGraph>>,
+    /// Objects of earlier flushes that the next one may delta against, see
+    /// [`crate::pack::RECENT_BASES`]. Only touched by the flusher.
+    recent_bases: Mutex<Snapshot>,
 }
 
 impl MemOdb {
     /// Create an empty store. Returned as an [`Arc`] because the store is shared between the owning
     /// transaction and the libgit2 backend registered on its repository. `limit` bounds the total
     /// buffered object data: once exceeded the store flushes itself to a packfile (`None` = unbounded).
-    /// `objects_dir` is where flushes land; pass [`crate::pack::objects_dir`] of the repository the
-    /// store is about to be registered on.
-    pub fn new(limit: Option<usize>, objects_dir: PathBuf) -> Arc<MemOdb> {
+    /// `objects_dir` is where flushes land and `object_hash` is what objects are hashed with; pass
+    /// [`crate::pack::objects_dir`] and [`crate::pack::object_hash`] of the repository the store is
+    /// about to be registered on.
+    pub fn new(
+        limit: Option<usize>,
+        objects_dir: PathBuf,
+        object_hash: gix_hash::Kind,
+    ) -> Arc<MemOdb> {
         Arc::new(MemOdb {
             inner: Mutex::new(Inner {
                 map: Default::default(),
@@ -74,8 +90,12 @@ impl MemOdb {
             }),
             limit,
             objects_dir,
+            object_hash,
             chunk_in_flight: AtomicBool::new(false),
             alternates: Mutex::new(None),
+            alternate_dirs: Mutex::new(Vec::new()),
+            commit_graph: OnceLock::new(),
+            recent_bases: Mutex::new(Vec::new()),
         })
     }
 
@@ -88,7 +108,25 @@ impl MemOdb {
             Some(odb) => odb,
             None => alternates.insert(git2::Odb::new()?),
         };
-        odb.add_disk_alternate(path)
+        odb.add_disk_alternate(path)?;
+        self.alternate_dirs.lock().unwrap().push(path.into());
+        Ok(())
+    }
+
+    /// The object directories the repository reads from: its own, those listed in its
+    /// `info/alternates`, and the runtime alternates.
+    pub(crate) fn object_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.objects_dir.clone()];
+        if let Ok(listed) = std::fs::read_to_string(self.objects_dir.join("info/alternates")) {
+            dirs.extend(
+                listed
+                    .lines()
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| self.objects_dir.join(line)),
+            );
+        }
+        dirs.extend(self.alternate_dirs.lock().unwrap().iter().cloned());
+        dirs
     }
 
     /// Whether `oid` exists in one of the registered alternates. `false` without filesystem
@@ -101,6 +139,20 @@ impl MemOdb {
             .is_some_and(|odb| odb.exists(oid))
     }
 
+    /// The commit-graph of the repository's object directory, if housekeeping has written one.
+    /// Loaded once per store: commits it lists never change, and commits written since are
+    /// simply not listed. A graph that fails to load is logged and treated as absent.
+    pub fn commit_graph(&self) -> Option<&josh_gix_ext::CommitGraph> {
+        self.commit_graph
+            .get_or_init(|| {
+                josh_gix_ext::CommitGraph::at(&self.objects_dir).unwrap_or_else(|e| {
+                    log::warn!("ignoring unreadable commit-graph: {e}");
+                    None
+                })
+            })
+            .as_ref()
+    }
+
     /// Buffer `oid` and return whether the store has now exceeded its size limit, so the caller
     /// can flush. A content-addressed duplicate is a no-op and never reports overflow: it adds
     /// no bytes, so it must not trigger a pack.
@@ -118,12 +170,29 @@ impl MemOdb {
     /// limit. Callers that dedup against on-disk objects gate before calling (see
     /// [`crate::odb::Odb`]); the store itself only dedups against its own buffered contents.
     pub fn write(self: &Arc<Self>, kind: Kind, data: &[u8]) -> ObjectId {
-        let id = gix_object::compute_hash(gix_hash::Kind::Sha1, kind, data)
-            .expect("failed to compute hash");
+        let id =
+            gix_object::compute_hash(self.object_hash, kind, data).expect("failed to compute hash");
         self.write_with_id(id, kind, data);
         id
     }
 
+    /// The hash objects in this store are identified by.
+    pub fn object_hash(&self) -> gix_hash::Kind {
+        self.object_hash
+    }
+
+    /// The repository's loose object store, for objects too big to buffer (see
+    /// [`crate::odb::Odb::write`]).
+    pub(crate) fn loose_store(&self) -> gix_odb::loose::Store {
+        gix_odb::loose::Store::at(
+            self.objects_dir.clone(),
+            gix_odb::loose::Options {
+                object_hash: self.object_hash,
+                ..Default::default()
+            },
+        )
+    }
+
     /// [`MemOdb::write`] with a caller-computed content hash, trusted verbatim.
     pub fn write_with_id(self: &Arc<Self>, id: ObjectId, kind: Kind, data: &[u8]) {
         if self.insert(id, kind, data.into()) {
@@ -213,7 +282,12 @@ impl MemOdb {
                 .collect()
         };
 
-        crate::pack::write_snapshot(&self.objects_dir, &snapshot)?;
+        crate::pack::write_snapshot(
+            &self.objects_dir,
+            self.object_hash,
+            &snapshot,
+            &mut self.recent_bases.lock().unwrap(),
+        )?;
 
         // Evict exactly the snapshotted oids (now durable: packed just above, or already on disk).
         // Writes that landed after the snapshot stay buffered for the next chunk or the drain, so a
@@ -239,7 +313,11 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let repo = git2::Repository::init(dir.path()).unwrap();
 
-        let store = MemOdb::new(None, crate::pack::objects_dir(&repo));
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
 
         let ids: Vec<ObjectId> = (0..4)
             .map(|i| store.write(Kind::Blob, format!("in-memory blob {i}").as_bytes()))
@@ -290,7 +368,11 @@ mod tests {
         // The worktree's gitdir differs from its common dir (the main gitdir).
         assert_ne!(wt_repo.path(), wt_repo.commondir());
 
-        let store = MemOdb::new(None, crate::pack::objects_dir(&wt_repo));
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&wt_repo),
+            crate::pack::object_hash(&wt_repo),
+        );
         let id = josh_gix_ext::git2_oid(&store.write(Kind::Blob, b"worktree blob"));
 
         // Must not fail on the (nonexistent) per-worktree objects/pack directory.
@@ -310,7 +392,11 @@ mod tests {
         let repo = git2::Repository::init(dir.path()).unwrap();
 
         // A 16-byte limit: each 100-byte blob overflows it, so every write enqueues a pack.
-        let store = MemOdb::new(Some(16), crate::pack::objects_dir(&repo));
+        let store = MemOdb::new(
+            Some(16),
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
 
         // The write overflowed and enqueued a background pack; it lands on the flusher thread, so
         // poll a fresh on-disk view until the object appears.
@@ -335,7 +421,11 @@ mod tests {
     fn flush_leaves_only_pack_and_idx() {
         let dir = tempfile::tempdir().unwrap();
         let repo = git2::Repository::init(dir.path()).unwrap();
-        let store = MemOdb::new(None, crate::pack::objects_dir(&repo));
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
 
         store.write(Kind::Blob, b"some blob");
         store.flush().unwrap();
@@ -352,6 +442,169 @@ mod tests {
         assert_eq!(exts, ["idx", "pack"]);
     }
 
+    /// The store hashes and packs with the object format it was created for: a SHA-256 store
+    /// hands out SHA-256 ids, and its flush lands a pack a SHA-256 object database reads back.
+    #[test]
+    fn flush_writes_sha256_pack() {
+        let dir = tempfile::tempdir().unwrap();
+        let objects = dir.path().join("objects");
+        std::fs::create_dir_all(&objects).unwrap();
+        let store = MemOdb::new(None, objects.clone(), gix_hash::Kind::Sha256);
+
+        let id = store.write(Kind::Blob, b"sha256 blob");
+        assert_eq!(id.kind(), gix_hash::Kind::Sha256);
+        assert_eq!(
+            id,
+            gix_object::compute_hash(gix_hash::Kind::Sha256, Kind::Blob, b"sha256 blob").unwrap()
+        );
+        store.flush().unwrap();
+        assert!(!store.contains(&id));
+
+        let odb = gix_odb::at_opts(
+            objects,
+            Vec::new(),
+            gix_odb::store::init::Options {
+                object_hash: gix_hash::Kind::Sha256,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut buf = Vec::new();
+        let data = gix_object::Find::try_find(&odb, &id, &mut buf)
+            .unwrap()
+            .expect("flushed object is on disk");
+        assert_eq!(data.kind, Kind::Blob);
+        assert_eq!(data.data, b"sha256 blob");
+    }
+
+    /// Near-identical trees, as filtering produces them, are packed as offset deltas against one
+    /// another; the pack reads back intact and is the same file for the same objects.
+    #[test]
+    fn flush_delta_compresses_similar_trees() {
+        fn flush_trees(path: &std::path::Path) -> (Vec<ObjectId>, std::path::PathBuf) {
+            let repo = git2::Repository::init(path).unwrap();
+            let store = MemOdb::new(
+                None,
+                crate::pack::objects_dir(&repo),
+                crate::pack::object_hash(&repo),
+            );
+            let blob = store.write(Kind::Blob, b"content");
+            let trees = (0..20)
+                .map(|revision| {
+                    let mut tree = Vec::new();
+                    for entry in 0..200 {
+                        let name = format!("file-{entry:03}-{}", u32::from(entry == revision));
+                        tree.extend_from_slice(format!("100644 {name}\0").as_bytes());
+                        tree.extend_from_slice(blob.as_bytes());
+                    }
+                    store.write(Kind::Tree, &tree)
+                })
+                .collect();
+            store.flush().unwrap();
+
+            let packs: Vec<_> = std::fs::read_dir(path.join(".git/objects/pack"))
+                .unwrap()
+                .map(|e| e.unwrap().path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "pack"))
+                .collect();
+            assert_eq!(packs.len(), 1);
+            (trees, packs[0].clone())
+        }
+
+        let first = tempfile::tempdir().unwrap();
+        let (trees, pack) = flush_trees(first.path());
+
+        let on_disk = git2::Repository::open(first.path()).unwrap();
+        for id in &trees {
+            let tree = on_disk.find_tree(josh_gix_ext::git2_oid(id)).unwrap();
+            assert_eq!(tree.len(), 200);
+        }
+
+        let index =
+            gix_pack::index::File::at(pack.with_extension("idx"), gix_hash::Kind::Sha1).unwrap();
+        let data = gix_pack::data::File::at(&pack, gix_hash::Kind::Sha1).unwrap();
+        let deltas = index
+            .iter()
+            .filter(|e| {
+                let entry = data.entry(e.pack_offset).unwrap();
+                matches!(entry.header, gix_pack::data::entry::Header::OfsDelta { .. })
+            })
+            .count();
+        // One tree is stored whole, every other one as a delta; the blob is too small to delta.
+        assert_eq!(deltas, trees.len() - 1);
+
+        let second = tempfile::tempdir().unwrap();
+        let (_, same_pack) = flush_trees(second.path());
+        assert_eq!(same_pack.file_name(), pack.file_name());
+    }
+
+    /// Near-identical trees written across flushes are delta-compressed too: the second pack
+    /// deltas against a tree of the first one, which it carries whole to stay self-contained.
+    #[test]
+    fn flush_deltas_against_previous_flush() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
+        let blob = store.write(Kind::Blob, b"content");
+        // The first tree has one more entry, so it sorts ahead of the later ones as a base.
+        let tree = |revision: usize| {
+            let mut tree = Vec::new();
+            for entry in 0..200 + usize::from(revision == 0) {
+                let name = format!("file-{entry:03}-{}", u32::from(entry == revision));
+                tree.extend_from_slice(format!("100644 {name}\0").as_bytes());
+                tree.extend_from_slice(blob.as_bytes());
+            }
+            tree
+        };
+        store.write(Kind::Tree, &tree(0));
+        store.flush().unwrap();
+        let packs = |path: &std::path::Path| -> Vec<std::path::PathBuf> {
+            std::fs::read_dir(path.join(".git/objects/pack"))
+                .unwrap()
+                .map(|e| e.unwrap().path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "pack"))
+                .collect()
+        };
+        let first = packs(dir.path());
+
+        let later: Vec<_> = (1..5)
+            .map(|revision| store.write(Kind::Tree, &tree(revision)))
+            .collect();
+        store.flush().unwrap();
+        let second: Vec<_> = packs(dir.path())
+            .into_iter()
+            .filter(|p| !first.contains(p))
+            .collect();
+        assert_eq!(second.len(), 1);
+
+        let on_disk = git2::Repository::open(dir.path()).unwrap();
+        for id in &later {
+            assert_eq!(
+                on_disk.find_tree(josh_gix_ext::git2_oid(id)).unwrap().len(),
+                200
+            );
+        }
+
+        let index =
+            gix_pack::index::File::at(second[0].with_extension("idx"), gix_hash::Kind::Sha1)
+                .unwrap();
+        let data = gix_pack::data::File::at(&second[0], gix_hash::Kind::Sha1).unwrap();
+        let deltas = index
+            .iter()
+            .filter(|e| {
+                let entry = data.entry(e.pack_offset).unwrap();
+                matches!(entry.header, gix_pack::data::entry::Header::OfsDelta { .. })
+            })
+            .count();
+        // Every new tree is a delta; the appended base is the one entry stored whole.
+        assert_eq!(deltas, later.len());
+        assert_eq!(index.num_objects() as usize, later.len() + 1);
+    }
+
     /// Poll a freshly-opened (backend-less) view of the repository until `id` is readable from disk,
     /// up to ~2s. Used to observe asynchronous background packs without racing the flusher thread.
     fn wait_on_disk(repo_path: &std::path::Path, id: git2::Oid) -> bool {
@@ -9,6 +9,19 @@
 //! objects. Write dedup goes through the store's own alternate mirror instead (see
 //! [`Odb::write`]) and never touches `disk`.
 //!
+//! In a partial clone the disk may lack objects the repository's promisor remote has. Given a
+//! [`Promisor`], reads that miss on disk fetch the object and look again; existence probes
+//! ([`Odb::contains`], [`gix_object::Exists`]) never fetch, like git's own `has_object` checks.
+//! Filters that only rearrange trees therefore run without the blobs they pass through; walks
+//! that read every blob of a tree ask for the missing ones at once with [`Odb::prefetch`].
+//!
+//! Objects larger than the big-object threshold (git's `core.bigFileThreshold`, 512 MiB by
+//! default) never enter the store: [`Odb::write`] and the streaming [`gix_object::Write`]
+//! methods put them straight into a loose object on disk, so a multi-gigabyte blob costs at
+//! most the one buffer its producer already holds. [`Odb::read_stream`] is the matching read
+//! side; it streams loose objects through libgit2 and packed ones through gix-pack, and falls
+//! back to a whole read only for deltified pack entries.
+//!
 //! Lock discipline: never call into `disk` while holding the store mutex — the git2 path
 //! locks `disk` first and the store second (inside the backend trampolines), so the facade
 //! always probes the store and the disk sequentially, holding at most one lock at a time.
@@ -37,16 +50,114 @@ impl std::ops::Deref for Bytes<'_> {
     }
 }
 
+impl AsRef<[u8]> for Bytes<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// A streaming read of an object's raw bytes (see [`Odb::read_stream`]): a cursor over a whole
+/// buffer for objects read at once, a libgit2 read stream for big loose objects, an inflating
+/// pack entry reader for big packed ones.
+pub enum Reader<'a> {
+    Whole(std::io::Cursor<Bytes<'a>>),
+    Stream(git2::OdbReader<'a>),
+    Packed(crate::pack::EntryReader),
+}
+
+impl std::io::Read for Reader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Reader::Whole(cursor) => cursor.read(buf),
+            Reader::Stream(reader) => reader.read(buf),
+            Reader::Packed(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// git's `core.bigFileThreshold` default, and the facade's unless told otherwise (see
+/// [`Odb::with_big_object_threshold`]).
+pub const DEFAULT_BIG_OBJECT_THRESHOLD: u64 = 512 * 1024 * 1024;
+
+/// Fetches objects a partial clone left out, on demand (git's "promisor remote").
+pub trait Promisor: Send + Sync {
+    /// Bring `oids` into the repository's object database, all in one go where the remote
+    /// allows. Returns whether the objects may now be there; `false` when they could not be
+    /// fetched, so reads of them fail as plain misses.
+    fn fetch(&self, oids: &[git2::Oid]) -> bool;
+}
+
 /// See the module docs. A cheap per-call value: an `Arc` clone onto the transaction's store
 /// plus the repository's odb handle, obtained via `Transaction::odb()`.
 pub struct Odb<'repo> {
     mem: Arc<MemOdb>,
     disk: git2::Odb<'repo>,
+    promisor: Option<Arc<dyn Promisor>>,
+    big_object_threshold: u64,
 }
 
 impl<'repo> Odb<'repo> {
     pub fn new(mem: Arc<MemOdb>, disk: git2::Odb<'repo>) -> Self {
-        Odb { mem, disk }
+        Odb {
+            mem,
+            disk,
+            promisor: None,
+            big_object_threshold: DEFAULT_BIG_OBJECT_THRESHOLD,
+        }
+    }
+
+    /// Objects larger than `threshold` bytes bypass the memory store: written straight to disk,
+    /// streamed on [`Odb::read_stream`].
+    pub fn with_big_object_threshold(mut self, threshold: u64) -> Self {
+        self.big_object_threshold = threshold;
+        self
+    }
+
+    /// Whether an object of `size` bytes is big enough to bypass the memory store.
+    pub fn is_big(&self, size: u64) -> bool {
+        size > self.big_object_threshold
+    }
+
+    /// Fetch objects missing on disk from `promisor` before giving up on them.
+    pub fn with_promisor(mut self, promisor: Option<Arc<dyn Promisor>>) -> Self {
+        self.promisor = promisor;
+        self
+    }
+
+    /// Run the disk lookup `find` for `oid`, and once more after fetching `oid` from the
+    /// promisor if it missed. libgit2 rescans the pack directory on a miss, so the second try
+    /// sees the pack the fetch added.
+    fn disk_lookup<'a, T>(
+        &'a self,
+        oid: git2::Oid,
+        find: impl Fn(&'a git2::Odb<'repo>) -> Result<T, git2::Error>,
+    ) -> Result<T, git2::Error> {
+        match find(&self.disk) {
+            Err(e) if e.code() == git2::ErrorCode::NotFound && !oid.is_zero() => {
+                match &self.promisor {
+                    Some(promisor) if promisor.fetch(&[oid]) => find(&self.disk),
+                    _ => Err(e),
+                }
+            }
+            result => result,
+        }
+    }
+
+    /// Fetch those of `oids` that are missing from the promisor, with a single request, ahead
+    /// of reading them one by one. Walks that read every blob of a tree call this with the
+    /// tree's entries so a partial clone does not start one fetch per blob. Without a promisor
+    /// this does nothing.
+    pub fn prefetch(&self, oids: impl IntoIterator<Item = git2::Oid>) {
+        let Some(promisor) = &self.promisor else {
+            return;
+        };
+        let missing: Vec<_> = oids
+            .into_iter()
+            .filter(|oid| !oid.is_zero() && !self.contains(*oid))
+            .collect();
+        if !missing.is_empty() {
+            promisor.fetch(&missing);
+        }
     }
 
     /// Read the raw bytes and kind of `oid`; memory hits are zero-copy. A missing object is an
@@ -55,7 +166,7 @@ impl<'repo> Odb<'repo> {
         if let Some((kind, data)) = self.mem.get(&josh_gix_ext::gix_oid(oid)) {
             return Ok((kind, Bytes::Mem(data)));
         }
-        let obj = self.disk.read(oid)?;
+        let obj = self.disk_lookup(oid, |disk| disk.read(oid))?;
         let kind = josh_gix_ext::gix_kind(obj.kind()).ok_or_else(|| {
             git2::Error::new(
                 git2::ErrorCode::Invalid,
@@ -66,12 +177,32 @@ impl<'repo> Odb<'repo> {
         Ok((kind, Bytes::Disk(obj)))
     }
 
+    /// Kind, size and a reader over the raw bytes of `oid`. Objects above the big-object
+    /// threshold stream from disk, loose ones through libgit2 and packed ones through gix-pack;
+    /// everything else, and big objects stored as a pack delta, is read whole first.
+    pub fn read_stream(&self, oid: git2::Oid) -> Result<(Kind, u64, Reader<'_>), git2::Error> {
+        let (kind, size) = self.read_header(oid)?;
+        let id = josh_gix_ext::gix_oid(oid);
+        if self.is_big(size) && !self.mem.contains(&id) {
+            if let Ok((reader, _, _)) = self.disk.reader(oid) {
+                return Ok((kind, size, Reader::Stream(reader)));
+            }
+            let dirs = self.mem.object_dirs();
+            if let Some(reader) = crate::pack::entry_reader(&dirs, self.mem.object_hash(), &id) {
+                return Ok((kind, size, Reader::Packed(reader)));
+            }
+            log::debug!("reading {} whole, it is stored as a delta", oid);
+        }
+        let (kind, bytes) = self.read(oid)?;
+        Ok((kind, size, Reader::Whole(std::io::Cursor::new(bytes))))
+    }
+
     /// Kind and size of `oid` without reading (or decompressing) its bytes.
     pub fn read_header(&self, oid: git2::Oid) -> Result<(Kind, u64), git2::Error> {
         if let Some(header) = self.mem.header(&josh_gix_ext::gix_oid(oid)) {
             return Ok(header);
         }
-        let (size, kind) = self.disk.read_header(oid)?;
+        let (size, kind) = self.disk_lookup(oid, |disk| disk.read_header(oid))?;
         let kind = josh_gix_ext::gix_kind(kind).ok_or_else(|| {
             git2::Error::new(
                 git2::ErrorCode::Invalid,
@@ -93,7 +224,7 @@ impl<'repo> Odb<'repo> {
         if let Some((kind, _)) = self.mem.header(&josh_gix_ext::gix_oid(oid)) {
             return Ok(Some(kind));
         }
-        let (_, kind) = match self.disk.read_header(oid) {
+        let (_, kind) = match self.disk_lookup(oid, |disk| disk.read_header(oid)) {
             Ok(h) => h,
             Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
             Err(e) => return Err(e),
@@ -110,19 +241,55 @@ impl<'repo> Odb<'repo> {
     /// durable there are buffered anyway and dropped at pack time, and a repository without
     /// alternates writes with zero filesystem I/O.
     pub fn write(&self, kind: Kind, data: &[u8]) -> git2::Oid {
-        let id = gix_object::compute_hash(gix_hash::Kind::Sha1, kind, data)
+        let id = gix_object::compute_hash(self.mem.object_hash(), kind, data)
             .expect("failed to compute hash");
         self.write_with_id(id, kind, data);
         josh_gix_ext::git2_oid(&id)
     }
 
-    /// [`Odb::write`] with a caller-computed content hash, trusted verbatim.
+    /// The hash of the repository's object format: what [`Odb::write`] hashes with, and what
+    /// every id read through this facade uses.
+    pub fn object_hash(&self) -> gix_hash::Kind {
+        self.mem.object_hash()
+    }
+
+    /// [`Odb::write`] with a caller-computed content hash, trusted verbatim. Big objects go to
+    /// disk; one that cannot be written there is buffered after all, as the write has no way
+    /// to fail.
     fn write_with_id(&self, id: ObjectId, kind: Kind, data: &[u8]) {
-        if self.mem.contains(&id) || self.mem.exists_in_alternates(josh_gix_ext::git2_oid(&id)) {
+        if self.is_buffered_or_alternate(&id) {
             return;
         }
+        if self.is_big(data.len() as u64) {
+            match self.write_loose(kind, data.len() as u64, &mut &data[..], Some(id)) {
+                Ok(_) => return,
+                Err(e) => log::warn!("buffering big object {} in memory: {}", id, e),
+            }
+        }
         self.mem.write_with_id(id, kind, data);
     }
+
+    /// The write gate shared by all write paths, see [`Odb::write`].
+    fn is_buffered_or_alternate(&self, id: &ObjectId) -> bool {
+        self.mem.contains(id) || self.mem.exists_in_alternates(josh_gix_ext::git2_oid(id))
+    }
+
+    /// Stream `from` into a loose object in the repository's object directory, hashing it on
+    /// the way unless `id` is known.
+    fn write_loose(
+        &self,
+        kind: Kind,
+        size: u64,
+        from: &mut dyn std::io::Read,
+        id: Option<ObjectId>,
+    ) -> Result<ObjectId, gix_object::write::Error> {
+        use gix_object::Write as _;
+        let loose = self.mem.loose_store();
+        match id {
+            Some(id) => loose.write_stream_with_known_id(kind, size, from, id),
+            None => loose.write_stream(kind, size, from),
+        }
+    }
 }
 
 impl gix_object::Find for Odb<'_> {
@@ -140,7 +307,8 @@ impl gix_object::Find for Odb<'_> {
                 data: buffer,
             }));
         }
-        let obj = match self.disk.read(josh_gix_ext::git2_oid(id)) {
+        let oid = josh_gix_ext::git2_oid(id);
+        let obj = match self.disk_lookup(oid, |disk| disk.read(oid)) {
             Ok(obj) => obj,
             Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
             Err(e) => return Err(Box::new(e)),
@@ -166,7 +334,8 @@ impl gix_object::FindHeader for Odb<'_> {
         if let Some((kind, size)) = self.mem.header(id) {
             return Ok(Some(gix_object::Header { kind, size }));
         }
-        let (size, kind) = match self.disk.read_header(josh_gix_ext::git2_oid(id)) {
+        let oid = josh_gix_ext::git2_oid(id);
+        let (size, kind) = match self.disk_lookup(oid, |disk| disk.read_header(oid)) {
             Ok(h) => h,
             Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
             Err(e) => return Err(Box::new(e)),
@@ -181,6 +350,12 @@ impl gix_object::FindHeader for Odb<'_> {
     }
 }
 
+impl josh_gix_ext::FindCommitGraph for Odb<'_> {
+    fn commit_graph(&self) -> Option<&josh_gix_ext::CommitGraph> {
+        self.mem.commit_graph()
+    }
+}
+
 impl gix_object::Exists for Odb<'_> {
     fn exists(&self, id: &gix_hash::oid) -> bool {
         self.mem.contains(id) || self.disk.exists(josh_gix_ext::git2_oid(id))
@@ -208,6 +383,11 @@ impl gix_object::Write for Odb<'_> {
         size: u64,
         from: &mut dyn std::io::Read,
     ) -> Result<ObjectId, gix_object::write::Error> {
+        if self.is_big(size) {
+            // The id is only known once the stream is through, so the write gate runs after
+            // the fact: the loose object stays on disk even when it duplicates an alternate.
+            return self.write_loose(kind, size, from, None);
+        }
         let mut data = Vec::with_capacity(size as usize);
         from.read_to_end(&mut data)?;
         self.write_buf(kind, &data)
@@ -220,6 +400,13 @@ impl gix_object::Write for Odb<'_> {
         from: &mut dyn std::io::Read,
         id: ObjectId,
     ) -> Result<ObjectId, gix_object::write::Error> {
+        if self.is_big(size) {
+            if self.is_buffered_or_alternate(&id) {
+                std::io::copy(from, &mut std::io::sink())?;
+                return Ok(id);
+            }
+            return self.write_loose(kind, size, from, Some(id));
+        }
         let mut data = Vec::with_capacity(size as usize);
         from.read_to_end(&mut data)?;
         self.write_buf_with_known_id(kind, &data, id)
@@ -241,7 +428,11 @@ mod tests {
     fn facade_write_visible_before_flush_durable_after() {
         let dir = tempfile::tempdir().unwrap();
         let repo = git2::Repository::init(dir.path()).unwrap();
-        let store = MemOdb::new(None, crate::pack::objects_dir(&repo));
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
 
         let odb = facade(&store, &repo);
         let oid = odb.write(Kind::Blob, b"facade blob");
@@ -275,7 +466,11 @@ mod tests {
         let repo = git2::Repository::init(&dir).unwrap();
         // A loose blob, so it is main-disk-only.
         let on_disk = repo.blob(b"already on disk").unwrap();
-        let store = MemOdb::new(None, crate::pack::objects_dir(&repo));
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
         let alt = crate::pack::objects_dir(&mirror);
         repo.odb()
             .unwrap()
@@ -305,7 +500,11 @@ mod tests {
     fn try_kind_virtualizes_empty_tree() {
         let dir = tempfile::tempdir().unwrap();
         let repo = git2::Repository::init(dir.path()).unwrap();
-        let store = MemOdb::new(None, crate::pack::objects_dir(&repo));
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
         let odb = facade(&store, &repo);
 
         let empty_tree = git2::Oid::from_str("4b825dc642cb6eb9a060e54bf8d69288fbee4904").unwrap();
@@ -326,7 +525,11 @@ mod tests {
     fn buffered_objects_resolve_through_the_gix_traits() {
         let dir = tempfile::tempdir().unwrap();
         let repo = git2::Repository::init(dir.path()).unwrap();
-        let store = MemOdb::new(None, crate::pack::objects_dir(&repo));
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
         let odb = facade(&store, &repo);
 
         let oid = odb.write(Kind::Blob, b"facade blob");
@@ -340,4 +543,148 @@ mod tests {
         assert_eq!(data.data, b"facade blob");
         assert!(odb.exists(&josh_gix_ext::gix_oid(oid)));
     }
+
+    /// Copies objects from another repository, counting the requests.
+    struct CopyFrom {
+        from: std::path::PathBuf,
+        to: std::path::PathBuf,
+        fetched: std::sync::Mutex<Vec<Vec<git2::Oid>>>,
+    }
+
+    impl Promisor for CopyFrom {
+        fn fetch(&self, oids: &[git2::Oid]) -> bool {
+            self.fetched.lock().unwrap().push(oids.to_vec());
+            let from = git2::Repository::open(&self.from).unwrap();
+            let from = from.odb().unwrap();
+            let to = git2::Repository::open(&self.to).unwrap();
+            let to = to.odb().unwrap();
+            for oid in oids {
+                let Ok(obj) = from.read(*oid) else {
+                    return false;
+                };
+                to.write(obj.kind(), obj.data()).unwrap();
+            }
+            true
+        }
+    }
+
+    /// Reads that miss on disk go to the promisor and retry; existence probes never fetch.
+    #[test]
+    fn promisor_fetches_on_read_miss_only() {
+        let remote_dir = tempfile::tempdir().unwrap();
+        let remote = git2::Repository::init_bare(remote_dir.path()).unwrap();
+        let blob = remote.blob(b"promised").unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(dir.path()).unwrap();
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
+        let promisor = Arc::new(CopyFrom {
+            from: remote_dir.path().to_owned(),
+            to: dir.path().to_owned(),
+            fetched: Default::default(),
+        });
+        let odb = facade(&store, &repo).with_promisor(Some(promisor.clone()));
+
+        assert!(!odb.contains(blob));
+        assert!(!odb.exists(&josh_gix_ext::gix_oid(blob)));
+        assert!(promisor.fetched.lock().unwrap().is_empty());
+
+        let (kind, data) = odb.read(blob).unwrap();
+        assert_eq!((kind, &*data), (Kind::Blob, &b"promised"[..]));
+        assert_eq!(*promisor.fetched.lock().unwrap(), vec![vec![blob]]);
+        assert!(odb.contains(blob));
+
+        // An object the promisor does not have either stays a plain miss.
+        let absent = git2::Oid::from_str("0123456789012345678901234567890123456789").unwrap();
+        assert_eq!(odb.try_kind(absent).unwrap(), None);
+        assert!(odb.read(absent).is_err());
+        assert_eq!(promisor.fetched.lock().unwrap().len(), 3);
+
+        // A prefetch asks for everything still missing at once, and only for that.
+        let more = [remote.blob(b"one").unwrap(), remote.blob(b"two").unwrap()];
+        odb.prefetch([blob, more[0], more[1]]);
+        assert_eq!(promisor.fetched.lock().unwrap()[3], more.to_vec());
+        assert!(more.iter().all(|oid| odb.contains(*oid)));
+        odb.prefetch(more);
+        assert_eq!(promisor.fetched.lock().unwrap().len(), 4);
+    }
+
+    /// Objects above the big-object threshold skip the store and land loose on disk, from
+    /// both write paths, and read back as a stream; smaller ones are buffered and read whole.
+    #[test]
+    fn big_objects_bypass_the_store_and_stream() {
+        use gix_object::Write as _;
+        use std::io::Read as _;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(dir.path()).unwrap();
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
+        let odb = facade(&store, &repo).with_big_object_threshold(16);
+
+        let big = b"a blob above the sixteen byte threshold";
+        let other: &[u8] = b"another big streamed blob";
+        let written = odb.write(Kind::Blob, big);
+        let streamed = odb
+            .write_stream(Kind::Blob, other.len() as u64, &mut &other[..])
+            .unwrap();
+        let small = odb.write(Kind::Blob, b"small");
+
+        for oid in [written, josh_gix_ext::git2_oid(&streamed)] {
+            assert!(!store.contains(&josh_gix_ext::gix_oid(oid)));
+            // On disk before any flush.
+            let fresh = git2::Repository::open(dir.path()).unwrap();
+            assert!(fresh.find_blob(oid).is_ok());
+        }
+        assert_eq!(
+            josh_gix_ext::git2_oid(&streamed),
+            git2::Oid::hash_object(git2::ObjectType::Blob, other).unwrap()
+        );
+        assert!(store.contains(&josh_gix_ext::gix_oid(small)));
+
+        let (kind, size, mut reader) = odb.read_stream(written).unwrap();
+        assert_eq!((kind, size), (Kind::Blob, big.len() as u64));
+        assert!(matches!(reader, Reader::Stream(_)));
+        let mut data = vec![];
+        reader.read_to_end(&mut data).unwrap();
+        assert_eq!(data, big);
+
+        let (_, _, mut reader) = odb.read_stream(small).unwrap();
+        assert!(matches!(reader, Reader::Whole(_)));
+        let mut data = vec![];
+        reader.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"small");
+    }
+
+    /// A big object that only exists in a pack streams from the pack entry.
+    #[test]
+    fn big_packed_objects_stream() {
+        use std::io::Read as _;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_bare(dir.path()).unwrap();
+        let store = MemOdb::new(
+            None,
+            crate::pack::objects_dir(&repo),
+            crate::pack::object_hash(&repo),
+        );
+        let big = b"a blob above the sixteen byte threshold".repeat(100);
+        let oid = facade(&store, &repo).write(Kind::Blob, &big);
+        store.flush().unwrap();
+
+        let odb = facade(&store, &repo).with_big_object_threshold(16);
+        let (kind, size, mut reader) = odb.read_stream(oid).unwrap();
+        assert_eq!((kind, size), (Kind::Blob, big.len() as u64));
+        assert!(matches!(reader, Reader::Packed(_)));
+        let mut data = vec![];
+        reader.read_to_end(&mut data).unwrap();
+        assert_eq!(data, big);
+    }
 }
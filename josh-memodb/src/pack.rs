@@ -5,16 +5,41 @@
 //! repository's pack directory, without ever opening a repository handle. Objects already present
 //! on disk are filtered out first, so a pack contains only genuinely-new objects and the on-disk
 //! layout stays deterministic.
+//!
+//! Filtering produces long runs of near-identical trees (and the distributed cache its shard
+//! commits), so objects are delta-compressed against similar objects of the same kind in the
+//! snapshot, with git's window and chain-depth bounds — see [`select_deltas`]. The runs do not
+//! stop at a flush boundary, so the roots of the previous flushes' delta chains stay candidates
+//! too (see [`RECENT_BASES`]).
 
 use std::io::{Seek, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 
-use gix_object::Exists;
+use gix_hash::ObjectId;
+use gix_object::{Exists, Kind};
 use gix_pack::data::output;
 
+use crate::delta;
 use crate::mem_odb::Snapshot;
 
+/// How many preceding objects in search order each object is tried against as a delta base;
+/// git's `pack.window` default.
+const DELTA_WINDOW: usize = 10;
+
+/// Longest chain of deltas on deltas a reader may have to resolve; git's `pack.depth` default.
+const MAX_DELTA_DEPTH: usize = 50;
+
+/// How many objects of earlier flushes are kept as delta base candidates for the next one. A
+/// delta against such a base is written against its oid, and the base is appended whole when the
+/// pack is indexed, so every pack stays self-contained.
+pub(crate) const RECENT_BASES: usize = 256;
+
+/// Objects smaller than this are stored whole: a delta would hardly save anything. The same
+/// cut-off git's `pack-objects` applies.
+const MIN_DELTA_SIZE: usize = 50;
+
 /// The directory where `repo`'s objects live (`<commondir>/objects`), captured at
 /// [`MemOdb::new`](crate::mem_odb::MemOdb::new) time while a repository handle exists.
 ///
@@ -25,25 +50,109 @@ pub fn objects_dir(repo: &git2::Repository) -> PathBuf {
     repo.commondir().join("objects")
 }
 
+/// The hash of `repo`'s object format (`extensions.objectFormat`), captured alongside
+/// [`objects_dir`] so the store hashes its writes, and indexes its packs, the way the repository
+/// does.
+pub fn object_hash(repo: &git2::Repository) -> gix_hash::Kind {
+    josh_gix_ext::hash_kind(repo.object_format())
+}
+
+/// A reader over an undeltified pack entry, inflating the object as it is read. The pack stays
+/// mapped rather than read, so only the bytes handed out are ever in memory.
+pub struct EntryReader {
+    pack: gix_pack::data::File,
+    /// Where the entry's remaining compressed bytes start.
+    pos: u64,
+    inflate: gix_zlib::Decompress,
+}
+
+impl std::io::Read for EntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut input = self
+            .pack
+            .entry_slice(self.pos..self.pack.pack_end() as u64)
+            .unwrap_or_default();
+        let available = input.len();
+        let read = gix_zlib::stream::inflate::read(&mut input, &mut self.inflate, buf)?;
+        self.pos += (available - input.len()) as u64;
+        Ok(read)
+    }
+}
+
+/// A reader over `oid` from the first pack in `object_dirs` that holds it. `None` when no pack
+/// does, or when the entry is a delta: a delta can only be applied to its base in memory, so
+/// such objects are read whole.
+pub(crate) fn entry_reader(
+    object_dirs: &[PathBuf],
+    object_hash: gix_hash::Kind,
+    oid: &gix_hash::oid,
+) -> Option<EntryReader> {
+    for dir in object_dirs {
+        let Ok(entries) = std::fs::read_dir(dir.join("pack")) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension() != Some("idx".as_ref()) {
+                continue;
+            }
+            let Ok(index) = gix_pack::index::File::at(&path, object_hash) else {
+                continue;
+            };
+            let Some(found) = index.lookup(oid) else {
+                continue;
+            };
+            let pack = gix_pack::data::File::at(path.with_extension("pack"), object_hash).ok()?;
+            let entry = pack.entry(index.pack_offset_at_index(found)).ok()?;
+            if !entry.header.is_base() {
+                return None;
+            }
+            return Some(EntryReader {
+                pack,
+                pos: entry.data_offset,
+                inflate: gix_zlib::Decompress::new(),
+            });
+        }
+    }
+    None
+}
+
 fn pack_error(e: impl std::fmt::Display) -> git2::Error {
     git2::Error::from_str(&format!("mem-odb pack write failed: {e}"))
 }
 
 /// Compress and write the objects of `snapshot` that are not already present in `objects_dir`
 /// (loose, packed, or via alternates) as a single packfile-plus-index pair in
-/// `objects_dir/pack`. A no-op if every object is already on disk.
+/// `objects_dir/pack`, both hashed with `object_hash`. A no-op if every object is already on disk.
 ///
-/// The pack is deterministic for a given snapshot: entries are compressed at a fixed level and
-/// serialized single-threaded in snapshot order, and the file pair is named after the pack
+/// `recent` holds objects packed by earlier flushes, offered as delta bases besides the snapshot's
+/// own; the roots of this pack's delta chains are added in front, keeping [`RECENT_BASES`] of them.
+///
+/// The pack is deterministic for a given snapshot and `recent`: delta bases are chosen by
+/// [`select_deltas`] from those alone, entries are compressed at a fixed level and
+/// serialized single-threaded in snapshot order (each delta's base moved up just ahead of it, as
+/// an offset delta must point backwards), and the file pair is named after the pack
 /// trailer checksum (the same rule libgit2 and modern git use), so identical snapshots produce
 /// identical packs. Files are written via tempfile-and-rename, index last, so a concurrent
 /// reader scanning for `.idx` files never sees a torn pair.
-pub(crate) fn write_snapshot(objects_dir: &Path, snapshot: &Snapshot) -> Result<(), git2::Error> {
+pub(crate) fn write_snapshot(
+    objects_dir: &Path,
+    object_hash: gix_hash::Kind,
+    snapshot: &Snapshot,
+    recent: &mut Snapshot,
+) -> Result<(), git2::Error> {
     // A fresh store handle per flush observes every pack written by previous flushes. Misses are
     // the expected case below, and the default refresh mode re-lists the pack directory on every
     // miss — disable it; the first lookup still loads all indices present now, and loose-object
     // probes stat the filesystem directly either way.
-    let mut odb = gix_odb::at(objects_dir.to_owned()).map_err(pack_error)?;
+    let mut odb = gix_odb::at_opts(
+        objects_dir.to_owned(),
+        Vec::new(),
+        gix_odb::store::init::Options {
+            object_hash,
+            ..Default::default()
+        },
+    )
+    .map_err(pack_error)?;
     odb.refresh = gix_odb::store::RefreshMode::Never;
 
     let to_pack: Vec<_> = snapshot
@@ -54,6 +163,8 @@ pub(crate) fn write_snapshot(objects_dir: &Path, snapshot: &Snapshot) -> Result<
     if to_pack.is_empty() {
         return Ok(());
     }
+    // A base that has gone from disk since (a concurrent gc) could not complete the pack.
+    recent.retain(|(oid, _, _)| odb.exists(oid));
     let num_entries = u32::try_from(to_pack.len())
         .map_err(|_| pack_error("more objects in one flush than a pack header can count"))?;
 
@@ -66,20 +177,53 @@ pub(crate) fn write_snapshot(objects_dir: &Path, snapshot: &Snapshot) -> Result<
     // a snapshot's size is only *typically* bounded by the store's chunk limit (unbounded stores
     // exist, and the limit is an overflow trigger, not a cap).
     let mut spool = std::io::BufWriter::new(tempfile::tempfile_in(&pack_dir).map_err(pack_error)?);
+    let bases: Vec<_> = recent
+        .iter()
+        .map(|(oid, kind, data)| (*oid, *kind, data))
+        .collect();
+    let deltas = select_deltas(&to_pack, &bases);
+    let order = write_order(&deltas);
+    let mut position = vec![0; to_pack.len()];
+    for (pos, &i) in order.iter().enumerate() {
+        position[i] = pos;
+    }
     let mut iter = output::bytes::FromEntriesIter::new(
-        to_pack.iter().map(|(oid, kind, data)| {
-            output::Entry::from_data(
-                &output::Count::from_data(*oid, None),
-                // Fixed level 6, the zlib/libgit2/git `pack.compression` default.
-                &gix_object::Data::new(data, *kind, gix_hash::Kind::Sha1),
-                gix_zlib::Compression::DEFAULT,
-            )
+        order.iter().map(|&i| {
+            let (oid, kind, data) = &to_pack[i];
+            let count = output::Count::from_data(*oid, None);
+            // Fixed level 6, the zlib/libgit2/git `pack.compression` default.
+            let compression = gix_zlib::Compression::DEFAULT;
+            match &deltas[i] {
+                None => output::Entry::from_data(
+                    &count,
+                    &gix_object::Data::new(data, *kind, object_hash),
+                    compression,
+                ),
+                // Deflate the delta the way a whole object is, then retag the entry: the
+                // serializer turns a `DeltaRef` into an offset back to the base's position.
+                // A recent base is not in this pack: refer to it by oid, for the indexer to
+                // append it.
+                Some((base, delta)) => output::Entry::from_data(
+                    &count,
+                    &gix_object::Data::new(delta, *kind, object_hash),
+                    compression,
+                )
+                .map(|entry| output::Entry {
+                    kind: match position.get(*base) {
+                        Some(&object_index) => output::entry::Kind::DeltaRef { object_index },
+                        None => output::entry::Kind::DeltaOid {
+                            id: bases[*base - to_pack.len()].0,
+                        },
+                    },
+                    ..entry
+                }),
+            }
             .map(|entry| vec![entry])
         }),
         &mut spool,
         num_entries,
         gix_pack::data::Version::V2,
-        gix_hash::Kind::Sha1,
+        object_hash,
     );
     for written in &mut iter {
         written.map_err(pack_error)?;
@@ -89,19 +233,25 @@ pub(crate) fn write_snapshot(objects_dir: &Path, snapshot: &Snapshot) -> Result<
     let mut spool = spool.into_inner().map_err(pack_error)?;
     spool.rewind().map_err(pack_error)?;
 
+    // Deltas against recent bases make this a thin pack; the indexer completes it by appending
+    // each such base, read back from disk, once.
+    let thin = deltas
+        .iter()
+        .flatten()
+        .any(|(base, _)| *base >= to_pack.len());
     let outcome = gix_pack::Bundle::write_to_directory(
         &mut std::io::BufReader::new(spool),
         Some(&pack_dir),
         &mut gix_features::progress::Discard,
         &AtomicBool::new(false),
-        None::<gix_object::find::Never>,
+        thin.then(|| odb.clone()),
         gix_pack::bundle::write::Options {
             thread_limit: Some(1),
             iteration_mode: gix_pack::data::input::Mode::Verify,
             index_version: gix_pack::index::Version::V2,
-            object_hash: gix_hash::Kind::Sha1,
+            object_hash,
             alloc_limit_bytes: None,
-            // Only used to complete thin packs, which this never writes.
+            // Only used for the bases appended to complete a thin pack.
             compression: gix_zlib::Compression::DEFAULT,
         },
     )
@@ -120,5 +270,88 @@ pub(crate) fn write_snapshot(objects_dir: &Path, snapshot: &Snapshot) -> Result<
             }
         }
     }
+
+    let roots: Snapshot = deltas
+        .iter()
+        .zip(&to_pack)
+        .filter(|(delta, (_, _, data))| delta.is_none() && data.len() >= MIN_DELTA_SIZE)
+        .map(|(_, (oid, kind, data))| (*oid, *kind, Arc::clone(data)))
+        .collect();
+    recent.splice(0..0, roots);
+    recent.truncate(RECENT_BASES);
     Ok(())
 }
+
+/// Choose a delta base for each object of `objects`, as `(base index, delta)`, or `None` to
+/// store it whole. `recent` are further base candidates, already on disk; an index past the end
+/// of `objects` points into them.
+///
+/// Mirrors git's `pack-objects` search: objects and candidates are sorted by kind, then size
+/// descending (ties broken by oid, so the order depends on the inputs alone), and each object is
+/// tried against the [`DELTA_WINDOW`] entries before it of the same kind, keeping the smallest
+/// delta under half the object's size. Bases always precede their deltas in this order, so chains
+/// cannot cycle; a base already [`MAX_DELTA_DEPTH`] deltas deep is passed over. A recent base is
+/// stored whole in the completed pack, so it starts a chain.
+fn select_deltas(
+    objects: &[(ObjectId, Kind, &Arc<[u8]>)],
+    recent: &[(ObjectId, Kind, &Arc<[u8]>)],
+) -> Vec<Option<(usize, Vec<u8>)>> {
+    let objects: Vec<_> = objects.iter().chain(recent).collect();
+    let mut search: Vec<usize> = (0..objects.len()).collect();
+    search.sort_by(|&a, &b| {
+        let (a_oid, a_kind, a_data) = &objects[a];
+        let (b_oid, b_kind, b_data) = &objects[b];
+        (a_kind, std::cmp::Reverse(a_data.len()), a_oid).cmp(&(
+            b_kind,
+            std::cmp::Reverse(b_data.len()),
+            b_oid,
+        ))
+    });
+
+    let packed = objects.len() - recent.len();
+    let mut deltas: Vec<Option<(usize, Vec<u8>)>> = vec![None; objects.len()];
+    let mut depth = vec![0; objects.len()];
+    for (n, &target) in search.iter().enumerate() {
+        let (_, kind, data) = &objects[target];
+        if target >= packed || data.len() < MIN_DELTA_SIZE {
+            continue;
+        }
+        // Leave room for a hash's worth of entry header, as git does.
+        let mut max_size = (data.len() / 2).saturating_sub(objects[target].0.kind().len_in_bytes());
+        for &base in search[n.saturating_sub(DELTA_WINDOW)..n].iter().rev() {
+            let (_, base_kind, base_data) = &objects[base];
+            if base_kind != kind || depth[base] >= MAX_DELTA_DEPTH {
+                continue;
+            }
+            if let Some(delta) = delta::encode(base_data, data, max_size) {
+                // Strictly smaller only, so the nearest base wins ties.
+                max_size = delta.len().saturating_sub(1);
+                depth[target] = depth[base] + 1;
+                deltas[target] = Some((base, delta));
+            }
+        }
+    }
+    deltas.truncate(packed);
+    deltas
+}
+
+/// The order to write objects in: snapshot order, except that a delta's base chain is written
+/// just before it when not already written. A snapshot without deltas is written as-is. Recent
+/// bases are not written here, see [`select_deltas`].
+fn write_order(deltas: &[Option<(usize, Vec<u8>)>]) -> Vec<usize> {
+    let mut written = vec![false; deltas.len()];
+    let mut order = Vec::with_capacity(deltas.len());
+    let mut chain = Vec::new();
+    for i in 0..deltas.len() {
+        let mut next = Some(i);
+        while let Some(j) = next.filter(|&j| j < deltas.len() && !written[j]) {
+            chain.push(j);
+            next = deltas[j].as_ref().map(|(base, _)| *base);
+        }
+        while let Some(j) = chain.pop() {
+            written[j] = true;
+            order.push(j);
+        }
+    }
+    order
+}
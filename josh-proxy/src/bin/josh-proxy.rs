@@ -278,6 +278,7 @@ fn pre_receive_hook() -> anyhow::Result<i32> {
 }
 
 fn main() {
+    josh_core::git::configure_libgit2();
     // josh-proxy creates a symlink to itself as a git update hook.
     // When it gets called by git as that hook, the binary name will end
     // end in "/update" and this will not be a new server.
@@ -1,12 +1,16 @@
-use std::{error::Error, fmt, sync::Arc};
+use std::{collections::HashMap, error::Error, fmt, sync::Arc};
 
+use futures::{SinkExt, StreamExt};
 use juniper::{
     GraphQLSubscriptionType, GraphQLType, GraphQLTypeAsync, InputValue, RootNode, ScalarValue,
+    Value,
     http::{GraphQLBatchRequest, GraphQLRequest as JuniperGraphQLRequest, GraphQLRequest},
 };
 use serde_json::error::Error as SerdeError;
+use serde_json::json;
 use url::form_urlencoded;
 
+use axum::extract::ws::{CloseFrame, Message, WebSocket};
 use axum::http::{Method, StatusCode};
 use axum::response::{IntoResponse, Response};
 
@@ -172,6 +176,221 @@ where
     (code, axum::response::Json(&res)).into_response()
 }
 
+/// Serve subscriptions over a WebSocket speaking the `graphql-transport-ws` protocol.
+/// `make_context` is called for every subscription the client starts.
+pub async fn serve_ws<CtxT, QueryT, MutationT, SubscriptionT, S, F>(
+    socket: WebSocket,
+    root_node: Arc<RootNode<QueryT, MutationT, SubscriptionT, S>>,
+    make_context: F,
+) where
+    QueryT: GraphQLTypeAsync<S, Context = CtxT> + Send + Sync + 'static,
+    QueryT::TypeInfo: Send + Sync,
+    MutationT: GraphQLTypeAsync<S, Context = CtxT> + Send + Sync + 'static,
+    MutationT::TypeInfo: Send + Sync,
+    SubscriptionT: GraphQLSubscriptionType<S, Context = CtxT> + Send + Sync + 'static,
+    SubscriptionT::TypeInfo: Send + Sync,
+    CtxT: Send + Sync + 'static,
+    S: ScalarValue + Send + Sync + 'static,
+    F: Fn() -> anyhow::Result<CtxT> + Send + Sync + 'static,
+{
+    let (mut sink, mut stream) = socket.split();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Message>();
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let close = matches!(message, Message::Close(_));
+            if sink.send(message).await.is_err() || close {
+                break;
+            }
+        }
+    });
+
+    let make_context = Arc::new(make_context);
+    let mut acknowledged = false;
+    let mut running = HashMap::<String, tokio::task::AbortHandle>::new();
+
+    while let Some(Ok(message)) = stream.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let message = match serde_json::from_str::<ClientMessage<S>>(&text) {
+            Ok(message) => message,
+            Err(e) => {
+                tx.send(ws_close(4400, &e.to_string())).ok();
+                break;
+            }
+        };
+
+        match message {
+            ClientMessage::ConnectionInit { .. } if acknowledged => {
+                tx.send(ws_close(4429, "Too many initialisation requests"))
+                    .ok();
+                break;
+            }
+            ClientMessage::ConnectionInit { .. } => {
+                acknowledged = true;
+                tx.send(ws_message(json!({ "type": "connection_ack" })))
+                    .ok();
+            }
+            ClientMessage::Ping { .. } => {
+                tx.send(ws_message(json!({ "type": "pong" }))).ok();
+            }
+            ClientMessage::Pong { .. } => {}
+            ClientMessage::Subscribe { .. } if !acknowledged => {
+                tx.send(ws_close(4401, "Unauthorized")).ok();
+                break;
+            }
+            ClientMessage::Subscribe { id, payload } => {
+                running.retain(|_, task| !task.is_finished());
+                if running.contains_key(&id) {
+                    tx.send(ws_close(
+                        4409,
+                        &format!("Subscriber for {} already exists", id),
+                    ))
+                    .ok();
+                    break;
+                }
+
+                let task = tokio::spawn(run_subscription(
+                    id.clone(),
+                    payload,
+                    root_node.clone(),
+                    make_context.clone(),
+                    tx.clone(),
+                ));
+                running.insert(id, task.abort_handle());
+            }
+            ClientMessage::Complete { id } => {
+                if let Some(task) = running.remove(&id) {
+                    task.abort();
+                }
+            }
+        }
+    }
+
+    for task in running.values() {
+        task.abort();
+    }
+    drop(tx);
+    writer.await.ok();
+}
+
+async fn run_subscription<CtxT, QueryT, MutationT, SubscriptionT, S, F>(
+    id: String,
+    request: GraphQLRequest<S>,
+    root_node: Arc<RootNode<QueryT, MutationT, SubscriptionT, S>>,
+    make_context: Arc<F>,
+    tx: tokio::sync::mpsc::UnboundedSender<Message>,
+) where
+    QueryT: GraphQLTypeAsync<S, Context = CtxT>,
+    QueryT::TypeInfo: Sync,
+    MutationT: GraphQLTypeAsync<S, Context = CtxT>,
+    MutationT::TypeInfo: Sync,
+    SubscriptionT: GraphQLSubscriptionType<S, Context = CtxT>,
+    SubscriptionT::TypeInfo: Sync,
+    CtxT: Sync,
+    S: ScalarValue + Send + Sync,
+    F: Fn() -> anyhow::Result<CtxT>,
+{
+    let error = |errors: serde_json::Value| {
+        ws_message(json!({ "id": id, "type": "error", "payload": errors }))
+    };
+
+    let context = match make_context() {
+        Ok(context) => context,
+        Err(e) => {
+            tx.send(error(json!([{ "message": e.to_string() }]))).ok();
+            return;
+        }
+    };
+
+    let (value, errors) =
+        match juniper::http::resolve_into_stream(&request, &root_node, &context).await {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                tx.send(error(serde_json::to_value(&e).unwrap_or_default()))
+                    .ok();
+                return;
+            }
+        };
+
+    if !errors.is_empty() {
+        tx.send(error(serde_json::to_value(&errors).unwrap_or_default()))
+            .ok();
+        return;
+    }
+
+    // Every root field of the subscription is a stream of its own; their items are sent as
+    // they come, each as a result carrying just that field.
+    let streams = match value {
+        Value::Object(fields) => fields
+            .into_iter()
+            .filter_map(|(name, value)| match value {
+                Value::Scalar(stream) => Some(stream.map(move |item| (name.clone(), item))),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let mut items = futures::stream::select_all(streams);
+
+    while let Some((name, item)) = items.next().await {
+        let payload = match item {
+            Ok(value) => json!({ "data": { name: value } }),
+            Err(e) => json!({ "data": null, "errors": [e] }),
+        };
+        let next = json!({ "id": id, "type": "next", "payload": payload });
+        if tx.send(ws_message(next)).is_err() {
+            return;
+        }
+    }
+
+    tx.send(ws_message(json!({ "id": id, "type": "complete" })))
+        .ok();
+}
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[serde(bound(deserialize = "S: ScalarValue"))]
+enum ClientMessage<S: ScalarValue> {
+    ConnectionInit {
+        #[serde(default)]
+        #[allow(dead_code)]
+        payload: Option<serde_json::Value>,
+    },
+    Ping {
+        #[serde(default)]
+        #[allow(dead_code)]
+        payload: Option<serde_json::Value>,
+    },
+    Pong {
+        #[serde(default)]
+        #[allow(dead_code)]
+        payload: Option<serde_json::Value>,
+    },
+    Subscribe {
+        id: String,
+        payload: GraphQLRequest<S>,
+    },
+    Complete {
+        id: String,
+    },
+}
+
+fn ws_message(message: serde_json::Value) -> Message {
+    Message::Text(message.to_string().into())
+}
+
+fn ws_close(code: u16, reason: &str) -> Message {
+    Message::Close(Some(CloseFrame {
+        code,
+        reason: reason.into(),
+    }))
+}
+
 fn gql_request_from_get<S>(input: &str) -> Result<JuniperGraphQLRequest<S>, GraphQLRequestError>
 where
     S: ScalarValue,
@@ -434,4 +653,119 @@ mod tests {
     async fn test_sync_axum_integration() {
         run_axum_integration(true).await
     }
+
+    #[tokio::test]
+    async fn test_ws_subscriptions() {
+        use futures::{SinkExt, StreamExt};
+        use juniper::tests::fixtures::starwars::schema::Subscription;
+        use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+        let root_node = Arc::new(RootNode::new(
+            Query,
+            EmptyMutation::<Database>::new(),
+            Subscription,
+        ));
+
+        let app = Router::new().route(
+            "/graphql",
+            axum::routing::get(move |ws: axum::extract::ws::WebSocketUpgrade| async move {
+                ws.protocols(["graphql-transport-ws"])
+                    .on_upgrade(move |socket| {
+                        super::serve_ws(socket, root_node, || Ok(Database::new()))
+                    })
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::task::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let connect = || async {
+            let mut request = format!("ws://{}/graphql", addr)
+                .into_client_request()
+                .unwrap();
+            request.headers_mut().insert(
+                "sec-websocket-protocol",
+                "graphql-transport-ws".parse().unwrap(),
+            );
+            let (socket, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+            assert_eq!(
+                response.headers()["sec-websocket-protocol"],
+                "graphql-transport-ws"
+            );
+            socket
+        };
+
+        let mut socket = connect().await;
+        let mut exchange = async |message: serde_json::Value| {
+            socket
+                .send(tungstenite::Message::text(message.to_string()))
+                .await
+                .unwrap();
+            match socket.next().await.unwrap().unwrap() {
+                tungstenite::Message::Text(text) => {
+                    serde_json::from_str::<serde_json::Value>(&text).unwrap()
+                }
+                tungstenite::Message::Close(frame) => {
+                    serde_json::json!({ "close": u16::from(frame.unwrap().code) })
+                }
+                other => panic!("unexpected message: {:?}", other),
+            }
+        };
+
+        let subscribe = serde_json::json!({
+            "id": "1",
+            "type": "subscribe",
+            "payload": { "query": "subscription { asyncHuman { name } }" },
+        });
+
+        assert_eq!(
+            exchange(subscribe.clone()).await,
+            serde_json::json!({ "close": 4401 })
+        );
+
+        // Without a message to send, the next one from the server is read.
+        let mut socket = connect().await;
+        let mut exchange = async |message: Option<serde_json::Value>| {
+            if let Some(message) = message {
+                socket
+                    .send(tungstenite::Message::text(message.to_string()))
+                    .await
+                    .unwrap();
+            }
+            let text = socket.next().await.unwrap().unwrap().into_text().unwrap();
+            serde_json::from_str::<serde_json::Value>(&text).unwrap()
+        };
+
+        assert_eq!(
+            exchange(Some(serde_json::json!({ "type": "connection_init" }))).await,
+            serde_json::json!({ "type": "connection_ack" })
+        );
+        assert_eq!(
+            exchange(Some(serde_json::json!({ "type": "ping" }))).await,
+            serde_json::json!({ "type": "pong" })
+        );
+        assert_eq!(
+            exchange(Some(subscribe)).await,
+            serde_json::json!({
+                "id": "1",
+                "type": "next",
+                "payload": { "data": { "asyncHuman": { "name": "Luke Skywalker" } } },
+            })
+        );
+        assert_eq!(
+            exchange(None).await,
+            serde_json::json!({ "id": "1", "type": "complete" })
+        );
+
+        let error = exchange(Some(serde_json::json!({
+            "id": "2",
+            "type": "subscribe",
+            "payload": { "query": "subscription { nope }" },
+        })))
+        .await;
+        assert_eq!(error["id"], "2");
+        assert_eq!(error["type"], "error");
+        assert!(error["payload"][0]["message"].is_string());
+    }
 }
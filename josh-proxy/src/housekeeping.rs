@@ -117,11 +117,15 @@ impl From<CommandResult> for ParsedCommandResult<CountObjectsOutput> {
 }
 
 fn run_command(path: &Path, cmd: &[&str]) -> CommandResult {
+    run_command_env(path, cmd, &[])
+}
+
+fn run_command_env(path: &Path, cmd: &[&str], env: &[(&str, &str)]) -> CommandResult {
     let shell = shell::Shell {
         cwd: path.to_owned(),
     };
 
-    let (stdout, stderr, code) = shell.command(cmd);
+    let (stdout, stderr, code) = shell.command_env(cmd, env, &[]);
 
     if let Some(code) = std::num::NonZero::new(code) {
         CommandResult::Err { code, stderr }
@@ -168,6 +172,29 @@ pub fn run(
         josh_core::housekeeping::refresh_known_filters(&transaction_mirror, &transaction_overlay)?;
     }
 
+    // The overlay's commits have parents in the mirror, which a `git` subprocess only finds
+    // through an explicit alternate.
+    let commit_graph_write = [&["git"], housekeeping::COMMIT_GRAPH_WRITE].concat();
+    trace_command_result!(
+        run_command(transaction_mirror.path(), &commit_graph_write),
+        "mirror",
+        "commit_graph"
+    );
+
+    let mirror_objects = repo_path.join("mirror").join("objects");
+    trace_command_result!(
+        run_command_env(
+            transaction_overlay.path(),
+            &commit_graph_write,
+            &[(
+                "GIT_ALTERNATE_OBJECT_DIRECTORIES",
+                mirror_objects.to_str().unwrap()
+            )]
+        ),
+        "overlay",
+        "commit_graph"
+    );
+
     if do_gc {
         trace_command_result!(
             run_command(
@@ -1,4 +1,4 @@
-use axum::body::Body;
+use axum::body::{Body, Bytes};
 use axum::http::{Response, StatusCode};
 use axum::response::IntoResponse;
 use futures::Stream;
@@ -69,6 +69,67 @@ where
     }
 }
 
+/// A blocking writer feeding a response body, for bodies produced on a blocking thread while
+/// they are sent. Bytes go out in chunks; `finish` sends the rest, or cuts the body off with
+/// an error so a failed download doesn't look complete.
+pub struct BodyWriter {
+    tx: tokio::sync::mpsc::Sender<Result<Bytes, std::io::Error>>,
+    buf: Vec<u8>,
+}
+
+const BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+pub fn body_writer() -> (BodyWriter, Body) {
+    let (tx, rx) = tokio::sync::mpsc::channel(4);
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|item| (item, rx))
+    });
+    let writer = BodyWriter {
+        tx,
+        buf: Vec::with_capacity(BODY_CHUNK_SIZE),
+    };
+    (writer, Body::from_stream(stream))
+}
+
+impl BodyWriter {
+    fn send(&mut self, item: Result<Bytes, std::io::Error>) -> std::io::Result<()> {
+        self.tx.blocking_send(item).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "response body dropped")
+        })
+    }
+
+    pub fn finish(mut self, result: anyhow::Result<()>) -> anyhow::Result<()> {
+        match result {
+            Ok(()) => Ok(std::io::Write::flush(&mut self)?),
+            Err(e) => {
+                let _ = self.send(Err(std::io::Error::other(e.to_string())));
+                Err(e)
+            }
+        }
+    }
+}
+
+impl std::io::Write for BodyWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        if self.buf.len() >= BODY_CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.buf.is_empty() {
+            let chunk = Bytes::from(std::mem::replace(
+                &mut self.buf,
+                Vec::with_capacity(BODY_CHUNK_SIZE),
+            ));
+            self.send(Ok(chunk))?;
+        }
+        Ok(())
+    }
+}
+
 pub fn make_exponential_backoff(max_retries: usize) -> backon::ExponentialBuilder {
     backon::ExponentialBuilder::default()
         .with_min_delay(std::time::Duration::from_secs(1))
@@ -214,6 +214,37 @@ pub fn fetch_refs_from_url(
     Ok(())
 }
 
+/// Read the mirror refs of `upstream_repo`, keyed by their full name.
+pub fn upstream_refs(
+    path: &std::path::Path,
+    upstream_repo: &str,
+) -> anyhow::Result<std::collections::HashMap<String, git2::Oid>> {
+    let prefix = format!("refs/josh/upstream/{}/", josh_core::to_ns(upstream_repo));
+    let repo = git2::Repository::open(path)?;
+    let refs = josh_core::cache::RefStore::open(&repo).prefixed(&prefix)?;
+    Ok(refs.into_iter().collect())
+}
+
+/// The refs that were created, moved or deleted between two snapshots taken by `upstream_refs`.
+pub fn ref_changes(
+    before: &std::collections::HashMap<String, git2::Oid>,
+    after: &std::collections::HashMap<String, git2::Oid>,
+) -> Vec<josh_graphql::graphql::RefChange> {
+    let mut names: Vec<_> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter(|name| before.get(*name) != after.get(*name))
+        .map(|name| josh_graphql::graphql::RefChange {
+            refname: name.clone(),
+            old: before.get(name).copied(),
+            new: after.get(name).copied(),
+        })
+        .collect()
+}
+
 type IoCleanupSender = tokio::sync::mpsc::UnboundedSender<service::IoCleanup>;
 
 pub struct TmpGitNamespace {
@@ -304,7 +335,7 @@ pub fn merge_meta(
         let meta_commit = josh_core::objects::CommitData::read(&odb, meta_oid)?;
         (meta_commit.tree_id()?, vec![meta_oid])
     } else {
-        (josh_core::filter::tree::empty_id(), vec![])
+        (josh_core::filter::tree::empty_id(odb.object_hash()), vec![])
     };
 
     for (path, add_lines) in meta_add.iter() {
@@ -17,6 +17,7 @@ use josh_rpc::calls::RequestedCommand;
 
 use axum::Router;
 use axum::body::Body;
+use axum::extract::ws::{WebSocketUpgrade, rejection::WebSocketUpgradeRejection};
 use axum::extract::{FromRequestParts, State};
 use axum::http::request::Parts;
 use axum::http::{Request, Response, StatusCode, header};
@@ -166,6 +167,8 @@ pub struct JoshProxyService {
     pub fetch_permits: Arc<std::sync::Mutex<HashMap<String, Arc<tokio::sync::Semaphore>>>>,
     pub filter_permits: Arc<tokio::sync::Semaphore>,
     pub poll: Polls,
+    // Mirror ref changes made by fetches, for GraphQL subscriptions
+    pub ref_changes: tokio::sync::broadcast::Sender<graphql::RefChange>,
     pub io_thread_tx: Option<tokio::sync::mpsc::UnboundedSender<IoCleanup>>,
     pub http_retry: usize,
 }
@@ -231,6 +234,7 @@ pub fn make_service(
         fetch_permits: Default::default(),
         filter_permits: Arc::new(tokio::sync::Semaphore::new(10)),
         poll: Default::default(),
+        ref_changes: tokio::sync::broadcast::channel(256).0,
         io_thread_tx,
         http_retry: http_retry.unwrap_or(3),
     }))
@@ -494,6 +498,43 @@ async fn handle_filters(service: Arc<JoshProxyService>, refresh: bool) -> impl I
     (StatusCode::OK, body_str).into_response()
 }
 
+#[derive(serde::Deserialize)]
+struct CacheGcParams {
+    /// Size bound, as accepted by `josh cache gc --max-size`
+    max_size: Option<String>,
+    /// Age bound in days
+    max_age: Option<u64>,
+}
+
+/// `/cache/stats` and `/cache/gc`: per-filter usage of the sled cache, and eviction from it.
+/// Both answer with the affected filters as JSON.
+async fn handle_cache(params: Option<CacheGcParams>) -> impl IntoResponse {
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        let stats = match params {
+            None => josh_core::cache::sled_stats()?,
+            Some(params) => {
+                let max_size = params
+                    .max_size
+                    .as_deref()
+                    .map(josh_core::filter::BlobSelect::parse_size)
+                    .transpose()?;
+                let max_age = params
+                    .max_age
+                    .map(|days| std::time::Duration::from_secs(days * 86400));
+                josh_core::cache::sled_gc(max_size, max_age)?
+            }
+        };
+        Ok(serde_json::to_string_pretty(&stats)?)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(body)) => (StatusCode::OK, body).into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 fn resolve_upstream_ref(
     transaction: &josh_core::cache::Transaction,
     repo: &str,
@@ -1390,6 +1431,54 @@ async fn prepare_namespace(
     Ok((temp_ns, refs))
 }
 
+fn graphql_context(
+    serv: &JoshProxyService,
+    upstream_repo: &str,
+) -> anyhow::Result<graphql::Context> {
+    let transaction_mirror = serv.open_mirror(Some(&format!(
+        "refs/josh/upstream/{}/",
+        &josh_core::to_ns(upstream_repo),
+    )))?;
+
+    Ok(graphql::context(open_overlay(serv)?, transaction_mirror))
+}
+
+/// The overlay transaction, with the mirror's objects at hand.
+fn open_overlay(serv: &JoshProxyService) -> anyhow::Result<josh_core::cache::Transaction> {
+    let transaction = serv.open_overlay(None)?;
+
+    transaction.add_disk_alternate(
+        serv.repo_path
+            .join("mirror")
+            .join("objects")
+            .to_str()
+            .unwrap(),
+    )?;
+
+    Ok(transaction)
+}
+
+fn ref_changes_stream(
+    receiver: tokio::sync::broadcast::Receiver<graphql::RefChange>,
+) -> graphql::RefChanges {
+    use tokio::sync::broadcast::error::RecvError;
+
+    Box::pin(futures::stream::unfold(
+        receiver,
+        |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(change) => return Some((change, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "subscriber lagging behind, ref changes dropped");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    ))
+}
+
 async fn handle_graphql(
     State(serv): State<Arc<JoshProxyService>>,
     HttpUpstream(upstream): HttpUpstream,
@@ -1399,6 +1488,7 @@ async fn handle_graphql(
     content_type: Option<axum_extra::extract::TypedHeader<axum_extra::headers::ContentType>>,
     axum::extract::RawQuery(query): axum::extract::RawQuery,
     auth: Option<axum::extract::Extension<crate::auth::Handle>>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
     body: String,
 ) -> Result<impl IntoResponse, ProxyError> {
     use axum_extra::response::ErasedJson;
@@ -1424,27 +1514,6 @@ async fn handle_graphql(
             .expect("Failed to build response"));
     }
 
-    let parsed = match crate::graphql::parse_req(method, content_type, query, body).await {
-        Ok(r) => r,
-        Err(resp) => return Ok(resp),
-    };
-
-    let transaction_mirror = serv.open_mirror(Some(&format!(
-        "refs/josh/upstream/{}/",
-        &josh_core::to_ns(upstream_repo),
-    )))?;
-
-    let transaction = serv.open_overlay(None)?;
-
-    transaction.add_disk_alternate(
-        serv.repo_path
-            .join("mirror")
-            .join("objects")
-            .to_str()
-            .unwrap(),
-    )?;
-
-    let context = Arc::new(graphql::context(transaction, transaction_mirror));
     let root_node = Arc::new(graphql::repo_schema(
         format!(
             "/{}",
@@ -1453,6 +1522,37 @@ async fn handle_graphql(
         false,
     ));
 
+    // Subscriptions are served on a WebSocket; the updates they see come from the fetches
+    // done for other requests, polling and `/~/notify`.
+    if let Ok(ws) = ws {
+        let upstream_repo = upstream_repo.to_string();
+        let make_context = move || {
+            let context = graphql_context(&serv, &upstream_repo)?;
+            let open_serv = serv.clone();
+            *context.ref_changes.lock().unwrap() = Some(graphql::RefChangeFeed {
+                changes: ref_changes_stream(serv.ref_changes.subscribe()),
+                open_transaction: Arc::new(move || open_overlay(&open_serv)),
+            });
+            Ok(context)
+        };
+
+        return Ok(ws
+            .protocols(["graphql-transport-ws"])
+            .on_upgrade(move |socket| crate::graphql::serve_ws(socket, root_node, make_context)));
+    }
+
+    let parsed = match crate::graphql::parse_req(method, content_type, query, body).await {
+        Ok(r) => r,
+        Err(resp) => return Ok(resp),
+    };
+
+    let mut context = graphql_context(&serv, upstream_repo)?;
+    context.user = auth
+        .parse()
+        .map(|(user, _)| user)
+        .filter(|user| !user.is_empty());
+    let context = Arc::new(context);
+
     let remote_auth = RemoteAuth::Http { auth };
     let res = {
         // First attempt to serve GraphQL query. If we can serve it
@@ -1516,10 +1616,10 @@ async fn handle_graphql(
             &*context.transaction_mirror.lock().unwrap(),
             &*context.meta_add.lock().unwrap(),
         )? {
-            to_push.insert((oid, refname, None));
+            to_push.insert((oid, refname, None, false));
         }
 
-        for (oid, refname, repo) in to_push {
+        for (oid, refname, repo, force) in to_push {
             let url = if let Some(repo) = repo {
                 format!("{}/{}", upstream, repo)
             } else {
@@ -1539,7 +1639,7 @@ async fn handle_graphql(
                 &remote_auth,
                 temp_ns.name(),
                 "QUERY_PUSH",
-                false,
+                force,
             )?;
         }
 
@@ -1551,19 +1651,433 @@ async fn handle_graphql(
     Ok(response)
 }
 
+// Webhook for upstream pushes: fetch right away, so subscribers see the change without
+// waiting for polling or the next request.
+async fn handle_notify(
+    State(serv): State<Arc<JoshProxyService>>,
+    HttpUpstream(upstream): HttpUpstream,
+    axum::extract::Path(path): axum::extract::Path<String>,
+    auth: Option<axum::extract::Extension<crate::auth::Handle>>,
+) -> Result<Response<Body>, ProxyError> {
+    let auth = auth
+        .map(|auth| auth.0)
+        .unwrap_or(crate::auth::Handle { hash: None });
+
+    let upstream_repo = path.trim_start_matches('/');
+    let remote_url = format!("{}/{}", upstream, upstream_repo);
+
+    let unauthorized = || {
+        Response::builder()
+            .header(header::WWW_AUTHENTICATE, "Basic realm=User Visible Realm")
+            .status(StatusCode::UNAUTHORIZED)
+            .body(Body::default())
+    };
+
+    if !crate::auth::check_http_auth(&remote_url, &auth, serv.require_auth, serv.http_retry).await?
+    {
+        return Ok(unauthorized()?);
+    }
+
+    match crate::upstream::fetch_upstream(
+        serv.clone(),
+        upstream_repo,
+        &RemoteAuth::Http { auth },
+        remote_url,
+        None,
+        None,
+        true,
+    )
+    .in_current_span()
+    .await
+    {
+        Ok(_) => Ok(StatusCode::NO_CONTENT.into_response()),
+        Err(FetchError::AuthRequired) => Ok(unauthorized()?),
+        Err(FetchError::Other(e)) => Err(e.into()),
+    }
+}
+
 async fn handle_graphiql(
     axum::extract::Path(path): axum::extract::Path<String>,
 ) -> Result<impl IntoResponse, ProxyError> {
     let upstream_repo = path.trim_start_matches('/');
     let addr = format!("/~/graphql/{}", upstream_repo);
 
-    let response = tokio::task::spawn_blocking(move || crate::graphql::graphiql(&addr, None))
-        .await
-        .map_err(|e| ProxyError(anyhow!("{}", e)))?;
+    let response =
+        tokio::task::spawn_blocking(move || crate::graphql::graphiql(&addr, Some(&addr)))
+            .await
+            .map_err(|e| ProxyError(anyhow!("{}", e)))?;
 
     Ok(response.into_response())
 }
 
+#[derive(Clone, Copy, Debug)]
+enum Download {
+    Raw,
+    TarGz,
+    Zip,
+}
+
+/// Content type for a raw file, guessed from its name; unknown names are text unless the blob
+/// looks binary. Anything a browser would run scripts from (html, svg, xml, js) is served as
+/// plain text, so repository content can't act on the proxy's origin.
+fn raw_content_type(path: &str, binary: bool) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("pdf") => "application/pdf",
+        Some("wasm") => "application/wasm",
+        Some("zip") => "application/zip",
+        Some("gz" | "tgz") => "application/gzip",
+        Some("tar") => "application/x-tar",
+        _ if binary => "application/octet-stream",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+/// Resolve the shortest ref at the start of `segments` that exists, leaving at least `keep`
+/// segments behind: a commit sha, `HEAD`, a full refname, or a branch or tag name. Returns the
+/// commit and the number of segments the ref took.
+fn resolve_download_ref(
+    transaction_mirror: &josh_core::cache::Transaction,
+    segments: &[&str],
+    keep: usize,
+) -> anyhow::Result<Option<(git2::Oid, usize)>> {
+    for taken in 1..=segments.len().saturating_sub(keep) {
+        let name = segments[..taken].join("/");
+        if taken == 1
+            && let Ok(oid) = git2::Oid::from_str(&name)
+            && name.len() == oid.to_string().len()
+        {
+            return Ok(Some((oid, taken)));
+        }
+        let candidates = if name == "HEAD" || name.starts_with("refs/") {
+            vec![name]
+        } else {
+            vec![
+                format!("refs/heads/{}", name),
+                format!("refs/tags/{}", name),
+            ]
+        };
+        for candidate in candidates {
+            if let Some(oid) =
+                transaction_mirror.resolve_ref(&transaction_mirror.refname(&candidate))?
+            {
+                return Ok(Some((oid, taken)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Serve a file (`/~/raw/<repo>.git<filter>.git/<ref>/<path>`) or an archive
+/// (`/~/archive/<repo>.git<filter>.git/<ref>.tar.gz` or `.zip`) of a filtered tree.
+async fn serve_download(
+    serv: Arc<JoshProxyService>,
+    upstream: String,
+    path: String,
+    auth: crate::auth::Handle,
+    if_none_match: Option<String>,
+    kind: Download,
+) -> Result<Response<Body>, ProxyError> {
+    let Some(parsed_url) = FilteredRepoUrl::from_str(&format!("/{}", path)) else {
+        return Ok((StatusCode::BAD_REQUEST, "Unable to parse url").into_response());
+    };
+    let upstream_repo = parsed_url.upstream_repo.trim_start_matches('/').to_string();
+    let filter = match josh_core::filter::parse(&parsed_url.filter_spec) {
+        Ok(filter) => serv.filter_prefix.chain(filter),
+        Err(e) => {
+            return Ok((
+                StatusCode::BAD_REQUEST,
+                format!("Failed to parse filter: {}", e),
+            )
+                .into_response());
+        }
+    };
+
+    let rest = parsed_url.pathinfo.trim_start_matches('/').to_string();
+    let rest = match kind {
+        Download::Raw => Some(rest),
+        Download::TarGz => rest.strip_suffix(".tar.gz").map(str::to_string),
+        Download::Zip => rest.strip_suffix(".zip").map(str::to_string),
+    };
+    let Some(rest) = rest.filter(|rest| !rest.is_empty()) else {
+        return Ok((StatusCode::NOT_FOUND, "File not found").into_response());
+    };
+
+    let remote_url = format!("{}/{}", upstream, upstream_repo);
+    if !crate::auth::check_http_auth(&remote_url, &auth, serv.require_auth, serv.http_retry).await?
+    {
+        return Ok(Response::builder()
+            .header(header::WWW_AUTHENTICATE, "Basic realm=User Visible Realm")
+            .status(StatusCode::UNAUTHORIZED)
+            .body(Body::default())?);
+    }
+
+    // Branches and tags come with the default fetch; a sha is fetched by itself.
+    let first = rest.split('/').next().unwrap_or_default();
+    let fetch_ref = match git2::Oid::from_str(first) {
+        Ok(_) => first,
+        Err(_) => "HEAD",
+    };
+    match crate::upstream::fetch_upstream(
+        serv.clone(),
+        &upstream_repo,
+        &RemoteAuth::Http { auth },
+        remote_url,
+        Some(fetch_ref),
+        None,
+        false,
+    )
+    .in_current_span()
+    .await
+    {
+        Ok(_) => {}
+        Err(FetchError::AuthRequired) => {
+            return Ok(Response::builder()
+                .header(header::WWW_AUTHENTICATE, "Basic realm=User Visible Realm")
+                .status(StatusCode::UNAUTHORIZED)
+                .body(Body::default())?);
+        }
+        Err(FetchError::Other(e)) => return Err(e.into()),
+    }
+
+    // The body is written on the blocking thread once the response is out, so the transaction
+    // stays there; the response head comes back over a channel.
+    let (head_tx, head_rx) = tokio::sync::oneshot::channel();
+    let span = Span::current();
+    let task = tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+        let _entered = span.enter();
+        let transaction_mirror = serv.open_mirror(Some(&format!(
+            "refs/josh/upstream/{}/",
+            &josh_core::to_ns(&upstream_repo),
+        )))?;
+        let transaction = serv.open_overlay(None)?;
+        transaction.add_disk_alternate(
+            serv.repo_path
+                .join("mirror")
+                .join("objects")
+                .to_str()
+                .unwrap(),
+        )?;
+        let odb = transaction.odb()?;
+
+        let (id, response) = match resolve_download(
+            &transaction,
+            &transaction_mirror,
+            &odb,
+            filter,
+            &upstream_repo,
+            &rest,
+            if_none_match,
+            kind,
+        )? {
+            Ok(found) => found,
+            Err(response) => {
+                let _ = head_tx.send(response);
+                return Ok(());
+            }
+        };
+
+        let (mut writer, body) = crate::http::body_writer();
+        if head_tx.send(response.body(body)?).is_err() {
+            return Ok(());
+        }
+        let result = match kind {
+            Download::Raw => write_blob(&odb, id, &mut writer),
+            Download::TarGz => {
+                josh_compose::archive::write_tar_gz(&transaction, &odb, id, &mut writer)
+            }
+            Download::Zip => josh_compose::archive::write_zip(&transaction, &odb, id, &mut writer),
+        };
+        if let Err(e) = &result {
+            tracing::warn!("download of {} failed: {:#}", id, e);
+        }
+        writer.finish(result)
+    });
+
+    match head_rx.await {
+        Ok(response) => Ok(response),
+        // The task ended before it had a response, so it failed.
+        Err(_) => {
+            task.in_current_span().await??;
+            Err(anyhow!("download ended without a response").into())
+        }
+    }
+}
+
+/// Resolve what a download serves: the blob or the tree and the head of the response, or the
+/// whole response when there is nothing to send (not found, or not modified).
+#[allow(clippy::too_many_arguments)]
+fn resolve_download(
+    transaction: &josh_core::cache::Transaction,
+    transaction_mirror: &josh_core::cache::Transaction,
+    odb: &josh_core::memodb::Odb,
+    filter: josh_core::filter::Filter,
+    upstream_repo: &str,
+    rest: &str,
+    if_none_match: Option<String>,
+    kind: Download,
+) -> anyhow::Result<Result<(git2::Oid, axum::http::response::Builder), Response<Body>>> {
+    let segments: Vec<&str> = rest.split('/').collect();
+    let keep = match kind {
+        Download::Raw => 1,
+        Download::TarGz | Download::Zip => 0,
+    };
+    let Some((commit_id, taken)) = resolve_download_ref(transaction_mirror, &segments, keep)?
+    else {
+        return Ok(Err((StatusCode::NOT_FOUND, "Ref not found").into_response()));
+    };
+    if keep == 0 && taken != segments.len() {
+        return Ok(Err((StatusCode::NOT_FOUND, "Ref not found").into_response()));
+    }
+
+    let filtered = josh_core::filter_commit(transaction, filter, commit_id)?;
+    if filtered.is_zero() {
+        return Ok(Err((StatusCode::NOT_FOUND, "Ref not found").into_response()));
+    }
+    let tree_id = josh_core::git::read_tree_id(odb, filtered)?;
+
+    let (id, content_type) = match kind {
+        Download::Raw => {
+            let file_path = segments[taken..].join("/");
+            let entry = josh_core::filter::tree::get_path_entry(
+                transaction,
+                odb,
+                tree_id,
+                Path::new(&file_path),
+            )?;
+            let Some(entry) = entry.filter(|e| !e.mode.is_tree() && !e.mode.is_commit()) else {
+                return Ok(Err(
+                    (StatusCode::NOT_FOUND, "File not found").into_response()
+                ));
+            };
+            (josh_core::objects::git2_oid(&entry.oid), None)
+        }
+        Download::TarGz => (tree_id, Some("application/gzip")),
+        Download::Zip => (tree_id, Some("application/zip")),
+    };
+
+    // Blobs and trees are content addressed, so their oid is a strong validator, checked
+    // before anything is read or built.
+    let etag = format!("\"{}\"", id);
+    if if_none_match.is_some_and(|tags| {
+        tags.split(',')
+            .any(|tag| tag.trim() == etag || tag.trim() == "*")
+    }) {
+        return Ok(Err(Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, etag)
+            .body(Body::default())?));
+    }
+
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::ETAG, etag);
+    match content_type {
+        Some(content_type) => {
+            let name = upstream_repo
+                .trim_end_matches(".git")
+                .rsplit('/')
+                .next()
+                .unwrap_or_default();
+            let extension = match kind {
+                Download::Zip => "zip",
+                _ => "tar.gz",
+            };
+            response = response.header(header::CONTENT_TYPE, content_type).header(
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"{}-{}.{}\"",
+                    name,
+                    segments.join("-"),
+                    extension
+                ),
+            );
+        }
+        None => {
+            let file_path = segments[taken..].join("/");
+            let binary = josh_core::filter::tree::is_binary_blob(odb, id);
+            let (_, size) = odb.read_header(id)?;
+            response = response
+                .header(header::CONTENT_TYPE, raw_content_type(&file_path, binary))
+                .header(header::CONTENT_LENGTH, size)
+                // Should a browser still render the file, it gets no scripts and its own origin.
+                .header(header::CONTENT_SECURITY_POLICY, "sandbox");
+        }
+    }
+    Ok(Ok((id, response)))
+}
+
+/// Copy a blob to `out`, streaming it from the object database.
+fn write_blob(
+    odb: &josh_core::memodb::Odb,
+    id: git2::Oid,
+    out: &mut impl std::io::Write,
+) -> anyhow::Result<()> {
+    let (_, _, mut content) = odb.read_stream(id)?;
+    std::io::copy(&mut content, out)?;
+    Ok(())
+}
+
+/// Every download response tells browsers to keep to the content type it declares.
+fn no_sniff(response: Result<Response<Body>, ProxyError>) -> Response<Body> {
+    let mut response = response.into_response();
+    response.headers_mut().insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        header::HeaderValue::from_static("nosniff"),
+    );
+    response
+}
+
+async fn handle_raw(
+    State(serv): State<Arc<JoshProxyService>>,
+    HttpUpstream(upstream): HttpUpstream,
+    axum::extract::Path(path): axum::extract::Path<String>,
+    auth: Option<axum::extract::Extension<crate::auth::Handle>>,
+    headers: axum::http::HeaderMap,
+) -> Response<Body> {
+    let auth = auth
+        .map(|auth| auth.0)
+        .unwrap_or(crate::auth::Handle { hash: None });
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    no_sniff(serve_download(serv, upstream, path, auth, if_none_match, Download::Raw).await)
+}
+
+async fn handle_archive(
+    State(serv): State<Arc<JoshProxyService>>,
+    HttpUpstream(upstream): HttpUpstream,
+    axum::extract::Path(path): axum::extract::Path<String>,
+    auth: Option<axum::extract::Extension<crate::auth::Handle>>,
+    headers: axum::http::HeaderMap,
+) -> Response<Body> {
+    let auth = auth
+        .map(|auth| auth.0)
+        .unwrap_or(crate::auth::Handle { hash: None });
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let kind = if path.ends_with(".zip") {
+        Download::Zip
+    } else {
+        Download::TarGz
+    };
+
+    no_sniff(serve_download(serv, upstream, path, auth, if_none_match, kind).await)
+}
+
 #[tracing::instrument]
 async fn handle_repo_update(
     State(_serv): State<Arc<JoshProxyService>>,
@@ -1638,6 +2152,15 @@ pub fn make_service_router(proxy_service: Arc<JoshProxyService>) -> Router {
                 handle_filters(service, true).await
             }),
         )
+        .route("/cache/stats", get(|| handle_cache(None)))
+        .route(
+            "/cache/gc",
+            post(
+                |axum::extract::Query(params): axum::extract::Query<CacheGcParams>| {
+                    handle_cache(Some(params))
+                },
+            ),
+        )
         .route("/repo_update", post(handle_repo_update))
         .route("/serve_namespace", post(handle_serve_namespace))
         // Serve graphql APIs
@@ -1646,6 +2169,10 @@ pub fn make_service_router(proxy_service: Arc<JoshProxyService>) -> Router {
             post(handle_graphql).get(handle_graphql),
         )
         .route("/~/graphiql/{*path}", get(handle_graphiql))
+        .route("/~/notify/{*path}", post(handle_notify))
+        // Serve files and archives of filtered trees
+        .route("/~/raw/{*path}", get(handle_raw))
+        .route("/~/archive/{*path}", get(handle_archive))
         .merge(git_operations_router)
         .layer(middleware::from_fn(auth_middleware))
         .layer(
@@ -1,10 +1,10 @@
-use crate::http::RetryableError;
+use crate::http::{IntoRetryable, RetryableError};
 use crate::service::{JoshProxyService, UpstreamProtocol};
 use crate::{FetchError, auth, run_git_with_auth};
 use anyhow::{Context, anyhow};
 use backon::BackoffBuilder;
 
-use josh_changes::{PushMode, baseref_and_options, build_to_push};
+use josh_changes::baseref_and_options;
 use josh_core::cache::{CacheStack, TransactionContext};
 
 use std::path::PathBuf;
@@ -35,18 +35,7 @@ pub struct RepoUpdate {
     pub context_propagator: std::collections::HashMap<String, String>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-#[serde(default)]
-#[derive(Default)]
-pub struct PushOptions {
-    pub merge: bool,
-    pub allow_orphans: bool,
-    pub edit: bool,
-    pub create: bool,
-    pub force: bool,
-    pub base: Option<String>,
-    pub author: Option<String>,
-}
+pub use josh_changes::PushOptions;
 
 pub trait Upstream {
     fn upstream(&self, protocol: UpstreamProtocol) -> Option<String>;
@@ -194,7 +183,7 @@ pub async fn fetch_upstream(
         .clone();
 
     let backoff = crate::http::make_exponential_backoff(service.http_retry).build();
-    let mut fetch_result = Ok(());
+    let mut fetch_result = Ok(vec![]);
 
     for delay in std::iter::once(None).chain(backoff.map(|d| Some(d))) {
         if let Some(delay) = delay {
@@ -231,13 +220,20 @@ pub async fn fetch_upstream(
 
             tokio::task::spawn_blocking(move || {
                 let _span_guard = span.enter();
+                let snapshot = || {
+                    crate::upstream_refs(&mirror_path, &upstream_repo)
+                        .map_err(|e| FetchError::Other(e).as_non_retryable())
+                };
+
+                let before = snapshot()?;
                 crate::fetch_refs_from_url(
                     &mirror_path,
                     &upstream_repo,
                     &remote_url,
                     &refs_to_fetch,
                     &remote_auth,
-                )
+                )?;
+                Ok(crate::ref_changes(&before, &snapshot()?))
             })
             .await?
         };
@@ -278,11 +274,16 @@ pub async fn fetch_upstream(
 
     let fetch_timers = service.fetch_timers.clone();
 
-    if fetch_result.is_ok() {
+    if let Ok(changes) = &fetch_result {
         fetch_timers
             .write()
             .unwrap()
             .insert(remote_url.clone(), std::time::Instant::now());
+
+        // Sending only fails when nobody is subscribed.
+        for change in changes {
+            service.ref_changes.send(change.clone()).ok();
+        }
     }
 
     match (fetch_result, remote_auth) {
@@ -341,9 +342,9 @@ pub fn process_repo_update(repo_update: RepoUpdate) -> anyhow::Result<String> {
 
         let old = git2::Oid::from_str(old)?;
         let author = push_options.author.as_deref().unwrap_or("");
-        let (baseref, push_to, options, push_mode) = baseref_and_options(refname, author)?;
+        let (baseref, _, _, _) = baseref_and_options(refname, author)?;
 
-        let old = if old == git2::Oid::ZERO_SHA1 {
+        let old = if old.is_zero() {
             let rev = format!("refs/namespaces/{}/{}", repo_update.git_ns, &baseref);
             let oid = transaction.resolve_ref(&rev)?.unwrap_or(old);
 
@@ -364,126 +365,21 @@ pub fn process_repo_update(repo_update: RepoUpdate) -> anyhow::Result<String> {
             old
         };
 
-        let original_target_ref = if let Some(base) = &push_options.base {
-            // Allow user to use just the branch name as the base:
-            let full_path_base_refname =
-                transaction_mirror.refname(&format!("refs/heads/{}", base));
-            if transaction_mirror
-                .resolve_ref(&full_path_base_refname)?
-                .is_some()
-            {
-                full_path_base_refname
-            } else {
-                transaction_mirror.refname(base)
-            }
-        } else {
-            transaction_mirror.refname(&baseref)
-        };
-
-        let original_target =
-            if let Some(oid) = transaction_mirror.resolve_ref(&original_target_ref)? {
-                tracing::debug!(
-                    original_target_oid = ?oid,
-                    original_target_ref = %original_target_ref,
-                    "resolve_original_target"
-                );
-
-                oid
-            } else {
-                tracing::debug!(
-                    original_target_ref = %original_target_ref,
-                    "resolve_original_target"
-                );
-
-                return Err(anyhow!(indoc::formatdoc!(
-                    r###"
-                    Reference {:?} does not exist on remote.
-                    If you want to create it, pass "-o base=<basebranch>" or "-o base=path/to/ref"
-                    to specify a base branch/reference.
-                    "###,
-                    baseref
-                )));
-            };
-
-        let reparent_orphans = if push_options.create {
-            Some(original_target)
-        } else {
-            None
-        };
-
         let filter = josh_core::filter::parse(&repo_update.filter_spec)?;
         let new_oid = git2::Oid::from_str(new)?;
-        let backward_new_oid = {
-            let unapply_result = josh_core::history::unapply_filter(
-                &transaction,
-                filter,
-                original_target,
-                old,
-                new_oid,
-                if push_options.merge || push_options.allow_orphans {
-                    josh_core::history::OrphansMode::Keep
-                } else if push_options.edit {
-                    josh_core::history::OrphansMode::Remove
-                } else {
-                    josh_core::history::OrphansMode::Fail
-                },
-                reparent_orphans,
-            )?;
-
-            tracing::debug!(
-                processed_old = ?old,
-                unapply_result = ?unapply_result,
-                "unapply_filter"
-            );
-
-            unapply_result
-        };
-
-        let oid_to_push = if push_options.merge {
-            if let Some(base_commit_id) = transaction_mirror.resolve_ref(&original_target_ref)? {
-                let signature = josh_core::git::josh_commit_signature()?;
-                let odb = transaction.odb()?;
-                let merged_tree = josh_core::objects::merge_commits(
-                    &odb,
-                    base_commit_id,
-                    backward_new_oid,
-                    None,
-                )?;
-                josh_core::objects::write_commit(
-                    &odb,
-                    merged_tree,
-                    &[base_commit_id, backward_new_oid],
-                    &signature,
-                    &signature,
-                    &format!("Merge from {}", &repo_update.filter_spec),
-                )?
-            } else {
-                return Err(anyhow!("josh_merge failed"));
-            }
-        } else {
-            backward_new_oid
-        };
-
-        let ref_with_options = if !options.is_empty() {
-            format!("{}{}{}", push_to, "%", options.join(","))
-        } else {
-            push_to
-        };
-
-        let to_push = build_to_push(
+        let plan = josh_changes::plan_push(
             &transaction,
-            &push_mode,
-            &baseref,
-            &ref_with_options,
-            oid_to_push,
-            original_target,
+            &transaction_mirror,
+            filter,
+            refname,
+            old,
+            new_oid,
+            &push_options,
         )?;
 
         let mut resp = vec![];
 
-        for push_ref in to_push {
-            let force_push = !matches!(push_mode, PushMode::Normal) || push_options.force;
-
+        for push_ref in plan.refs {
             let (text, status) = push_head_url(
                 &transaction,
                 &format!("{}/objects", repo_update.mirror_git_dir),
@@ -493,7 +389,7 @@ pub fn process_repo_update(repo_update: RepoUpdate) -> anyhow::Result<String> {
                 &repo_update.remote_auth,
                 &repo_update.git_ns,
                 &push_ref.change_id,
-                force_push,
+                plan.force,
             )?;
 
             if status != 0 {
@@ -514,7 +410,7 @@ pub fn process_repo_update(repo_update: RepoUpdate) -> anyhow::Result<String> {
             }
         }
 
-        let reapply = josh_core::filter::apply_to_commit(filter, oid_to_push, &transaction)?;
+        let reapply = josh_core::filter::apply_to_commit(filter, plan.oid, &transaction)?;
 
         if new_oid != reapply {
             if std::env::var("JOSH_REWRITE_REFS").is_ok() {
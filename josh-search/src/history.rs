@@ -0,0 +1,227 @@
+//! Pickaxe search: [`search_history`] finds the commits of a history that change the number of
+//! occurrences of a string, like `git log -S`, skipping merges. josh-filter's `--search-history` and the GraphQL
+//! `searchHistory` field are both built on it.
+//!
+//! A commit is compared with its parent in two steps. The trigram indexes of both trees answer
+//! first: when neither contains every trigram of the string, no file can contain it on either
+//! side and the commit is skipped without reading its trees. Otherwise only files that differ
+//! between the trees (identical subtrees are skipped by oid) and that are candidates on at
+//! least one side are read and counted.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{
+    Objects, distinct_trigrams, join_path, read_blob_text, read_tree_entries, to_git2, to_gix,
+    trigram_candidates,
+};
+
+/// A tree to compare in [`occurrence_changes`], with its trigram index if there is one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub tree: git2::Oid,
+    pub index: Option<git2::Oid>,
+}
+
+/// How the number of occurrences of the string changed in one file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OccurrenceChange {
+    pub path: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+/// A commit found by [`search_history`], with the files where the count changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryMatch {
+    pub commit: git2::Oid,
+    pub changes: Vec<OccurrenceChange>,
+}
+
+/// The files whose number of (non-overlapping) occurrences of `needle` differs between `old`
+/// and `new`, in path order. `old` is `None` for a root commit.
+pub fn occurrence_changes(
+    src: &dyn Objects,
+    old: Option<Snapshot>,
+    new: Snapshot,
+    needle: &str,
+) -> anyhow::Result<Vec<OccurrenceChange>> {
+    if needle.is_empty() {
+        return Err(anyhow::anyhow!("empty search string"));
+    }
+    if old.is_some_and(|old| old.tree == new.tree) {
+        return Ok(vec![]);
+    }
+
+    let trigrams = distinct_trigrams(needle);
+    // `None` when the side's index cannot narrow its files down.
+    let candidates = |side: Option<Snapshot>| -> anyhow::Result<Option<BTreeSet<String>>> {
+        let Some(side) = side else {
+            return Ok(Some(BTreeSet::new()));
+        };
+        match side.index {
+            Some(index) if !trigrams.is_empty() => Ok(Some(
+                trigram_candidates(src, index, side.tree, &trigrams)?
+                    .into_iter()
+                    .collect(),
+            )),
+            _ => Ok(None),
+        }
+    };
+    let before = candidates(old)?;
+    let after = candidates(Some(new))?;
+    if before.as_ref().is_some_and(BTreeSet::is_empty)
+        && after.as_ref().is_some_and(BTreeSet::is_empty)
+    {
+        return Ok(vec![]);
+    }
+    let relevant = |path: &str| {
+        [&before, &after].iter().any(|side| match side {
+            Some(files) => files.contains(path),
+            None => true,
+        })
+    };
+
+    let mut changed = BTreeMap::new();
+    changed_blobs(
+        src,
+        old.map(|old| old.tree),
+        Some(new.tree),
+        "",
+        &mut changed,
+    )?;
+
+    let count = |oid: Option<git2::Oid>| match oid {
+        Some(oid) => read_blob_text(src, to_gix(oid)).matches(needle).count(),
+        None => 0,
+    };
+    let mut changes = vec![];
+    for (path, (a, b)) in changed {
+        if !relevant(&path) {
+            continue;
+        }
+        let (before, after) = (count(a), count(b));
+        if before != after {
+            changes.push(OccurrenceChange {
+                path,
+                before,
+                after,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Collect the blob paths that differ between the trees `old` and `new` (either may be absent),
+/// with their blob on each side.
+fn changed_blobs(
+    src: &dyn Objects,
+    old: Option<git2::Oid>,
+    new: Option<git2::Oid>,
+    prefix: &str,
+    out: &mut BTreeMap<String, (Option<git2::Oid>, Option<git2::Oid>)>,
+) -> anyhow::Result<()> {
+    let entries = |tree: Option<git2::Oid>| match tree {
+        Some(tree) => read_tree_entries(src, tree),
+        None => Ok(vec![]),
+    };
+    let mut sides: BTreeMap<_, [Option<gix_object::tree::Entry>; 2]> = BTreeMap::new();
+    for (i, tree) in [old, new].into_iter().enumerate() {
+        for entry in entries(tree)? {
+            let name = entry.filename.clone();
+            sides.entry(name).or_default()[i] = Some(entry);
+        }
+    }
+
+    for (name, [a, b]) in sides {
+        if a == b {
+            continue;
+        }
+        let path = join_path(prefix, std::str::from_utf8(&name)?);
+        let side = |entry: &Option<gix_object::tree::Entry>, tree: bool| {
+            entry
+                .as_ref()
+                .filter(|e| e.mode.is_tree() == tree && !e.mode.is_commit())
+                .map(|e| to_git2(e.oid))
+        };
+        let (old_tree, new_tree) = (side(&a, true), side(&b, true));
+        if old_tree.is_some() || new_tree.is_some() {
+            changed_blobs(src, old_tree, new_tree, &path, out)?;
+        }
+        let (old_blob, new_blob) = (side(&a, false), side(&b, false));
+        if old_blob != new_blob {
+            out.insert(path, (old_blob, new_blob));
+        }
+    }
+    Ok(())
+}
+
+/// The tree and parents of the commit `oid`.
+fn read_commit(src: &dyn Objects, oid: git2::Oid) -> anyhow::Result<(git2::Oid, Vec<git2::Oid>)> {
+    let mut buffer = Vec::new();
+    let data = src
+        .try_find(&to_gix(oid), &mut buffer)
+        .map_err(|e| anyhow::anyhow!("read commit {}: {}", oid, e))?
+        .ok_or_else(|| anyhow::anyhow!("object {} not found", oid))?;
+    if data.kind != gix_object::Kind::Commit {
+        return Err(anyhow::anyhow!("object {} is not a commit", oid));
+    }
+    let commit = gix_object::CommitRef::from_bytes(&buffer, to_gix(oid).kind())?;
+    Ok((
+        to_git2(commit.tree()),
+        commit.parents().map(to_git2).collect(),
+    ))
+}
+
+/// Go through `commits`, newest first, and return the commits that change the number of
+/// occurrences of `needle` compared with their parent. Like `git log -S`, merge commits are
+/// skipped: what a merge brings in is found in the commits of the merged branches. The caller
+/// decides the history to search by the commits it passes, typically a walk of everything
+/// reachable from a head.
+///
+/// `index` maps a tree to its trigram index, or to `None` when there is none; consecutive trees
+/// share most subtrees, so a memoized indexer makes this cheap. The search stops after
+/// `max_results` matches.
+pub fn search_history(
+    src: &dyn Objects,
+    commits: impl IntoIterator<Item = anyhow::Result<git2::Oid>>,
+    needle: &str,
+    index: &mut dyn FnMut(git2::Oid) -> anyhow::Result<Option<git2::Oid>>,
+    max_results: Option<usize>,
+) -> anyhow::Result<Vec<HistoryMatch>> {
+    let mut matches = vec![];
+    // The parent side of the last comparison: on a linear stretch it is the next commit.
+    let mut previous: Option<(git2::Oid, Snapshot)> = None;
+    for commit in commits {
+        if max_results.is_some_and(|max| matches.len() >= max) {
+            break;
+        }
+        let commit = commit?;
+        let (tree, parents) = read_commit(src, commit)?;
+        if parents.len() > 1 {
+            continue;
+        }
+        let new = match previous {
+            Some((oid, snapshot)) if oid == commit => snapshot,
+            _ => Snapshot {
+                tree,
+                index: index(tree)?,
+            },
+        };
+        let old = match parents.first() {
+            Some(&parent) => {
+                let tree = read_commit(src, parent)?.0;
+                Some(Snapshot {
+                    tree,
+                    index: index(tree)?,
+                })
+            }
+            None => None,
+        };
+        let changes = occurrence_changes(src, old, new, needle)?;
+        if !changes.is_empty() {
+            matches.push(HistoryMatch { commit, changes });
+        }
+        previous = parents.first().copied().zip(old);
+    }
+    Ok(matches)
+}
@@ -28,11 +28,26 @@
 //!
 //! Searching extracts the query's trigrams, resolves each with a single three-level lookup, and
 //! intersects the mirror subtrees; the resulting candidate files are exact (files containing all
-//! query trigrams), leaving only string-level verification to [`search_matches`].
+//! query trigrams), leaving only string-level verification to [`search_matches`]. Regular
+//! expressions are decomposed into an AND/OR of trigrams first (see [`query`]), evaluated the
+//! same way, and verified by [`regex_matches`]. [`search`] puts it together for callers,
+//! with path scoping, case folding, context lines and paging. [`search_history`] compares the
+//! indexes of consecutive commits to find where a string was introduced or removed.
+//! [`symbol_index`] builds a second index of the same shape over identifiers, answering
+//! [`definitions`] and [`references`] queries for code navigation.
 //!
 //! This crate is independent of the josh filter machinery: it operates on plain [`git2`] objects
 //! and memoizes tree-to-index mappings through the [`IndexCache`] trait the caller provides.
 
+mod history;
+mod options;
+pub mod query;
+mod symbols;
+
+pub use history::{HistoryMatch, OccurrenceChange, Snapshot, occurrence_changes, search_history};
+pub use options::{FileMatches, Line, SearchOptions, SearchPage, search};
+pub use symbols::{Language, SymbolLocation, definitions, references, symbol_index};
+
 use gix_object::WriteTo;
 use gix_object::bstr::BString;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
@@ -46,12 +61,12 @@ pub trait IndexCache {
     fn set_index(&self, tree: git2::Oid, index: git2::Oid);
 }
 
-fn empty_tree() -> gix_hash::ObjectId {
-    gix_hash::ObjectId::empty_tree(gix_hash::Kind::Sha1)
+fn empty_tree(hash: gix_hash::Kind) -> gix_hash::ObjectId {
+    gix_hash::ObjectId::empty_tree(hash)
 }
 
-fn empty_blob() -> gix_hash::ObjectId {
-    gix_hash::ObjectId::empty_blob(gix_hash::Kind::Sha1)
+fn empty_blob(hash: gix_hash::Kind) -> gix_hash::ObjectId {
+    gix_hash::ObjectId::empty_blob(hash)
 }
 
 fn to_gix(oid: git2::Oid) -> gix_hash::ObjectId {
@@ -90,8 +105,15 @@ fn distinct_trigrams(content: &str) -> BTreeSet<[u8; 3]> {
         .collect()
 }
 
-/// Read the blob `oid` as text, or "" if it is absent, contains a NUL byte, or is not UTF-8.
+/// Read the blob `oid` as text, or "" if it is absent, contains a NUL byte, is not UTF-8 or is
+/// too big to read whole (see [`Objects::is_big`]).
 fn read_blob_text(src: &dyn Objects, oid: gix_hash::ObjectId) -> String {
+    if src
+        .try_header(&oid)
+        .is_ok_and(|header| header.is_some_and(|header| src.is_big(header.size)))
+    {
+        return "".to_owned();
+    }
     let mut buffer = Vec::new();
     let Ok(Some(data)) = src.try_find(&oid, &mut buffer) else {
         return "".to_owned();
@@ -158,10 +180,22 @@ pub struct Indexer {
 pub trait Objects:
     gix_object::Find + gix_object::FindHeader + gix_object::Exists + gix_object::Write
 {
+    /// Whether an object of `size` bytes is above the big-object threshold (git's
+    /// `core.bigFileThreshold`). Such blobs are never read whole, so they are neither indexed
+    /// nor searched.
+    fn is_big(&self, size: u64) -> bool;
 }
-impl<T: gix_object::Find + gix_object::FindHeader + gix_object::Exists + gix_object::Write> Objects
-    for T
-{
+
+impl Objects for josh_memodb::Odb<'_> {
+    fn is_big(&self, size: u64) -> bool {
+        josh_memodb::Odb::is_big(self, size)
+    }
+}
+
+impl Objects for josh_gix_ext::Git2Odb<'_> {
+    fn is_big(&self, size: u64) -> bool {
+        size > josh_memodb::DEFAULT_BIG_OBJECT_THRESHOLD
+    }
 }
 
 /// Directories at or below BOTH limits are recorded at directory granularity: their mirrors
@@ -179,6 +213,9 @@ struct Run<'a> {
     /// `(source tree, index)` pairs of this call, memoized by [`flush`](Run::flush) only after
     /// their objects reach the ODB: an [`IndexCache`] entry must never point at missing objects.
     roots: Vec<(git2::Oid, gix_hash::ObjectId)>,
+    /// The hash of the indexed repository (that of the source tree's id), which index objects
+    /// are written with.
+    hash: gix_hash::Kind,
 }
 
 impl Run<'_> {
@@ -187,13 +224,13 @@ impl Run<'_> {
     /// from plain name order for the mixed blob/tree entries index trees contain.
     fn write_tree(&mut self, mut entries: Vec<gix_object::tree::Entry>) -> gix_hash::ObjectId {
         if entries.is_empty() {
-            return empty_tree();
+            return empty_tree(self.hash);
         }
         entries.sort();
         let tree = gix_object::Tree { entries };
         let mut buffer = Vec::with_capacity(tree.size() as usize);
         tree.write_to(&mut buffer).expect("failed to write tree");
-        let hash = gix_object::compute_hash(gix_hash::Kind::Sha1, gix_object::Kind::Tree, &buffer)
+        let hash = gix_object::compute_hash(self.hash, gix_object::Kind::Tree, &buffer)
             .expect("failed to compute hash");
         if !self.ix.trees.contains_key(&hash) {
             self.ix
@@ -214,14 +251,14 @@ impl Run<'_> {
             return Ok(tree.clone());
         }
         let tree = if let Some((_, data)) = self.ix.pending.get(&oid) {
-            gix_object::TreeRef::from_bytes(data, gix_hash::Kind::Sha1)?.into_owned()
+            gix_object::TreeRef::from_bytes(data, self.hash)?.into_owned()
         } else {
             let mut buffer = Vec::new();
             self.src
                 .try_find(&oid, &mut buffer)
                 .map_err(|e| anyhow::anyhow!("read tree {}: {}", oid, e))?
                 .ok_or_else(|| anyhow::anyhow!("object {} not found", oid))?;
-            gix_object::TreeRef::from_bytes(&buffer, gix_hash::Kind::Sha1)?.into_owned()
+            gix_object::TreeRef::from_bytes(&buffer, self.hash)?.into_owned()
         };
         let tree = std::sync::Arc::new(tree);
         self.ix.trees.insert(oid, tree.clone());
@@ -247,7 +284,7 @@ impl Run<'_> {
                 .push(t[2]);
         }
         if spine.is_empty() {
-            return empty_tree();
+            return empty_tree(self.hash);
         }
 
         let mut l1 = Vec::new();
@@ -282,7 +319,7 @@ impl Run<'_> {
         let mirror = self.write_tree(vec![gix_object::tree::Entry {
             mode: gix_object::tree::EntryKind::Blob.into(),
             filename: name.into(),
-            oid: empty_blob(),
+            oid: empty_blob(self.hash),
         }]);
         let id = self.content_spine(content, gix_object::tree::EntryKind::Tree.into(), mirror);
 
@@ -299,7 +336,7 @@ impl Run<'_> {
         let id = self.content_spine(
             content,
             gix_object::tree::EntryKind::Blob.into(),
-            empty_blob(),
+            empty_blob(self.hash),
         );
         self.ix.blob_spine_memo.insert(oid, id);
         id
@@ -371,7 +408,7 @@ impl Run<'_> {
         name: &str,
         index: gix_hash::ObjectId,
     ) -> anyhow::Result<gix_hash::ObjectId> {
-        if index == empty_tree() {
+        if index == empty_tree(self.hash) {
             return Ok(index);
         }
         let key = (index, name.to_owned());
@@ -417,11 +454,11 @@ impl Run<'_> {
     ) -> anyhow::Result<gix_hash::ObjectId> {
         // Union merge: order, duplicates and empty inputs contribute nothing, so normalize
         // the key for maximum memo hits.
-        inputs.retain(|id| *id != empty_tree());
+        inputs.retain(|id| *id != empty_tree(self.hash));
         inputs.sort();
         inputs.dedup();
         if inputs.is_empty() {
-            return Ok(empty_tree());
+            return Ok(empty_tree(self.hash));
         }
         if inputs.len() == 1 {
             return Ok(inputs[0]);
@@ -520,8 +557,8 @@ impl Run<'_> {
         // Index leaves are the empty blob, and an empty directory's index is the empty tree;
         // neither lives in `pending`, so make sure both exist before anything references them.
         for (kind, id) in [
-            (gix_object::Kind::Blob, empty_blob()),
-            (gix_object::Kind::Tree, empty_tree()),
+            (gix_object::Kind::Blob, empty_blob(self.hash)),
+            (gix_object::Kind::Tree, empty_tree(self.hash)),
         ] {
             if !self.src.exists(&id) {
                 self.src
@@ -543,7 +580,7 @@ impl Run<'_> {
                 continue;
             };
             if let gix_object::Kind::Tree = kind {
-                for entry in gix_object::TreeRef::from_bytes(data, gix_hash::Kind::Sha1)?.entries {
+                for entry in gix_object::TreeRef::from_bytes(data, self.hash)?.entries {
                     stack.push(entry.oid.to_owned());
                 }
             }
@@ -576,6 +613,7 @@ pub fn trigram_index(
         cache,
         ix: indexer,
         roots: Vec::new(),
+        hash: to_gix(tree).kind(),
     };
     let index = run.index_tree_oid(tree)?;
     run.flush()?;
@@ -599,9 +637,96 @@ pub fn search_candidates(
         collect_paths(src, source_tree, "", &mut results)?;
         return Ok(results);
     }
+    trigram_candidates(src, index_tree, source_tree, &trigrams)
+}
+
+/// The candidate files for the regular expression `pattern`: those satisfying its trigram
+/// [`query::Query`], in path order. A pattern that requires no trigram makes every file of
+/// `source_tree` a candidate, and [`regex_matches`] does the filtering.
+pub fn regex_candidates(
+    src: &dyn Objects,
+    index_tree: git2::Oid,
+    source_tree: git2::Oid,
+    pattern: &str,
+) -> anyhow::Result<Vec<String>> {
+    let query = query::regex_query(pattern)?;
+    match eval_query(src, index_tree, source_tree, &query)? {
+        Some(candidates) => Ok(candidates.into_iter().collect()),
+        None => {
+            let mut results = vec![];
+            collect_paths(src, source_tree, "", &mut results)?;
+            Ok(results)
+        }
+    }
+}
 
+/// The files satisfying `query`, or `None` when it does not constrain them at all.
+///
+/// The trigrams ANDed at one level are resolved together by a single [`intersect_walk`];
+/// only the nested ORs become sets that are intersected and united here.
+fn eval_query(
+    src: &dyn Objects,
+    index_tree: git2::Oid,
+    source_tree: git2::Oid,
+    query: &query::Query,
+) -> anyhow::Result<Option<BTreeSet<String>>> {
+    use query::Query;
+    let eval_trigrams = |trigrams: &BTreeSet<[u8; 3]>| -> anyhow::Result<BTreeSet<String>> {
+        Ok(trigram_candidates(src, index_tree, source_tree, trigrams)?
+            .into_iter()
+            .collect())
+    };
+    Ok(match query {
+        Query::All => None,
+        Query::None => Some(BTreeSet::new()),
+        Query::Trigram(trigram) => Some(eval_trigrams(&BTreeSet::from([*trigram]))?),
+        Query::And(subs) => {
+            let trigrams: BTreeSet<[u8; 3]> = subs
+                .iter()
+                .filter_map(|sub| match sub {
+                    Query::Trigram(trigram) => Some(*trigram),
+                    _ => None,
+                })
+                .collect();
+            let mut result = match trigrams.is_empty() {
+                true => None,
+                false => Some(eval_trigrams(&trigrams)?),
+            };
+            for sub in subs.iter().filter(|sub| !matches!(sub, Query::Trigram(_))) {
+                if result.as_ref().is_some_and(BTreeSet::is_empty) {
+                    break;
+                }
+                if let Some(files) = eval_query(src, index_tree, source_tree, sub)? {
+                    result = Some(match result {
+                        Some(result) => result.intersection(&files).cloned().collect(),
+                        None => files,
+                    });
+                }
+            }
+            result
+        }
+        Query::Or(subs) => {
+            let mut result = BTreeSet::new();
+            for sub in subs {
+                match eval_query(src, index_tree, source_tree, sub)? {
+                    Some(files) => result.extend(files),
+                    None => return Ok(None),
+                }
+            }
+            Some(result)
+        }
+    })
+}
+
+/// The files containing every one of `trigrams` (at least one).
+fn trigram_candidates(
+    src: &dyn Objects,
+    index_tree: git2::Oid,
+    source_tree: git2::Oid,
+    trigrams: &BTreeSet<[u8; 3]>,
+) -> anyhow::Result<Vec<String>> {
     let mut roots = vec![];
-    for t in &trigrams {
+    for t in trigrams {
         let path = format!("{:02x}/{:02x}/{:02x}", t[0], t[1], t[2]);
         match path_entry(src, index_tree, std::path::Path::new(&path))? {
             Some(oid) => roots.push(oid),
@@ -629,6 +754,7 @@ pub fn search_candidates(
             .collect();
     }
 
+    let mut results = vec![];
     intersect_walk(src, &roots, source_tree, "", &mut results)?;
     Ok(results)
 }
@@ -751,7 +877,7 @@ fn read_tree_entries(
         return Err(anyhow::anyhow!("object {} is not a tree", oid));
     }
     Ok(
-        gix_object::TreeRef::from_bytes(&buffer, gix_hash::Kind::Sha1)?
+        gix_object::TreeRef::from_bytes(&buffer, to_gix(oid).kind())?
             .into_owned()
             .entries,
     )
@@ -774,7 +900,7 @@ fn collect_paths(
     if data.kind != gix_object::Kind::Tree {
         return Ok(());
     }
-    let tree = gix_object::TreeRef::from_bytes(&buffer, gix_hash::Kind::Sha1)?.into_owned();
+    let tree = gix_object::TreeRef::from_bytes(&buffer, to_gix(oid).kind())?.into_owned();
     for entry in tree.entries {
         let name = std::str::from_utf8(&entry.filename)?;
         let path = join_path(prefix, name);
@@ -803,6 +929,30 @@ pub fn search_matches(
     searchstring: &str,
     candidates: &Vec<String>,
 ) -> anyhow::Result<SearchMatchesResult> {
+    Ok(matching_lines(src, tree, candidates, |l| {
+        l.contains(searchstring)
+    }))
+}
+
+/// Like [`search_matches`], for the lines matching the regular expression `pattern`.
+pub fn regex_matches(
+    src: &dyn Objects,
+    tree: git2::Oid,
+    pattern: &str,
+    candidates: &Vec<String>,
+) -> anyhow::Result<SearchMatchesResult> {
+    let regex = regex::Regex::new(pattern)?;
+    Ok(matching_lines(src, tree, candidates, |l| regex.is_match(l)))
+}
+
+/// The lines of the `candidates` (paths in `tree`) that `is_match`, numbered from 1; files
+/// without one are left out.
+fn matching_lines(
+    src: &dyn Objects,
+    tree: git2::Oid,
+    candidates: &Vec<String>,
+    is_match: impl Fn(&str) -> bool,
+) -> SearchMatchesResult {
     let mut results = vec![];
 
     for c in candidates {
@@ -811,7 +961,7 @@ pub fn search_matches(
         let mut bresults = vec![];
 
         for (linenr, l) in b.lines().enumerate() {
-            if l.contains(searchstring) {
+            if is_match(l) {
                 bresults.push((linenr + 1, l.to_owned()));
             }
         }
@@ -821,7 +971,7 @@ pub fn search_matches(
         }
     }
 
-    Ok(results)
+    results
 }
 
 /// Like [`read_blob_text`], but for a path inside `tree`.
@@ -851,7 +1001,7 @@ fn path_entry(
         if data.kind != gix_object::Kind::Tree {
             return Ok(None);
         }
-        let parsed = gix_object::TreeRef::from_bytes(&buffer, gix_hash::Kind::Sha1)?;
+        let parsed = gix_object::TreeRef::from_bytes(&buffer, to_gix(current).kind())?;
         let name = std::os::unix::ffi::OsStrExt::as_bytes(component.as_os_str());
         let Some(entry) = parsed.entries.iter().find(|e| e.filename == name) else {
             return Ok(None);
@@ -919,6 +1069,23 @@ mod tests {
         josh_gix_ext::Git2Odb(Box::leak(Box::new(repo.odb().unwrap())))
     }
 
+    /// `1 + max(parent numbers)`, the property josh's cached sequence numbers guarantee.
+    fn sequence_number(
+        repo: &git2::Repository,
+        memo: &std::cell::RefCell<HashMap<git2::Oid, u64>>,
+        oid: git2::Oid,
+    ) -> anyhow::Result<u64> {
+        if let Some(&n) = memo.borrow().get(&oid) {
+            return Ok(n);
+        }
+        let mut n = 1;
+        for parent in repo.find_commit(oid)?.parent_ids() {
+            n = n.max(sequence_number(repo, memo, parent)? + 1);
+        }
+        memo.borrow_mut().insert(oid, n);
+        Ok(n)
+    }
+
     fn commit_tree<'a>(repo: &'a git2::Repository, files: &[(&str, &str)]) -> git2::Tree<'a> {
         let mut builder = git2::build::TreeUpdateBuilder::new();
         for (path, content) in files {
@@ -998,6 +1165,351 @@ mod tests {
         assert_eq!(index, index2);
     }
 
+    #[test]
+    fn regex_search() {
+        let (_tmp, repo) = test_repo();
+        let cache = MapCache::default();
+
+        let mut files: Vec<(String, String)> = (0..20)
+            .map(|i| (format!("big/file_{:02}", i), format!("filler{:02}", i)))
+            .collect();
+        files.push(("big/fn_a".to_owned(), "fn parse_args() {}".to_owned()));
+        files.push(("big/fn_b".to_owned(), "fn parse_input() {}".to_owned()));
+        files.push(("big/call".to_owned(), "parse_args();".to_owned()));
+        let files: Vec<(&str, &str)> = files.iter().map(|(p, c)| (&p[..], &c[..])).collect();
+        let tree = commit_tree(&repo, &files);
+        let index =
+            trigram_index(&objects(&repo), &cache, &mut Indexer::default(), tree.id()).unwrap();
+
+        let candidates =
+            regex_candidates(&objects(&repo), index, tree.id(), r"fn parse_(args|input)").unwrap();
+        assert_eq!(candidates, vec!["big/fn_a", "big/fn_b"]);
+        let matches =
+            regex_matches(&objects(&repo), tree.id(), r"fn parse_\w+\(", &candidates).unwrap();
+        assert_eq!(matches.len(), 2);
+
+        // The trigrams of "parse_" narrow the candidates; the regex decides.
+        let candidates =
+            regex_candidates(&objects(&repo), index, tree.id(), r"^parse_\w+").unwrap();
+        assert_eq!(candidates, vec!["big/call", "big/fn_a", "big/fn_b"]);
+        let matches =
+            regex_matches(&objects(&repo), tree.id(), r"^parse_\w+", &candidates).unwrap();
+        assert_eq!(
+            matches,
+            vec![("big/call".to_owned(), vec![(1, "parse_args();".to_owned())])]
+        );
+
+        // No trigram required: every file is a candidate.
+        let candidates = regex_candidates(&objects(&repo), index, tree.id(), r"\d+").unwrap();
+        assert_eq!(candidates.len(), 23);
+
+        assert!(regex_candidates(&objects(&repo), index, tree.id(), "(").is_err());
+    }
+
+    #[test]
+    fn big_blobs_are_neither_indexed_nor_searched() {
+        let (_tmp, repo) = test_repo();
+        let cache = MapCache::default();
+        let store = josh_memodb::MemOdb::new(
+            None,
+            josh_memodb::objects_dir(&repo),
+            josh_memodb::object_hash(&repo),
+        );
+        let odb = josh_memodb::Odb::new(store, repo.odb().unwrap()).with_big_object_threshold(16);
+
+        let tree = commit_tree(
+            &repo,
+            &[
+                ("small", "parse_args();"),
+                ("big", "fn parse_args() -> Vec<String> {}"),
+            ],
+        );
+        let index = trigram_index(&odb, &cache, &mut Indexer::default(), tree.id()).unwrap();
+
+        let candidates = regex_candidates(&odb, index, tree.id(), "parse_args").unwrap();
+        assert_eq!(candidates, vec!["small"]);
+        let matches =
+            regex_matches(&odb, tree.id(), "parse_args", &vec!["big".to_owned()]).unwrap();
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn search_options() {
+        let (_tmp, repo) = test_repo();
+        let cache = MapCache::default();
+
+        let tree = commit_tree(
+            &repo,
+            &[
+                ("docs/guide.md", "Needle in the docs"),
+                ("src/a.rs", "one\nneedle\ntwo\nthree\nneedle\nfour"),
+                ("src/b.rs", "needle"),
+                ("src/c.rs", "NEEDLE"),
+            ],
+        );
+        let index =
+            trigram_index(&objects(&repo), &cache, &mut Indexer::default(), tree.id()).unwrap();
+        let pattern = |glob: &str| josh_filter::pattern::CompiledPattern::compile(glob).unwrap();
+        let paths = |page: &SearchPage| -> Vec<String> {
+            page.files.iter().map(|file| file.path.clone()).collect()
+        };
+
+        // With and without the index, the same results.
+        for index in [Some(index), None] {
+            let run = |options: &SearchOptions| {
+                search(&objects(&repo), index, tree.id(), "needle", options).unwrap()
+            };
+
+            let page = run(&SearchOptions::default());
+            assert_eq!(paths(&page), vec!["src/a.rs", "src/b.rs"]);
+            assert_eq!(page.next, None);
+
+            let page = run(&SearchOptions {
+                ignore_case: true,
+                ..Default::default()
+            });
+            assert_eq!(
+                paths(&page),
+                vec!["docs/guide.md", "src/a.rs", "src/b.rs", "src/c.rs"]
+            );
+
+            let page = run(&SearchOptions {
+                ignore_case: true,
+                include: vec![pattern("src/*")],
+                exclude: vec![pattern("src/a.rs")],
+                ..Default::default()
+            });
+            assert_eq!(paths(&page), vec!["src/b.rs", "src/c.rs"]);
+
+            // Context lines around each hit, shared where they overlap.
+            let page = run(&SearchOptions {
+                include: vec![pattern("src/a.rs")],
+                before: 1,
+                after: 2,
+                ..Default::default()
+            });
+            let lines: Vec<(usize, bool)> = page.files[0]
+                .lines
+                .iter()
+                .map(|line| (line.number, line.is_match))
+                .collect();
+            assert_eq!(
+                lines,
+                vec![
+                    (1, false),
+                    (2, true),
+                    (3, false),
+                    (4, false),
+                    (5, true),
+                    (6, false)
+                ]
+            );
+
+            // Paging through all matching lines, one at a time.
+            let mut options = SearchOptions {
+                max_results: Some(1),
+                ..Default::default()
+            };
+            let mut seen = vec![];
+            loop {
+                let page = run(&options);
+                for file in &page.files {
+                    for line in &file.lines {
+                        seen.push((file.path.clone(), line.number));
+                    }
+                }
+                match page.next {
+                    Some(next) => options.cursor = Some(next),
+                    None => break,
+                }
+            }
+            assert_eq!(
+                seen,
+                vec![
+                    ("src/a.rs".to_owned(), 2),
+                    ("src/a.rs".to_owned(), 5),
+                    ("src/b.rs".to_owned(), 1)
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn history_search() {
+        let (_tmp, repo) = test_repo();
+        let cache = MapCache::default();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+
+        let mut commits = vec![];
+        for files in [
+            &[("a.txt", "foo"), ("b.txt", "x")][..],
+            &[("a.txt", "foo"), ("b.txt", "y")],
+            &[("a.txt", "foo foo"), ("b.txt", "y")],
+            &[("a.txt", "foo foo"), ("b.txt", "y"), ("sub/c.txt", "a foo")],
+            &[("b.txt", "y"), ("sub/c.txt", "a foo")],
+        ] {
+            let tree = commit_tree(&repo, files);
+            let parents: Vec<git2::Commit> = commits
+                .last()
+                .map(|&oid| repo.find_commit(oid).unwrap())
+                .into_iter()
+                .collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            commits.push(
+                repo.commit(None, &signature, &signature, "c", &tree, &parents)
+                    .unwrap(),
+            );
+        }
+        // A side branch off the first commit, merged at the end. The merge itself is skipped.
+        let commit = |files: &[(&str, &str)], parents: &[git2::Oid]| {
+            let parents: Vec<git2::Commit> = parents
+                .iter()
+                .map(|&oid| repo.find_commit(oid).unwrap())
+                .collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            repo.commit(
+                None,
+                &signature,
+                &signature,
+                "c",
+                &commit_tree(&repo, files),
+                &parents,
+            )
+            .unwrap()
+        };
+        let side = commit(
+            &[("a.txt", "foo"), ("b.txt", "x"), ("side.txt", "foo")],
+            &[commits[0]],
+        );
+        let head = commit(
+            &[("b.txt", "y"), ("side.txt", "foo"), ("sub/c.txt", "a foo")],
+            &[commits[4], side],
+        );
+        let odb = objects(&repo);
+        let history = || {
+            let (repo, memo) = (&repo, std::cell::RefCell::new(HashMap::new()));
+            let mut walk =
+                josh_gix_ext::HistoryWalk::new(&odb, move |oid| sequence_number(repo, &memo, oid));
+            walk.push(head).unwrap();
+            walk
+        };
+
+        let change = |path: &str, before, after| OccurrenceChange {
+            path: path.to_owned(),
+            before,
+            after,
+        };
+        let expected = vec![
+            HistoryMatch {
+                commit: commits[4],
+                changes: vec![change("a.txt", 2, 0)],
+            },
+            HistoryMatch {
+                commit: commits[3],
+                changes: vec![change("sub/c.txt", 0, 1)],
+            },
+            HistoryMatch {
+                commit: commits[2],
+                changes: vec![change("a.txt", 1, 2)],
+            },
+            HistoryMatch {
+                commit: side,
+                changes: vec![change("side.txt", 0, 1)],
+            },
+            HistoryMatch {
+                commit: commits[0],
+                changes: vec![change("a.txt", 0, 1)],
+            },
+        ];
+
+        // With and without the index, the same results.
+        for indexed in [true, false] {
+            let mut index = |tree: git2::Oid| -> anyhow::Result<Option<git2::Oid>> {
+                Ok(match indexed {
+                    true => Some(trigram_index(
+                        &objects(&repo),
+                        &cache,
+                        &mut Indexer::default(),
+                        tree,
+                    )?),
+                    false => None,
+                })
+            };
+            let run = |index: &mut dyn FnMut(git2::Oid) -> anyhow::Result<Option<git2::Oid>>,
+                       needle: &str,
+                       max_results| {
+                search_history(&objects(&repo), history(), needle, index, max_results).unwrap()
+            };
+            assert_eq!(run(&mut index, "foo", None), expected);
+            assert_eq!(run(&mut index, "foo", Some(2)), expected[..2]);
+            assert_eq!(run(&mut index, "absent", None), vec![]);
+        }
+        assert!(search_history(&objects(&repo), history(), "", &mut |_| Ok(None), None).is_err());
+    }
+
+    #[test]
+    fn symbol_index_end_to_end() {
+        let (_tmp, repo) = test_repo();
+        let cache = MapCache::default();
+        let location = |path: &str, line| SymbolLocation {
+            path: path.to_owned(),
+            line,
+        };
+
+        let files = [
+            (
+                "core/src/lib.rs",
+                "pub fn parse() {}\n\npub fn run() {\n    parse();\n}\n",
+            ),
+            (
+                "tools/gen.py",
+                "from core import parse\n\ndef main():\n    parse()\n",
+            ),
+            ("README.md", "fn parse is documented here"),
+        ];
+        let tree = commit_tree(&repo, &files);
+        let index = symbol_index(&objects(&repo), &cache, tree.id()).unwrap();
+
+        assert_eq!(
+            definitions(&objects(&repo), index, "parse").unwrap(),
+            vec![location("core/src/lib.rs", 1)]
+        );
+        assert_eq!(
+            references(&objects(&repo), index, "parse").unwrap(),
+            vec![
+                location("core/src/lib.rs", 4),
+                location("tools/gen.py", 1),
+                location("tools/gen.py", 4)
+            ]
+        );
+        assert_eq!(
+            definitions(&objects(&repo), index, "main").unwrap(),
+            vec![location("tools/gen.py", 3)]
+        );
+        assert!(
+            definitions(&objects(&repo), index, "absent")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            definitions(&objects(&repo), index, "../x")
+                .unwrap()
+                .is_empty()
+        );
+
+        // Reindexing a changed tree reuses the unchanged subtrees and equals a cold build.
+        let mut changed = files.to_vec();
+        changed[1].1 = "def main():\n    pass\n";
+        let tree = commit_tree(&repo, &changed);
+        let incremental = symbol_index(&objects(&repo), &cache, tree.id()).unwrap();
+        let cold = symbol_index(&objects(&repo), &MapCache::default(), tree.id()).unwrap();
+        assert_eq!(incremental, cold);
+        assert_eq!(
+            references(&objects(&repo), incremental, "parse").unwrap(),
+            vec![location("core/src/lib.rs", 4)]
+        );
+    }
+
     #[test]
     fn coarse_and_fine_granularity() {
         let (_tmp, repo) = test_repo();
@@ -0,0 +1,187 @@
+//! Scoped, bounded search: [`search`] runs a literal or regex query over a tree, restricted to
+//! paths selected by globs, with optional case folding and context lines, one page of results
+//! at a time. josh-filter's `--search` and the GraphQL search fields are all built on it.
+
+use josh_filter::pattern::{CompiledPattern, PATTERN_MATCH_OPTIONS};
+
+use super::{Objects, collect_paths, get_blob_path, regex_candidates, search_candidates};
+
+/// How [`search`] matches, scopes and bounds its results.
+#[derive(Clone, Default)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression (`regex` crate syntax) instead of a literal.
+    pub regex: bool,
+    /// Match ASCII and Unicode case-insensitively.
+    pub ignore_case: bool,
+    /// Search only files whose path matches one of these globs; every file when empty.
+    pub include: Vec<CompiledPattern>,
+    /// Skip files whose path matches any of these globs.
+    pub exclude: Vec<CompiledPattern>,
+    /// Lines of context shown before and after each matching line.
+    pub before: usize,
+    pub after: usize,
+    /// Stop after this many matching lines; [`SearchPage::next`] continues from there.
+    pub max_results: Option<usize>,
+    /// Resume after this cursor of a previous page of the same search on the same tree.
+    pub cursor: Option<String>,
+}
+
+impl SearchOptions {
+    /// Whether the file at `path` is in scope.
+    fn selects(&self, path: &str) -> bool {
+        let matches =
+            |pattern: &CompiledPattern| pattern.full.matches_with(path, PATTERN_MATCH_OPTIONS);
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+}
+
+/// One line of a [`FileMatches`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    /// Counted from 1.
+    pub number: usize,
+    pub text: String,
+    /// `false` for a context line.
+    pub is_match: bool,
+}
+
+/// The matching lines of one file, in order, with their context.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileMatches {
+    pub path: String,
+    pub lines: Vec<Line>,
+    /// Resumes the search after the last matching line of this file.
+    pub cursor: String,
+}
+
+/// One page of results of [`search`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchPage {
+    pub files: Vec<FileMatches>,
+    /// Set when [`SearchOptions::max_results`] cut the results short: pass it as
+    /// [`SearchOptions::cursor`] to get the next page.
+    pub next: Option<String>,
+}
+
+/// Cursors are opaque to callers; inside they are the last line returned and its file.
+fn cursor(path: &str, line: usize) -> String {
+    format!("{}:{}", line, path)
+}
+
+fn parse_cursor(cursor: &str) -> anyhow::Result<(usize, &str)> {
+    cursor
+        .split_once(':')
+        .and_then(|(line, path)| Some((line.parse().ok()?, path)))
+        .ok_or_else(|| anyhow::anyhow!("invalid search cursor: {}", cursor))
+}
+
+/// Search the files of `tree` for `query`. With an `index_tree` (the trigram index of `tree`)
+/// only candidate files are read; without one every file in scope is.
+pub fn search(
+    src: &dyn Objects,
+    index_tree: Option<git2::Oid>,
+    tree: git2::Oid,
+    query: &str,
+    options: &SearchOptions,
+) -> anyhow::Result<SearchPage> {
+    // A case-folded literal search runs as a regex too: the literal's trigrams would miss
+    // non-ASCII case variants, which the regex analysis spells out.
+    let pattern = (options.regex || options.ignore_case).then(|| {
+        let pattern = match options.regex {
+            true => query.to_owned(),
+            false => regex::escape(query),
+        };
+        match options.ignore_case {
+            true => format!("(?i){}", pattern),
+            false => pattern,
+        }
+    });
+    let regex = pattern.as_deref().map(regex::Regex::new).transpose()?;
+    let is_match = |line: &str| match &regex {
+        Some(regex) => regex.is_match(line),
+        None => line.contains(query),
+    };
+
+    let mut candidates = match (index_tree, &pattern) {
+        (Some(index_tree), Some(pattern)) => regex_candidates(src, index_tree, tree, pattern)?,
+        (Some(index_tree), None) => search_candidates(src, index_tree, tree, query)?,
+        (None, _) => {
+            let mut all = vec![];
+            collect_paths(src, tree, "", &mut all)?;
+            all
+        }
+    };
+    candidates.retain(|path| options.selects(path));
+
+    let mut resume = None;
+    if let Some(cursor) = &options.cursor {
+        let (line, path) = parse_cursor(cursor)?;
+        let start = candidates
+            .iter()
+            .position(|candidate| candidate == path)
+            .ok_or_else(|| anyhow::anyhow!("search cursor {} is not in the results", cursor))?;
+        candidates.drain(..start);
+        resume = Some(line);
+    }
+
+    let mut page = SearchPage::default();
+    let mut remaining = options.max_results.unwrap_or(usize::MAX);
+    for (i, path) in candidates.iter().enumerate() {
+        let text = get_blob_path(src, tree, std::path::Path::new(path));
+        let lines: Vec<&str> = text.lines().collect();
+        let skip = match i {
+            0 => resume.unwrap_or(0),
+            _ => 0,
+        };
+        let hits: Vec<usize> = (skip..lines.len())
+            .filter(|&n| is_match(lines[n]))
+            .collect();
+        if hits.is_empty() {
+            continue;
+        }
+        if remaining == 0 {
+            page.next = Some(match page.files.last() {
+                Some(last) => last.cursor.clone(),
+                None => cursor(path, skip),
+            });
+            break;
+        }
+        let taken = &hits[..hits.len().min(remaining)];
+        remaining -= taken.len();
+        page.files.push(file_matches(path, &lines, taken, options));
+        if taken.len() < hits.len() {
+            page.next = page.files.last().map(|last| last.cursor.clone());
+            break;
+        }
+    }
+    Ok(page)
+}
+
+/// The `hits` (0-based line indices) of `lines` with their context; overlapping context is
+/// shown once.
+fn file_matches(
+    path: &str,
+    lines: &[&str],
+    hits: &[usize],
+    options: &SearchOptions,
+) -> FileMatches {
+    let mut shown = std::collections::BTreeSet::new();
+    for &hit in hits {
+        let last = (hit + options.after).min(lines.len() - 1);
+        shown.extend(hit.saturating_sub(options.before)..=last);
+    }
+    let last_hit = *hits.last().expect("a file with matches");
+    FileMatches {
+        path: path.to_owned(),
+        lines: shown
+            .into_iter()
+            .map(|n| Line {
+                number: n + 1,
+                text: lines[n].to_owned(),
+                is_match: hits.binary_search(&n).is_ok(),
+            })
+            .collect(),
+        cursor: cursor(path, last_hit + 1),
+    }
+}
@@ -0,0 +1,466 @@
+//! Trigram queries for regular expressions, after Russ Cox's codesearch
+//! (<https://swtch.com/~rsc/regexp/regexp4.html>).
+//!
+//! A regex is analyzed bottom-up: for every node we keep what the strings it matches are known
+//! to look like — the whole set of them when it is small ("exact"), otherwise sets of possible
+//! prefixes and suffixes — plus a [`Query`] of trigrams every match must contain. Concatenation
+//! crosses the sets (and learns the trigrams spanning the seam), alternation unions them and ORs
+//! the queries; sets that grow too big are turned into trigram requirements and cut down to
+//! short prefixes and suffixes. The result is an AND/OR of trigrams that every file containing a
+//! match satisfies, so candidates stay a superset and the regex itself does the verification.
+//!
+//! All strings are [`fold_byte`]-normalized as they enter the analysis, like indexed content
+//! is, so case-insensitive classes such as `[Aa]` collapse to a single string.
+
+use std::collections::BTreeSet;
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+use super::fold_byte;
+
+/// Exact sets larger than this are turned into trigrams plus prefixes and suffixes.
+const MAX_EXACT: usize = 7;
+
+/// Prefix and suffix sets are cut down to shorter strings until they are no larger than this.
+const MAX_SET: usize = 20;
+
+/// Classes of more characters than this count as "any character".
+const MAX_CLASS: u32 = 100;
+
+/// A boolean query over trigrams: a file can match only if it satisfies the query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query {
+    /// Every file.
+    All,
+    /// No file.
+    None,
+    Trigram([u8; 3]),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+impl Query {
+    pub fn and(self, other: Query) -> Query {
+        if self.implies(&other) {
+            return self;
+        }
+        if other.implies(&self) {
+            return other;
+        }
+        let mut subs = match self {
+            Query::And(subs) => subs,
+            q => vec![q],
+        };
+        let others = match other {
+            Query::And(subs) => subs,
+            q => vec![q],
+        };
+        for q in others {
+            if !subs.iter().any(|sub| sub.implies(&q)) {
+                subs.retain(|sub| !q.implies(sub));
+                subs.push(q);
+            }
+        }
+        match subs.len() {
+            1 => subs.remove(0),
+            _ => Query::And(subs),
+        }
+    }
+
+    pub fn or(self, other: Query) -> Query {
+        if self.implies(&other) {
+            return other;
+        }
+        if other.implies(&self) {
+            return self;
+        }
+        let mut subs = match self {
+            Query::Or(subs) => subs,
+            q => vec![q],
+        };
+        let others = match other {
+            Query::Or(subs) => subs,
+            q => vec![q],
+        };
+        for q in others {
+            if !subs.iter().any(|sub| q.implies(sub)) {
+                subs.retain(|sub| !sub.implies(&q));
+                subs.push(q);
+            }
+        }
+        match subs.len() {
+            1 => subs.remove(0),
+            _ => Query::Or(subs),
+        }
+    }
+
+    /// Whether every file satisfying `self` satisfies `other`, as far as the structure of the
+    /// two tells. Lets `abc OR (abc AND bcd)` collapse to `abc`.
+    fn implies(&self, other: &Query) -> bool {
+        if self == other {
+            return true;
+        }
+        match (self, other) {
+            (Query::None, _) | (_, Query::All) => true,
+            (Query::And(subs), _) if subs.iter().any(|sub| sub.implies(other)) => true,
+            (_, Query::Or(subs)) if subs.iter().any(|sub| self.implies(sub)) => true,
+            (_, Query::And(subs)) => subs.iter().all(|sub| self.implies(sub)),
+            (Query::Or(subs), _) => subs.iter().all(|sub| sub.implies(other)),
+            _ => false,
+        }
+    }
+
+    /// AND the query with "one of the strings of `set` occurs". Strings shorter than a trigram
+    /// say nothing, so neither does a set containing one.
+    fn and_trigrams(self, set: &Strings) -> Query {
+        if min_len(set) < 3 {
+            return self;
+        }
+        let any = set.iter().fold(Query::None, |any, s| {
+            let all = s
+                .windows(3)
+                .filter(|w| std::str::from_utf8(w).is_ok())
+                .fold(Query::All, |all, w| {
+                    all.and(Query::Trigram([w[0], w[1], w[2]]))
+                });
+            any.or(all)
+        });
+        self.and(any)
+    }
+}
+
+/// The trigram query of the regular expression `pattern`, parsed with the `regex` crate's
+/// default syntax.
+pub fn regex_query(pattern: &str) -> anyhow::Result<Query> {
+    let hir = regex_syntax::Parser::new().parse(pattern)?;
+    let info = analyze(&hir).simplify(true);
+    Ok(match &info.exact {
+        Some(exact) => info.query.and_trigrams(exact),
+        None => info.query,
+    })
+}
+
+/// Folded byte strings.
+type Strings = BTreeSet<Vec<u8>>;
+
+fn min_len(set: &Strings) -> usize {
+    set.iter().map(Vec::len).min().unwrap_or(0)
+}
+
+fn cross(a: &Strings, b: &Strings) -> Strings {
+    a.iter()
+        .flat_map(|a| b.iter().map(move |b| [&a[..], &b[..]].concat()))
+        .collect()
+}
+
+fn union(a: &Strings, b: &Strings) -> Strings {
+    a.union(b).cloned().collect()
+}
+
+/// What is known about the strings a regex node matches.
+struct Info {
+    can_empty: bool,
+    /// Every string the node matches, when that set is small enough to keep.
+    exact: Option<Strings>,
+    /// Possible prefixes and suffixes of the strings matched; meaningful when `exact` is not.
+    prefix: Strings,
+    suffix: Strings,
+    query: Query,
+}
+
+impl Info {
+    fn exact(set: Strings) -> Info {
+        Info {
+            can_empty: set.contains(&vec![]),
+            exact: Some(set),
+            prefix: Strings::new(),
+            suffix: Strings::new(),
+            query: Query::All,
+        }
+    }
+
+    fn empty_string() -> Info {
+        Info::exact(Strings::from([vec![]]))
+    }
+
+    fn any_char() -> Info {
+        Info {
+            can_empty: false,
+            exact: None,
+            prefix: Strings::from([vec![]]),
+            suffix: Strings::from([vec![]]),
+            query: Query::All,
+        }
+    }
+
+    fn any_match() -> Info {
+        Info {
+            can_empty: true,
+            ..Info::any_char()
+        }
+    }
+
+    fn no_match() -> Info {
+        Info {
+            can_empty: false,
+            exact: None,
+            prefix: Strings::new(),
+            suffix: Strings::new(),
+            query: Query::None,
+        }
+    }
+
+    /// Move what `exact` says into the query, and keep prefix and suffix sets small.
+    fn simplify(mut self, force: bool) -> Info {
+        if let Some(exact) = self
+            .exact
+            .take_if(|e| e.len() > MAX_EXACT || min_len(e) >= 4 || (force && min_len(e) >= 3))
+        {
+            self.query = self.query.and_trigrams(&exact);
+            for s in exact {
+                if s.len() < 3 {
+                    self.prefix.insert(s.clone());
+                    self.suffix.insert(s);
+                } else {
+                    self.prefix.insert(s[..2].to_vec());
+                    self.suffix.insert(s[s.len() - 2..].to_vec());
+                }
+            }
+        }
+        if self.exact.is_none() {
+            let prefix = std::mem::take(&mut self.prefix);
+            self.prefix = self.simplify_set(prefix, false);
+            let suffix = std::mem::take(&mut self.suffix);
+            self.suffix = self.simplify_set(suffix, true);
+        }
+        self
+    }
+
+    /// Require one of the strings of `set` in the query, then cut the strings down to at most
+    /// two bytes, and shorter still while the set is too large. A string another string of the
+    /// set is a prefix (suffix) of adds nothing and is dropped.
+    fn simplify_set(&mut self, mut set: Strings, suffix: bool) -> Strings {
+        self.query = std::mem::replace(&mut self.query, Query::All).and_trigrams(&set);
+        let mut n = 3;
+        while n > 0 && (n == 3 || set.len() > MAX_SET) {
+            set = set
+                .into_iter()
+                .map(|s| match s.len() {
+                    len if len < n => s,
+                    len if suffix => s[len - (n - 1)..].to_vec(),
+                    _ => s[..n - 1].to_vec(),
+                })
+                .collect();
+            n -= 1;
+        }
+        set.iter()
+            .filter(|s| {
+                !set.iter().any(|t| {
+                    t.len() < s.len()
+                        && if suffix {
+                            s.ends_with(t)
+                        } else {
+                            s.starts_with(t)
+                        }
+                })
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+fn analyze(hir: &Hir) -> Info {
+    let info = match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => Info::empty_string(),
+        HirKind::Literal(literal) => Info::exact(Strings::from([literal
+            .0
+            .iter()
+            .map(|&b| fold_byte(b))
+            .collect()])),
+        HirKind::Class(class) => analyze_class(class),
+        HirKind::Capture(capture) => analyze(&capture.sub),
+        HirKind::Repetition(repetition) => match (repetition.min, repetition.max) {
+            (0, Some(1)) => alternate(analyze(&repetition.sub), Info::empty_string()),
+            (0, _) => Info::any_match(),
+            (1, Some(1)) => analyze(&repetition.sub),
+            // At least one repetition: prefixes and suffixes stay, exactness does not.
+            _ => {
+                let mut info = analyze(&repetition.sub);
+                if let Some(exact) = info.exact.take() {
+                    info.prefix = exact.clone();
+                    info.suffix = exact;
+                }
+                info
+            }
+        },
+        HirKind::Concat(subs) => subs
+            .iter()
+            .fold(Info::empty_string(), |info, sub| concat(info, analyze(sub))),
+        HirKind::Alternation(subs) => subs
+            .iter()
+            .map(analyze)
+            .reduce(alternate)
+            .unwrap_or_else(Info::no_match),
+    };
+    info.simplify(false)
+}
+
+fn analyze_class(class: &Class) -> Info {
+    let set: Strings = match class {
+        Class::Unicode(class) => {
+            let size: u32 = class
+                .iter()
+                .map(|range| u32::from(range.end()) - u32::from(range.start()) + 1)
+                .sum();
+            if size > MAX_CLASS {
+                return Info::any_char();
+            }
+            class
+                .iter()
+                .flat_map(|range| range.start()..=range.end())
+                .map(|c| c.to_string().bytes().map(fold_byte).collect())
+                .collect()
+        }
+        Class::Bytes(class) => {
+            let size: u32 = class
+                .iter()
+                .map(|range| u32::from(range.end()) - u32::from(range.start()) + 1)
+                .sum();
+            if size > MAX_CLASS {
+                return Info::any_char();
+            }
+            class
+                .iter()
+                .flat_map(|range| range.start()..=range.end())
+                .map(|b| vec![fold_byte(b)])
+                .collect()
+        }
+    };
+    if set.is_empty() {
+        return Info::no_match();
+    }
+    Info::exact(set)
+}
+
+fn concat(x: Info, y: Info) -> Info {
+    let mut xy = Info {
+        can_empty: x.can_empty && y.can_empty,
+        exact: None,
+        prefix: Strings::new(),
+        suffix: Strings::new(),
+        query: Query::All,
+    };
+    match (&x.exact, &y.exact) {
+        (Some(x_exact), Some(y_exact)) => xy.exact = Some(cross(x_exact, y_exact)),
+        _ => {
+            xy.prefix = match &x.exact {
+                Some(x_exact) => cross(x_exact, &y.prefix),
+                None if x.can_empty => union(&x.prefix, &y.prefix),
+                None => x.prefix.clone(),
+            };
+            xy.suffix = match &y.exact {
+                Some(y_exact) => cross(&x.suffix, y_exact),
+                None if y.can_empty => union(&y.suffix, &x.suffix),
+                None => y.suffix.clone(),
+            };
+        }
+    }
+    // The trigrams spanning the seam between x and y are in neither side's query yet.
+    if x.exact.is_none()
+        && y.exact.is_none()
+        && x.suffix.len() <= MAX_SET
+        && y.prefix.len() <= MAX_SET
+        && min_len(&x.suffix) + min_len(&y.prefix) >= 3
+    {
+        xy.query = xy.query.and_trigrams(&cross(&x.suffix, &y.prefix));
+    }
+    xy.query = xy.query.and(x.query).and(y.query);
+    xy
+}
+
+fn alternate(x: Info, y: Info) -> Info {
+    let mut xy = Info {
+        can_empty: x.can_empty || y.can_empty,
+        exact: None,
+        prefix: Strings::new(),
+        suffix: Strings::new(),
+        query: Query::All,
+    };
+    let (mut x_query, mut y_query) = (x.query, y.query);
+    match (x.exact, y.exact) {
+        (Some(x_exact), Some(y_exact)) => xy.exact = Some(union(&x_exact, &y_exact)),
+        (Some(x_exact), None) => {
+            xy.prefix = union(&x_exact, &y.prefix);
+            xy.suffix = union(&x_exact, &y.suffix);
+            x_query = x_query.and_trigrams(&x_exact);
+        }
+        (None, Some(y_exact)) => {
+            xy.prefix = union(&x.prefix, &y_exact);
+            xy.suffix = union(&x.suffix, &y_exact);
+            y_query = y_query.and_trigrams(&y_exact);
+        }
+        (None, None) => {
+            xy.prefix = union(&x.prefix, &y.prefix);
+            xy.suffix = union(&x.suffix, &y.suffix);
+        }
+    }
+    xy.query = x_query.or(y_query);
+    xy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str) -> Query {
+        let b = s.as_bytes();
+        Query::Trigram([b[0], b[1], b[2]])
+    }
+
+    fn and(qs: &[&str]) -> Query {
+        qs.iter().fold(Query::All, |q, s| q.and(t(s)))
+    }
+
+    #[test]
+    fn literals_require_all_their_trigrams() {
+        assert_eq!(regex_query("hello").unwrap(), and(&["hel", "ell", "llo"]));
+        // Folded like indexed content: case and punctuation classes collapse.
+        assert_eq!(
+            regex_query("(?i)Hello").unwrap(),
+            regex_query("hello").unwrap()
+        );
+        assert_eq!(regex_query("f\\(x").unwrap(), and(&["f x"]));
+        // Too short to say anything.
+        assert_eq!(regex_query("ab").unwrap(), Query::All);
+        assert_eq!(regex_query(".*").unwrap(), Query::All);
+    }
+
+    #[test]
+    fn alternation_ors_and_concatenation_ands() {
+        assert_eq!(
+            regex_query("abc|xyz").unwrap(),
+            Query::Or(vec![t("abc"), t("xyz")])
+        );
+        assert_eq!(regex_query("abc.*xyz").unwrap(), and(&["abc", "xyz"]));
+        // Small classes multiply out into alternatives.
+        assert_eq!(
+            regex_query("ab[cd]").unwrap(),
+            Query::Or(vec![t("abc"), t("abd")])
+        );
+        // An optional part leaves only what is certain.
+        assert_eq!(regex_query("abcd?").unwrap(), t("abc"));
+        // A repeated part keeps its trigrams and the seams around it.
+        assert_eq!(
+            regex_query("x(abc)+y").unwrap(),
+            and(&["abc", "xab", "bcy"])
+        );
+        // A class too large to enumerate breaks the string in two.
+        assert_eq!(regex_query("abc\\wxyz").unwrap(), and(&["abc", "xyz"]));
+    }
+
+    #[test]
+    fn impossible_classes_match_nothing() {
+        assert_eq!(regex_query("abc[^\\s\\S]").unwrap(), Query::None);
+        assert_eq!(regex_query("abc[^\\s\\S]|xyz").unwrap(), t("xyz"));
+    }
+}
@@ -0,0 +1,438 @@
+//! Symbol index for code navigation: where identifiers are defined and where they are used.
+//!
+//! Like the trigram index, the symbol index of a tree is itself a git tree, with a hex spine
+//! and a mirror of the source tree below it:
+//!
+//! ```text
+//! def/<hex(first byte)>/<symbol>/path/to/file
+//! ref/<hex(first byte)>/<symbol>/path/to/file
+//! ```
+//!
+//! where each leaf is a blob of the line numbers (one per line, counted from 1) at which the
+//! file defines or references the symbol. Indexes are built compositionally per directory and
+//! memoized per subtree through the same [`IndexCache`] trait, so reindexing the next commit
+//! only rebuilds the directories that changed.
+//!
+//! Symbols are extracted per [`Language`], chosen by file extension, from the file's
+//! tree-sitter syntax tree: the name of a definition (a function, type, module, constant, ...)
+//! is a definition and any other identifier a reference. Names are not resolved, so a method
+//! and a free function of the same name are one symbol.
+
+use std::collections::BTreeMap;
+
+use gix_object::bstr::BString;
+
+use super::{
+    IndexCache, Objects, empty_tree, hex_name, join_path, path_entry, read_blob_text,
+    read_tree_entries, to_git2, to_gix, tree_entry,
+};
+
+/// A language whose files get symbols in the index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl Language {
+    /// The language of the file `name`, by extension.
+    pub fn from_path(name: &str) -> Option<Language> {
+        match name.rsplit_once('.')?.1 {
+            "rs" => Some(Language::Rust),
+            "py" | "pyi" => Some(Language::Python),
+            "ts" | "mts" | "cts" => Some(Language::TypeScript),
+            "tsx" => Some(Language::Tsx),
+            "go" => Some(Language::Go),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Language::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Language::Python => tree_sitter_python::LANGUAGE.into(),
+            Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Language::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Language::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+
+    /// Syntax nodes that name a symbol.
+    fn is_identifier(self, kind: &str) -> bool {
+        match self {
+            Language::Rust => matches!(
+                kind,
+                "identifier"
+                    | "type_identifier"
+                    | "field_identifier"
+                    | "shorthand_field_identifier"
+            ),
+            Language::Python => kind == "identifier",
+            Language::TypeScript | Language::Tsx => matches!(
+                kind,
+                "identifier"
+                    | "type_identifier"
+                    | "property_identifier"
+                    | "shorthand_property_identifier"
+                    | "shorthand_property_identifier_pattern"
+            ),
+            Language::Go => matches!(
+                kind,
+                "identifier" | "type_identifier" | "field_identifier" | "package_identifier"
+            ),
+        }
+    }
+
+    /// Syntax nodes whose `name` defines a symbol.
+    fn is_definition(self, kind: &str) -> bool {
+        match self {
+            Language::Rust => matches!(
+                kind,
+                "function_item"
+                    | "function_signature_item"
+                    | "struct_item"
+                    | "enum_item"
+                    | "union_item"
+                    | "trait_item"
+                    | "type_item"
+                    | "associated_type"
+                    | "mod_item"
+                    | "const_item"
+                    | "static_item"
+                    | "macro_definition"
+            ),
+            Language::Python => matches!(kind, "function_definition" | "class_definition"),
+            Language::TypeScript | Language::Tsx => matches!(
+                kind,
+                "function_declaration"
+                    | "generator_function_declaration"
+                    | "function_signature"
+                    | "class_declaration"
+                    | "abstract_class_declaration"
+                    | "interface_declaration"
+                    | "type_alias_declaration"
+                    | "enum_declaration"
+                    | "internal_module"
+                    | "module"
+                    | "method_definition"
+                    | "method_signature"
+                    | "abstract_method_signature"
+                    | "variable_declarator"
+            ),
+            Language::Go => matches!(
+                kind,
+                "function_declaration"
+                    | "method_declaration"
+                    | "type_spec"
+                    | "type_alias"
+                    | "const_spec"
+                    | "var_spec"
+            ),
+        }
+    }
+}
+
+/// The symbols `text` defines and references, each with the lines it occurs on.
+type FileSymbols = (BTreeMap<String, Vec<usize>>, BTreeMap<String, Vec<usize>>);
+
+/// Parse `text` and collect its identifiers: the name of a definition is defined, any other
+/// identifier referenced. Files with syntax errors still yield what the parser recovered.
+fn extract(language: Language, text: &str) -> anyhow::Result<FileSymbols> {
+    let (mut defs, mut refs) = (BTreeMap::new(), BTreeMap::new());
+    let record = |map: &mut BTreeMap<String, Vec<usize>>, word: &str, line: usize| {
+        let lines: &mut Vec<usize> = map.entry(word.to_owned()).or_default();
+        if lines.last() != Some(&line) {
+            lines.push(line);
+        }
+    };
+
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&language.grammar())?;
+    let tree = parser
+        .parse(text, None)
+        .ok_or_else(|| anyhow::anyhow!("parsing was cancelled"))?;
+    let mut cursor = tree.walk();
+    loop {
+        let node = cursor.node();
+        let parent = node.parent().map(|parent| parent.kind());
+        // Rust lifetimes ('a) are identifiers in the grammar, but not symbols.
+        if language.is_identifier(node.kind()) && parent != Some("lifetime") {
+            let word = &text[node.byte_range()];
+            let line = node.start_position().row + 1;
+            let defining = cursor.field_name() == Some("name")
+                && parent.is_some_and(|p| language.is_definition(p));
+            match defining {
+                true => record(&mut defs, word, line),
+                false => record(&mut refs, word, line),
+            }
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return Ok((defs, refs));
+            }
+        }
+    }
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$'
+}
+
+/// The index kinds: `def` for definitions, `ref` for references.
+const KINDS: [&str; 2] = ["def", "ref"];
+
+/// Build (or look up) the symbol index of `tree`.
+pub fn symbol_index(
+    src: &dyn Objects,
+    cache: &dyn IndexCache,
+    tree: git2::Oid,
+) -> anyhow::Result<git2::Oid> {
+    if let Some(index) = cache.get_index(tree) {
+        return Ok(index);
+    }
+
+    // (kind, symbol) -> the mirror entries of this directory below it.
+    let mut spine: BTreeMap<(&str, BString), Vec<gix_object::tree::Entry>> = BTreeMap::new();
+    for entry in read_tree_entries(src, tree)? {
+        let name = std::str::from_utf8(&entry.filename)?;
+        if entry.mode.is_tree() {
+            let child = symbol_index(src, cache, to_git2(entry.oid))?;
+            for (kind, symbol, mirror) in read_spine(src, child)? {
+                spine
+                    .entry((kind, symbol))
+                    .or_default()
+                    .push(tree_entry(entry.filename.clone(), mirror));
+            }
+        } else if !entry.mode.is_commit() {
+            let Some(language) = Language::from_path(name) else {
+                continue;
+            };
+            let (defs, refs) = extract(language, &read_blob_text(src, entry.oid))?;
+            for (kind, symbols) in KINDS.into_iter().zip([defs, refs]) {
+                for (symbol, lines) in symbols {
+                    let lines: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+                    let blob = src
+                        .write_buf(gix_object::Kind::Blob, lines.as_bytes())
+                        .map_err(|e| anyhow::anyhow!("write index object: {}", e))?;
+                    spine
+                        .entry((kind, symbol.into()))
+                        .or_default()
+                        .push(gix_object::tree::Entry {
+                            mode: gix_object::tree::EntryKind::Blob.into(),
+                            filename: entry.filename.clone(),
+                            oid: blob,
+                        });
+                }
+            }
+        }
+    }
+
+    let hash = to_gix(tree).kind();
+    let mut kinds: BTreeMap<&str, BTreeMap<u8, Vec<gix_object::tree::Entry>>> = BTreeMap::new();
+    for ((kind, symbol), entries) in spine {
+        let mirror = write_tree(src, hash, entries)?;
+        kinds
+            .entry(kind)
+            .or_default()
+            .entry(symbol[0])
+            .or_default()
+            .push(tree_entry(symbol, mirror));
+    }
+    let mut root = vec![];
+    for (kind, spines) in kinds {
+        let mut hex = vec![];
+        for (b, symbols) in spines {
+            hex.push(tree_entry(hex_name(b), write_tree(src, hash, symbols)?));
+        }
+        root.push(tree_entry(kind.into(), write_tree(src, hash, hex)?));
+    }
+    let index = to_git2(write_tree(src, hash, root)?);
+    cache.set_index(tree, index);
+    Ok(index)
+}
+
+/// Write `entries` as a tree, in git's canonical order.
+fn write_tree(
+    src: &dyn Objects,
+    hash: gix_hash::Kind,
+    mut entries: Vec<gix_object::tree::Entry>,
+) -> anyhow::Result<gix_hash::ObjectId> {
+    if entries.is_empty() && src.exists(&empty_tree(hash)) {
+        return Ok(empty_tree(hash));
+    }
+    entries.sort();
+    src.write(&gix_object::Tree { entries })
+        .map_err(|e| anyhow::anyhow!("write index object: {}", e))
+}
+
+/// Every `(kind, symbol, mirror)` of the symbol index `index`.
+fn read_spine(
+    src: &dyn Objects,
+    index: git2::Oid,
+) -> anyhow::Result<Vec<(&'static str, BString, gix_hash::ObjectId)>> {
+    let mut spine = vec![];
+    for kind in read_tree_entries(src, index)? {
+        let Some(kind_name) = KINDS.into_iter().find(|k| kind.filename == k.as_bytes()) else {
+            continue;
+        };
+        for hex in read_tree_entries(src, to_git2(kind.oid))? {
+            for symbol in read_tree_entries(src, to_git2(hex.oid))? {
+                spine.push((kind_name, symbol.filename, symbol.oid));
+            }
+        }
+    }
+    Ok(spine)
+}
+
+/// A place in the source tree where a symbol is defined or referenced.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolLocation {
+    pub path: String,
+    /// Counted from 1.
+    pub line: usize,
+}
+
+/// Where `symbol` is defined, in path and line order.
+pub fn definitions(
+    src: &dyn Objects,
+    index: git2::Oid,
+    symbol: &str,
+) -> anyhow::Result<Vec<SymbolLocation>> {
+    locations(src, index, "def", symbol)
+}
+
+/// Where `symbol` is referenced (other than defined), in path and line order.
+pub fn references(
+    src: &dyn Objects,
+    index: git2::Oid,
+    symbol: &str,
+) -> anyhow::Result<Vec<SymbolLocation>> {
+    locations(src, index, "ref", symbol)
+}
+
+fn locations(
+    src: &dyn Objects,
+    index: git2::Oid,
+    kind: &str,
+    symbol: &str,
+) -> anyhow::Result<Vec<SymbolLocation>> {
+    let Some(&first) = symbol.as_bytes().first() else {
+        return Ok(vec![]);
+    };
+    if !symbol.bytes().all(is_word_byte) {
+        return Ok(vec![]);
+    }
+    let path = format!("{}/{:02x}/{}", kind, first, symbol);
+    let mut out = vec![];
+    if let Some(mirror) = path_entry(src, index, std::path::Path::new(&path))? {
+        collect_locations(src, mirror, "", &mut out)?;
+    }
+    Ok(out)
+}
+
+fn collect_locations(
+    src: &dyn Objects,
+    mirror: git2::Oid,
+    prefix: &str,
+    out: &mut Vec<SymbolLocation>,
+) -> anyhow::Result<()> {
+    for entry in read_tree_entries(src, mirror)? {
+        let path = join_path(prefix, std::str::from_utf8(&entry.filename)?);
+        if entry.mode.is_tree() {
+            collect_locations(src, to_git2(entry.oid), &path, out)?;
+            continue;
+        }
+        for line in read_blob_text(src, entry.oid).lines() {
+            out.push(SymbolLocation {
+                path: path.clone(),
+                line: line.parse()?,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(language: Language, text: &str) -> (Vec<(String, Vec<usize>)>, Vec<String>) {
+        let (defs, refs) = extract(language, text).unwrap();
+        (defs.into_iter().collect(), refs.into_keys().collect())
+    }
+
+    #[test]
+    fn extracts_definitions_and_references() {
+        let (defs, refs) = symbols(
+            Language::Rust,
+            "// fn commented\nfn parse<'a>(s: &'a str) -> Tree {\n    let c = '\"';\n    \
+             build(r#\"fn raw\"#, \"fn quoted\")\n}\nstatic mut COUNT: usize = 0;\n",
+        );
+        assert_eq!(
+            defs,
+            vec![("COUNT".to_owned(), vec![6]), ("parse".to_owned(), vec![2])]
+        );
+        assert_eq!(refs, vec!["Tree", "build", "c", "s"]);
+
+        let (defs, refs) = symbols(
+            Language::Python,
+            "class Shape:\n    \"\"\"def doc()\"\"\"\n    def area(self):  # def no\n        \
+             return width * 2\n",
+        );
+        assert_eq!(
+            defs,
+            vec![("Shape".to_owned(), vec![1]), ("area".to_owned(), vec![3])]
+        );
+        assert_eq!(refs, vec!["self", "width"]);
+
+        let (defs, refs) = symbols(
+            Language::Go,
+            "func (s *Server) Serve(l Listener) error {\n\treturn `func raw`\n}\ntype Server struct{}\n",
+        );
+        assert_eq!(
+            defs,
+            vec![
+                ("Serve".to_owned(), vec![1]),
+                ("Server".to_owned(), vec![4])
+            ]
+        );
+        assert_eq!(refs, vec!["Listener", "Server", "error", "l", "s"]);
+
+        let (defs, refs) = symbols(
+            Language::TypeScript,
+            "export interface Props { size: number }\nexport const render = (p: Props) => `${p}`;\n",
+        );
+        assert_eq!(
+            defs,
+            vec![
+                ("Props".to_owned(), vec![1]),
+                ("render".to_owned(), vec![2])
+            ]
+        );
+        assert_eq!(refs, vec!["Props", "p", "size"]);
+
+        let (defs, refs) = symbols(
+            Language::Tsx,
+            "export function App() {\n  return <Button onClick={handle} />;\n}\n",
+        );
+        assert_eq!(defs, vec![("App".to_owned(), vec![1])]);
+        assert_eq!(refs, vec!["Button", "handle", "onClick"]);
+    }
+
+    #[test]
+    fn languages_by_extension() {
+        assert_eq!(Language::from_path("lib.rs"), Some(Language::Rust));
+        assert_eq!(Language::from_path("view.ts"), Some(Language::TypeScript));
+        assert_eq!(Language::from_path("view.tsx"), Some(Language::Tsx));
+        assert_eq!(Language::from_path("main.go"), Some(Language::Go));
+        assert_eq!(Language::from_path("setup.py"), Some(Language::Python));
+        assert_eq!(Language::from_path("README.md"), None);
+        assert_eq!(Language::from_path("Makefile"), None);
+    }
+}
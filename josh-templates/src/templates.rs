@@ -3,6 +3,7 @@ use josh_core::cache;
 use josh_core::cache::{CacheStack, TransactionContext};
 use serde_json::json;
 
+#[derive(Clone)]
 struct GraphQLHelper {
     repo_path: std::path::PathBuf,
     cache: std::sync::Arc<CacheStack>,
@@ -14,6 +15,25 @@ impl GraphQLHelper {
     fn transaction_context(&self, path: impl AsRef<std::path::Path>) -> TransactionContext {
         TransactionContext::new(path, self.cache.clone()).with_ref_prefix(&self.ref_prefix)
     }
+
+    /// Open the overlay (which can read the objects of the mirror) and the mirror, or the repo
+    /// itself twice when there is no such split.
+    fn open_transactions(&self) -> anyhow::Result<(cache::Transaction, cache::Transaction)> {
+        let mirror_path = self.repo_path.join("mirror");
+        let overlay_path = self.repo_path.join("overlay");
+
+        Ok(
+            if let Ok(to) = self.transaction_context(&overlay_path).open() {
+                to.add_disk_alternate(mirror_path.join("objects").to_str().unwrap())?;
+                (to, self.transaction_context(&mirror_path).open()?)
+            } else {
+                (
+                    self.transaction_context(&self.repo_path).open()?,
+                    self.transaction_context(&self.repo_path).open()?,
+                )
+            },
+        )
+    }
 }
 
 impl GraphQLHelper {
@@ -23,7 +43,6 @@ impl GraphQLHelper {
         template_name: &str,
     ) -> anyhow::Result<serde_json::Value> {
         let mirror_path = self.repo_path.join("mirror");
-        let overlay_path = self.repo_path.join("overlay");
 
         let path = if let Some(f) = hash.get("file") {
             f.render()
@@ -61,22 +80,7 @@ impl GraphQLHelper {
             variables.insert(k.to_string(), juniper::InputValue::scalar(v.render()));
         }
 
-        let (transaction, transaction_mirror) =
-            if let Ok(to) = self.transaction_context(&overlay_path).open() {
-                to.add_disk_alternate(
-                    self.repo_path
-                        .join("mirror")
-                        .join("objects")
-                        .to_str()
-                        .unwrap(),
-                )?;
-                (to, self.transaction_context(&mirror_path).open()?)
-            } else {
-                (
-                    self.transaction_context(&self.repo_path).open()?,
-                    self.transaction_context(&self.repo_path).open()?,
-                )
-            };
+        let (transaction, transaction_mirror) = self.open_transactions()?;
 
         let (res, _errors) = juniper::execute_sync(
             &query,
@@ -117,6 +121,67 @@ impl handlebars::HelperDef for GraphQLHelper {
     }
 }
 
+/// `documents glob=... format=... pointer=... filter=...`: the files of the (filtered) tree
+/// matching `glob`, parsed and projected like `Revision.documents`, as a list of
+/// `{path, id, value}`.
+struct DocumentsHelper(GraphQLHelper);
+
+impl DocumentsHelper {
+    fn documents(
+        &self,
+        hash: &std::collections::BTreeMap<&str, handlebars::PathAndJson>,
+    ) -> anyhow::Result<serde_json::Value> {
+        let param = |name: &str| hash.get(name).map(|value| value.render());
+
+        let glob = param("glob").ok_or_else(|| anyhow!("missing glob"))?;
+        let filter = josh_core::filter::parse(&param("filter").unwrap_or_else(|| ":/".into()))?;
+
+        let (transaction, _) = self.0.open_transactions()?;
+        let tree = josh_core::objects::CommitData::read(&transaction.odb()?, self.0.commit_id)?
+            .tree_id()?;
+        let tree = josh_core::filter::apply(
+            &transaction,
+            filter,
+            josh_core::filter::Rewrite::from_tree(tree),
+        )?
+        .tree_id();
+
+        let documents = josh_graphql::graphql::find_documents(
+            &transaction,
+            tree,
+            &glob,
+            param("format").as_deref(),
+            param("pointer").as_deref(),
+        )?;
+
+        Ok(documents
+            .into_iter()
+            .map(|found| {
+                json!({
+                    "path": found.path.to_string_lossy(),
+                    "id": found.id.to_string(),
+                    "value": found.value,
+                })
+            })
+            .collect())
+    }
+}
+
+impl handlebars::HelperDef for DocumentsHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &handlebars::Helper,
+        _: &handlebars::Handlebars,
+        _: &handlebars::Context,
+        _: &mut handlebars::RenderContext,
+    ) -> Result<handlebars::ScopedJson<'rc>, handlebars::RenderError> {
+        Ok(handlebars::ScopedJson::Derived(
+            self.documents(h.hash())
+                .map_err(|e| handlebars::RenderErrorReason::Other(format!("{}", e)))?,
+        ))
+    }
+}
+
 mod helpers {
     handlebars::handlebars_helper!(concat_helper: |x: str, y: str| format!("{}{}", x, y) );
 }
@@ -227,15 +292,17 @@ pub fn render(
     let mut handlebars = handlebars::Handlebars::new();
     handlebars.register_template_string(path, template)?;
     handlebars.register_helper("concat", Box::new(helpers::concat_helper));
+    let graphql_helper = GraphQLHelper {
+        repo_path,
+        cache,
+        ref_prefix: ref_prefix.to_owned(),
+        commit_id,
+    };
     handlebars.register_helper(
-        "graphql",
-        Box::new(GraphQLHelper {
-            repo_path,
-            cache,
-            ref_prefix: ref_prefix.to_owned(),
-            commit_id,
-        }),
+        "documents",
+        Box::new(DocumentsHelper(graphql_helper.clone())),
     );
+    handlebars.register_helper("graphql", Box::new(graphql_helper));
     handlebars.set_strict_mode(true);
 
     let rendered = match handlebars.render(path, &json!(params)) {
@@ -0,0 +1,64 @@
+  $ export TESTTMP=${PWD}
+
+Lint works on a spec given on the command line, outside of any repository
+
+  $ josh filter lint ':/sub1:prefix=a'
+
+  $ josh filter lint ':[a=:/lib,b=:/lib/sub,c=:/app]'
+  <spec>:1:11: warning[shadowed-entry]: `b=:/lib/sub` never selects anything: every path it would take is already taken by `a=:/lib` (line 1)
+  [1]
+
+  $ josh filter lint ':rev(_:/a,<=1234:/b)'
+  <spec>:1:11: warning[unreachable-rev-arm]: `<=1234:/b` is never used: the `_` arm (line 1) before it matches every commit
+  [1]
+
+Problems are reported at the innermost op only
+
+  $ josh filter lint ':[a=:/x:subtract[:/y,:/z]]'
+  <spec>:1:8: warning[not-invertible]: changes cannot be pushed through `:subtract[:/y,:/z]`: it has no inverse
+  [1]
+
+  $ josh filter lint ':/a:bogus'
+  <spec>:1:4: error[invalid-filter]: Invalid filter: ":bogus"
+  [1]
+
+  $ josh filter lint ':/a:link'
+  <spec>:1:4: error[experimental]: `:link` uses the link filter, which requires JOSH_EXPERIMENTAL_FEATURES=1
+  [1]
+
+  $ JOSH_EXPERIMENTAL_FEATURES=1 josh filter lint ':/a:link'
+
+Workspace files are linted with --file, with positions per line
+
+  $ cat > workspace.josh <<EOF
+  > # vendored libraries
+  > libs = :/lib
+  > libs/extra = :/lib/extra
+  > app = :/app:[
+  >     :/src
+  >     :/src
+  > ]
+  > EOF
+
+  $ josh filter lint --file workspace.josh
+  workspace.josh:3:1: warning[shadowed-entry]: `libs/extra = :/lib/extra` never selects anything: every path it would take is already taken by `libs = :/lib` (line 2)
+  workspace.josh:6:5: warning[shadowed-entry]: `:/src` never selects anything: every path it would take is already taken by `:/src` (line 5)
+  [1]
+
+  $ josh filter lint --json ':[a=:/x,a=:/x]'
+  [
+    {
+      "severity": "warning",
+      "code": "shadowed-entry",
+      "message": "`a=:/x` never selects anything: every path it would take is already taken by `a=:/x` (line 1)",
+      "line": 1,
+      "column": 9,
+      "start": 8,
+      "end": 13
+    }
+  ]
+  [1]
+
+  $ josh filter lint ':[a=:/x'
+  <spec>:1:8: error[syntax]: expected filter_insert, filter_group, filter_subdir, filter_stored, filter_starlark, filter_treeid, filter_treeref, filter_treederef, filter_nop, filter_presub, filter, filter_noarg, filter_message, filter_rev, filter_unapply, filter_replace, filter_squash, filter_scope, or filter_meta
+  [1]
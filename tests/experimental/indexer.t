@@ -48,6 +48,37 @@
   $ josh-filter :/ --search "line"
   sub1/file2:3:  one line
   2b1320977125dad24866056fa94acf30d77d9453
+  $ josh-filter :/ --regex --search "(Test|Another) doc"
+  sub1/file1:1: First Test document
+  sub1/file2:1: Another document with more 
+  2b1320977125dad24866056fa94acf30d77d9453
+  $ josh-filter :/ --regex --search "^ one"
+  sub1/file2:3:  one line
+  2b1320977125dad24866056fa94acf30d77d9453
+  $ josh-filter :/ --search "e" --ignore-case --search-include "sub1/*" --context 1 --max-results 2
+  sub1/file1:1: First Test document
+  sub1/file2:1: Another document with more 
+  sub1/file2-2-  than 
+  more results: --search-after '1:sub1/file2'
+  2b1320977125dad24866056fa94acf30d77d9453
+  $ josh-filter :/ --search "e" --ignore-case --search-include "sub1/*" --search-after '1:sub1/file2'
+  sub1/file2:3:  one line
+  2b1320977125dad24866056fa94acf30d77d9453
+  $ josh-filter :/ --search "document" --search-exclude "sub1/file1"
+  sub1/file2:1: Another document with more 
+  2b1320977125dad24866056fa94acf30d77d9453
+  $ josh-filter :/sub1 --search-history "document" --max-results 1
+  dd01781208f52a56a22376e1aa14105d7d565cb1
+    file2: 0 -> 1
+  dd01781208f52a56a22376e1aa14105d7d565cb1
+  $ josh-filter :/ --search-history "document"
+  58185e276a4aa4d4022fb91fbc7a002643d3edf5
+    sub1/file2: 0 -> 1
+  f6c1b0800e313f9bed953ad23e0095a9a7832078
+    sub1/file1: 0 -> 1
+  2b1320977125dad24866056fa94acf30d77d9453
+  $ josh-filter :/ --search-history "absent"
+  2b1320977125dad24866056fa94acf30d77d9453
 
   $ josh-filter :/ -g 'query { rev(at: "refs/heads/master") { results: search(string: "e") { path { path }, matches { line, text }} }}'
   2b1320977125dad24866056fa94acf30d77d9453
@@ -114,6 +145,39 @@
     }
   }
 
+  $ josh-filter :/ -g 'query { rev(at: "refs/heads/master", filter: ":/sub1") { searchHistory(string: "document") { rev { hash }, changes { path, before, after } } }}'
+  2b1320977125dad24866056fa94acf30d77d9453
+  {
+    "rev": {
+      "searchHistory": [
+        {
+          "rev": {
+            "hash": "dd01781208f52a56a22376e1aa14105d7d565cb1"
+          },
+          "changes": [
+            {
+              "path": "file2",
+              "before": 0,
+              "after": 1
+            }
+          ]
+        },
+        {
+          "rev": {
+            "hash": "201461b031877c94f50c80b45b7af4b7982e1f86"
+          },
+          "changes": [
+            {
+              "path": "file1",
+              "before": 0,
+              "after": 1
+            }
+          ]
+        }
+      ]
+    }
+  }
+
   $ git diff ${EMPTY_TREE}..refs/heads/index
   diff --git a/20/20/20/sub1 b/20/20/20/sub1
   new file mode 100644
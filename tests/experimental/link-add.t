@@ -227,8 +227,11 @@
     help    Print this message or the help of the given subcommand(s)
   
   Options:
-        --no-distributed-cache  Disable the distributed filter cache (don't read, write or fetch it)
-    -h, --help                  Print help
+        --no-distributed-cache       Disable the distributed filter cache (don't read, write or fetch it)
+        --cache-url <CACHE_URL>      URL of a josh-cache-server to share the filter cache with [env: JOSH_CACHE_URL=]
+        --cache-upload               Also upload new cache records to the --cache-url server
+        --cache-token <CACHE_TOKEN>  Upload token of the --cache-url server, required by --cache-upload [env: JOSH_CACHE_TOKEN]
+    -h, --help                       Print help
 
   $ josh link add --help
   Add a link with optional filter and target branch
@@ -241,10 +244,13 @@
     [FILTER]  Optional filter to apply to the linked repository
   
   Options:
-        --no-distributed-cache  Disable the distributed filter cache (don't read, write or fetch it)
-        --target <TARGET>       Target branch to link (defaults to HEAD)
-        --mode <MODE>           Link mode: embedded, snapshot, or pointer (defaults to snapshot) [default: snapshot]
-    -h, --help                  Print help
+        --no-distributed-cache       Disable the distributed filter cache (don't read, write or fetch it)
+        --target <TARGET>            Target branch to link (defaults to HEAD)
+        --cache-url <CACHE_URL>      URL of a josh-cache-server to share the filter cache with [env: JOSH_CACHE_URL=]
+        --mode <MODE>                Link mode: embedded, snapshot, or pointer (defaults to snapshot) [default: snapshot]
+        --cache-upload               Also upload new cache records to the --cache-url server
+        --cache-token <CACHE_TOKEN>  Upload token of the --cache-url server, required by --cache-upload [env: JOSH_CACHE_TOKEN]
+    -h, --help                       Print help
 
 # Test josh link fetch command
 # First, create a link file directly in the master branch for testing
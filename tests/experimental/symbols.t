@@ -0,0 +1,74 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q testrepo 1> /dev/null
+  $ cd testrepo
+
+  $ mkdir -p core/src tools
+  $ printf 'pub fn parse() {}\n\npub fn run() {\n    parse();\n}\n' > core/src/lib.rs
+  $ printf 'from core import parse\n\ndef main():\n    parse()\n' > tools/gen.py
+  $ git add .
+  $ git commit -m "add code" 1> /dev/null
+
+  $ josh-filter :SYMBOLS --update refs/heads/symbols
+  fa287a53e662090a1b111c78fb3944580b52dd05
+  $ git ls-tree -r --name-only refs/heads/symbols
+  def/6d/main/tools/gen.py
+  def/70/parse/core/src/lib.rs
+  def/72/run/core/src/lib.rs
+  ref/63/core/tools/gen.py
+  ref/70/parse/core/src/lib.rs
+  ref/70/parse/tools/gen.py
+  $ git show refs/heads/symbols:ref/70/parse/tools/gen.py
+  1
+  4
+
+  $ josh-filter :/ -g 'query { rev(at: "refs/heads/master") { definitions(symbol: "parse") { path { path }, line }, references(symbol: "parse") { path { path }, line } }}'
+  56a5cc19bd1307c4ba27c5aae3b31cf7394f6dea
+  {
+    "rev": {
+      "definitions": [
+        {
+          "path": {
+            "path": "core/src/lib.rs"
+          },
+          "line": 1
+        }
+      ],
+      "references": [
+        {
+          "path": {
+            "path": "core/src/lib.rs"
+          },
+          "line": 4
+        },
+        {
+          "path": {
+            "path": "tools/gen.py"
+          },
+          "line": 1
+        },
+        {
+          "path": {
+            "path": "tools/gen.py"
+          },
+          "line": 4
+        }
+      ]
+    }
+  }
+
+  $ josh-filter :/ -g 'query { rev(at: "refs/heads/master", filter: ":/tools") { definitions(symbol: "main") { path { path }, line } }}'
+  56a5cc19bd1307c4ba27c5aae3b31cf7394f6dea
+  {
+    "rev": {
+      "definitions": [
+        {
+          "path": {
+            "path": "gen.py"
+          },
+          "line": 3
+        }
+      ]
+    }
+  }
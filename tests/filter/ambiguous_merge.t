@@ -93,6 +93,16 @@
   8cbae19889134bd4ec430c0b79c61ee8547e0d1c
   [3] :prefix=sub1
   [4] :/sub1
+  [5] :~(
+      cache="unapply"
+  )[
+      ::sub1/
+  ]
+  [6] :~(
+      cache="original"
+  )[
+      ::sub1/
+  ]
   [13] reachable_roots
   [13] sequence_number
 
@@ -0,0 +1,78 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q repo 1>/dev/null
+  $ cd repo
+
+  $ mkdir -p src assets tests
+  $ echo "fn main() {}" > src/main.rs
+  $ printf 'PNG\0\0\0' > assets/logo.png
+  $ head -c 2048 /dev/zero | tr '\0' 'x' > assets/big.txt
+  $ echo "#[test]" > tests/it.rs
+  $ cat > .gitattributes <<EOF2
+  > /tests export-ignore
+  > *.txt export-ignore
+  > EOF2
+
+  $ mkdir -p src/gen
+  $ echo "generated" > src/gen/out.txt
+  $ echo "out.txt -export-ignore" > src/gen/.gitattributes
+
+  $ git add .
+  $ git commit -q -m initial
+
+  $ josh-filter -p ':exclude[:binary,:larger=1k,:export-ignore]'
+  :exclude[
+      :binary
+      :larger=1024
+      :export-ignore
+  ]
+
+  $ josh-filter :binary --update refs/heads/filtered
+  16e7ec0c5c1c26fdaa63664cb47c9bbccd704e22
+
+  $ git ls-tree -r --name-only refs/heads/filtered
+  assets/logo.png
+
+  $ josh-filter :larger=1k --update refs/heads/filtered
+  422df96f71281235e3227b3e92d439d3b3a6097a
+
+  $ git ls-tree -r --name-only refs/heads/filtered
+  assets/big.txt
+
+  $ josh-filter :export-ignore --update refs/heads/filtered
+  9aa77918c94f453ade3189f18e1feb60e0c7b488
+
+  $ git ls-tree -r --name-only refs/heads/filtered
+  assets/big.txt
+  tests/it.rs
+
+  $ josh-filter ':exclude[:binary,:larger=1k,:export-ignore]' --update refs/heads/filtered
+  7b916c390036e65b8267e10c1e686e5a85b4d72c
+
+  $ git ls-tree -r --name-only refs/heads/filtered
+  .gitattributes
+  src/gen/.gitattributes
+  src/gen/out.txt
+  src/main.rs
+
+  $ josh-filter ':invert[:binary]' --update refs/heads/filtered
+  16e7ec0c5c1c26fdaa63664cb47c9bbccd704e22
+
+  $ git ls-tree -r --name-only refs/heads/filtered
+  assets/logo.png
+
+  $ josh-filter :larger
+  ERROR: Filter ":larger" requires an argument.
+  
+  Note: use "=" to provide the argument value:
+  
+    :larger=size
+  
+  Where `size` is a number of bytes, optionally suffixed with k, M or G
+  
+  [1]
+
+  $ josh-filter :larger=lots
+  ERROR: invalid size: "lots"
+  [1]
@@ -0,0 +1,58 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q repo 1>/dev/null
+  $ cd repo
+
+  $ mkdir -p lib/sub docs app
+  $ echo a > lib/a.rs
+  $ echo b > lib/README.md
+  $ echo c > lib/sub/c.rs
+  $ echo d > docs/guide.md
+  $ echo e > app/main.rs
+  $ echo f > README.md
+  $ git add .
+  $ git commit -q -m initial
+
+Every output path is attributed to its compose entry, and missing paths get a reason
+
+  $ josh-filter --explain ':[a=:/lib:exclude[::*.md],b=:/lib/sub,c=:/app,a=:/docs]'
+  a/a.rs <- lib/a.rs by :/lib:exclude[::*.md]:prefix=a
+  a/guide.md <- docs/guide.md by :/docs:prefix=a
+  a/sub/c.rs <- lib/sub/c.rs by :/lib:exclude[::*.md]:prefix=a
+  c/main.rs <- app/main.rs by :/app:prefix=c
+  dropped README.md: not selected
+  dropped lib/README.md: removed by :/lib:exclude[::*.md]:prefix=a
+  shadowed b/c.rs <- lib/sub/c.rs by :/lib/sub:prefix=b: source taken by :/lib:exclude[::*.md]:prefix=a
+
+On an output path conflict the later entry wins
+
+  $ echo g > docs/a.rs
+  $ git add docs
+  $ git commit -q -m "add docs/a.rs"
+  $ josh-filter --explain ':[a=:/lib:exclude[::*.md],a=:/docs]'
+  a/a.rs <- docs/a.rs by :/docs:prefix=a
+  a/guide.md <- docs/guide.md by :/docs:prefix=a
+  a/sub/c.rs <- lib/sub/c.rs by :/lib:exclude[::*.md]:prefix=a
+  dropped README.md: not selected
+  dropped app/main.rs: not selected
+  dropped lib/README.md: removed by :/lib:exclude[::*.md]:prefix=a
+  dropped lib/a.rs: a/a.rs overwritten by :/docs:prefix=a
+
+Workspaces are resolved against the input commit
+
+  $ mkdir -p ws
+  $ cat > ws/workspace.josh <<EOF2
+  > lib = :/lib
+  > EOF2
+  $ git add ws
+  $ git commit -q -m ws
+  $ josh-filter --explain ':workspace=ws'
+  lib/README.md <- lib/README.md by ::lib/
+  lib/a.rs <- lib/a.rs by ::lib/
+  lib/sub/c.rs <- lib/sub/c.rs by ::lib/
+  workspace.josh <- ws/workspace.josh by :/ws::workspace.josh
+  dropped README.md: not selected
+  dropped app/main.rs: not selected
+  dropped docs/a.rs: not selected
+  dropped docs/guide.md: not selected
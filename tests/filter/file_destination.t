@@ -133,6 +133,11 @@ Test File filter with destination path --reverse
   [1] :/src
   [1] :/subdir
   [1] ::renamed.txt=original.txt
+  [1] :~(
+      cache="original"
+  )[
+      :/src:/subdir::renamed.txt=original.txt
+  ]
   [3] reachable_roots
   [3] sequence_number
 
@@ -187,6 +192,11 @@ Test File filter backward compatibility --reverse
   [1] ::file.txt
   [1] :prefix=src
   [1] :prefix=subdir
+  [1] :~(
+      cache="original"
+  )[
+      src = :/src:/subdir::file.txt:prefix=subdir
+  ]
   [5] reachable_roots
   [5] sequence_number
 
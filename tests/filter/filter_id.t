@@ -664,6 +664,19 @@ Test :INDEX (the filter is experimental; parsing it needs the opt-in)
   
   1 directory, 1 file
 
+Test :SYMBOLS (the filter is experimental; parsing it needs the opt-in)
+  $ josh-filter -i ':SYMBOLS' 2>&1 | head -1
+  ERROR: :SYMBOLS filter requires JOSH_EXPERIMENTAL_FEATURES=1
+  $ FILTER_HASH=$(JOSH_EXPERIMENTAL_FEATURES=1 josh-filter -i ':SYMBOLS')
+  $ josh-filter -p ${FILTER_HASH}
+  :SYMBOLS
+  $ git read-tree --reset -u ${FILTER_HASH}
+  $ tree
+  .
+  `-- symbols
+  
+  1 directory, 1 file
+
 Test :INVERT
   $ FILTER_HASH=$(josh-filter -i ':INVERT')
   $ josh-filter -p ${FILTER_HASH}
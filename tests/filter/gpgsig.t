@@ -57,6 +57,12 @@ Remove the signature, the shas are different.
   cb22ebb8e47b109f7add68b1043e561e0db09802
   [1] :/extra
   [1] :prefix=extra
+  [1] :~(
+      cache="original"
+      gpgsig="remove"
+  )[
+      :/
+  ]
   [1] :~(
       gpgsig="remove"
   )[
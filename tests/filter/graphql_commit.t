@@ -0,0 +1,145 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q repo 1> /dev/null
+  $ cd repo
+
+  $ mkdir sub other
+  $ echo one > sub/file
+  $ echo "#!/bin/sh" > sub/run
+  $ chmod +x sub/run
+  $ echo gone > sub/old
+  $ echo unrelated > other/file
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+  $ BASE=$(git rev-parse HEAD)
+  $ echo moved on > other/file
+  $ git commit -am "change other" 1> /dev/null
+
+Edit files of the :/sub projection of an older commit; the change lands on top of master
+  $ cat > query <<EOF
+  > mutation {
+  >   rev(at: "${BASE}", filter: ":/sub") {
+  >     commit(
+  >       message: "edit sub",
+  >       edits: [
+  >         { path: "file", content: "two\n" },
+  >         { path: "dir/new", content: "new\n" },
+  >         { path: "old" }
+  >       ],
+  >       target: "refs/heads/master",
+  >       author: { name: "Jane", email: "jane@example.com" }
+  >     ) {
+  >       rev {
+  >         summary
+  >         authorEmail
+  >         parents { summary }
+  >         files { path text }
+  >       }
+  >       warnings
+  >     }
+  >   }
+  > }
+  > EOF
+  $ josh-filter -g "$(cat query)"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  {
+    "rev": {
+      "commit": {
+        "rev": {
+          "summary": "edit sub",
+          "authorEmail": "jane@example.com",
+          "parents": [
+            {
+              "summary": "change other"
+            }
+          ],
+          "files": [
+            {
+              "path": "other/file",
+              "text": "moved on\n"
+            },
+            {
+              "path": "sub/dir/new",
+              "text": "new\n"
+            },
+            {
+              "path": "sub/file",
+              "text": "two\n"
+            },
+            {
+              "path": "sub/run",
+              "text": "#!/bin/sh\n"
+            }
+          ]
+        },
+        "warnings": []
+      }
+    }
+  }
+
+The result can be pushed as a merge instead
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit sub\", edits: [{ path: \"file\", content: \"two\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }, options: { merge: true }) { rev { summary parents { summary } } } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  {
+    "rev": {
+      "commit": {
+        "rev": {
+          "summary": "Merge from :/sub",
+          "parents": [
+            {
+              "summary": "change other"
+            },
+            {
+              "summary": "edit sub"
+            }
+          ]
+        }
+      }
+    }
+  }
+
+Edited files keep their mode
+  $ HASH=$(josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit run\", edits: [{ path: \"run\", content: \"exit 0\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { rev { hash } } } }" | grep hash | sed 's/.*: "\(.*\)"/\1/')
+  $ git ls-tree ${HASH} sub/run
+  100755 blob eec2061c3de8c12c54d6af46d2cd27337b7505bd	sub/run
+
+A commit needs an author
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit\", edits: [{ path: \"file\" }], target: \"refs/heads/master\") { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+
+Edit paths are relative, without empty, "..", or ".git" components
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit\", edits: [{ path: \"/x\", content: \"x\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit\", edits: [{ path: \"../x\", content: \"x\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit\", edits: [{ path: \"a/../x\", content: \"x\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit\", edits: [{ path: \"\", content: \"x\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit\", edits: [{ path: \"a//b\", content: \"x\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit\", edits: [{ path: \".git/config\", content: \"x\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit\", edits: [{ path: \"a/.GIT/x\", content: \"x\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+  $ git fsck --no-dangling 2>&1
+
+Edits that do not change anything, deleting missing files and unknown targets are errors
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"noop\", edits: [{ path: \"file\", content: \"one\n\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"delete\", edits: [{ path: \"missing\" }], target: \"refs/heads/master\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
+  $ josh-filter -g "mutation { rev(at: \"${BASE}\", filter: \":/sub\") { commit(message: \"edit\", edits: [{ path: \"file\" }], target: \"refs/heads/missing\", author: { name: \"Jane\", email: \"jane@example.com\" }) { warnings } } }"
+  4dd2c731367850df527c55a8eca455e108c85f6f
+  null
@@ -0,0 +1,183 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q repo 1> /dev/null
+  $ cd repo
+
+  $ mkdir sub other
+  $ printf "one\ntwo\nthree\n" > sub/file
+  $ echo unrelated > other/file
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+  $ printf "one\n2\nthree\nfour\n" > sub/file
+  $ echo changed > other/file
+  $ git commit -am "change lines" 1> /dev/null
+  $ echo new > sub/new
+  $ git add .
+  $ git commit -m "add new" 1> /dev/null
+
+Hunks of a commit against its filtered first parent
+  $ cat > query <<EOF
+  > query {
+  >   rev(at: "HEAD~1", filter: ":/sub") {
+  >     diff(contextLines: 1) {
+  >       path
+  >       from { path }
+  >       to { path }
+  >       binary
+  >       hunks {
+  >         header
+  >         oldStart
+  >         oldLines
+  >         newStart
+  >         newLines
+  >         lines { origin content oldLine newLine }
+  >       }
+  >     }
+  >   }
+  > }
+  > EOF
+  $ josh-filter -g "$(cat query)"
+  e8908d3abba4716aa2650ceb34fa03f72b910cad
+  {
+    "rev": {
+      "diff": [
+        {
+          "path": "file",
+          "from": {
+            "path": "file"
+          },
+          "to": {
+            "path": "file"
+          },
+          "binary": false,
+          "hunks": [
+            {
+              "header": "@@ -1,3 +1,4 @@",
+              "oldStart": 1,
+              "oldLines": 3,
+              "newStart": 1,
+              "newLines": 4,
+              "lines": [
+                {
+                  "origin": " ",
+                  "content": "one",
+                  "oldLine": 1,
+                  "newLine": 1
+                },
+                {
+                  "origin": "-",
+                  "content": "two",
+                  "oldLine": 2,
+                  "newLine": null
+                },
+                {
+                  "origin": "+",
+                  "content": "2",
+                  "oldLine": null,
+                  "newLine": 2
+                },
+                {
+                  "origin": " ",
+                  "content": "three",
+                  "oldLine": 3,
+                  "newLine": 3
+                },
+                {
+                  "origin": "+",
+                  "content": "four",
+                  "oldLine": null,
+                  "newLine": 4
+                }
+              ]
+            }
+          ]
+        }
+      ]
+    }
+  }
+
+Against another revision, restricted to some paths
+  $ josh-filter -g 'query { rev(at: "HEAD", filter: ":/sub") { diff(against: "HEAD~2", paths: ["new"]) { path from { path } to { path } hunks { header } } } }'
+  e8908d3abba4716aa2650ceb34fa03f72b910cad
+  {
+    "rev": {
+      "diff": [
+        {
+          "path": "new",
+          "from": null,
+          "to": {
+            "path": "new"
+          },
+          "hunks": [
+            {
+              "header": "@@ -0,0 +1 @@"
+            }
+          ]
+        }
+      ]
+    }
+  }
+  $ josh-filter -g 'query { rev(at: "HEAD", filter: ":/sub") { diff(against: "HEAD~2", paths: ["missing"]) { path } } }'
+  e8908d3abba4716aa2650ceb34fa03f72b910cad
+  {
+    "rev": {
+      "diff": []
+    }
+  }
+
+Blame attributes lines to the (original) commits that last changed them
+  $ josh-filter -g 'query { rev(at: "HEAD", filter: ":/sub") { file(path: "file") { blame { startLine endLine rev { summary original: rev { hash } } } } } }'
+  e8908d3abba4716aa2650ceb34fa03f72b910cad
+  {
+    "rev": {
+      "file": {
+        "blame": [
+          {
+            "startLine": 1,
+            "endLine": 1,
+            "rev": {
+              "summary": "add files",
+              "original": {
+                "hash": "6f6fe1e933ecb4fb8a542b8df8da6aa46eee1767"
+              }
+            }
+          },
+          {
+            "startLine": 2,
+            "endLine": 2,
+            "rev": {
+              "summary": "change lines",
+              "original": {
+                "hash": "0e63108e658ce854f5ddf7f18ea7ecd02e81620d"
+              }
+            }
+          },
+          {
+            "startLine": 3,
+            "endLine": 3,
+            "rev": {
+              "summary": "add files",
+              "original": {
+                "hash": "6f6fe1e933ecb4fb8a542b8df8da6aa46eee1767"
+              }
+            }
+          },
+          {
+            "startLine": 4,
+            "endLine": 4,
+            "rev": {
+              "summary": "change lines",
+              "original": {
+                "hash": "0e63108e658ce854f5ddf7f18ea7ecd02e81620d"
+              }
+            }
+          }
+        ]
+      }
+    }
+  }
+  $ git log --format="%H %s"
+  e8908d3abba4716aa2650ceb34fa03f72b910cad add new
+  0e63108e658ce854f5ddf7f18ea7ecd02e81620d change lines
+  6f6fe1e933ecb4fb8a542b8df8da6aa46eee1767 add files
@@ -0,0 +1,210 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q repo 1> /dev/null
+  $ cd repo
+
+  $ mkdir -p crates/a crates/b crates/b/nested web
+  $ cat > Cargo.toml <<EOF
+  > [workspace]
+  > members = ["crates/*"]
+  > EOF
+  $ cat > crates/a/Cargo.toml <<EOF
+  > [package]
+  > name = "a"
+  > version = "1.2.0"
+  > EOF
+  $ cat > crates/b/Cargo.toml <<EOF
+  > [package]
+  > name = "b"
+  > version = "0.3.1"
+  > EOF
+  $ cat > crates/b/nested/Cargo.toml <<EOF
+  > [package]
+  > name = "nested"
+  > version = "0.0.1"
+  > EOF
+  $ echo '{"name": "web", "version": "2.0.0"}' > web/package.json
+  $ printf "name: web\nversion: 2.0.1\n" > web/release.yml
+  $ echo '{"channel": "stable"}' > web/settings
+  $ git add .
+  $ git commit -m "add manifests" 1> /dev/null
+
+Versions of all crates; the workspace manifest has no package and is left out
+  $ cat > query <<EOF
+  > query {
+  >   rev(at: "HEAD") {
+  >     documents(glob: "**/Cargo.toml", pointer: "/package/version") {
+  >       path { path }
+  >       document { string }
+  >     }
+  >   }
+  > }
+  > EOF
+  $ josh-filter -g "$(cat query)"
+  5d09cda6d404b70c68d6e032eac6ffae0706154f
+  {
+    "rev": {
+      "documents": [
+        {
+          "path": {
+            "path": "crates/a/Cargo.toml"
+          },
+          "document": {
+            "string": "1.2.0"
+          }
+        },
+        {
+          "path": {
+            "path": "crates/b/Cargo.toml"
+          },
+          "document": {
+            "string": "0.3.1"
+          }
+        },
+        {
+          "path": {
+            "path": "crates/b/nested/Cargo.toml"
+          },
+          "document": {
+            "string": "0.0.1"
+          }
+        }
+      ]
+    }
+  }
+
+A single * does not match across directories, and the pattern applies to the filtered tree
+  $ cat > query <<EOF
+  > query {
+  >   rev(at: "HEAD", filter: ":/crates") {
+  >     documents(glob: "*/Cargo.toml") {
+  >       path { path }
+  >       document {
+  >         name: string(at: "/package/name")
+  >         version: string(at: "/package/version")
+  >       }
+  >     }
+  >   }
+  > }
+  > EOF
+  $ josh-filter -g "$(cat query)"
+  5d09cda6d404b70c68d6e032eac6ffae0706154f
+  {
+    "rev": {
+      "documents": [
+        {
+          "path": {
+            "path": "a/Cargo.toml"
+          },
+          "document": {
+            "name": "a",
+            "version": "1.2.0"
+          }
+        },
+        {
+          "path": {
+            "path": "b/Cargo.toml"
+          },
+          "document": {
+            "name": "b",
+            "version": "0.3.1"
+          }
+        }
+      ]
+    }
+  }
+
+The format follows the extension unless given
+  $ cat > query <<EOF
+  > query {
+  >   rev(at: "HEAD", filter: ":/web") {
+  >     manifests: documents(glob: "[pr]*.*", pointer: "/version") {
+  >       path { path }
+  >       document { string }
+  >     }
+  >     release: documents(glob: "release.yml") { document { version: string(at: "/version") } }
+  >     settings: documents(glob: "settings", format: "json") { document { string(at: "/channel") } }
+  >     none: documents(glob: "missing/*.toml") { path { path } }
+  >   }
+  > }
+  > EOF
+  $ josh-filter -g "$(cat query)"
+  5d09cda6d404b70c68d6e032eac6ffae0706154f
+  {
+    "rev": {
+      "manifests": [
+        {
+          "path": {
+            "path": "package.json"
+          },
+          "document": {
+            "string": "2.0.0"
+          }
+        },
+        {
+          "path": {
+            "path": "release.yml"
+          },
+          "document": {
+            "string": "2.0.1"
+          }
+        }
+      ],
+      "release": [
+        {
+          "document": {
+            "version": "2.0.1"
+          }
+        }
+      ],
+      "settings": [
+        {
+          "document": {
+            "string": "stable"
+          }
+        }
+      ],
+      "none": []
+    }
+  }
+
+Documents from a template
+  $ cat > notes.hbs <<EOF
+  > {{ #each (documents glob="crates/**/Cargo.toml" pointer="/package") }}
+  > - {{ this.value.name }} {{ this.value.version }} ({{ this.path }})
+  > {{ /each~}}
+  > {{ #each (documents glob="*.json" filter=":/web") }}
+  > - {{ this.value.name }} {{ this.value.version }} ({{ this.path }})
+  > {{ /each~}}
+  > EOF
+  $ git add notes.hbs
+  $ git commit -m "add notes" 1> /dev/null
+  $ josh-filter -q render=notes.hbs
+  09b74c16c67ca8b2c5664fe91ecd242f438f1d70
+  - a 1.2.0 (crates/a/Cargo.toml)
+  - b 0.3.1 (crates/b/Cargo.toml)
+  - nested 0.0.1 (crates/b/nested/Cargo.toml)
+  - web 2.0.0 (package.json)
+
+Files that don't parse or have no known format are left out, and wildcards skip hidden names
+  $ mkdir -p conf/.local
+  $ echo '{"level": 1}' > conf/one.json
+  $ echo '{"level":' > conf/broken.json
+  $ echo '{"level": 3}' > conf/README
+  $ echo '{"level": 4}' > conf/.local/override.json
+  $ git add conf
+  $ git commit -m "add conf" 1> /dev/null
+  $ josh-filter -g 'query { rev(at: "HEAD", filter: ":/conf") { documents(glob: "**/*") { path { path } } } }'
+  044e961a7a704df157f73011b70e1aa2476ce2fb
+  {
+    "rev": {
+      "documents": [
+        {
+          "path": {
+            "path": "one.json"
+          }
+        }
+      ]
+    }
+  }
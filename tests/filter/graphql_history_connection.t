@@ -0,0 +1,260 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q repo 1> /dev/null
+  $ cd repo
+
+  $ mkdir a b
+  $ echo 1 > a/file
+  $ git add .
+  $ git commit -m "add a" 1> /dev/null
+  $ echo 1 > b/file
+  $ git add .
+  $ git commit -m "add b" 1> /dev/null
+  $ git checkout -q -b side
+  $ echo 2 > a/file
+  $ git commit -am "change a on side" 1> /dev/null
+  $ git checkout -q master
+  $ echo 2 > b/file
+  $ git commit -am "change b" 1> /dev/null
+  $ git merge -q --no-ff side -m "merge side" 1> /dev/null
+  $ echo 3 > a/file
+  $ git commit -am "change a" 1> /dev/null
+
+Pages of first-parent history, resumed from the end cursor
+  $ cat > query <<EOF
+  > query {
+  >   rev(at: "refs/heads/master") {
+  >     historyConnection(first: 2) {
+  >       edges { node { summary } }
+  >       pageInfo { hasNextPage hasPreviousPage }
+  >     }
+  >   }
+  > }
+  > EOF
+  $ josh-filter -g "$(cat query)"
+  d2f1c0b618ea9afb50c8322f44b616ea54a1a2b7
+  {
+    "rev": {
+      "historyConnection": {
+        "edges": [
+          {
+            "node": {
+              "summary": "change a"
+            }
+          },
+          {
+            "node": {
+              "summary": "merge side"
+            }
+          }
+        ],
+        "pageInfo": {
+          "hasNextPage": true,
+          "hasPreviousPage": false
+        }
+      }
+    }
+  }
+
+  $ CURSOR=$(josh-filter -g 'query { rev(at: "refs/heads/master") { historyConnection(first: 2) { pageInfo { endCursor } } } }' | grep endCursor | sed 's/.*: "\(.*\)"/\1/')
+  $ josh-filter -g "query { rev(at: \"refs/heads/master\") { historyConnection(first: 5, after: \"${CURSOR}\") { edges { node { summary } } pageInfo { hasNextPage hasPreviousPage } } } }"
+  d2f1c0b618ea9afb50c8322f44b616ea54a1a2b7
+  {
+    "rev": {
+      "historyConnection": {
+        "edges": [
+          {
+            "node": {
+              "summary": "change b"
+            }
+          },
+          {
+            "node": {
+              "summary": "add b"
+            }
+          },
+          {
+            "node": {
+              "summary": "add a"
+            }
+          }
+        ],
+        "pageInfo": {
+          "hasNextPage": false,
+          "hasPreviousPage": true
+        }
+      }
+    }
+  }
+
+The whole DAG in topological order
+  $ josh-filter -g 'query { rev(at: "refs/heads/master") { historyConnection(first: 10, firstParent: false) { edges { node { summary } } } } }'
+  d2f1c0b618ea9afb50c8322f44b616ea54a1a2b7
+  {
+    "rev": {
+      "historyConnection": {
+        "edges": [
+          {
+            "node": {
+              "summary": "change a"
+            }
+          },
+          {
+            "node": {
+              "summary": "merge side"
+            }
+          },
+          {
+            "node": {
+              "summary": "change b"
+            }
+          },
+          {
+            "node": {
+              "summary": "change a on side"
+            }
+          },
+          {
+            "node": {
+              "summary": "add b"
+            }
+          },
+          {
+            "node": {
+              "summary": "add a"
+            }
+          }
+        ]
+      }
+    }
+  }
+
+Pages of the whole DAG, resumed from the end cursor
+  $ cat > query <<EOF
+  > query {
+  >   rev(at: "refs/heads/master", filter: ":/a") {
+  >     historyConnection(first: 2, firstParent: false) {
+  >       edges { node { summary } }
+  >       pageInfo { hasNextPage endCursor }
+  >     }
+  >   }
+  > }
+  > EOF
+  $ josh-filter -g "$(cat query)" | grep -v endCursor
+  d2f1c0b618ea9afb50c8322f44b616ea54a1a2b7
+  {
+    "rev": {
+      "historyConnection": {
+        "edges": [
+          {
+            "node": {
+              "summary": "change a"
+            }
+          },
+          {
+            "node": {
+              "summary": "merge side"
+            }
+          }
+        ],
+        "pageInfo": {
+          "hasNextPage": true,
+        }
+      }
+    }
+  }
+  $ CURSOR=$(josh-filter -g "$(cat query)" | grep endCursor | sed 's/.*: "\(.*\)"/\1/')
+  $ josh-filter -g "query { rev(at: \"refs/heads/master\", filter: \":/a\") { historyConnection(first: 5, firstParent: false, after: \"${CURSOR}\") { edges { node { summary } } pageInfo { hasNextPage hasPreviousPage } } } }"
+  d2f1c0b618ea9afb50c8322f44b616ea54a1a2b7
+  {
+    "rev": {
+      "historyConnection": {
+        "edges": [
+          {
+            "node": {
+              "summary": "change a on side"
+            }
+          },
+          {
+            "node": {
+              "summary": "add a"
+            }
+          }
+        ],
+        "pageInfo": {
+          "hasNextPage": false,
+          "hasPreviousPage": true
+        }
+      }
+    }
+  }
+
+Only the commits that change a path, like git log -- path
+  $ josh-filter -g 'query { rev(at: "refs/heads/master") { history(limit: 10, path: "a") { summary } } }'
+  d2f1c0b618ea9afb50c8322f44b616ea54a1a2b7
+  {
+    "rev": {
+      "history": [
+        {
+          "summary": "change a"
+        },
+        {
+          "summary": "merge side"
+        },
+        {
+          "summary": "add a"
+        }
+      ]
+    }
+  }
+  $ josh-filter -g 'query { rev(at: "refs/heads/master") { historyConnection(first: 10, firstParent: false, path: "a/file") { edges { node { summary } } } } }'
+  d2f1c0b618ea9afb50c8322f44b616ea54a1a2b7
+  {
+    "rev": {
+      "historyConnection": {
+        "edges": [
+          {
+            "node": {
+              "summary": "change a"
+            }
+          },
+          {
+            "node": {
+              "summary": "change a on side"
+            }
+          },
+          {
+            "node": {
+              "summary": "add a"
+            }
+          }
+        ]
+      }
+    }
+  }
+  $ josh-filter -g 'query { rev(at: "refs/heads/master", filter: ":/a") { historyConnection(first: 10) { edges { node { summary } } } } }'
+  d2f1c0b618ea9afb50c8322f44b616ea54a1a2b7
+  {
+    "rev": {
+      "historyConnection": {
+        "edges": [
+          {
+            "node": {
+              "summary": "change a"
+            }
+          },
+          {
+            "node": {
+              "summary": "merge side"
+            }
+          },
+          {
+            "node": {
+              "summary": "add a"
+            }
+          }
+        ]
+      }
+    }
+  }
@@ -0,0 +1,88 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q repo 1> /dev/null
+  $ cd repo
+
+  $ mkdir sub
+  $ printf "needle one\nhay\n" > a.txt
+  $ printf "hay\nneedle two\n" > b.txt
+  $ printf "needle three\n" > sub/c.txt
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+Pages of search results, resumed from the end cursor
+  $ cat > query <<EOF
+  > query {
+  >   rev(at: "refs/heads/master") {
+  >     searchConnection(string: "needle", first: 2) {
+  >       edges { path { path } matches { line text } }
+  >       pageInfo { hasNextPage hasPreviousPage endCursor }
+  >     }
+  >   }
+  > }
+  > EOF
+  $ josh-filter -g "$(cat query)"
+  930fd809f9701a0ecc1aaaa3d48ba106603fcf1d
+  {
+    "rev": {
+      "searchConnection": {
+        "edges": [
+          {
+            "path": {
+              "path": "a.txt"
+            },
+            "matches": [
+              {
+                "line": 1,
+                "text": "needle one"
+              }
+            ]
+          },
+          {
+            "path": {
+              "path": "b.txt"
+            },
+            "matches": [
+              {
+                "line": 2,
+                "text": "needle two"
+              }
+            ]
+          }
+        ],
+        "pageInfo": {
+          "hasNextPage": true,
+          "hasPreviousPage": false,
+          "endCursor": "2:b.txt"
+        }
+      }
+    }
+  }
+
+  $ CURSOR=$(josh-filter -g "$(cat query)" | grep endCursor | sed 's/.*: "\(.*\)"/\1/')
+  $ josh-filter -g "query { rev(at: \"refs/heads/master\") { searchConnection(string: \"needle\", first: 2, after: \"${CURSOR}\") { edges { path { path } matches { line text } } pageInfo { hasNextPage hasPreviousPage } } } }"
+  930fd809f9701a0ecc1aaaa3d48ba106603fcf1d
+  {
+    "rev": {
+      "searchConnection": {
+        "edges": [
+          {
+            "path": {
+              "path": "sub/c.txt"
+            },
+            "matches": [
+              {
+                "line": 1,
+                "text": "needle three"
+              }
+            ]
+          }
+        ],
+        "pageInfo": {
+          "hasNextPage": false,
+          "hasPreviousPage": true
+        }
+      }
+    }
+  }
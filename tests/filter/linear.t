@@ -65,6 +65,18 @@
 
   $ josh-filter -s :linear refs/heads/master --update refs/heads/filtered --reverse
   65fb0dcfe9fd24ab4d7027ff1359bd44847bd21a
+  [1] :~(
+      cache="unapply"
+      history="linear"
+  )[
+      :/
+  ]
+  [2] :~(
+      cache="original"
+      history="linear"
+  )[
+      :/
+  ]
   [4] :~(
       history="linear"
   )[
@@ -0,0 +1,103 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q repo 1>/dev/null
+  $ cd repo
+
+  $ cat > .gitattributes <<EOF2
+  > *.bat eol=crlf
+  > *.dat -text
+  > EOF2
+  $ printf 'one\r\ntwo\r\n' > dos.txt
+  $ printf 'one\ntwo\n' > unix.txt
+  $ printf 'echo hi\n' > run.bat
+  $ printf 'raw\r\n' > blob.dat
+  $ printf '\377\376h\000i\000\n\000' > wide.txt
+
+  $ git add . 2>/dev/null
+  $ git commit -q -m initial
+
+  $ josh-filter -p ':utf8:eol=lf'
+  :utf8:eol=lf
+  $ josh-filter -p ':invert[:eol=crlf]'
+  :restore[:eol=crlf]
+  $ josh-filter -p ':eol=cr'
+  ERROR: invalid line ending: "cr" (expected "lf" or "crlf")
+  [1]
+
+  $ josh-filter ':utf8:eol=lf' --update refs/heads/filtered
+  1558b2c9afc432eec7de4af5922c5134fd887a6e
+  $ for f in blob.dat dos.txt run.bat unix.txt wide.txt; do echo "$f: $(git show refs/heads/filtered:$f | cat -A | paste -sd' ')"; done
+  blob.dat: raw^M$
+  dos.txt: one$ two$
+  run.bat: echo hi^M$
+  unix.txt: one$ two$
+  wide.txt: hi$
+
+  $ git checkout -q filtered
+  $ printf 'one\ntwo\nthree\n' > unix.txt
+  $ git commit -q -a -m "edit unix.txt"
+
+  $ josh-filter ':utf8:eol=lf' --reverse master --update refs/heads/filtered
+  99f5c1a4ab38a5916c2437c0dd433d63042e7bf5
+
+
+Only the edit comes back; untouched files keep their original bytes
+
+  $ git diff --stat master~1 master
+   unix.txt | 1 +
+   1 file changed, 1 insertion(+)
+  $ git show master:dos.txt | cat -A
+  one^M$
+  two^M$
+  $ git show master:wide.txt | od -c
+  0000000 377 376   h  \0   i  \0  \n  \0
+  0000010
+
+A file pushed back with line endings the normalized side never has is refused, since there is no
+original to restore it to
+
+  $ git checkout -q filtered
+  $ printf 'one\r\ntwo\r\nthree\r\n' > dos.txt
+  $ git -c core.autocrlf=false commit -q -a -m "crlf dos.txt"
+  $ josh-filter ':utf8:eol=lf' --reverse master --update refs/heads/filtered
+  
+  Can't apply "crlf dos.txt" (8a1f15a0d28e2689a1e5f919132e2e17e8deb83d)
+  restore: dos.txt is not in lf form and has no recorded original
+
+Restore records belong to the path they were made for: a file added on the normalized side with
+the normalized content of another file stays as it was added
+
+  $ git checkout -q filtered
+  $ git reset -q --hard HEAD~1
+  $ printf 'one\ntwo\n' > new.txt
+  $ git add new.txt
+  $ git commit -q -m "add new.txt"
+  $ josh-filter ':utf8:eol=lf' --reverse master --update refs/heads/filtered
+  5445cbb613d6a1e1c112f3243fb75078c63111f0
+  $ git show master:new.txt | cat -A
+  one$
+  two$
+  $ git show master:dos.txt | cat -A
+  one^M$
+  two^M$
+
+Files with the same content upstream each get their own original back
+
+  $ git checkout -q master
+  $ printf 'one\r\ntwo\r\n' > copy.txt
+  $ git -c core.autocrlf=false add copy.txt 2>/dev/null
+  $ git commit -q -m "add copy.txt"
+  $ josh-filter ':utf8:eol=lf' --update refs/heads/filtered
+  edf726811f50610e2d9f7deada246654140617c5
+  $ git checkout -q filtered
+  $ printf 'one\ntwo\nthree\nfour\n' > unix.txt
+  $ git commit -q -a -m "edit unix.txt again"
+  $ josh-filter ':utf8:eol=lf' --reverse master --update refs/heads/filtered
+  d5f1e95ca9b658f56d411065cb5b6da75867749e
+  $ git diff --stat master~1 master
+   unix.txt | 1 +
+   1 file changed, 1 insertion(+)
+  $ git show master:copy.txt | cat -A
+  one^M$
+  two^M$
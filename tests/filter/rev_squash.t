@@ -85,7 +85,36 @@ resolution must reverse like a fall-through commit
   $ git update-ref refs/heads/filtered HEAD
   $ josh-filter -s "$FILTER" refs/heads/master --update refs/heads/filtered --reverse --check-roundtrip
   502c4f2c20e968f20dc97f4bb54b6a1598f690fd
+  [3] :~(
+      cache="unapply"
+      history="keep-trivial-merges,no-splice"
+  )[
+      :rev(<=104346ac7daf00a08bef19a999e4c7601aae519a:prefix=subtree,<=75a11dcdd41d68e57d9d9f07862bd284a99da6f0:SQUASH)
+  ]
+  [3] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [3] :~(
+      cache="unapply"
+  )[
+      :~(
+          history="keep-trivial-merges,no-splice"
+  )[
+          :rev(<=104346ac7daf00a08bef19a999e4c7601aae519a:prefix=subtree,<=75a11dcdd41d68e57d9d9f07862bd284a99da6f0:SQUASH)
+      ]:/subtree
+  ]
   [4] :/subtree
+  [4] :~(
+      cache="original"
+  )[
+      :~(
+          history="keep-trivial-merges,no-splice"
+  )[
+          :rev(<=104346ac7daf00a08bef19a999e4c7601aae519a:prefix=subtree,<=75a11dcdd41d68e57d9d9f07862bd284a99da6f0:SQUASH)
+      ]:/subtree
+  ]
   [5] :~(
       history="keep-trivial-merges,no-splice"
   )[
@@ -130,12 +159,41 @@ would disconnect one of them: the filter fails loudly instead
   $ export R2=$(git rev-parse HEAD)
 
   $ josh-filter -s ":~(history=\"keep-trivial-merges,no-splice\")[:rev(<=$SUBTREE_TIP:prefix=subtree,<=$SUB2_TIP:prefix=subtree2,<=$R2:SQUASH)]" refs/heads/master --update refs/heads/broken
+  [3] :~(
+      cache="unapply"
+      history="keep-trivial-merges,no-splice"
+  )[
+      :rev(<=104346ac7daf00a08bef19a999e4c7601aae519a:prefix=subtree,<=75a11dcdd41d68e57d9d9f07862bd284a99da6f0:SQUASH)
+  ]
+  [3] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [3] :~(
+      cache="unapply"
+  )[
+      :~(
+          history="keep-trivial-merges,no-splice"
+  )[
+          :rev(<=104346ac7daf00a08bef19a999e4c7601aae519a:prefix=subtree,<=75a11dcdd41d68e57d9d9f07862bd284a99da6f0:SQUASH)
+      ]:/subtree
+  ]
   [3] :~(
       history="keep-trivial-merges,no-splice"
   )[
       :rev(<=104346ac7daf00a08bef19a999e4c7601aae519a:prefix=subtree,<=64eeef227e94b848388854ea5e8b86a043c7ac12:prefix=subtree2,<=2019cb20591f675b8600a09209e884ccaaf8d1cd:SQUASH)
   ]
   [4] :/subtree
+  [4] :~(
+      cache="original"
+  )[
+      :~(
+          history="keep-trivial-merges,no-splice"
+  )[
+          :rev(<=104346ac7daf00a08bef19a999e4c7601aae519a:prefix=subtree,<=75a11dcdd41d68e57d9d9f07862bd284a99da6f0:SQUASH)
+      ]:/subtree
+  ]
   [5] :~(
       history="keep-trivial-merges,no-splice"
   )[
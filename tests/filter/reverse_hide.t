@@ -37,6 +37,16 @@
   $ josh-filter -s :exclude[::sub2/] --reverse master --update refs/heads/hidden
   98fd34a66dabd3178d921b29e865e465042736bb
   [1] :exclude[::sub2/]
+  [1] :~(
+      cache="unapply"
+  )[
+      :exclude[::sub2/]
+  ]
+  [2] :~(
+      cache="original"
+  )[
+      :exclude[::sub2/]
+  ]
   [2] reachable_roots
   [2] sequence_number
 
@@ -74,8 +84,18 @@
   $ josh-filter -s :exclude[::sub2/] --reverse master --update refs/heads/hidden
   d81806911addec824f6bd9c1d9386e3290237bf0
   [2] :exclude[::sub2/]
+  [2] :~(
+      cache="unapply"
+  )[
+      :exclude[::sub2/]
+  ]
   [3] reachable_roots
   [3] sequence_number
+  [5] :~(
+      cache="original"
+  )[
+      :exclude[::sub2/]
+  ]
   $ git log --graph --pretty=%s refs/heads/master
   * empty commit
   * add sub1/file3
@@ -37,6 +37,16 @@
   $ josh-filter -s :exclude[::sub2/] --reverse master --update refs/heads/hidden
   04a66ac914f2040990c1a47c7dc152fe02b1c337
   [1] :exclude[::sub2/]
+  [1] :~(
+      cache="unapply"
+  )[
+      :exclude[::sub2/]
+  ]
+  [2] :~(
+      cache="original"
+  )[
+      :exclude[::sub2/]
+  ]
   [2] reachable_roots
   [2] sequence_number
 
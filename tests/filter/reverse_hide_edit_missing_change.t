@@ -52,6 +52,16 @@
   $ josh-filter -s :exclude[::sub2/] --reverse master --update refs/heads/hidden
   55031991a5c2f493f2d62201828d8f20844ab219
   [1] :exclude[::sub2/]
+  [1] :~(
+      cache="unapply"
+  )[
+      :exclude[::sub2/]
+  ]
+  [2] :~(
+      cache="original"
+  )[
+      :exclude[::sub2/]
+  ]
   [2] reachable_roots
   [2] sequence_number
 
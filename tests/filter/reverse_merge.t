@@ -80,6 +80,16 @@
   $ josh-filter -s :exclude[::sub2/] --reverse master --update refs/heads/hidden_master
   1496b9e75273ad3a0de58812a731a7a50b0d2a66
   [3] :exclude[::sub2/]
+  [4] :~(
+      cache="unapply"
+  )[
+      :exclude[::sub2/]
+  ]
+  [5] :~(
+      cache="original"
+  )[
+      :exclude[::sub2/]
+  ]
   [8] reachable_roots
   [8] sequence_number
 
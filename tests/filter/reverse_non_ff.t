@@ -34,7 +34,17 @@ Meanwhile a concurrent commit lands on master
 
 Pushing without re-pulling first is refused
   $ josh-filter -s :/sub refs/heads/master --update refs/heads/filtered --reverse
+  [1] :~(
+      cache="unapply"
+  )[
+      :/sub
+  ]
   [2] :/sub
+  [3] :~(
+      cache="original"
+  )[
+      :/sub
+  ]
   [5] reachable_roots
   [5] sequence_number
   ERROR: refusing non-fast-forward update of refs/heads/master -- it contains commits that the reverse apply would discard. Re-apply the filter and rebase the filtered changes onto the result, or pass --force
@@ -46,7 +56,17 @@ Pushing without re-pulling first is refused
 With --force the update happens and the concurrent commit is discarded
   $ josh-filter -s :/sub refs/heads/master --update refs/heads/filtered --reverse --force
   cae93bf6a6f602f2777c66ce12a76065b21d30f9
+  [1] :~(
+      cache="unapply"
+  )[
+      :/sub
+  ]
   [2] :/sub
+  [3] :~(
+      cache="original"
+  )[
+      :/sub
+  ]
   [5] reachable_roots
   [5] sequence_number
 
@@ -58,7 +78,17 @@ After a proper pull (re-filter) the same push fast-forwards cleanly
   $ git update-ref refs/heads/master $RACED_MASTER
   $ josh-filter -s :/sub refs/heads/master --update refs/heads/filtered2
   6436bdb983b15392190096cc9dd832af2ae017ed
+  [1] :~(
+      cache="unapply"
+  )[
+      :/sub
+  ]
   [2] :/sub
+  [3] :~(
+      cache="original"
+  )[
+      :/sub
+  ]
   [5] reachable_roots
   [5] sequence_number
   $ git checkout -q -b work2 refs/heads/filtered2
@@ -69,6 +99,16 @@ After a proper pull (re-filter) the same push fast-forwards cleanly
   $ josh-filter -s :/sub refs/heads/master --update refs/heads/filtered2 --reverse
   0b1cb14b07f000ffbed470ae7abc3c698ebb7781
   [2] :/sub
+  [2] :~(
+      cache="unapply"
+  )[
+      :/sub
+  ]
+  [4] :~(
+      cache="original"
+  )[
+      :/sub
+  ]
   [5] reachable_roots
   [5] sequence_number
 
@@ -87,6 +87,35 @@ the reconstructed OID (not "Roundtrip failed").
 
   $ josh-filter -s "${FILTER}" master --update refs/heads/fmerge --reverse --check-roundtrip
   47d6b2a094f6aa47dbcb60a36b1aebf068c529f4
+  [2] :~(
+      cache="unapply"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=e2607aa41afb761fc6b0a24dc6d8d16c7e30a978:prefix=sub)
+  ]
+  [2] :~(
+      cache="unapply"
+  )[
+      :/sub
+  ]
+  [2] :~(
+      cache="unapply"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=e2607aa41afb761fc6b0a24dc6d8d16c7e30a978:prefix=sub)
+      ]:/sub
+  ]
+  [3] :~(
+      cache="original"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=e2607aa41afb761fc6b0a24dc6d8d16c7e30a978:prefix=sub)
+      ]:/sub
+  ]
   [4] :/sub
   [4] :~(
       history="keep-trivial-merges"
@@ -42,10 +42,22 @@
 
   $ josh-filter -s $FILTER --reverse master --update refs/heads/filtered
   4031be37b86723bab26952dcd055a4d7294aa827
+  [1] :~(
+      cache="unapply"
+  )[
+      a = ::*.a
+      :prefix=rest
+  ]
   [2] :[
       a = ::*.a
       :prefix=rest
   ]
+  [2] :~(
+      cache="original"
+  )[
+      a = ::*.a
+      :prefix=rest
+  ]
   [2] reachable_roots
   [2] sequence_number
 
@@ -31,6 +31,16 @@ A commit on top of the squashed view pushes back onto the original history
 
   $ josh-filter -s :SQUASH refs/heads/master --update refs/heads/filtered --reverse
   e947255d78b17600bb2b9036e5c86104cdf639ee
+  [1] :~(
+      cache="unapply"
+  )[
+      :SQUASH
+  ]
+  [2] :~(
+      cache="original"
+  )[
+      :SQUASH
+  ]
 
   $ git log --graph --pretty=%s refs/heads/master
   * add file3
@@ -45,6 +55,16 @@ A commit on top of the squashed view pushes back onto the original history
 Re-filtering squashes the updated history back into a single commit
   $ josh-filter -s :SQUASH refs/heads/master --update refs/heads/refiltered
   8fe1090d633aa8413a2f04e20c0c11efe6dfc332
+  [1] :~(
+      cache="unapply"
+  )[
+      :SQUASH
+  ]
+  [2] :~(
+      cache="original"
+  )[
+      :SQUASH
+  ]
 
   $ git log --graph --pretty=%s refs/heads/refiltered
   * add file3
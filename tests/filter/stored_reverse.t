@@ -64,6 +64,11 @@
 
   $ josh-filter -s :+st/config --reverse master --update refs/heads/filtered
   b3ae61d547a0c794fa987774cf2d02131c5c3ad7
+  [1] :~(
+      cache="unapply"
+  )[
+      :+st/config
+  ]
   [2] :+st/config
   [2] :subtract[
           :[
@@ -73,6 +78,11 @@
           ]
           :/st::config.josh
       ]
+  [2] :~(
+      cache="original"
+  )[
+      :+st/config
+  ]
   [3] reachable_roots
   [3] sequence_number
   $ git checkout master
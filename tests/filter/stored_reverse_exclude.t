@@ -63,6 +63,11 @@
 
   $ josh-filter -s :+st/config --reverse master --update refs/heads/filtered
   6182f2f07949f7ccfd2e1ac0dfbd65b29f8e3d84
+  [1] :~(
+      cache="unapply"
+  )[
+      :+st/config
+  ]
   [2] :+st/config
   [2] :subtract[
           :[
@@ -72,6 +77,11 @@
           ]
           :/st::config.josh
       ]
+  [2] :~(
+      cache="original"
+  )[
+      :+st/config
+  ]
   [3] reachable_roots
   [3] sequence_number
   $ git checkout master
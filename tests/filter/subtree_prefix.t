@@ -115,6 +115,35 @@ Work in the subtree, and sync that back.
   $ git commit -am "add even more content" 1>/dev/null
   $ josh-filter -s $FILTER refs/heads/master --update refs/heads/subtree --reverse
   103bfec17c47adbe70a95fca90caefb989b6cda6
+  [1] :~(
+      cache="unapply"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+      ]:/subtree
+  ]
+  [2] :~(
+      cache="original"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+      ]:/subtree
+  ]
   [4] :/subtree
   [4] :~(
       history="keep-trivial-merges"
@@ -143,6 +172,35 @@ Work in the subtree, and sync that back.
 And then re-extract, which should re-construct the same subtree.
   $ josh-filter -s $FILTER refs/heads/master --update refs/heads/subtree2
   d4baf6a78a4f3966055c12821bce8a9e0933a3c7
+  [1] :~(
+      cache="unapply"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+      ]:/subtree
+  ]
+  [2] :~(
+      cache="original"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+      ]:/subtree
+  ]
   [5] :/subtree
   [5] :~(
       history="keep-trivial-merges"
@@ -214,6 +272,35 @@ exactly what `subtree-sync` already points at from the earlier sync -- josh-filt
   * add file2 (in subtree)
   $ josh-filter -s $FILTER refs/heads/master --update refs/heads/subtree --reverse
   f7f92ee197391da3f48d5a2a1d7016f97751a758
+  [3] :~(
+      cache="unapply"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+  ]
+  [3] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [3] :~(
+      cache="unapply"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+      ]:/subtree
+  ]
+  [5] :~(
+      cache="original"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+      ]:/subtree
+  ]
   [9] :/subtree
   [9] :~(
       history="keep-trivial-merges"
@@ -258,6 +345,35 @@ taken back into the main history.
 
   $ josh-filter -s $FILTER refs/heads/master --update refs/heads/subtree --reverse --force
   b0a4107ddd6054442a8eaac89a2af0ab375607eb
+  [4] :~(
+      cache="unapply"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+  ]
+  [4] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [4] :~(
+      cache="unapply"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+      ]:/subtree
+  ]
+  [8] :~(
+      cache="original"
+  )[
+      :~(
+          history="keep-trivial-merges"
+  )[
+          :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+      ]:/subtree
+  ]
   [11] :/subtree
   [11] :~(
       history="keep-trivial-merges"
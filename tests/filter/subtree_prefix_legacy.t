@@ -108,6 +108,28 @@ Work in the subtree, and sync that back.
   $ git commit -am "add even more content" 1>/dev/null
   $ josh-filter -s $FILTER refs/heads/master --update refs/heads/subtree --reverse
   103bfec17c47adbe70a95fca90caefb989b6cda6
+  [1] :~(
+      cache="unapply"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree):/subtree
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+  ]
+  [2] :~(
+      cache="original"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree):/subtree
+  ]
   [4] :~(
       history="keep-trivial-merges"
   )[
@@ -140,6 +162,28 @@ Work in the subtree, and sync that back.
 And then re-extract, which should re-construct the same subtree.
   $ josh-filter -s $FILTER refs/heads/master --update refs/heads/subtree2
   d4baf6a78a4f3966055c12821bce8a9e0933a3c7
+  [1] :~(
+      cache="unapply"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree):/subtree
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+  ]
+  [2] :~(
+      cache="original"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree):/subtree
+  ]
   [5] :~(
       history="keep-trivial-merges"
   )[
@@ -224,6 +268,28 @@ And finally, sync first from main to sub and then back.
   * add file2 (in subtree)
   $ josh-filter -s $FILTER refs/heads/master --update refs/heads/subtree --reverse
   6ac0ba56575859cfaacd5818084333e532ffc442
+  [8] :~(
+      cache="unapply"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree):/subtree
+  ]
+  [8] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [8] :~(
+      cache="unapply"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+  ]
+  [9] :~(
+      cache="original"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree):/subtree
+  ]
   [9] :~(
       history="keep-trivial-merges"
   )[
@@ -280,6 +346,28 @@ taken back into the main history.
 
   $ josh-filter -s $FILTER refs/heads/master --update refs/heads/subtree --reverse --force
   f814033dd0148da19a3199cd3cb2d21464ce85a3
+  [11] :~(
+      cache="unapply"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree):/subtree
+  ]
+  [11] :~(
+      cache="unapply"
+  )[
+      :/subtree
+  ]
+  [11] :~(
+      cache="unapply"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree)
+  ]
+  [13] :~(
+      cache="original"
+      history="keep-trivial-merges"
+  )[
+      :rev(<=c036f944faafb865e0585e4fa5e005afa0aeea3f:prefix=subtree):/subtree
+  ]
   [13] :~(
       history="keep-trivial-merges"
   )[
@@ -58,20 +58,40 @@
 
   $ josh-filter -s :workspace=ws --reverse master --update refs/heads/filtered
   fdfbfdcdc052aeb50f5033ec3d7f0cee3340d253
+  [1] :~(
+      cache="unapply"
+  )[
+      :workspace=ws
+  ]
   [2] :[
       a = :/sub1:exclude[::file1]
       ::sub2/subsub/
   ]
   [2] :workspace=ws
+  [2] :~(
+      cache="original"
+  )[
+      :workspace=ws
+  ]
   [3] reachable_roots
   [3] sequence_number
   $ josh-filter -s :workspace=ws --reverse --check-roundtrip master --update refs/heads/filtered
   Roundtrip failed
+  [1] :~(
+      cache="unapply"
+  )[
+      :workspace=ws
+  ]
   [2] :[
       a = :/sub1:exclude[::file1]
       ::sub2/subsub/
   ]
   [3] :workspace=ws
+  [5] :~(
+      cache="original"
+  )[
+      :workspace=ws
+  ]
   [9] reachable_roots
   [9] sequence_number
   $ git checkout master
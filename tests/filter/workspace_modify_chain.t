@@ -55,7 +55,27 @@
   [1] :/subsub
   [1] :prefix=sub2
   [1] :prefix=subsub
+  [1] :~(
+      cache="unapply"
+  )[
+      :/sub2
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :workspace=ws
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :workspace=ws:/sub2
+  ]
   [2] :workspace=ws
+  [2] :~(
+      cache="original"
+  )[
+      :workspace=ws:/sub2
+  ]
   [3] :/sub2
   [7] reachable_roots
   [7] sequence_number
@@ -64,7 +84,27 @@
   [1] :/subsub
   [1] :prefix=sub2
   [1] :prefix=subsub
+  [1] :~(
+      cache="unapply"
+  )[
+      :/sub2
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :workspace=ws
+  ]
+  [1] :~(
+      cache="unapply"
+  )[
+      :workspace=ws:/sub2
+  ]
   [3] :workspace=ws
+  [3] :~(
+      cache="original"
+  )[
+      :workspace=ws:/sub2
+  ]
   [4] :/sub2
   [9] reachable_roots
   [9] sequence_number
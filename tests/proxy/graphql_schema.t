@@ -110,7 +110,9 @@
         "queryType": {
           "name": "Repository"
         },
-        "subscriptionType": null,
+        "subscriptionType": {
+          "name": "RepositorySubscription"
+        },
         "types": [
           {
             "description": null,
@@ -248,6 +250,80 @@
             "name": "__TypeKind",
             "possibleTypes": null
           },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": null,
+            "inputFields": [
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "name",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "email",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              }
+            ],
+            "interfaces": null,
+            "kind": "INPUT_OBJECT",
+            "name": "AuthorInput",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": null,
+            "inputFields": [
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "path",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "content",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
+              }
+            ],
+            "interfaces": null,
+            "kind": "INPUT_OBJECT",
+            "name": "FileEditInput",
+            "possibleTypes": null
+          },
           {
             "description": null,
             "enumValues": null,
@@ -295,6 +371,87 @@
             "name": "MarkersInput",
             "possibleTypes": null
           },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": null,
+            "inputFields": [
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "merge",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "Boolean",
+                  "ofType": null
+                }
+              },
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "allowOrphans",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "Boolean",
+                  "ofType": null
+                }
+              },
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "edit",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "Boolean",
+                  "ofType": null
+                }
+              },
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "create",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "Boolean",
+                  "ofType": null
+                }
+              },
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "force",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "Boolean",
+                  "ofType": null
+                }
+              },
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "base",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
+              },
+              {
+                "defaultValue": null,
+                "description": null,
+                "name": "author",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
+              }
+            ],
+            "interfaces": null,
+            "kind": "INPUT_OBJECT",
+            "name": "PushOptionsInput",
+            "possibleTypes": null
+          },
           {
             "description": null,
             "enumValues": null,
@@ -334,11 +491,15 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "from",
+                "name": "startLine",
                 "type": {
-                  "kind": "OBJECT",
-                  "name": "Path",
-                  "ofType": null
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "Int",
+                    "ofType": null
+                  }
                 }
               },
               {
@@ -346,18 +507,38 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "to",
+                "name": "endLine",
                 "type": {
-                  "kind": "OBJECT",
-                  "name": "Path",
-                  "ofType": null
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "Int",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "rev",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "Revision",
+                    "ofType": null
+                  }
                 }
               }
             ],
             "inputFields": null,
             "interfaces": [],
             "kind": "OBJECT",
-            "name": "DiffPath",
+            "name": "BlameRange",
             "possibleTypes": null
           },
           {
@@ -365,79 +546,232 @@
             "enumValues": null,
             "fields": [
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "at",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
-                    }
-                  },
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "default",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
-                    }
-                  }
-                ],
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "string",
+                "name": "rev",
                 "type": {
-                  "kind": "SCALAR",
-                  "name": "String",
-                  "ofType": null
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "Revision",
+                    "ofType": null
+                  }
                 }
               },
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "at",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
-                    }
-                  },
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "default",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "Boolean",
-                      "ofType": null
-                    }
-                  }
-                ],
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "bool",
+                "name": "warnings",
                 "type": {
-                  "kind": "SCALAR",
-                  "name": "Boolean",
-                  "ofType": null
-                }
-              },
-              {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "at",
-                    "type": {
-                      "kind": "SCALAR",
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  }
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "CommitResult",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "origin",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "content",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "oldLine",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "Int",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "newLine",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "Int",
+                  "ofType": null
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "DiffLine",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "from",
+                "type": {
+                  "kind": "OBJECT",
+                  "name": "Path",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "to",
+                "type": {
+                  "kind": "OBJECT",
+                  "name": "Path",
+                  "ofType": null
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "DiffPath",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "at",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "default",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "string",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "at",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "default",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Boolean",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "bool",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "Boolean",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "at",
+                    "type": {
+                      "kind": "SCALAR",
                       "name": "String",
                       "ofType": null
                     }
@@ -553,22 +887,14 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "data",
+                "name": "path",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "LIST",
-                    "name": null,
-                    "ofType": {
-                      "kind": "NON_NULL",
-                      "name": null,
-                      "ofType": {
-                        "kind": "OBJECT",
-                        "name": "Document",
-                        "ofType": null
-                      }
-                    }
+                    "kind": "OBJECT",
+                    "name": "Path",
+                    "ofType": null
                   }
                 }
               },
@@ -577,13 +903,13 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "count",
+                "name": "document",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "SCALAR",
-                    "name": "Int",
+                    "kind": "OBJECT",
+                    "name": "Document",
                     "ofType": null
                   }
                 }
@@ -592,7 +918,7 @@
             "inputFields": null,
             "interfaces": [],
             "kind": "OBJECT",
-            "name": "Markers",
+            "name": "DocumentMatch",
             "possibleTypes": null
           },
           {
@@ -616,94 +942,143 @@
                 }
               },
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "relative",
-                    "type": {
-                      "kind": "NON_NULL",
-                      "name": null,
-                      "ofType": {
-                        "kind": "SCALAR",
-                        "name": "String",
-                        "ofType": null
-                      }
-                    }
-                  }
-                ],
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "dir",
+                "name": "from",
                 "type": {
-                  "kind": "NON_NULL",
-                  "name": null,
-                  "ofType": {
-                    "kind": "OBJECT",
-                    "name": "Path",
-                    "ofType": null
-                  }
+                  "kind": "OBJECT",
+                  "name": "Path",
+                  "ofType": null
                 }
               },
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "topic",
-                    "type": {
-                      "kind": "NON_NULL",
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "to",
+                "type": {
+                  "kind": "OBJECT",
+                  "name": "Path",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "binary",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "Boolean",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "hunks",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
                       "name": null,
                       "ofType": {
-                        "kind": "SCALAR",
-                        "name": "String",
+                        "kind": "OBJECT",
+                        "name": "Hunk",
                         "ofType": null
                       }
                     }
                   }
-                ],
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "FileDiff",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "meta",
+                "name": "rev",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
                     "kind": "OBJECT",
-                    "name": "Markers",
+                    "name": "Revision",
                     "ofType": null
                   }
                 }
               },
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "filter",
-                    "type": {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "changes",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
                       "kind": "NON_NULL",
                       "name": null,
                       "ofType": {
-                        "kind": "SCALAR",
-                        "name": "String",
+                        "kind": "OBJECT",
+                        "name": "OccurrenceChange",
                         "ofType": null
                       }
                     }
                   }
-                ],
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "HistoryMatch",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "rev",
+                "name": "header",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "OBJECT",
-                    "name": "Revision",
+                    "kind": "SCALAR",
+                    "name": "String",
                     "ofType": null
                   }
                 }
@@ -713,13 +1088,13 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "hash",
+                "name": "oldStart",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
                     "kind": "SCALAR",
-                    "name": "String",
+                    "name": "Int",
                     "ofType": null
                   }
                 }
@@ -729,11 +1104,15 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "text",
+                "name": "oldLines",
                 "type": {
-                  "kind": "SCALAR",
-                  "name": "String",
-                  "ofType": null
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "Int",
+                    "ofType": null
+                  }
                 }
               },
               {
@@ -741,13 +1120,13 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "toml",
+                "name": "newStart",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "OBJECT",
-                    "name": "Document",
+                    "kind": "SCALAR",
+                    "name": "Int",
                     "ofType": null
                   }
                 }
@@ -757,13 +1136,13 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "json",
+                "name": "newLines",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "OBJECT",
-                    "name": "Document",
+                    "kind": "SCALAR",
+                    "name": "Int",
                     "ofType": null
                   }
                 }
@@ -773,14 +1152,22 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "yaml",
+                "name": "lines",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "OBJECT",
-                    "name": "Document",
-                    "ofType": null
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "DiffLine",
+                        "ofType": null
+                      }
+                    }
                   }
                 }
               }
@@ -788,7 +1175,7 @@
             "inputFields": null,
             "interfaces": [],
             "kind": "OBJECT",
-            "name": "Path",
+            "name": "Hunk",
             "possibleTypes": null
           },
           {
@@ -800,40 +1187,37 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "name",
+                "name": "data",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "SCALAR",
-                    "name": "String",
-                    "ofType": null
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "Document",
+                        "ofType": null
+                      }
+                    }
                   }
                 }
               },
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "filter",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
-                    }
-                  }
-                ],
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "rev",
+                "name": "count",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "OBJECT",
-                    "name": "Revision",
+                    "kind": "SCALAR",
+                    "name": "Int",
                     "ofType": null
                   }
                 }
@@ -842,7 +1226,7 @@
             "inputFields": null,
             "interfaces": [],
             "kind": "OBJECT",
-            "name": "Reference",
+            "name": "Markers",
             "possibleTypes": null
           },
           {
@@ -854,7 +1238,7 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "name",
+                "name": "path",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
@@ -866,77 +1250,33 @@
                 }
               },
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "pattern",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
-                    }
-                  }
-                ],
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "refs",
+                "name": "before",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "LIST",
-                    "name": null,
-                    "ofType": {
-                      "kind": "NON_NULL",
-                      "name": null,
-                      "ofType": {
-                        "kind": "OBJECT",
-                        "name": "Reference",
-                        "ofType": null
-                      }
-                    }
+                    "kind": "SCALAR",
+                    "name": "Int",
+                    "ofType": null
                   }
                 }
               },
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "at",
-                    "type": {
-                      "kind": "NON_NULL",
-                      "name": null,
-                      "ofType": {
-                        "kind": "SCALAR",
-                        "name": "String",
-                        "ofType": null
-                      }
-                    }
-                  },
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "filter",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
-                    }
-                  }
-                ],
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "rev",
+                "name": "after",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "OBJECT",
-                    "name": "Revision",
+                    "kind": "SCALAR",
+                    "name": "Int",
                     "ofType": null
                   }
                 }
@@ -945,7 +1285,7 @@
             "inputFields": null,
             "interfaces": [],
             "kind": "OBJECT",
-            "name": "Repository",
+            "name": "OccurrenceChange",
             "possibleTypes": null
           },
           {
@@ -953,63 +1293,94 @@
             "enumValues": null,
             "fields": [
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "at",
-                    "type": {
-                      "kind": "NON_NULL",
-                      "name": null,
-                      "ofType": {
-                        "kind": "SCALAR",
-                        "name": "String",
-                        "ofType": null
-                      }
-                    }
-                  },
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "filter",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
-                    }
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "hasNextPage",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "Boolean",
+                    "ofType": null
                   }
-                ],
+                }
+              },
+              {
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "rev",
+                "name": "hasPreviousPage",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "OBJECT",
-                    "name": "RevMut",
+                    "kind": "SCALAR",
+                    "name": "Boolean",
                     "ofType": null
                   }
                 }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "startCursor",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "endCursor",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
               }
             ],
             "inputFields": null,
             "interfaces": [],
             "kind": "OBJECT",
-            "name": "RepositoryMut",
+            "name": "PageInfo",
             "possibleTypes": null
           },
           {
             "description": null,
             "enumValues": null,
             "fields": [
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "path",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
               {
                 "args": [
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "target",
+                    "name": "relative",
                     "type": {
                       "kind": "NON_NULL",
                       "name": null,
@@ -1019,28 +1390,18 @@
                         "ofType": null
                       }
                     }
-                  },
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "repo",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
-                    }
                   }
                 ],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "push",
+                "name": "dir",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "SCALAR",
-                    "name": "Boolean",
+                    "kind": "OBJECT",
+                    "name": "Path",
                     "ofType": null
                   }
                 }
@@ -1060,26 +1421,35 @@
                         "ofType": null
                       }
                     }
-                  },
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "meta",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "Markers",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "add",
+                    "name": "filter",
                     "type": {
                       "kind": "NON_NULL",
                       "name": null,
                       "ofType": {
-                        "kind": "LIST",
-                        "name": null,
-                        "ofType": {
-                          "kind": "NON_NULL",
-                          "name": null,
-                          "ofType": {
-                            "kind": "INPUT_OBJECT",
-                            "name": "MarkersInput",
-                            "ofType": null
-                          }
-                        }
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
                       }
                     }
                   }
@@ -1087,34 +1457,23 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "meta",
+                "name": "rev",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "SCALAR",
-                    "name": "Boolean",
+                    "kind": "OBJECT",
+                    "name": "Revision",
                     "ofType": null
                   }
                 }
-              }
-            ],
-            "inputFields": null,
-            "interfaces": [],
-            "kind": "OBJECT",
-            "name": "RevMut",
-            "possibleTypes": null
-          },
-          {
-            "description": null,
-            "enumValues": null,
-            "fields": [
+              },
               {
                 "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "filter",
+                "name": "hash",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
@@ -1130,14 +1489,34 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "hash",
+                "name": "text",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "blame",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "SCALAR",
-                    "name": "String",
-                    "ofType": null
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "BlameRange",
+                        "ofType": null
+                      }
+                    }
                   }
                 }
               },
@@ -1146,13 +1525,13 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "authorEmail",
+                "name": "toml",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "SCALAR",
-                    "name": "String",
+                    "kind": "OBJECT",
+                    "name": "Document",
                     "ofType": null
                   }
                 }
@@ -1162,13 +1541,13 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "summary",
+                "name": "json",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "SCALAR",
-                    "name": "String",
+                    "kind": "OBJECT",
+                    "name": "Document",
                     "ofType": null
                   }
                 }
@@ -1178,38 +1557,34 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "message",
+                "name": "yaml",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "SCALAR",
-                    "name": "String",
+                    "kind": "OBJECT",
+                    "name": "Document",
                     "ofType": null
                   }
                 }
-              },
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "Path",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "format",
-                    "type": {
-                      "kind": "NON_NULL",
-                      "name": null,
-                      "ofType": {
-                        "kind": "SCALAR",
-                        "name": "String",
-                        "ofType": null
-                      }
-                    }
-                  }
-                ],
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "date",
+                "name": "name",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
@@ -1221,35 +1596,14 @@
                 }
               },
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "filter",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
-                    }
-                  },
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "original",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "Boolean",
-                      "ofType": null
-                    }
-                  }
-                ],
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "rev",
+                "name": "upstream",
                 "type": {
-                  "kind": "OBJECT",
-                  "name": "Revision",
+                  "kind": "SCALAR",
+                  "name": "String",
                   "ofType": null
                 }
               },
@@ -1258,23 +1612,1023 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "parents",
+                "name": "previousUpstream",
                 "type": {
-                  "kind": "NON_NULL",
-                  "name": null,
-                  "ofType": {
-                    "kind": "LIST",
-                    "name": null,
-                    "ofType": {
-                      "kind": "NON_NULL",
-                      "name": null,
-                      "ofType": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "filtered",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "previousFiltered",
+                "type": {
+                  "kind": "SCALAR",
+                  "name": "String",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "rev",
+                "type": {
+                  "kind": "OBJECT",
+                  "name": "Revision",
+                  "ofType": null
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "RefUpdate",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "name",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "filter",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "rev",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "Revision",
+                    "ofType": null
+                  }
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "Reference",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "name",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "pattern",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "refs",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "Reference",
+                        "ofType": null
+                      }
+                    }
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "at",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "filter",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "rev",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "Revision",
+                    "ofType": null
+                  }
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "Repository",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "at",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "filter",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "rev",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "RevMut",
+                    "ofType": null
+                  }
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "RepositoryMut",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "pattern",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "filter",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "refUpdates",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "RefUpdate",
+                    "ofType": null
+                  }
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "RepositorySubscription",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "target",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "repo",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "push",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "Boolean",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "message",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "edits",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "LIST",
+                        "name": null,
+                        "ofType": {
+                          "kind": "NON_NULL",
+                          "name": null,
+                          "ofType": {
+                            "kind": "INPUT_OBJECT",
+                            "name": "FileEditInput",
+                            "ofType": null
+                          }
+                        }
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "target",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "author",
+                    "type": {
+                      "kind": "INPUT_OBJECT",
+                      "name": "AuthorInput",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "options",
+                    "type": {
+                      "kind": "INPUT_OBJECT",
+                      "name": "PushOptionsInput",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "commit",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "CommitResult",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "topic",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "add",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "LIST",
+                        "name": null,
+                        "ofType": {
+                          "kind": "NON_NULL",
+                          "name": null,
+                          "ofType": {
+                            "kind": "INPUT_OBJECT",
+                            "name": "MarkersInput",
+                            "ofType": null
+                          }
+                        }
+                      }
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "meta",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "Boolean",
+                    "ofType": null
+                  }
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "RevMut",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "filter",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "hash",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "authorEmail",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "summary",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "message",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "format",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "date",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "filter",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "original",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Boolean",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "rev",
+                "type": {
+                  "kind": "OBJECT",
+                  "name": "Revision",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "parents",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "Revision",
+                        "ofType": null
+                      }
+                    }
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "limit",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "offset",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "path",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "history",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "Revision",
+                        "ofType": null
+                      }
+                    }
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "first",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "after",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "path",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "firstParent",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Boolean",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "historyConnection",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "RevisionConnection",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "at",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "depth",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "files",
+                "type": {
+                  "kind": "LIST",
+                  "name": null,
+                  "ofType": {
+                    "kind": "NON_NULL",
+                    "name": null,
+                    "ofType": {
+                      "kind": "OBJECT",
+                      "name": "Path",
+                      "ofType": null
+                    }
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "at",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "depth",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "dirs",
+                "type": {
+                  "kind": "LIST",
+                  "name": null,
+                  "ofType": {
+                    "kind": "NON_NULL",
+                    "name": null,
+                    "ofType": {
+                      "kind": "OBJECT",
+                      "name": "Path",
+                      "ofType": null
+                    }
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "at",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "depth",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "changedFiles",
+                "type": {
+                  "kind": "LIST",
+                  "name": null,
+                  "ofType": {
+                    "kind": "NON_NULL",
+                    "name": null,
+                    "ofType": {
+                      "kind": "OBJECT",
+                      "name": "DiffPath",
+                      "ofType": null
+                    }
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "against",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "paths",
+                    "type": {
+                      "kind": "LIST",
+                      "name": null,
+                      "ofType": {
+                        "kind": "NON_NULL",
+                        "name": null,
+                        "ofType": {
+                          "kind": "SCALAR",
+                          "name": "String",
+                          "ofType": null
+                        }
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "contextLines",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "diff",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
                         "kind": "OBJECT",
-                        "name": "Revision",
+                        "name": "FileDiff",
+                        "ofType": null
+                      }
+                    }
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "path",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
                         "ofType": null
                       }
                     }
                   }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "file",
+                "type": {
+                  "kind": "OBJECT",
+                  "name": "Path",
+                  "ofType": null
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "path",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "dir",
+                "type": {
+                  "kind": "OBJECT",
+                  "name": "Path",
+                  "ofType": null
                 }
               },
               {
@@ -1282,20 +2636,34 @@
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "limit",
+                    "name": "glob",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "format",
                     "type": {
                       "kind": "SCALAR",
-                      "name": "Int",
+                      "name": "String",
                       "ofType": null
                     }
                   },
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "offset",
+                    "name": "pointer",
                     "type": {
                       "kind": "SCALAR",
-                      "name": "Int",
+                      "name": "String",
                       "ofType": null
                     }
                   }
@@ -1303,7 +2671,7 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "history",
+                "name": "documents",
                 "type": {
                   "kind": "NON_NULL",
                   "name": null,
@@ -1315,29 +2683,119 @@
                       "name": null,
                       "ofType": {
                         "kind": "OBJECT",
-                        "name": "Revision",
+                        "name": "DocumentMatch",
                         "ofType": null
                       }
                     }
                   }
                 }
               },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "warnings",
+                "type": {
+                  "kind": "LIST",
+                  "name": null,
+                  "ofType": {
+                    "kind": "NON_NULL",
+                    "name": null,
+                    "ofType": {
+                      "kind": "OBJECT",
+                      "name": "Warning",
+                      "ofType": null
+                    }
+                  }
+                }
+              },
               {
                 "args": [
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "at",
+                    "name": "string",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "regex",
                     "type": {
                       "kind": "SCALAR",
-                      "name": "String",
+                      "name": "Boolean",
                       "ofType": null
                     }
                   },
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "depth",
+                    "name": "caseSensitive",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Boolean",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "include",
+                    "type": {
+                      "kind": "LIST",
+                      "name": null,
+                      "ofType": {
+                        "kind": "NON_NULL",
+                        "name": null,
+                        "ofType": {
+                          "kind": "SCALAR",
+                          "name": "String",
+                          "ofType": null
+                        }
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "exclude",
+                    "type": {
+                      "kind": "LIST",
+                      "name": null,
+                      "ofType": {
+                        "kind": "NON_NULL",
+                        "name": null,
+                        "ofType": {
+                          "kind": "SCALAR",
+                          "name": "String",
+                          "ofType": null
+                        }
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "contextBefore",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "contextAfter",
                     "type": {
                       "kind": "SCALAR",
                       "name": "Int",
@@ -1348,7 +2806,7 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "files",
+                "name": "search",
                 "type": {
                   "kind": "LIST",
                   "name": null,
@@ -1357,7 +2815,7 @@
                     "name": null,
                     "ofType": {
                       "kind": "OBJECT",
-                      "name": "Path",
+                      "name": "SearchResult",
                       "ofType": null
                     }
                   }
@@ -1368,39 +2826,125 @@
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "at",
+                    "name": "string",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "regex",
                     "type": {
                       "kind": "SCALAR",
-                      "name": "String",
+                      "name": "Boolean",
                       "ofType": null
                     }
                   },
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "depth",
+                    "name": "caseSensitive",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Boolean",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "include",
+                    "type": {
+                      "kind": "LIST",
+                      "name": null,
+                      "ofType": {
+                        "kind": "NON_NULL",
+                        "name": null,
+                        "ofType": {
+                          "kind": "SCALAR",
+                          "name": "String",
+                          "ofType": null
+                        }
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "exclude",
+                    "type": {
+                      "kind": "LIST",
+                      "name": null,
+                      "ofType": {
+                        "kind": "NON_NULL",
+                        "name": null,
+                        "ofType": {
+                          "kind": "SCALAR",
+                          "name": "String",
+                          "ofType": null
+                        }
+                      }
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "contextBefore",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "contextAfter",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "Int",
+                      "ofType": null
+                    }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "first",
                     "type": {
                       "kind": "SCALAR",
                       "name": "Int",
                       "ofType": null
                     }
+                  },
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "after",
+                    "type": {
+                      "kind": "SCALAR",
+                      "name": "String",
+                      "ofType": null
+                    }
                   }
                 ],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "dirs",
+                "name": "searchConnection",
                 "type": {
-                  "kind": "LIST",
+                  "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "NON_NULL",
-                    "name": null,
-                    "ofType": {
-                      "kind": "OBJECT",
-                      "name": "Path",
-                      "ofType": null
-                    }
+                    "kind": "OBJECT",
+                    "name": "SearchConnection",
+                    "ofType": null
                   }
                 }
               },
@@ -1409,17 +2953,21 @@
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "at",
+                    "name": "string",
                     "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
                     }
                   },
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "depth",
+                    "name": "first",
                     "type": {
                       "kind": "SCALAR",
                       "name": "Int",
@@ -1430,17 +2978,60 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "changedFiles",
+                "name": "searchHistory",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "HistoryMatch",
+                        "ofType": null
+                      }
+                    }
+                  }
+                }
+              },
+              {
+                "args": [
+                  {
+                    "defaultValue": null,
+                    "description": null,
+                    "name": "symbol",
+                    "type": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "SCALAR",
+                        "name": "String",
+                        "ofType": null
+                      }
+                    }
+                  }
+                ],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "definitions",
                 "type": {
-                  "kind": "LIST",
+                  "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "NON_NULL",
+                    "kind": "LIST",
                     "name": null,
                     "ofType": {
-                      "kind": "OBJECT",
-                      "name": "DiffPath",
-                      "ofType": null
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "SymbolMatch",
+                        "ofType": null
+                      }
                     }
                   }
                 }
@@ -1450,7 +3041,7 @@
                   {
                     "defaultValue": null,
                     "description": null,
-                    "name": "path",
+                    "name": "symbol",
                     "type": {
                       "kind": "NON_NULL",
                       "name": null,
@@ -1465,88 +3056,167 @@
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "file",
+                "name": "references",
                 "type": {
-                  "kind": "OBJECT",
-                  "name": "Path",
-                  "ofType": null
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "SymbolMatch",
+                        "ofType": null
+                      }
+                    }
+                  }
                 }
-              },
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "Revision",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "path",
-                    "type": {
-                      "kind": "SCALAR",
-                      "name": "String",
-                      "ofType": null
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "edges",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                      "kind": "NON_NULL",
+                      "name": null,
+                      "ofType": {
+                        "kind": "OBJECT",
+                        "name": "RevisionEdge",
+                        "ofType": null
+                      }
                     }
                   }
-                ],
+                }
+              },
+              {
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "dir",
+                "name": "pageInfo",
                 "type": {
-                  "kind": "OBJECT",
-                  "name": "Path",
-                  "ofType": null
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "PageInfo",
+                    "ofType": null
+                  }
                 }
-              },
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "RevisionConnection",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
               {
                 "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "warnings",
+                "name": "cursor",
                 "type": {
-                  "kind": "LIST",
+                  "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "NON_NULL",
-                    "name": null,
-                    "ofType": {
-                      "kind": "OBJECT",
-                      "name": "Warning",
-                      "ofType": null
-                    }
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
                   }
                 }
               },
               {
-                "args": [
-                  {
-                    "defaultValue": null,
-                    "description": null,
-                    "name": "string",
-                    "type": {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "node",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "Revision",
+                    "ofType": null
+                  }
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "RevisionEdge",
+            "possibleTypes": null
+          },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "edges",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
                       "kind": "NON_NULL",
                       "name": null,
                       "ofType": {
-                        "kind": "SCALAR",
-                        "name": "String",
+                        "kind": "OBJECT",
+                        "name": "SearchResult",
                         "ofType": null
                       }
                     }
                   }
-                ],
+                }
+              },
+              {
+                "args": [],
                 "deprecationReason": null,
                 "description": null,
                 "isDeprecated": false,
-                "name": "search",
+                "name": "pageInfo",
                 "type": {
-                  "kind": "LIST",
+                  "kind": "NON_NULL",
                   "name": null,
                   "ofType": {
-                    "kind": "NON_NULL",
-                    "name": null,
-                    "ofType": {
-                      "kind": "OBJECT",
-                      "name": "SearchResult",
-                      "ofType": null
-                    }
+                    "kind": "OBJECT",
+                    "name": "PageInfo",
+                    "ofType": null
                   }
                 }
               }
@@ -1554,7 +3224,7 @@
             "inputFields": null,
             "interfaces": [],
             "kind": "OBJECT",
-            "name": "Revision",
+            "name": "SearchConnection",
             "possibleTypes": null
           },
           {
@@ -1592,6 +3262,22 @@
                     "ofType": null
                   }
                 }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "context",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "Boolean",
+                    "ofType": null
+                  }
+                }
               }
             ],
             "inputFields": null,
@@ -1643,6 +3329,22 @@
                     }
                   }
                 }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "cursor",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "String",
+                    "ofType": null
+                  }
+                }
               }
             ],
             "inputFields": null,
@@ -1651,6 +3353,49 @@
             "name": "SearchResult",
             "possibleTypes": null
           },
+          {
+            "description": null,
+            "enumValues": null,
+            "fields": [
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "path",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "OBJECT",
+                    "name": "Path",
+                    "ofType": null
+                  }
+                }
+              },
+              {
+                "args": [],
+                "deprecationReason": null,
+                "description": null,
+                "isDeprecated": false,
+                "name": "line",
+                "type": {
+                  "kind": "NON_NULL",
+                  "name": null,
+                  "ofType": {
+                    "kind": "SCALAR",
+                    "name": "Int",
+                    "ofType": null
+                  }
+                }
+              }
+            ],
+            "inputFields": null,
+            "interfaces": [],
+            "kind": "OBJECT",
+            "name": "SymbolMatch",
+            "possibleTypes": null
+          },
           {
             "description": null,
             "enumValues": null,
@@ -0,0 +1,33 @@
+  $ . ${TESTDIR}/setup_test_env.sh
+  $ cd ${TESTTMP}
+
+  $ git clone -q http://localhost:8001/real_repo.git 1> /dev/null
+  warning: You appear to have cloned an empty repository.
+  $ cd real_repo
+  $ echo hello > file
+  $ git add file
+  $ git commit -m "add file" 1> /dev/null
+  $ git push 1> /dev/null
+  To http://localhost:8001/real_repo.git
+   * [new branch]      master -> master
+
+A notification makes the proxy fetch the repository right away
+  $ curl -s -o /dev/null -w "%{http_code}\n" -X POST http://localhost:8002/~/notify/real_repo.git
+  204
+  $ test "$(git -C ${TESTTMP}/remote/scratch/mirror rev-parse refs/josh/upstream/real_repo.git/refs/heads/master)" = "$(git rev-parse HEAD)" && echo fetched
+  fetched
+
+Also when the last fetch was only just done
+  $ echo world >> file
+  $ git commit -am "update file" 1> /dev/null
+  $ git push 1> /dev/null
+  To http://localhost:8001/real_repo.git
+     *..*  master -> master (glob)
+  $ curl -s -o /dev/null -w "%{http_code}\n" -X POST http://localhost:8002/~/notify/real_repo.git
+  204
+  $ test "$(git -C ${TESTTMP}/remote/scratch/mirror rev-parse refs/josh/upstream/real_repo.git/refs/heads/master)" = "$(git rev-parse HEAD)" && echo fetched
+  fetched
+
+Notifications are POST requests
+  $ curl -s -o /dev/null -w "%{http_code}\n" http://localhost:8002/~/notify/real_repo.git
+  405
@@ -0,0 +1,72 @@
+  $ . ${TESTDIR}/setup_test_env.sh
+  $ cd ${TESTTMP}
+
+  $ git clone -q http://localhost:8001/real_repo.git 1> /dev/null
+  warning: You appear to have cloned an empty repository.
+  $ cd real_repo
+  $ mkdir -p sub/dir other
+  $ echo hello > sub/file.txt
+  $ echo {} > sub/dir/data.json
+  $ echo "<script>alert(1)</script>" > sub/page.html
+  $ echo unrelated > other/file
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+  $ git push 1> /dev/null
+  To http://localhost:8001/real_repo.git
+   * [new branch]      master -> master
+
+Raw files of a filtered tree, by branch or commit
+  $ curl -s http://localhost:8002/~/raw/real_repo.git:/sub.git/master/file.txt
+  hello
+  $ curl -s http://localhost:8002/~/raw/real_repo.git:/sub.git/$(git rev-parse HEAD)/dir/data.json
+  {}
+  $ curl -s http://localhost:8002/~/raw/real_repo.git/refs/heads/master/other/file
+  unrelated
+  $ curl -s -o /dev/null -w "%{http_code} %{content_type}\n" http://localhost:8002/~/raw/real_repo.git:/sub.git/master/dir/data.json
+  200 application/json
+  $ curl -s -o /dev/null -w "%{http_code} %{content_type}\n" http://localhost:8002/~/raw/real_repo.git:/sub.git/master/file.txt
+  200 text/plain; charset=utf-8
+  $ curl -s -o /dev/null -w "%{http_code}\n" http://localhost:8002/~/raw/real_repo.git:/sub.git/master/missing
+  404
+
+Files a browser would run scripts from are plain text, in a sandbox, and never sniffed
+  $ curl -s -o /dev/null -w "%{http_code} %{content_type}\n" http://localhost:8002/~/raw/real_repo.git:/sub.git/master/page.html
+  200 text/plain; charset=utf-8
+  $ curl -s -D - -o /dev/null http://localhost:8002/~/raw/real_repo.git:/sub.git/master/page.html | grep -i "^content-security-policy\|^x-content-type-options" | tr -d "\r"
+  content-security-policy: sandbox
+  x-content-type-options: nosniff
+  $ curl -s -D - -o /dev/null http://localhost:8002/~/raw/real_repo.git:/sub.git/master/missing | grep -i "^x-content-type-options" | tr -d "\r"
+  x-content-type-options: nosniff
+  $ curl -s -o /dev/null -w "%{http_code}\n" http://localhost:8002/~/raw/real_repo.git:/sub.git/nobranch/file.txt
+  404
+
+The ETag is the blob id
+  $ curl -s -D - -o /dev/null http://localhost:8002/~/raw/real_repo.git:/sub.git/master/file.txt | grep -i "^etag" | tr -d "\r"
+  etag: "ce013625030ba8dba906f756967f9e9ca394464a"
+  $ curl -s -o /dev/null -w "%{http_code}\n" -H 'If-None-Match: "ce013625030ba8dba906f756967f9e9ca394464a"' http://localhost:8002/~/raw/real_repo.git:/sub.git/master/file.txt
+  304
+
+Archives of a filtered tree
+  $ curl -s http://localhost:8002/~/archive/real_repo.git:/sub.git/master.tar.gz | tar tzf -
+  dir/
+  dir/data.json
+  file.txt
+  page.html
+  $ curl -s -o sub.zip http://localhost:8002/~/archive/real_repo.git:/sub.git/master.zip
+  $ unzip -Z1 sub.zip
+  dir/
+  dir/data.json
+  file.txt
+  page.html
+  $ unzip -p sub.zip file.txt
+  hello
+  $ curl -s -D - -o /dev/null http://localhost:8002/~/archive/real_repo.git:/sub.git/master.zip | grep -i "^content-disposition" | tr -d "\r"
+  content-disposition: attachment; filename="real_repo-master.zip"
+  $ curl -s -o /dev/null -w "%{http_code}\n" http://localhost:8002/~/archive/real_repo.git:/sub.git/master.rar
+  404
+
+The archive ETag is the tree id
+  $ curl -s -D - -o /dev/null http://localhost:8002/~/archive/real_repo.git:/sub.git/master.tar.gz | grep -i "^etag" | tr -d "\r"
+  etag: "2a10279e027a0e79060765011344e580b1c64364"
+  $ curl -s -o /dev/null -w "%{http_code}\n" -H 'If-None-Match: "2a10279e027a0e79060765011344e580b1c64364"' http://localhost:8002/~/archive/real_repo.git:/sub.git/master.zip
+  304